@@ -1,11 +1,8 @@
-use clap::{ArgAction, Parser, Subcommand, ValueHint, builder::PossibleValuesParser};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueHint, builder::PossibleValuesParser};
 use clap_complete::Shell;
 
-use arhiv::{
-    ArhivServer,
-    definitions::{TRACK_TYPE, get_standard_schema},
-};
-use baza::entities::Id;
+use arhiv::{ArhivServer, definitions::TRACK_TYPE};
+use baza::{DEFAULT_PAGE_SIZE, entities::Id};
 use baza_common::get_crate_version;
 
 #[derive(Parser, Debug)]
@@ -21,6 +18,36 @@ pub(crate) struct CLIArgs {
     pub(crate) verbose: u8,
 }
 
+#[derive(Args, Debug)]
+pub(crate) struct ListOptions {
+    /// Restrict results to a document type. Can be used more than once.
+    #[arg(long = "type")]
+    pub(crate) document_types: Vec<String>,
+    /// Sort by `<field>[:asc|:desc]`, where field is a schema field or one of `@updated_at`,
+    /// `@created_at`, `@title`, `@snapshots_count`. Can be used more than once.
+    #[arg(long)]
+    pub(crate) sort: Vec<String>,
+    /// Number of documents per page
+    #[arg(long, default_value_t = DEFAULT_PAGE_SIZE)]
+    pub(crate) page_size: usize,
+    /// Page number, starting at 0
+    #[arg(long, default_value_t = 0, conflicts_with = "cursor")]
+    pub(crate) page: usize,
+    /// Continue from the cursor printed with the previous page
+    #[arg(long)]
+    pub(crate) cursor: Option<String>,
+    /// Show only conflicted documents
+    #[arg(long, default_value_t = false)]
+    pub(crate) conflicts: bool,
+    /// Show documents as they were committed at the time (ISO8601 or `YYYY-MM-DD`), or at the
+    /// revision
+    #[arg(long)]
+    pub(crate) as_of: Option<String>,
+    /// Print machine-readable JSON
+    #[arg(long, default_value_t = false)]
+    pub(crate) json: bool,
+}
+
 #[derive(Subcommand, Debug)]
 pub(crate) enum CLICommand {
     /// Initialize Arhiv instance on local machine
@@ -59,6 +86,15 @@ pub(crate) enum CLICommand {
         #[arg(value_hint = ValueHint::DirPath)]
         backup_dir: String,
     },
+    /// Apply pending data migrations
+    Migrate {
+        /// Only list documents that would change, without modifying the storage
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
     /// Check or apply an Arhiv backup restore
     Restore {
         #[command(subcommand)]
@@ -102,43 +138,56 @@ pub(crate) enum CLICommand {
     },
     /// Commit pending changes
     Commit,
-    /// List recent documents
-    List {
-        /// Restrict results to a document type. Can be used more than once.
-        #[arg(long = "type", value_parser = PossibleValuesParser::new(
-                            get_standard_schema().get_document_types(),
-                        ))]
-        document_types: Vec<String>,
-        /// Page number, starting at 0
-        #[arg(long, default_value_t = 0)]
-        page: u8,
-        /// Show only conflicted documents
+    /// Remove old document snapshots according to the history settings
+    PruneHistory {
+        /// Only report what would be removed, without modifying the storage
         #[arg(long, default_value_t = false)]
-        conflicts: bool,
+        dry_run: bool,
         /// Print machine-readable JSON
         #[arg(long, default_value_t = false)]
         json: bool,
     },
-    /// Search documents
-    Search {
-        /// Full-text search query
-        #[arg(required = true, num_args = 1.., value_name = "QUERY")]
-        query: Vec<String>,
-        /// Restrict results to a document type. Can be used more than once.
-        #[arg(long = "type", value_parser = PossibleValuesParser::new(
-                            get_standard_schema().get_document_types(),
-                        ))]
-        document_types: Vec<String>,
-        /// Page number, starting at 0
-        #[arg(long, default_value_t = 0)]
-        page: u8,
-        /// Show only conflicted documents
+    /// Remove BLOB files that no asset uses, and optionally assets that no document references
+    Gc {
+        /// Also erase assets that no document references
         #[arg(long, default_value_t = false)]
-        conflicts: bool,
+        unreferenced_assets: bool,
+        /// Only report the garbage, without removing it
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+        /// Remove the garbage without asking for confirmation
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
         /// Print machine-readable JSON
         #[arg(long, default_value_t = false)]
         json: bool,
     },
+    /// Check integrity of the storage file, document snapshots and asset BLOBs
+    Fsck {
+        /// Backup directory to look for the copies that could repair the issues
+        #[arg(long = "backup-dir", value_name = "DIR", value_hint = ValueHint::DirPath)]
+        backup_dirs: Vec<String>,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// List recent documents
+    List {
+        /// Field predicates, like `status:InProgress rating>=Good`
+        #[arg(value_name = "QUERY")]
+        query: Vec<String>,
+        #[command(flatten)]
+        options: ListOptions,
+    },
+    /// Search documents
+    Search {
+        /// Full-text search query with optional field predicates, like
+        /// `authors:"Le Guin" type:book rating>=Good`
+        #[arg(required = true, num_args = 1.., value_name = "QUERY")]
+        query: Vec<String>,
+        #[command(flatten)]
+        options: ListOptions,
+    },
     /// List conflicted documents
     Conflicts {
         /// Print machine-readable JSON
@@ -171,6 +220,30 @@ pub(crate) enum CLICommand {
         #[arg(long, default_value_t = false)]
         json: bool,
     },
+    /// List committed changes across all documents, the most recent first
+    Activity {
+        /// Show only changes of one document
+        #[arg(long)]
+        id: Option<Id>,
+        /// Restrict changes to a document type. Can be used more than once.
+        #[arg(long = "type")]
+        document_types: Vec<String>,
+        /// Show changes committed at or after the time (ISO8601 or `YYYY-MM-DD`)
+        #[arg(long)]
+        since: Option<String>,
+        /// Show changes committed at or before the time (ISO8601 or `YYYY-MM-DD`)
+        #[arg(long)]
+        until: Option<String>,
+        /// Number of changes per page
+        #[arg(long, default_value_t = DEFAULT_PAGE_SIZE)]
+        page_size: usize,
+        /// Page number, starting at 0
+        #[arg(long, default_value_t = 0)]
+        page: usize,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
     /// Work with committed document snapshots
     Snapshot {
         #[command(subcommand)]
@@ -198,16 +271,18 @@ pub(crate) enum CLICommand {
         /// Id of the document
         #[arg()]
         id: Id,
+        /// Show the document as it was committed at the time (ISO8601 or `YYYY-MM-DD`), or at
+        /// the revision
+        #[arg(long)]
+        as_of: Option<String>,
         /// Print raw document head JSON
         #[arg(long, default_value_t = false)]
         json: bool,
     },
     /// Add new document
     Add {
-        /// One of known document types
-        #[arg(value_parser = PossibleValuesParser::new(
-                            get_standard_schema().get_document_types(),
-                        ))]
+        /// One of known document types, including custom ones
+        #[arg()]
         document_type: String,
         /// JSON object with document props
         #[arg()]
@@ -234,9 +309,7 @@ pub(crate) enum CLICommand {
     /// Print schema information
     Schema {
         /// Document type to describe. Prints all types when omitted.
-        #[arg(value_parser = PossibleValuesParser::new(
-                            get_standard_schema().get_document_types(),
-                        ))]
+        #[arg()]
         document_type: Option<String>,
         /// Print machine-readable JSON
         #[arg(long, default_value_t = false)]
@@ -247,6 +320,11 @@ pub(crate) enum CLICommand {
         #[command(subcommand)]
         command: CollectionCommand,
     },
+    /// Create and run saved searches
+    SavedSearch {
+        #[command(subcommand)]
+        command: SavedSearchCommand,
+    },
     /// Work with encrypted assets
     Asset {
         #[command(subcommand)]
@@ -332,6 +410,43 @@ pub(crate) enum CollectionCommand {
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub(crate) enum SavedSearchCommand {
+    /// Save a list query as a document
+    Create {
+        /// Name of the saved search
+        #[arg()]
+        name: String,
+        /// Full-text search query with optional field predicates, like `rating>=Good`
+        #[arg(value_name = "QUERY")]
+        query: Vec<String>,
+        /// Restrict results to a document type. Can be used more than once.
+        #[arg(long = "type")]
+        document_types: Vec<String>,
+        /// Sort by `<field>[:asc|:desc]`. Can be used more than once.
+        #[arg(long)]
+        sort: Vec<String>,
+    },
+    /// List documents matching a saved search
+    Run {
+        /// Id of the saved search document
+        #[arg()]
+        id: Id,
+        /// Number of documents per page
+        #[arg(long, default_value_t = DEFAULT_PAGE_SIZE)]
+        page_size: usize,
+        /// Page number, starting at 0
+        #[arg(long, default_value_t = 0, conflicts_with = "cursor")]
+        page: usize,
+        /// Continue from the cursor printed with the previous page
+        #[arg(long)]
+        cursor: Option<String>,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub(crate) enum RestoreCommand {
     /// Validate a backup manifest without mutating live storage
@@ -374,6 +489,67 @@ pub(crate) enum ConflictCommand {
         #[arg(long, default_value_t = false)]
         json: bool,
     },
+    /// List conflicting fields with their base, branch and staged values
+    Fields {
+        /// Id of the document
+        #[arg()]
+        id: Id,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Stage a resolution of the conflicting fields; the conflict is cleared on commit
+    Resolve {
+        /// Id of the document
+        #[arg()]
+        id: Id,
+        /// Take the field value from a side: FIELD=base or FIELD=N, where N is the branch number
+        #[arg(long = "pick", value_name = "FIELD=SIDE")]
+        picks: Vec<String>,
+        /// Set the field value: FIELD=VALUE, where VALUE is parsed as JSON if possible
+        #[arg(long = "set", value_name = "FIELD=VALUE")]
+        values: Vec<String>,
+        /// Lock key to be checked before updating a locked document
+        #[arg(long)]
+        lock_key: Option<String>,
+    },
+    /// Show the latest committed merge of the document branches
+    MergePoint {
+        /// Id of the document
+        #[arg()]
+        id: Id,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Undo the latest committed merge: stage one of the merged branches, or merge them again
+    Undo {
+        /// Id of the document
+        #[arg()]
+        id: Id,
+        /// Stage the branch with the number, as printed by merge-point
+        #[arg(
+            long,
+            conflicts_with = "strategy",
+            required_unless_present = "strategy"
+        )]
+        branch: Option<usize>,
+        /// Merge the branches again using the strategy: auto, prefer-older or prefer-newer
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Lock key to be checked before updating a locked document
+        #[arg(long)]
+        lock_key: Option<String>,
+    },
+    /// List automatic conflict resolutions with the per-field merge decisions
+    Log {
+        /// Only show the merges of the document
+        #[arg()]
+        id: Option<Id>,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -440,4 +616,13 @@ pub(crate) enum AssetCommand {
         #[arg(value_hint = ValueHint::FilePath)]
         output_file: String,
     },
+    /// Merge assets with the same content, updating documents that reference them
+    Dedupe {
+        /// Only report the duplicates, without modifying the documents
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+        /// Print machine-readable JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
 }
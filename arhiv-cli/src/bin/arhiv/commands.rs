@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fs::{self, read_to_string},
     io, process,
 };
@@ -9,24 +10,30 @@ use clap_complete::generate;
 
 use arhiv::{Arhiv, server::media::generate_qrcode_svg};
 use baza::{
-    BazaManager, Filter, RestoreCheckReport, RestoreOptions,
+    ActivityFilter, AsOf, AssetsDedupeReport, BazaManager, DataMigrationReport, FieldResolution,
+    Filter, FsckIssueKind, FsckReport, GarbageReport, HistoryPruneReport, MergeStrategy,
+    RestoreCheckReport, RestoreOptions,
     entities::{Document, DocumentData, DocumentLockKey, DocumentType, Id, Revision},
+    schema::SAVED_SEARCH_TYPE,
+};
+use baza_common::{
+    Timestamp, ensure_file_exists, file_exists, into_absolute_path, remove_file_if_exists,
 };
-use baza_common::{ensure_file_exists, file_exists, into_absolute_path, remove_file_if_exists};
 
 use crate::{
     cli::{
         AssetCommand, CLIArgs, CLICommand, CollectionCommand, ConflictCommand, DiffCommand,
-        RestoreCommand, SnapshotCommand,
+        ListOptions, RestoreCommand, SavedSearchCommand, SnapshotCommand,
     },
     output::{
-        get_document_head, latest_original_snapshot, print_conflict_details, print_conflicts,
-        print_document, print_document_data_diff, print_document_details, print_document_history,
-        print_document_list, print_documents_by_ids, print_schema, print_snapshot,
-        sorted_original_snapshots,
+        get_document_head, latest_original_snapshot, print_activity, print_conflict_details,
+        print_conflict_fields, print_conflicts, print_document, print_document_data_diff,
+        print_document_details, print_document_history, print_document_list,
+        print_documents_by_ids, print_merge_point, print_merge_records, print_schema,
+        print_snapshot, sorted_original_snapshots,
     },
     server::handle_server_command,
-    session::{prompt_password, unlocked_desktop_arhiv},
+    session::{prompt_confirmation, prompt_password, unlocked_desktop_arhiv},
 };
 
 enum CollectionUpdate {
@@ -210,28 +217,79 @@ pub(crate) async fn handle_command(command: CLICommand) -> Result<()> {
                 println!("Committed documents");
             }
         }
-        CLICommand::List {
-            document_types,
-            page,
-            conflicts,
-            json,
-        } => {
+        CLICommand::PruneHistory { dry_run, json } => {
             let arhiv = unlocked_desktop_arhiv()?;
 
-            let filter = build_filter(document_types, String::new(), page, conflicts);
-            print_document_list(&arhiv, &filter, json)?;
+            let mut baza = arhiv.baza.open_mut()?;
+            let report = baza.prune_history(dry_run)?;
+
+            print_history_prune_report(&report, json, !dry_run)?;
         }
-        CLICommand::Search {
-            query,
-            document_types,
-            page,
-            conflicts,
+        CLICommand::Gc {
+            unreferenced_assets,
+            dry_run,
+            yes,
             json,
         } => {
+            ensure!(
+                !json || dry_run || yes,
+                "--json requires either --dry-run or --yes"
+            );
+
+            let arhiv = unlocked_desktop_arhiv()?;
+
+            let mut baza = arhiv.baza.open_mut()?;
+            let report = baza.find_garbage()?;
+
+            if report.is_empty() || dry_run {
+                print_garbage_report(&report, json, false)?;
+                return Ok(());
+            }
+
+            if !yes {
+                print_garbage_report(&report, false, false)?;
+
+                let prompt = if unreferenced_assets {
+                    "Remove orphaned BLOBs and erase unreferenced assets?"
+                } else {
+                    "Remove orphaned BLOBs?"
+                };
+                if !prompt_confirmation(prompt)? {
+                    return Ok(());
+                }
+            }
+
+            let report = baza.collect_garbage(unreferenced_assets)?;
+            baza.save_changes()?;
+
+            print_garbage_report(&report, json, true)?;
+        }
+        CLICommand::Fsck { backup_dirs, json } => {
             let arhiv = unlocked_desktop_arhiv()?;
 
-            let filter = build_filter(document_types, query.join(" "), page, conflicts);
-            print_document_list(&arhiv, &filter, json)?;
+            let report = arhiv.baza.open()?.fsck(&backup_dirs)?;
+
+            print_fsck_report(&report, json)?;
+
+            if !report.is_ok() {
+                process::exit(1);
+            }
+        }
+        CLICommand::List { query, options } => {
+            let arhiv = unlocked_desktop_arhiv()?;
+
+            let json = options.json;
+            let as_of = parse_as_of(options.as_of.as_deref())?;
+            let filter = build_filter(join_query_args(query), options)?;
+            print_document_list(&arhiv, &filter, as_of, json)?;
+        }
+        CLICommand::Search { query, options } => {
+            let arhiv = unlocked_desktop_arhiv()?;
+
+            let json = options.json;
+            let as_of = parse_as_of(options.as_of.as_deref())?;
+            let filter = build_filter(join_query_args(query), options)?;
+            print_document_list(&arhiv, &filter, as_of, json)?;
         }
         CLICommand::Conflicts { json } => {
             let arhiv = unlocked_desktop_arhiv()?;
@@ -276,6 +334,37 @@ pub(crate) async fn handle_command(command: CLICommand) -> Result<()> {
 
             print_document_history(&arhiv, &id, json)?;
         }
+        CLICommand::Activity {
+            id,
+            document_types,
+            since,
+            until,
+            page_size,
+            page,
+            json,
+        } => {
+            let arhiv = unlocked_desktop_arhiv()?;
+
+            let filter = ActivityFilter {
+                id,
+                document_types: document_types.into_iter().map(DocumentType::new).collect(),
+                since: since
+                    .as_deref()
+                    .map(|value| parse_time(value, false))
+                    .transpose()?,
+                until: until
+                    .as_deref()
+                    .map(|value| parse_time(value, true))
+                    .transpose()?,
+                page_size: Some(page_size),
+                page,
+            };
+
+            let baza = arhiv.baza.open()?;
+            let page = baza.list_activity(&filter)?;
+
+            print_activity(&baza.get_document_expert(), &page, json)?;
+        }
         CLICommand::Snapshot {
             command: SnapshotCommand::Get { id, rev, json },
         } => {
@@ -285,7 +374,7 @@ pub(crate) async fn handle_command(command: CLICommand) -> Result<()> {
             let baza = arhiv.baza.open()?;
             let snapshot = baza.get_document_snapshot(&id, &rev)?;
 
-            print_snapshot(&arhiv.baza.get_document_expert(), &snapshot, json)?;
+            print_snapshot(&baza.get_document_expert(), &snapshot, json)?;
         }
         CLICommand::Revert { id, rev, lock_key } => {
             let arhiv = unlocked_desktop_arhiv()?;
@@ -307,17 +396,19 @@ pub(crate) async fn handle_command(command: CLICommand) -> Result<()> {
 
             handle_diff_command(&arhiv, command)?;
         }
-        CLICommand::Get { id, json } => {
+        CLICommand::Get { id, as_of, json } => {
             let arhiv = unlocked_desktop_arhiv()?;
 
+            let as_of = parse_as_of(as_of.as_deref())?;
             let baza = arhiv.baza.open()?;
-            let head = baza.get_document(&id);
+            let view = baza.open_view(as_of)?;
+            let head = view.get_document(&id);
 
             if let Some(head) = head {
                 if json {
                     serde_json::to_writer_pretty(std::io::stdout(), &head)?;
                 } else {
-                    print_document_details(&arhiv.baza.get_document_expert(), &baza, head)?;
+                    print_document_details(&view, head)?;
                 }
             } else {
                 eprintln!("Document with id '{}' not found", id);
@@ -374,15 +465,21 @@ pub(crate) async fn handle_command(command: CLICommand) -> Result<()> {
             document_type,
             json,
         } => {
-            let arhiv = Arhiv::new_desktop();
+            let arhiv = unlocked_desktop_arhiv()?;
+            let baza = arhiv.baza.open()?;
 
-            print_schema(arhiv.baza.get_schema(), document_type, json)?;
+            print_schema(baza.get_schema(), document_type, json)?;
         }
         CLICommand::Collection { command } => {
             let arhiv = unlocked_desktop_arhiv()?;
 
             handle_collection_command(&arhiv, command)?;
         }
+        CLICommand::SavedSearch { command } => {
+            let arhiv = unlocked_desktop_arhiv()?;
+
+            handle_saved_search_command(&arhiv, command)?;
+        }
         CLICommand::Asset {
             command:
                 AssetCommand::Create {
@@ -430,6 +527,19 @@ pub(crate) async fn handle_command(command: CLICommand) -> Result<()> {
 
             println!("Exported asset {id} into {output_file}");
         }
+        CLICommand::Asset {
+            command: AssetCommand::Dedupe { dry_run, json },
+        } => {
+            let arhiv = unlocked_desktop_arhiv()?;
+
+            let mut baza = arhiv.baza.open_mut()?;
+            let report = baza.dedupe_assets(dry_run)?;
+            if !dry_run {
+                baza.save_changes()?;
+            }
+
+            print_assets_dedupe_report(&report, json, !dry_run)?;
+        }
         CLICommand::Import {
             document_type,
             file_paths,
@@ -465,6 +575,17 @@ pub(crate) async fn handle_command(command: CLICommand) -> Result<()> {
                 .backup(&backup_dir)
                 .context("must be able to backup")?;
         }
+        CLICommand::Migrate { dry_run, json } => {
+            let arhiv = unlocked_desktop_arhiv()?;
+
+            let report = arhiv.baza.dry_run_data_migrations()?;
+            if !dry_run && report.errors.is_empty() {
+                // opening the baza applies pending migrations
+                arhiv.baza.open()?;
+            }
+
+            print_data_migration_report(&report, json, !dry_run)?;
+        }
         CLICommand::Restore { command } => {
             handle_restore_command(command)?;
         }
@@ -551,29 +672,363 @@ fn print_restore_check_report(report: &RestoreCheckReport, applied: bool) {
     }
 }
 
-fn build_filter(
-    document_types: Vec<String>,
-    query: String,
-    page: u8,
-    only_conflicts: bool,
-) -> Filter {
-    Filter {
-        document_types: document_types.into_iter().map(DocumentType::new).collect(),
-        query,
-        page,
-        only_conflicts,
+fn print_fsck_report(report: &FsckReport, json_output: bool) -> Result<()> {
+    if json_output {
+        serde_json::to_writer_pretty(io::stdout(), report)?;
+        return Ok(());
+    }
+
+    for issue in &report.issues {
+        let subject = match (&issue.id, &issue.rev) {
+            (Some(id), Some(rev)) => format!("snapshot {id} {}", rev.to_safe_string()),
+            (Some(id), None) => format!("BLOB {id}"),
+            (None, _) => "storage file".to_string(),
+        };
+        println!("{:<10}  {subject}: {}", issue.kind, issue.details);
+
+        for repair_from in &issue.repair_from {
+            println!("            could be repaired from {repair_from}");
+        }
+    }
+
+    println!(
+        "Checked {} snapshots and {} BLOBs",
+        report.checked_snapshots, report.checked_blobs
+    );
+
+    if report.is_ok() {
+        println!("No issues found");
+    } else {
+        let counts = [
+            FsckIssueKind::Missing,
+            FsckIssueKind::Corrupt,
+            FsckIssueKind::Mismatched,
+            FsckIssueKind::Orphaned,
+        ]
+        .into_iter()
+        .filter_map(|kind| {
+            let count = report.count_issues(kind);
+
+            (count > 0).then(|| format!("{count} {kind}"))
+        })
+        .collect::<Vec<_>>();
+
+        println!(
+            "Found {} issues: {}",
+            report.issues.len(),
+            counts.join(", ")
+        );
     }
+
+    Ok(())
+}
+
+fn print_garbage_report(report: &GarbageReport, json_output: bool, applied: bool) -> Result<()> {
+    if json_output {
+        serde_json::to_writer_pretty(
+            io::stdout(),
+            &serde_json::json!({
+                "applied": applied,
+                "report": report,
+            }),
+        )?;
+        return Ok(());
+    }
+
+    if report.is_empty() {
+        println!("No garbage found");
+        return Ok(());
+    }
+
+    if !applied {
+        for blob in &report.orphaned_blobs {
+            let erased = if blob.erased_asset {
+                "  erased asset"
+            } else {
+                ""
+            };
+            println!(
+                "BLOB   {}  {:<7}  {} bytes{erased}",
+                blob.id, blob.location, blob.size
+            );
+        }
+        for asset in &report.unreferenced_assets {
+            println!(
+                "Asset  {}  {}  {} bytes  unreferenced",
+                asset.id, asset.filename, asset.size
+            );
+        }
+        println!();
+    }
+
+    let action = if applied { "Removed" } else { "Would remove" };
+    println!(
+        "{action} {} orphaned BLOBs ({} bytes)",
+        report.orphaned_blobs.len(),
+        report.get_orphaned_blobs_size()
+    );
+
+    if !report.unreferenced_assets.is_empty() {
+        let action = if applied { "Erased" } else { "Found" };
+        println!(
+            "{action} {} unreferenced assets ({} bytes)",
+            report.unreferenced_assets.len(),
+            report.get_unreferenced_assets_size()
+        );
+    }
+    if applied && !report.unreferenced_assets.is_empty() {
+        println!("Commit the changes to remove BLOBs of the erased assets");
+    }
+
+    Ok(())
+}
+
+fn print_assets_dedupe_report(
+    report: &AssetsDedupeReport,
+    json_output: bool,
+    applied: bool,
+) -> Result<()> {
+    if json_output {
+        serde_json::to_writer_pretty(
+            io::stdout(),
+            &serde_json::json!({
+                "applied": applied,
+                "report": report,
+            }),
+        )?;
+        return Ok(());
+    }
+
+    for duplicate in &report.duplicates {
+        println!(
+            "{} -> {} ({} bytes)",
+            duplicate.id, duplicate.kept_id, duplicate.size
+        );
+    }
+
+    let action = if applied { "Merged" } else { "Would merge" };
+    println!(
+        "{action} {} duplicate assets ({} bytes) referenced by {} documents",
+        report.duplicates.len(),
+        report.get_duplicates_size(),
+        report.updated_documents.len()
+    );
+    if applied && !report.duplicates.is_empty() {
+        println!("Commit the changes to remove the duplicate BLOBs");
+    }
+
+    Ok(())
+}
+
+fn print_history_prune_report(
+    report: &HistoryPruneReport,
+    json_output: bool,
+    applied: bool,
+) -> Result<()> {
+    if json_output {
+        serde_json::to_writer_pretty(
+            io::stdout(),
+            &serde_json::json!({
+                "applied": applied,
+                "report": report,
+            }),
+        )?;
+        return Ok(());
+    }
+
+    let action = if applied { "Removed" } else { "Would remove" };
+    println!(
+        "{action} {} of {} snapshots of {} documents",
+        report.pruned_snapshots, report.total_snapshots, report.pruned_documents
+    );
+    println!(
+        "Storage size: {} -> {} bytes ({} bytes reclaimed)",
+        report.storage_size,
+        report.pruned_storage_size,
+        report.get_reclaimed_size()
+    );
+
+    Ok(())
+}
+
+fn print_data_migration_report(
+    report: &DataMigrationReport,
+    json_output: bool,
+    applied: bool,
+) -> Result<()> {
+    if json_output {
+        serde_json::to_writer_pretty(
+            io::stdout(),
+            &serde_json::json!({
+                "applied": applied && report.errors.is_empty(),
+                "report": report,
+            }),
+        )?;
+        return Ok(());
+    }
+
+    if report.source_data_version >= report.target_data_version {
+        println!("Data version {} is up to date", report.source_data_version);
+        return Ok(());
+    }
+
+    println!(
+        "Data migration from version {} to {}",
+        report.source_data_version, report.target_data_version
+    );
+    for migration in &report.migrations {
+        println!("  {migration}");
+    }
+
+    println!("Documents to change: {}", report.documents.len());
+    for document in &report.documents {
+        println!(
+            "  {} {} ({} snapshots): {}",
+            document.document_type,
+            document.id,
+            document.snapshots,
+            document.fields.join(", ")
+        );
+    }
+
+    if !report.errors.is_empty() {
+        println!("Errors: {}", report.errors.len());
+        for error in &report.errors {
+            println!("  {error}");
+        }
+        bail!("Data migration can't be applied");
+    }
+
+    if applied {
+        println!("Migrated to data version {}", report.target_data_version);
+    }
+
+    Ok(())
+}
+
+/// Joins query arguments, re-quoting values that the shell unquoted, e.g. `authors:Le Guin` or
+/// `@authors:Le Guin`.
+fn join_query_args(args: Vec<String>) -> String {
+    args.into_iter()
+        .map(|arg| {
+            if !arg.contains(char::is_whitespace) || arg.contains('"') {
+                return arg;
+            }
+
+            let value_start = arg
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '@')))
+                .filter(|pos| *pos > 0)
+                .map(|pos| {
+                    let operator_len = arg[pos..]
+                        .chars()
+                        .take_while(|c| matches!(c, ':' | '<' | '>' | '='))
+                        .count();
+
+                    pos + operator_len
+                })
+                .filter(|value_start| arg[..*value_start].ends_with([':', '<', '>', '=']))
+                .unwrap_or(0);
+
+            format!("{}\"{}\"", &arg[..value_start], &arg[value_start..])
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn build_filter(query: String, options: ListOptions) -> Result<Filter> {
+    let sort = options
+        .sort
+        .iter()
+        .map(|sort_key| sort_key.parse())
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Filter {
+        document_types: options
+            .document_types
+            .into_iter()
+            .map(DocumentType::new)
+            .collect(),
+        query,
+        sort,
+        page_size: Some(options.page_size),
+        page: options.page,
+        cursor: options.cursor,
+        only_conflicts: options.conflicts,
+    })
 }
 
 fn parse_revision(value: &str) -> Result<Revision> {
     Revision::from_safe_string(value).with_context(|| format!("Failed to parse revision '{value}'"))
 }
 
+/// Parses ISO8601 time, or a `YYYY-MM-DD` date as the start or the end of the day in UTC.
+fn parse_time(value: &str, end_of_day: bool) -> Result<Timestamp> {
+    if let Ok(time) = Timestamp::parse_iso8601_time(value) {
+        return Ok(time);
+    }
+
+    let time = if end_of_day {
+        "T23:59:59.999999999Z"
+    } else {
+        "T00:00:00Z"
+    };
+
+    Timestamp::parse_iso8601_time(&format!("{value}{time}"))
+        .with_context(|| format!("Failed to parse '{value}' as a time or a date"))
+}
+
+fn parse_as_of(value: Option<&str>) -> Result<Option<AsOf>> {
+    value
+        .map(|value| value.parse().context("Failed to parse --as-of"))
+        .transpose()
+}
+
+fn handle_saved_search_command(arhiv: &Arhiv, command: SavedSearchCommand) -> Result<()> {
+    match command {
+        SavedSearchCommand::Create {
+            name,
+            query,
+            document_types,
+            sort,
+        } => {
+            let mut data = DocumentData::new();
+            data.set("name", name);
+            data.set("query", join_query_args(query));
+            data.set("document_types", document_types.join(","));
+            data.set("sort", sort.join(","));
+
+            let document = Document::new_with_data(DocumentType::new(SAVED_SEARCH_TYPE), data);
+
+            let mut baza = arhiv.baza.open_mut()?;
+            let document = baza.stage_document(document, &None)?.clone();
+            baza.save_changes()?;
+
+            print_document(&document);
+        }
+        SavedSearchCommand::Run {
+            id,
+            page_size,
+            page,
+            cursor,
+            json,
+        } => {
+            let mut filter = arhiv.baza.open()?.get_saved_search_filter(&id)?;
+            filter.page_size = Some(page_size);
+            filter.page = page;
+            filter.cursor = cursor;
+
+            print_document_list(arhiv, &filter, None, json)?;
+        }
+    }
+
+    Ok(())
+}
+
 fn handle_collection_command(arhiv: &Arhiv, command: CollectionCommand) -> Result<()> {
     match command {
         CollectionCommand::List { id, json } => {
-            let document_expert = arhiv.baza.get_document_expert();
             let baza = arhiv.baza.open()?;
+            let document_expert = baza.get_document_expert();
             get_document_head(&baza, &id)?;
 
             let mut collection_ids = baza
@@ -595,8 +1050,8 @@ fn handle_collection_command(arhiv: &Arhiv, command: CollectionCommand) -> Resul
             collection_id,
             json,
         } => {
-            let document_expert = arhiv.baza.get_document_expert();
             let baza = arhiv.baza.open()?;
+            let document_expert = baza.get_document_expert();
             let collection = get_document_head(&baza, &collection_id)?.get_single_document();
             let member_ids = document_expert.collection_member_ids(collection)?;
 
@@ -662,8 +1117,8 @@ fn update_collection(
     update: CollectionUpdate,
 ) -> Result<()> {
     let lock_key = lock_key.map(DocumentLockKey::from_string);
-    let document_expert = arhiv.baza.get_document_expert();
     let mut baza = arhiv.baza.open_mut()?;
+    let document_expert = baza.get_document_expert();
     let mut collection = baza.must_get_document(collection_id)?.clone();
 
     match update {
@@ -692,16 +1147,126 @@ fn handle_conflict_command(arhiv: &Arhiv, command: ConflictCommand) -> Result<()
             let head = get_document_head(&baza, &id)?;
             ensure!(head.is_conflict(), "Document {id} is not conflicted");
 
-            print_conflict_details(&arhiv.baza.get_document_expert(), head, json)?;
+            print_conflict_details(&baza.get_document_expert(), head, json)?;
+        }
+        ConflictCommand::Fields { id, json } => {
+            let baza = arhiv.baza.open()?;
+            let conflict = baza.get_document_conflict(&id)?;
+
+            print_conflict_fields(&conflict, json)?;
+        }
+        ConflictCommand::Resolve {
+            id,
+            picks,
+            values,
+            lock_key,
+        } => {
+            ensure!(
+                !picks.is_empty() || !values.is_empty(),
+                "At least one --pick or --set must be provided"
+            );
+
+            let mut resolutions = HashMap::new();
+            for pick in picks {
+                let (field, side) = split_field_arg(&pick)?;
+                let resolution = if side == "base" {
+                    FieldResolution::Base
+                } else {
+                    let branch: usize = side.parse().with_context(|| {
+                        format!("Side of field '{field}' must be 'base' or a branch number")
+                    })?;
+                    ensure!(branch > 0, "Branch numbers start from 1");
+
+                    FieldResolution::Branch(branch - 1)
+                };
+
+                ensure!(
+                    resolutions.insert(field.to_string(), resolution).is_none(),
+                    "Field '{field}' is resolved more than once"
+                );
+            }
+            for value in values {
+                let (field, value) = split_field_arg(&value)?;
+                let value = serde_json::from_str(value)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+
+                ensure!(
+                    resolutions
+                        .insert(field.to_string(), FieldResolution::Value(value))
+                        .is_none(),
+                    "Field '{field}' is resolved more than once"
+                );
+            }
+
+            let lock_key = lock_key.map(DocumentLockKey::from_string);
+
+            let mut baza = arhiv.baza.open_mut()?;
+            let document = baza
+                .resolve_document_conflict(&id, &resolutions, &lock_key)?
+                .clone();
+            baza.save_changes()?;
+
+            print_document(&document);
+            println!("Staged conflict resolution for document {id}; commit to clear the conflict");
+        }
+        ConflictCommand::MergePoint { id, json } => {
+            let baza = arhiv.baza.open()?;
+            let merge_point = baza.find_merge_point(&id)?;
+
+            print_merge_point(&merge_point, json)?;
+        }
+        ConflictCommand::Undo {
+            id,
+            branch,
+            strategy,
+            lock_key,
+        } => {
+            let lock_key = lock_key.map(DocumentLockKey::from_string);
+
+            let mut baza = arhiv.baza.open_mut()?;
+            let document = match (branch, strategy) {
+                (Some(branch), None) => {
+                    ensure!(branch > 0, "Branch numbers start from 1");
+
+                    baza.revert_document_to_merge_branch(&id, branch - 1, &lock_key)?
+                        .clone()
+                }
+                (None, Some(strategy)) => {
+                    let strategy: MergeStrategy = strategy.parse()?;
+
+                    baza.remerge_document(&id, strategy, &lock_key)?.clone()
+                }
+                _ => bail!("Either --branch or --strategy must be provided"),
+            };
+            baza.save_changes()?;
+
+            print_document(&document);
+            println!("Staged undone merge of document {id}");
+        }
+        ConflictCommand::Log { id, json } => {
+            let baza = arhiv.baza.open()?;
+            let records = baza.list_merge_records(id.as_ref())?;
+
+            print_merge_records(&records, json)?;
         }
     }
 
     Ok(())
 }
 
+/// Splits `FIELD=VALUE` argument into the field name and the value.
+fn split_field_arg(arg: &str) -> Result<(&str, &str)> {
+    let (field, value) = arg
+        .split_once('=')
+        .with_context(|| format!("Expected FIELD=VALUE, got '{arg}'"))?;
+    ensure!(!field.is_empty(), "Field name must not be empty in '{arg}'");
+
+    Ok((field, value))
+}
+
 fn handle_diff_command(arhiv: &Arhiv, command: DiffCommand) -> Result<()> {
-    let document_expert = arhiv.baza.get_document_expert();
     let baza = arhiv.baza.open()?;
+    let document_expert = baza.get_document_expert();
 
     match command {
         DiffCommand::Staged { id } => {
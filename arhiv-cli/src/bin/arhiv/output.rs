@@ -3,16 +3,20 @@ use std::cmp::Ordering;
 use anyhow::{Context, Result};
 
 use baza::{
-    Baza, DocumentExpert, DocumentHead, Filter, diff_document_data,
+    ActivityPage, AsOf, Baza, BazaView, ConflictBranch, DocumentConflict, DocumentExpert,
+    DocumentHead, Filter, MergePoint, MergeRecord, diff_document_data,
     entities::{Document, DocumentType, Id},
     schema::DataSchema,
 };
 
 use arhiv::Arhiv;
 
+const SNIPPET_HIGHLIGHT_OPEN: &str = "[";
+const SNIPPET_HIGHLIGHT_CLOSE: &str = "]";
+
 pub(crate) fn print_conflicts(arhiv: &Arhiv, json_output: bool) -> Result<()> {
-    let document_expert = arhiv.baza.get_document_expert();
     let baza = arhiv.baza.open()?;
+    let document_expert = baza.get_document_expert();
     let mut conflicts = baza.iter_conflicts().collect::<Vec<_>>();
     conflicts.sort_by_key(|head| head.get_id().to_string());
 
@@ -47,8 +51,8 @@ pub(crate) fn print_conflicts(arhiv: &Arhiv, json_output: bool) -> Result<()> {
 }
 
 pub(crate) fn print_document_history(arhiv: &Arhiv, id: &Id, json_output: bool) -> Result<()> {
-    let document_expert = arhiv.baza.get_document_expert();
     let baza = arhiv.baza.open()?;
+    let document_expert = baza.get_document_expert();
     get_document_head(&baza, id)?;
     let snapshots = baza.list_document_snapshots(id)?;
 
@@ -132,6 +136,285 @@ pub(crate) fn print_conflict_details(
     Ok(())
 }
 
+pub(crate) fn print_conflict_fields(conflict: &DocumentConflict, json_output: bool) -> Result<()> {
+    if json_output {
+        let fields = conflict
+            .fields
+            .iter()
+            .map(|field| {
+                serde_json::json!({
+                    "field": field.field,
+                    "base": field.base,
+                    "branches": field.branches,
+                    "resolution": field.resolution,
+                })
+            })
+            .collect::<Vec<_>>();
+        let branches = conflict
+            .branches
+            .iter()
+            .map(|branch| {
+                serde_json::json!({
+                    "rev": branch.rev.to_safe_string(),
+                    "updatedAt": branch.updated_at,
+                    "isErased": branch.is_erased,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_writer_pretty(
+            std::io::stdout(),
+            &serde_json::json!({
+                "id": conflict.id,
+                "documentType": conflict.document_type,
+                "baseRev": conflict.base_rev.as_ref().map(|rev| rev.to_safe_string()),
+                "branches": branches,
+                "fields": fields,
+            }),
+        )?;
+        return Ok(());
+    }
+
+    println!("Conflict for document {}", conflict.id);
+    println!(
+        "Base: {}",
+        conflict
+            .base_rev
+            .as_ref()
+            .map_or_else(|| "-".to_string(), |rev| rev.to_safe_string())
+    );
+    for (index, branch) in conflict.branches.iter().enumerate() {
+        print_branch_row(index, branch);
+    }
+
+    if conflict.fields.is_empty() {
+        println!();
+        println!("No conflicting fields");
+    }
+
+    for field in &conflict.fields {
+        println!();
+        println!("Field {}", field.field);
+        println!("  base: {}", format_field_value(field.base.as_ref()));
+        for (index, value) in field.branches.iter().enumerate() {
+            println!("  {}: {}", index + 1, format_field_value(value.as_ref()));
+        }
+        println!(
+            "  staged: {}",
+            format_field_value(field.resolution.as_ref())
+        );
+    }
+
+    Ok(())
+}
+
+pub(crate) fn print_merge_point(merge_point: &MergePoint, json_output: bool) -> Result<()> {
+    if json_output {
+        let branches = merge_point
+            .branches
+            .iter()
+            .map(|branch| {
+                serde_json::json!({
+                    "rev": branch.rev.to_safe_string(),
+                    "updatedAt": branch.updated_at,
+                    "isErased": branch.is_erased,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_writer_pretty(
+            std::io::stdout(),
+            &serde_json::json!({
+                "id": merge_point.id,
+                "mergeRev": merge_point.merge_rev.to_safe_string(),
+                "mergedAt": merge_point.merged_at,
+                "baseRev": merge_point.base_rev.as_ref().map(|rev| rev.to_safe_string()),
+                "branches": branches,
+            }),
+        )?;
+        return Ok(());
+    }
+
+    println!("Merge of document {}", merge_point.id);
+    println!(
+        "Merged: rev {} updated {}",
+        merge_point.merge_rev.to_safe_string(),
+        merge_point.merged_at.default_date_time_format()
+    );
+    println!(
+        "Base: {}",
+        merge_point
+            .base_rev
+            .as_ref()
+            .map_or_else(|| "-".to_string(), |rev| rev.to_safe_string())
+    );
+    for (index, branch) in merge_point.branches.iter().enumerate() {
+        print_branch_row(index, branch);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn print_activity(
+    document_expert: &DocumentExpert<'_>,
+    page: &ActivityPage,
+    json_output: bool,
+) -> Result<()> {
+    if json_output {
+        let events = page
+            .events
+            .iter()
+            .map(|event| {
+                let title = document_expert.get_title(&event.document_type, &event.data)?;
+
+                Ok(serde_json::json!({
+                    "id": event.id,
+                    "rev": &event.rev,
+                    "revSafe": event.rev.to_safe_string(),
+                    "updatedAt": event.updated_at,
+                    "kind": event.kind,
+                    "documentType": event.document_type,
+                    "title": title,
+                    "changes": event.changes,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        serde_json::to_writer_pretty(
+            std::io::stdout(),
+            &serde_json::json!({
+                "events": events,
+                "hasMore": page.has_more,
+                "total": page.total,
+            }),
+        )?;
+        return Ok(());
+    }
+
+    if page.total == 0 {
+        println!("No changes found");
+        return Ok(());
+    }
+
+    println!(
+        "Changes: {} total, showing {}{}",
+        page.total,
+        page.events.len(),
+        if page.has_more {
+            ", more available"
+        } else {
+            ""
+        }
+    );
+
+    for event in &page.events {
+        let title = document_expert.get_title(&event.document_type, &event.data)?;
+
+        println!(
+            "{}  {:<8}  {:<12}  {}  {}",
+            event.updated_at.default_date_time_format(),
+            event.kind,
+            event.document_type,
+            event.id,
+            single_line(&title),
+        );
+
+        if !event.changes.is_empty() {
+            let changes = event
+                .changes
+                .iter()
+                .map(|change| format!("{} ({})", change.field, change.kind))
+                .collect::<Vec<_>>();
+
+            println!("    fields: {}", changes.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn print_merge_records(records: &[MergeRecord], json_output: bool) -> Result<()> {
+    if json_output {
+        let records = records
+            .iter()
+            .map(|record| {
+                let decisions = record
+                    .decisions
+                    .iter()
+                    .map(|decision| {
+                        serde_json::json!({
+                            "field": decision.field,
+                            "decision": decision.kind.to_string(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                serde_json::json!({
+                    "id": record.id,
+                    "documentType": record.document_type,
+                    "mergedAt": record.merged_at,
+                    "baseRev": record.base_rev.as_ref().map(|rev| rev.to_safe_string()),
+                    "revs": record.revs.iter().map(|rev| rev.to_safe_string()).collect::<Vec<_>>(),
+                    "decisions": decisions,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_writer_pretty(std::io::stdout(), &records)?;
+        return Ok(());
+    }
+
+    if records.is_empty() {
+        println!("No automatic merges");
+        return Ok(());
+    }
+
+    for (index, record) in records.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+
+        println!(
+            "{} [{} {}]",
+            record.merged_at.default_date_time_format(),
+            record.document_type,
+            record.id
+        );
+        println!(
+            "  base: {}",
+            record
+                .base_rev
+                .as_ref()
+                .map_or_else(|| "-".to_string(), |rev| rev.to_safe_string())
+        );
+        for (index, rev) in record.revs.iter().enumerate() {
+            println!("  branch {}: {}", index + 1, rev.to_safe_string());
+        }
+        for decision in &record.decisions {
+            match &decision.field {
+                Some(field) => println!("  {field}: {}", decision.kind),
+                None => println!("  document: {}", decision.kind),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_branch_row(index: usize, branch: &ConflictBranch) {
+    println!(
+        "Branch {}: rev {} updated {}{}",
+        index + 1,
+        branch.rev.to_safe_string(),
+        branch.updated_at.default_date_time_format(),
+        if branch.is_erased { " [erased]" } else { "" }
+    );
+}
+
+fn format_field_value(value: Option<&serde_json::Value>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| single_line(&value.to_string()))
+}
+
 pub(crate) fn print_snapshot(
     document_expert: &DocumentExpert<'_>,
     document: &Document,
@@ -353,16 +636,37 @@ pub(crate) fn get_document_head<'b>(baza: &'b Baza, id: &Id) -> Result<&'b Docum
         .with_context(|| format!("Can't find document {id}"))
 }
 
-pub(crate) fn print_document_list(arhiv: &Arhiv, filter: &Filter, json_output: bool) -> Result<()> {
-    let document_expert = arhiv.baza.get_document_expert();
+pub(crate) fn print_document_list(
+    arhiv: &Arhiv,
+    filter: &Filter,
+    as_of: Option<AsOf>,
+    json_output: bool,
+) -> Result<()> {
     let baza = arhiv.baza.open()?;
-    let page = baza.list_documents(filter)?;
+    let view = baza.open_view(as_of)?;
+    let document_expert = view.get_document_expert();
+
+    for document_type in &filter.document_types {
+        view.get_schema().get_data_description(document_type)?;
+    }
+
+    let page = view.list_documents(filter)?;
 
     if json_output {
         let documents = page
             .items
             .into_iter()
-            .map(|head| document_summary_json(&document_expert, head))
+            .map(|head| {
+                let mut summary = document_summary_json(&document_expert, head)?;
+                summary["snippets"] = serde_json::json!(
+                    page.snippets
+                        .get(head.get_id())
+                        .map(Vec::as_slice)
+                        .unwrap_or_default()
+                );
+
+                Ok(summary)
+            })
             .collect::<Result<Vec<_>>>()?;
 
         serde_json::to_writer_pretty(
@@ -371,6 +675,7 @@ pub(crate) fn print_document_list(arhiv: &Arhiv, filter: &Filter, json_output: b
                 "documents": documents,
                 "hasMore": page.has_more,
                 "total": page.total,
+                "nextCursor": page.next_cursor,
             }),
         )?;
         return Ok(());
@@ -394,6 +699,20 @@ pub(crate) fn print_document_list(arhiv: &Arhiv, filter: &Filter, json_output: b
 
     for head in page.items {
         print_document_row(&document_expert, head)?;
+
+        for snippet in page.snippets.get(head.get_id()).into_iter().flatten() {
+            println!(
+                "    {}: {}",
+                snippet.field,
+                snippet
+                    .snippet
+                    .render(SNIPPET_HIGHLIGHT_OPEN, SNIPPET_HIGHLIGHT_CLOSE)
+            );
+        }
+    }
+
+    if let Some(next_cursor) = page.next_cursor {
+        println!("Next page: --cursor {next_cursor}");
     }
 
     Ok(())
@@ -437,11 +756,8 @@ pub(crate) fn print_document_row(
     Ok(())
 }
 
-pub(crate) fn print_document_details(
-    document_expert: &DocumentExpert<'_>,
-    baza: &Baza,
-    head: &DocumentHead,
-) -> Result<()> {
+pub(crate) fn print_document_details(view: &BazaView, head: &DocumentHead) -> Result<()> {
+    let document_expert = view.get_document_expert();
     let document = representative_document(head);
     let title = document_expert.get_title(&document.document_type, &document.data)?;
     let refs = document_expert.extract_refs(&document.document_type, &document.data)?;
@@ -460,12 +776,15 @@ pub(crate) fn print_document_details(
     println!("Refs: {}", format_ids(refs.get_all_document_refs()));
     println!(
         "Backrefs: {}",
-        format_ids(baza.find_document_backrefs(&document.id))
+        format_ids(view.find_document_backrefs(&document.id))
     );
     println!(
         "Collections: {}",
-        format_ids(baza.find_document_collections(&document.id))
+        format_ids(view.find_document_collections(&document.id))
     );
+    if let Some(as_of) = view.get_as_of() {
+        println!("As of: {as_of}");
+    }
     println!("Data:\n{data}");
 
     Ok(())
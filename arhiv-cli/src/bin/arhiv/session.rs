@@ -1,5 +1,5 @@
 use anyhow::{Context, Result, bail};
-use dialoguer::{Password, theme::ColorfulTheme};
+use dialoguer::{Confirm, Password, theme::ColorfulTheme};
 
 use arhiv::{Arhiv, CacheUnlockResult};
 use baza::BazaManager;
@@ -60,3 +60,11 @@ pub(crate) fn prompt_password(min_length: usize, with_confirmation: bool) -> Res
         .map(|value| value.into())
         .context("Failed to prompt password")
 }
+
+pub(crate) fn prompt_confirmation(prompt: &str) -> Result<bool> {
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .context("Failed to prompt confirmation")
+}
@@ -31,17 +31,10 @@ impl Arhiv {
             asset.data.media_type
         );
 
-        let file_name = remove_file_extension(&asset.data.filename)?;
-
-        let mut iter = file_name.split('-');
-
-        let artist = iter
-            .next()
-            .context("couldn't extract track artist from filename")?;
-
-        let title = iter
-            .next()
-            .context("couldn't extract track title from filename")?;
+        let (artist, title) = match (&asset.data.artist, &asset.data.title) {
+            (Some(artist), Some(title)) => (artist.clone(), title.clone()),
+            _ => parse_track_file_name(&asset.data.filename)?,
+        };
 
         let mut data = DocumentData::new();
         data.set("artist", artist);
@@ -61,3 +54,20 @@ impl Arhiv {
         Ok(document)
     }
 }
+
+/// Extracts the artist and the title from a file name like `Artist - Title.mp3`.
+fn parse_track_file_name(filename: &str) -> Result<(String, String)> {
+    let file_name = remove_file_extension(filename)?;
+
+    let mut iter = file_name.split('-');
+
+    let artist = iter
+        .next()
+        .context("couldn't extract track artist from filename")?;
+
+    let title = iter
+        .next()
+        .context("couldn't extract track title from filename")?;
+
+    Ok((artist.to_string(), title.to_string()))
+}
@@ -7,7 +7,8 @@ use std::{cmp::min, sync::Arc};
 use anyhow::{Result, bail};
 
 use baza::{
-    AutoCommitService, AutoCommitTask, BazaManager, BazaPaths, DEV_MODE, StorageKeyUnlockResult,
+    AssetTextService, AssetTextTask, AutoCommitService, AutoCommitTask, BazaManager, BazaPaths,
+    DEV_MODE, StorageKeyUnlockResult,
 };
 use baza_common::{
     SecretString, get_linux_data_home, get_linux_downloads_dir, get_linux_home_dir,
@@ -81,6 +82,7 @@ pub struct Arhiv {
     pub baza: Arc<BazaManager>,
     keyring: ArhivKeyring,
     auto_commit_task: Option<AutoCommitTask>,
+    asset_text_task: Option<AssetTextTask>,
     file_browser_root_dir: String,
 }
 
@@ -101,6 +103,7 @@ impl Arhiv {
             keyring: options.keyring,
 
             auto_commit_task: None,
+            asset_text_task: None,
             file_browser_root_dir: options.file_browser_root_dir,
         }
     }
@@ -121,6 +124,14 @@ impl Arhiv {
         self.auto_commit_task = Some(task);
     }
 
+    pub fn init_asset_text_service(&mut self) {
+        let service =
+            AssetTextService::new(self.baza.clone(), AssetTextService::DEFAULT_CHECK_INTERVAL);
+        let task = service.start();
+
+        self.asset_text_task = Some(task);
+    }
+
     pub fn create(&self, password: SecretString) -> Result<()> {
         log::info!("Creating new Arhiv");
 
@@ -211,6 +222,10 @@ impl Arhiv {
             auto_commit_task.abort();
         }
 
+        if let Some(ref asset_text_task) = self.asset_text_task {
+            asset_text_task.abort();
+        }
+
         std::thread::sleep(std::time::Duration::from_millis(100));
 
         log::info!("Stopped Arhiv");
@@ -22,10 +22,15 @@ pub async fn materialize_asset_urls(
     baza_manager: &BazaManager,
     document: &mut Document,
 ) -> Result<()> {
-    let document_expert = baza_manager.get_document_expert();
-    let fields = document_expert.asset_ref_fields(&document.document_type)?;
-
-    for field in fields {
+    let fields = baza_manager
+        .open()?
+        .get_document_expert()
+        .asset_ref_fields(&document.document_type)?
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for field in &fields {
         materialize_asset_field(baza_manager, document, field).await?;
     }
 
@@ -37,13 +42,13 @@ async fn materialize_asset_field(
     document: &mut Document,
     field: &Field,
 ) -> Result<()> {
-    match field.field_type {
+    match &field.field_type {
         FieldType::Ref(_) => {
-            let Some(value) = document.data.get_str(field.name) else {
+            let Some(value) = document.data.get_str(&field.name) else {
                 return Ok(());
             };
 
-            let Some(asset_id) = materialize_asset_url(baza_manager, field.name, value).await?
+            let Some(asset_id) = materialize_asset_url(baza_manager, &field.name, value).await?
             else {
                 return Ok(());
             };
@@ -53,20 +58,21 @@ async fn materialize_asset_field(
                 field.name,
                 asset_id
             );
-            document.data.set(field.name, asset_id);
+            document.data.set(&field.name, asset_id);
         }
 
         FieldType::RefList(_) => {
             let mut values = document
                 .data
-                .get_ref_list(field.name)?
+                .get_ref_list(&field.name)?
                 .unwrap_or_default()
                 .into_iter()
                 .map(|value| value.to_string())
                 .collect::<Vec<_>>();
 
             for value in &mut values {
-                let Some(asset_id) = materialize_asset_url(baza_manager, field.name, value).await?
+                let Some(asset_id) =
+                    materialize_asset_url(baza_manager, &field.name, value).await?
                 else {
                     continue;
                 };
@@ -79,7 +85,7 @@ async fn materialize_asset_field(
                 *value = asset_id.to_string();
             }
 
-            document.data.set(field.name, values);
+            document.data.set(&field.name, values);
         }
 
         _ => unreachable!("only ref fields might reference assets"),
@@ -9,124 +9,142 @@ pub const BOOK_COLLECTION_TYPE: &str = "book collection";
 pub fn get_book_definitions() -> Vec<DataDescription> {
     vec![
         DataDescription {
-            document_type: BOOK_TYPE,
-            title_format: "${authors} - ${title}",
+            document_type: BOOK_TYPE.into(),
+            title_format: "${authors} - ${title}".into(),
             fields: vec![
                 Field {
-                    name: "title",
+                    name: "title".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "authors",
+                    name: "authors".into(),
                     field_type: FieldType::People {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "cover",
-                    field_type: FieldType::Ref(&[ASSET_TYPE]),
+                    name: "cover".into(),
+                    field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "language",
-                    field_type: LANGUAGE_FIELD,
+                    name: "language".into(),
+                    field_type: language_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "original_language",
-                    field_type: LANGUAGE_FIELD,
+                    name: "original_language".into(),
+                    field_type: language_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "publication_date",
+                    name: "publication_date".into(),
                     field_type: FieldType::Date {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "translators",
+                    name: "translators".into(),
                     field_type: FieldType::People {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "publisher",
+                    name: "publisher".into(),
                     field_type: FieldType::String {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "pages",
+                    name: "pages".into(),
                     field_type: FieldType::NaturalNumber {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 // for audiobooks
                 Field {
-                    name: "narrators",
+                    name: "narrators".into(),
                     field_type: FieldType::People {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "duration",
+                    name: "duration".into(),
                     field_type: FieldType::Duration {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 // --
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "status",
-                    field_type: STATUS_FIELD,
+                    name: "status".into(),
+                    field_type: status_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "rating",
-                    field_type: RATING_FIELD,
+                    name: "rating".into(),
+                    field_type: rating_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "comment",
+                    name: "comment".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
         DataDescription {
-            document_type: BOOK_COLLECTION_TYPE,
-            title_format: "${name}",
+            document_type: BOOK_COLLECTION_TYPE.into(),
+            title_format: "${name}".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "books",
-                    field_type: FieldType::RefList(&[BOOK_TYPE]),
+                    name: "books".into(),
+                    field_type: FieldType::RefList(vec![BOOK_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
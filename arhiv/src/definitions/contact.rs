@@ -9,86 +9,98 @@ pub const CONTACT_COLLECTION_TYPE: &str = "contact collection";
 pub fn get_contact_definitions() -> Vec<DataDescription> {
     vec![
         DataDescription {
-            document_type: CONTACT_TYPE,
-            title_format: "${name}",
+            document_type: CONTACT_TYPE.into(),
+            title_format: "${name}".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "cover",
-                    field_type: FieldType::Ref(&[ASSET_TYPE]),
+                    name: "cover".into(),
+                    field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "is_company",
+                    name: "is_company".into(),
                     field_type: FieldType::Flag {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "date_of_birth",
+                    name: "date_of_birth".into(),
                     field_type: FieldType::Date {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "date_of_death",
+                    name: "date_of_death".into(),
                     field_type: FieldType::Date {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "primary_language",
-                    field_type: LANGUAGE_FIELD,
+                    name: "primary_language".into(),
+                    field_type: language_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "addresses",
+                    name: "addresses".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "contacts",
+                    name: "contacts".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "info",
+                    name: "info".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
         DataDescription {
-            document_type: CONTACT_COLLECTION_TYPE,
-            title_format: "${name}",
+            document_type: CONTACT_COLLECTION_TYPE.into(),
+            title_format: "${name}".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "contacts",
-                    field_type: FieldType::RefList(&[CONTACT_TYPE]),
+                    name: "contacts".into(),
+                    field_type: FieldType::RefList(vec![CONTACT_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
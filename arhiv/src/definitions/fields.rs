@@ -25,7 +25,9 @@ const LANGUAGES: &[&str] = &[
     "Arabic",
 ];
 
-pub const LANGUAGE_FIELD: FieldType = FieldType::Enum(LANGUAGES);
+pub fn language_field() -> FieldType {
+    enum_field(LANGUAGES)
+}
 
 const RATINGS: &[&str] = &[
     "Bad",     //
@@ -36,8 +38,16 @@ const RATINGS: &[&str] = &[
     "Great",
 ];
 
-pub const RATING_FIELD: FieldType = FieldType::Enum(RATINGS);
+pub fn rating_field() -> FieldType {
+    enum_field(RATINGS)
+}
 
 const STATUSES: &[&str] = &["InProgress", "OnHold", "Completed", "Dropped"];
 
-pub const STATUS_FIELD: FieldType = FieldType::Enum(STATUSES);
+pub fn status_field() -> FieldType {
+    enum_field(STATUSES)
+}
+
+pub fn enum_field(values: &[&str]) -> FieldType {
+    FieldType::Enum(values.iter().map(ToString::to_string).collect())
+}
@@ -9,122 +9,140 @@ pub const FILM_COLLECTION_TYPE: &str = "film collection";
 pub fn get_film_definitions() -> Vec<DataDescription> {
     vec![
         DataDescription {
-            document_type: FILM_TYPE,
-            title_format: "${title} (${release_date})",
+            document_type: FILM_TYPE.into(),
+            title_format: "${title} (${release_date})".into(),
             fields: vec![
                 Field {
-                    name: "title",
+                    name: "title".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "cover",
-                    field_type: FieldType::Ref(&[ASSET_TYPE]),
+                    name: "cover".into(),
+                    field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "release_date",
+                    name: "release_date".into(),
                     field_type: FieldType::Date {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "language",
-                    field_type: LANGUAGE_FIELD,
+                    name: "language".into(),
+                    field_type: language_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "original_language",
-                    field_type: LANGUAGE_FIELD,
+                    name: "original_language".into(),
+                    field_type: language_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "countries_of_origin",
+                    name: "countries_of_origin".into(),
                     field_type: FieldType::Countries {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "creators",
+                    name: "creators".into(),
                     field_type: FieldType::People {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "cast",
+                    name: "cast".into(),
                     field_type: FieldType::People {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "duration",
+                    name: "duration".into(),
                     field_type: FieldType::Duration {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "seasons",
+                    name: "seasons".into(),
                     field_type: FieldType::NaturalNumber {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "episodes",
+                    name: "episodes".into(),
                     field_type: FieldType::NaturalNumber {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "status",
-                    field_type: STATUS_FIELD,
+                    name: "status".into(),
+                    field_type: status_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "rating",
-                    field_type: RATING_FIELD,
+                    name: "rating".into(),
+                    field_type: rating_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "comment",
+                    name: "comment".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
         DataDescription {
-            document_type: FILM_COLLECTION_TYPE,
-            title_format: "${name}",
+            document_type: FILM_COLLECTION_TYPE.into(),
+            title_format: "${name}".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "films",
-                    field_type: FieldType::RefList(&[FILM_TYPE]),
+                    name: "films".into(),
+                    field_type: FieldType::RefList(vec![FILM_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
@@ -9,86 +9,98 @@ pub const GAME_COLLECTION_TYPE: &str = "game collection";
 pub fn get_game_definitions() -> Vec<DataDescription> {
     vec![
         DataDescription {
-            document_type: GAME_TYPE,
-            title_format: "${name} (${release_date})",
+            document_type: GAME_TYPE.into(),
+            title_format: "${name} (${release_date})".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "cover",
-                    field_type: FieldType::Ref(&[ASSET_TYPE]),
+                    name: "cover".into(),
+                    field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "release_date",
+                    name: "release_date".into(),
                     field_type: FieldType::Date {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "developers",
+                    name: "developers".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "language",
-                    field_type: LANGUAGE_FIELD,
+                    name: "language".into(),
+                    field_type: language_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "status",
-                    field_type: STATUS_FIELD,
+                    name: "status".into(),
+                    field_type: status_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "rating",
-                    field_type: RATING_FIELD,
+                    name: "rating".into(),
+                    field_type: rating_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "comment",
+                    name: "comment".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
         DataDescription {
-            document_type: GAME_COLLECTION_TYPE,
-            title_format: "${name}",
+            document_type: GAME_COLLECTION_TYPE.into(),
+            title_format: "${name}".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "games",
-                    field_type: FieldType::RefList(&[GAME_TYPE]),
+                    name: "games".into(),
+                    field_type: FieldType::RefList(vec![GAME_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
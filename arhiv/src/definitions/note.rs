@@ -4,20 +4,22 @@ pub const NOTE_TYPE: &str = "note";
 
 pub fn get_note_definitions() -> Vec<DataDescription> {
     vec![DataDescription {
-        document_type: NOTE_TYPE,
-        title_format: "${title}",
+        document_type: NOTE_TYPE.into(),
+        title_format: "${title}".into(),
         fields: vec![
             Field {
-                name: "title",
+                name: "title".into(),
                 field_type: FieldType::String {},
                 mandatory: true,
                 readonly: false,
+                ordered: None,
             },
             Field {
-                name: "data",
+                name: "data".into(),
                 field_type: FieldType::MarkupString {},
                 mandatory: false,
                 readonly: false,
+                ordered: None,
             },
         ],
     }]
@@ -4,20 +4,22 @@ pub const TAG_TYPE: &str = "tag";
 
 pub fn get_tag_definitions() -> Vec<DataDescription> {
     vec![DataDescription {
-        document_type: TAG_TYPE,
-        title_format: "${title}",
+        document_type: TAG_TYPE.into(),
+        title_format: "${title}".into(),
         fields: vec![
             Field {
-                name: "title",
+                name: "title".into(),
                 field_type: FieldType::String {},
                 mandatory: true,
                 readonly: false,
+                ordered: None,
             },
             Field {
-                name: "items",
-                field_type: FieldType::RefList(&[]),
+                name: "items".into(),
+                field_type: FieldType::RefList(vec![]),
                 mandatory: false,
                 readonly: false,
+                ordered: None,
             },
         ],
     }]
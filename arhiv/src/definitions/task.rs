@@ -1,5 +1,7 @@
 use baza::schema::*;
 
+use super::fields::enum_field;
+
 pub const PROJECT_TYPE: &str = "project";
 
 pub const TASK_TYPE: &str = "task";
@@ -9,50 +11,56 @@ pub const TASK_STATUS: &[&str] = &["Todo", "InProgress", "Done", "Cancelled"];
 pub fn get_task_definitions() -> Vec<DataDescription> {
     vec![
         DataDescription {
-            document_type: PROJECT_TYPE,
-            title_format: "${name}",
+            document_type: PROJECT_TYPE.into(),
+            title_format: "${name}".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "tasks",
-                    field_type: FieldType::RefList(&[TASK_TYPE]),
+                    name: "tasks".into(),
+                    field_type: FieldType::RefList(vec![TASK_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
         DataDescription {
-            document_type: TASK_TYPE,
-            title_format: "${title}",
+            document_type: TASK_TYPE.into(),
+            title_format: "${title}".into(),
             fields: vec![
                 Field {
-                    name: "title",
+                    name: "title".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "status",
-                    field_type: FieldType::Enum(TASK_STATUS),
+                    name: "status".into(),
+                    field_type: enum_field(TASK_STATUS),
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
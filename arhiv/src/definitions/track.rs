@@ -14,74 +14,84 @@ pub const TRACK_COLLECTION_TYPE: &str = "track collection";
 pub fn get_track_definitions() -> Vec<DataDescription> {
     vec![
         DataDescription {
-            document_type: TRACK_TYPE,
-            title_format: "${artist} - ${title}",
+            document_type: TRACK_TYPE.into(),
+            title_format: "${artist} - ${title}".into(),
             fields: vec![
                 Field {
-                    name: "title",
+                    name: "title".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "artist",
+                    name: "artist".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "track",
-                    field_type: FieldType::Ref(&[ASSET_TYPE]),
+                    name: "track".into(),
+                    field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "cover",
-                    field_type: FieldType::Ref(&[ASSET_TYPE]),
+                    name: "cover".into(),
+                    field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "release_date",
+                    name: "release_date".into(),
                     field_type: FieldType::Date {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "language",
-                    field_type: LANGUAGE_FIELD,
+                    name: "language".into(),
+                    field_type: language_field(),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "comment",
+                    name: "comment".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
         DataDescription {
-            document_type: TRACK_COLLECTION_TYPE,
-            title_format: "${name}",
+            document_type: TRACK_COLLECTION_TYPE.into(),
+            title_format: "${name}".into(),
             fields: vec![
                 Field {
-                    name: "name",
+                    name: "name".into(),
                     field_type: FieldType::String {},
                     mandatory: true,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "description",
+                    name: "description".into(),
                     field_type: FieldType::MarkupString {},
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
                 Field {
-                    name: "tracks",
-                    field_type: FieldType::RefList(&[TRACK_TYPE]),
+                    name: "tracks".into(),
+                    field_type: FieldType::RefList(vec![TRACK_TYPE.into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 },
             ],
         },
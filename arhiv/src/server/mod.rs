@@ -46,6 +46,7 @@ impl ArhivServer {
 
         let mut arhiv = Arhiv::new(options);
         arhiv.init_auto_commit_service();
+        arhiv.init_asset_text_service();
 
         let arhiv = Arc::new(arhiv);
 
@@ -5,7 +5,8 @@ use serde::Serialize;
 
 use crate::{assets::materialize_asset_urls, server::media::generate_qrcode_svg};
 use baza::{
-    DocumentExpert, Filter, StagingError, ValidationError,
+    ActivityFilter, DocumentExpert, FieldResolution, Filter, ListPage, SearchSnippet, StagingError,
+    ValidationError,
     entities::{Document, DocumentType},
     markup::MarkupStr,
     schema::DataSchema,
@@ -17,8 +18,10 @@ use baza_common::{
 
 use crate::CacheUnlockResult;
 use crate::ui::dto::{
-    APIRequest, APIResponse, DirEntry, DocumentBackref, GetDocumentsResult, ListDocumentsResult,
-    SaveDocumentErrors, UnlockArhivOutcome,
+    APIRequest, APIResponse, ActivityEventInfo, ConflictBranchInfo, ConflictFieldChoice,
+    ConflictFieldInfo, DirEntry, DocumentBackref, DocumentSnippet, FieldChangeInfo,
+    GetDocumentsResult, ListDocumentsResult, MergeDecisionInfo, MergeRecordInfo, OrphanedBlobInfo,
+    SaveDocumentErrors, SnippetFragment, UnlockArhivOutcome, UnreferencedAssetInfo,
 };
 
 use super::ServerContext;
@@ -30,61 +33,74 @@ pub async fn handle_api_request(ctx: &ServerContext, request: APIRequest) -> Res
         APIRequest::ListDocuments {
             document_types,
             query,
+            sort,
+            page_size,
             page,
+            cursor,
             only_conflicts,
+            as_of,
         } => {
             let document_types = document_types.into_iter().map(DocumentType::new).collect();
             let filter = Filter {
                 query,
                 document_types,
+                sort: sort.unwrap_or_default(),
+                page_size,
                 page,
+                cursor,
                 only_conflicts,
             };
 
-            let document_expert = arhiv.baza.get_document_expert();
+            let baza = arhiv.baza.open()?;
+            let view = baza.open_view(as_of)?;
+            let page = view.list_documents(&filter)?;
 
+            APIResponse::ListDocuments {
+                has_more: page.has_more,
+                documents: list_page_into_results(&page, view.get_schema())?,
+                total: page.total,
+                next_cursor: page.next_cursor,
+            }
+        }
+        APIRequest::RunSavedSearch {
+            id,
+            page_size,
+            page,
+            cursor,
+            as_of,
+        } => {
             let baza = arhiv.baza.open()?;
-            let page = baza.list_documents(&filter)?;
 
-            let documents = page
-                .items
-                .into_iter()
-                .map(|head| {
-                    let doc = head.get_single_document();
-
-                    let asset_id = document_expert.get_cover_asset_id(doc)?;
-
-                    Ok(ListDocumentsResult {
-                        title: document_expert.get_title(&doc.document_type, &doc.data)?,
-                        cover: asset_id,
-                        id: doc.id.clone(),
-                        document_type: doc.document_type.clone().into(),
-                        updated_at: doc.updated_at,
-                        data: doc.data.clone(),
-                        has_conflict: head.is_conflict(),
-                    })
-                })
-                .collect::<Result<_>>()?;
+            let mut filter = baza.get_saved_search_filter(&id)?;
+            filter.page_size = page_size;
+            filter.page = page;
+            filter.cursor = cursor;
 
-            APIResponse::ListDocuments {
+            let view = baza.open_view(as_of)?;
+            let page = view.list_documents(&filter)?;
+
+            APIResponse::RunSavedSearch {
                 has_more: page.has_more,
-                documents,
+                documents: list_page_into_results(&page, view.get_schema())?,
                 total: page.total,
+                next_cursor: page.next_cursor,
             }
         }
         APIRequest::GetDocuments {
             ids,
             ignore_missing,
+            as_of,
         } => {
             let ignore_missing = ignore_missing.unwrap_or_default();
 
             let baza = arhiv.baza.open()?;
+            let view = baza.open_view(as_of)?;
 
-            let schema = arhiv.baza.get_schema();
+            let schema = view.get_schema();
 
             let mut documents = Vec::with_capacity(ids.len());
             for id in &ids {
-                let head = baza.get_document(id);
+                let head = view.get_document(id);
 
                 if let Some(head) = head {
                     documents.push(head.get_single_document());
@@ -104,19 +120,20 @@ pub async fn handle_api_request(ctx: &ServerContext, request: APIRequest) -> Res
                 status: status.to_string(),
             }
         }
-        APIRequest::GetDocument { ref id } => {
+        APIRequest::GetDocument { ref id, as_of } => {
             let baza = arhiv.baza.open()?;
-            let head = baza.get_document(id).context("Document is missing")?;
+            let view = baza.open_view(as_of)?;
+            let head = view.get_document(id).context("Document is missing")?;
             let document = head.get_single_document();
             let snapshots_count = head.get_snapshots_count();
 
-            let document_expert = arhiv.baza.get_document_expert();
+            let document_expert = view.get_document_expert();
 
-            let backrefs = baza
+            let backrefs = view
                 .find_document_backrefs(id)
                 .into_iter()
                 .map(|id| {
-                    let item = baza.must_get_document(&id)?;
+                    let item = view.must_get_document(&id)?;
 
                     Ok(DocumentBackref {
                         title: document_expert.get_title(&item.document_type, &item.data)?,
@@ -126,11 +143,11 @@ pub async fn handle_api_request(ctx: &ServerContext, request: APIRequest) -> Res
                 })
                 .collect::<Result<_>>()?;
 
-            let collections = baza
+            let collections = view
                 .find_document_collections(id)
                 .into_iter()
                 .map(|id| {
-                    let item = baza.must_get_document(&id)?;
+                    let item = view.must_get_document(&id)?;
 
                     Ok(DocumentBackref {
                         title: document_expert.get_title(&item.document_type, &item.data)?,
@@ -316,7 +333,7 @@ pub async fn handle_api_request(ctx: &ServerContext, request: APIRequest) -> Res
                 bail!("Collection {collection_id} is locked")
             }
 
-            let document_expert = arhiv.baza.get_document_expert();
+            let document_expert = baza.get_document_expert();
             let mut collection = baza.must_get_document(&collection_id)?.clone();
 
             document_expert.reorder_collection_member(&mut collection, &id, new_pos)?;
@@ -400,6 +417,231 @@ pub async fn handle_api_request(ctx: &ServerContext, request: APIRequest) -> Res
 
             APIResponse::CountConflicts { conflicts_count }
         }
+        APIRequest::GetDocumentConflict { id } => {
+            let conflict = arhiv.baza.open()?.get_document_conflict(&id)?;
+
+            APIResponse::GetDocumentConflict {
+                id: conflict.id,
+                document_type: conflict.document_type.to_string(),
+                base_rev: conflict.base_rev.map(|rev| rev.to_safe_string()),
+                branches: conflict
+                    .branches
+                    .into_iter()
+                    .map(|branch| ConflictBranchInfo {
+                        rev: branch.rev.to_safe_string(),
+                        updated_at: branch.updated_at,
+                        is_erased: branch.is_erased,
+                    })
+                    .collect(),
+                fields: conflict
+                    .fields
+                    .into_iter()
+                    .map(|field| ConflictFieldInfo {
+                        field: field.field,
+                        base: field.base,
+                        branches: field.branches,
+                        resolution: field.resolution,
+                    })
+                    .collect(),
+            }
+        }
+        APIRequest::ResolveDocumentConflict {
+            id,
+            lock_key,
+            fields,
+        } => {
+            let resolutions = fields
+                .into_iter()
+                .map(|item| {
+                    let resolution = match item.choice {
+                        ConflictFieldChoice::Base {} => FieldResolution::Base,
+                        ConflictFieldChoice::Branch { index } => FieldResolution::Branch(index),
+                        ConflictFieldChoice::Value { value } => FieldResolution::Value(value),
+                    };
+
+                    (item.field, resolution)
+                })
+                .collect();
+
+            let mut baza = arhiv.baza.open_mut()?;
+            if let Err(err) = baza.resolve_document_conflict(&id, &resolutions, &lock_key) {
+                match err {
+                    StagingError::Validation(validation_error) => {
+                        APIResponse::ResolveDocumentConflict {
+                            errors: Some(validation_error.into()),
+                        }
+                    }
+                    StagingError::Other(error) => return Err(error),
+                }
+            } else {
+                baza.save_changes()?;
+
+                APIResponse::ResolveDocumentConflict { errors: None }
+            }
+        }
+        APIRequest::ListMergeRecords { id } => {
+            let records = arhiv
+                .baza
+                .open()?
+                .list_merge_records(id.as_ref())?
+                .into_iter()
+                .map(|record| MergeRecordInfo {
+                    id: record.id,
+                    document_type: record.document_type.to_string(),
+                    merged_at: record.merged_at,
+                    base_rev: record.base_rev.map(|rev| rev.to_safe_string()),
+                    revs: record.revs.iter().map(|rev| rev.to_safe_string()).collect(),
+                    decisions: record
+                        .decisions
+                        .into_iter()
+                        .map(|decision| MergeDecisionInfo {
+                            field: decision.field,
+                            decision: decision.kind.to_string(),
+                        })
+                        .collect(),
+                })
+                .collect();
+
+            APIResponse::ListMergeRecords { records }
+        }
+        APIRequest::GetMergePoint { id } => {
+            let merge_point = arhiv.baza.open()?.find_merge_point(&id)?;
+
+            APIResponse::GetMergePoint {
+                id: merge_point.id,
+                merge_rev: merge_point.merge_rev.to_safe_string(),
+                merged_at: merge_point.merged_at,
+                base_rev: merge_point.base_rev.map(|rev| rev.to_safe_string()),
+                branches: merge_point
+                    .branches
+                    .into_iter()
+                    .map(|branch| ConflictBranchInfo {
+                        rev: branch.rev.to_safe_string(),
+                        updated_at: branch.updated_at,
+                        is_erased: branch.is_erased,
+                    })
+                    .collect(),
+            }
+        }
+        APIRequest::UndoMerge {
+            id,
+            lock_key,
+            branch,
+            strategy,
+        } => {
+            let mut baza = arhiv.baza.open_mut()?;
+
+            match (branch, strategy) {
+                (Some(branch), None) => {
+                    baza.revert_document_to_merge_branch(&id, branch, &lock_key)?;
+                }
+                (None, Some(strategy)) => {
+                    baza.remerge_document(&id, strategy, &lock_key)?;
+                }
+                _ => bail!("Either branch or strategy must be provided"),
+            }
+
+            baza.save_changes()?;
+
+            APIResponse::UndoMerge {}
+        }
+        APIRequest::ListActivity {
+            id,
+            document_types,
+            since,
+            until,
+            page_size,
+            page,
+        } => {
+            let filter = ActivityFilter {
+                id,
+                document_types: document_types.into_iter().map(DocumentType::new).collect(),
+                since,
+                until,
+                page_size,
+                page,
+            };
+
+            let baza = arhiv.baza.open()?;
+            let page = baza.list_activity(&filter)?;
+
+            let document_expert = baza.get_document_expert();
+            let events = page
+                .events
+                .into_iter()
+                .map(|event| {
+                    Ok(ActivityEventInfo {
+                        title: document_expert.get_title(&event.document_type, &event.data)?,
+                        id: event.id,
+                        rev: event.rev.to_safe_string(),
+                        updated_at: event.updated_at,
+                        kind: event.kind.to_string(),
+                        document_type: event.document_type.to_string(),
+                        changes: event
+                            .changes
+                            .into_iter()
+                            .map(|change| FieldChangeInfo {
+                                field: change.field,
+                                change: change.kind.to_string(),
+                            })
+                            .collect(),
+                    })
+                })
+                .collect::<Result<_>>()?;
+
+            APIResponse::ListActivity {
+                events,
+                has_more: page.has_more,
+                total: page.total,
+            }
+        }
+        APIRequest::FindGarbage {} => {
+            let baza = arhiv.baza.open()?;
+            let report = baza.find_garbage()?;
+
+            let reclaimable_size =
+                report.get_orphaned_blobs_size() + report.get_unreferenced_assets_size();
+
+            APIResponse::FindGarbage {
+                orphaned_blobs: report
+                    .orphaned_blobs
+                    .into_iter()
+                    .map(|blob| OrphanedBlobInfo {
+                        id: blob.id,
+                        location: blob.location.to_string(),
+                        size: blob.size,
+                        erased_asset: blob.erased_asset,
+                    })
+                    .collect(),
+                unreferenced_assets: report
+                    .unreferenced_assets
+                    .into_iter()
+                    .map(|asset| UnreferencedAssetInfo {
+                        id: asset.id,
+                        filename: asset.filename,
+                        size: asset.size,
+                    })
+                    .collect(),
+                reclaimable_size,
+            }
+        }
+        APIRequest::CollectGarbage {
+            erase_unreferenced_assets,
+        } => {
+            let mut baza = arhiv.baza.open_mut()?;
+            let report = baza.collect_garbage(erase_unreferenced_assets)?;
+            baza.save_changes()?;
+
+            APIResponse::CollectGarbage {
+                removed_blobs: report.orphaned_blobs.len(),
+                removed_size: report.get_orphaned_blobs_size(),
+                erased_assets: report
+                    .unreferenced_assets
+                    .into_iter()
+                    .map(|asset| asset.id)
+                    .collect(),
+            }
+        }
     };
 
     Ok(response)
@@ -502,6 +744,52 @@ fn sort_entries(entries: &mut [DirEntry]) {
     });
 }
 
+fn list_page_into_results(
+    page: &ListPage,
+    schema: &DataSchema,
+) -> Result<Vec<ListDocumentsResult>> {
+    let document_expert = DocumentExpert::new(schema);
+
+    page.items
+        .iter()
+        .map(|head| {
+            let doc = head.get_single_document();
+
+            let asset_id = document_expert.get_cover_asset_id(doc)?;
+
+            Ok(ListDocumentsResult {
+                title: document_expert.get_title(&doc.document_type, &doc.data)?,
+                cover: asset_id,
+                id: doc.id.clone(),
+                document_type: doc.document_type.clone().into(),
+                updated_at: doc.updated_at,
+                data: doc.data.clone(),
+                has_conflict: head.is_conflict(),
+                snippets: page
+                    .snippets
+                    .get(&doc.id)
+                    .map(|snippets| snippets.iter().map(into_document_snippet).collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect::<Result<_>>()
+}
+
+fn into_document_snippet(snippet: &SearchSnippet) -> DocumentSnippet {
+    DocumentSnippet {
+        field: snippet.field.clone(),
+        fragments: snippet
+            .snippet
+            .fragments()
+            .into_iter()
+            .map(|(text, highlight)| SnippetFragment {
+                text: text.to_string(),
+                highlight,
+            })
+            .collect(),
+    }
+}
+
 fn documents_into_results(
     documents: Vec<&Document>,
     schema: &DataSchema,
@@ -109,13 +109,25 @@ pub fn build_ui_router(
 async fn config_handler(ctx: State<ServerContext>) -> Result<impl IntoResponse, ServerError> {
     let arhiv = &ctx.arhiv;
 
+    let arhiv_missing = !arhiv.baza.storage_exists()?;
+
+    // custom document types are stored in the baza, so they are known only once it is unlocked
+    let baza = if !arhiv_missing && arhiv.baza.is_unlocked() {
+        Some(arhiv.baza.open()?)
+    } else {
+        None
+    };
+    let schema = baza
+        .as_ref()
+        .map_or_else(|| arhiv.baza.get_schema(), |baza| baza.get_schema());
+
     let config = serde_json::to_string_pretty(&ArhivUIConfig {
         storage_dir: arhiv.baza.get_storage_dir(),
         base_path: UI_BASE_PATH,
-        schema: arhiv.baza.get_schema(),
+        schema,
         use_local_storage: true,
         min_password_length: BazaManager::MIN_PASSWORD_LENGTH,
-        arhiv_missing: !arhiv.baza.storage_exists()?,
+        arhiv_missing,
         arhiv_key_missing: !arhiv.baza.key_exists()?,
         arhiv_locked: arhiv.baza.is_locked(),
         dev_mode: DEV_MODE,
@@ -0,0 +1,111 @@
+import { useState } from 'react';
+import { DocumentDTO, SavedSearchData } from '../../dto';
+import { Callback, cx } from '../../utils/index';
+import { useSuspenseQuery } from '../../utils/suspense';
+import { formatDocumentType } from '../../utils/schema';
+import { CardContainer } from '../CardContainer';
+import { useCardContext } from '../controller';
+import { useDocumentChange } from '../documentChangeUtils';
+import { DropdownMenu, DropdownOptions } from '../../components/DropdownMenu';
+import { ProgressLocker } from '../../components/ProgressLocker';
+import { IconButton } from '../../components/Button';
+import { Pagination } from '../../components/Catalog/Pagination';
+import { DocumentTitle } from './DocumentTitle';
+import { CONFLICT_INDICATOR, STAGED_INDICATOR } from './Indicators';
+
+type SavedSearchCardProps = {
+  document: DocumentDTO;
+  isUpdating: boolean;
+  onForceEditor: Callback;
+  options: DropdownOptions;
+};
+
+export function SavedSearchCard({
+  document,
+  isUpdating,
+  onForceEditor,
+  options,
+}: SavedSearchCardProps) {
+  const { card, controller } = useCardContext();
+
+  const savedSearchData = document.data as SavedSearchData;
+
+  const [page, setPage] = useState(0);
+
+  const {
+    value: result,
+    isUpdating: isSearching,
+    triggerRefresh,
+  } = useSuspenseQuery({
+    typeName: 'RunSavedSearch',
+    id: document.id,
+    page,
+  });
+
+  useDocumentChange(
+    result.documents.map((item) => item.id),
+    () => {
+      triggerRefresh(true);
+    },
+  );
+
+  return (
+    <CardContainer
+      leftToolbar={
+        <>
+          <DropdownMenu icon="dots-horizontal" align="bottom-left" options={options} />
+          {document.isStaged && STAGED_INDICATOR}
+          {document.hasConflict && CONFLICT_INDICATOR}
+        </>
+      }
+      title={<DocumentTitle documentType={document.documentType} title={document.title} />}
+      showTitleOnScroll
+      rightToolbar={
+        <IconButton
+          icon="pencil-square"
+          size="lg"
+          title="Open editor"
+          onClick={onForceEditor}
+          className="relative"
+        />
+      }
+    >
+      {(isUpdating || isSearching) && <ProgressLocker />}
+
+      <h1 className="heading-1 text-2xl mt-4 text-center text-sky-900 dark:text-sky-300 tracking-wider">
+        {savedSearchData.name}
+      </h1>
+      <div className="font-medium text-xs text-slate-400 uppercase tracking-wider text-center mb-8">
+        SAVED SEARCH
+      </div>
+
+      <div className="font-mono text-sm text-gray-500 mb-4 break-anywhere">
+        {savedSearchData.document_types && <div>type: {savedSearchData.document_types}</div>}
+        {savedSearchData.query && <div>query: {savedSearchData.query}</div>}
+        {savedSearchData.sort && <div>sort: {savedSearchData.sort}</div>}
+      </div>
+
+      <div className="section-heading mb-4">{result.total} documents</div>
+
+      <ul className="divide-y border-gray-200">
+        {result.documents.map((item) => (
+          <li
+            key={item.id}
+            className={cx('cursor-pointer pr-2 py-2 hover:var-item-active-bg-color', {
+              'bg-red-700/20': item.hasConflict,
+            })}
+            onClick={() => {
+              controller.pushDocument(card.id, item.id);
+            }}
+          >
+            <div className="section-heading">{formatDocumentType(item.documentType)}</div>
+            <div className="font-bold break-anywhere">{item.title}</div>
+          </li>
+        ))}
+        {result.documents.length === 0 && <li className="text-center mb-4">No results 😿</li>}
+      </ul>
+
+      <Pagination page={page} hasMore={result.hasMore} onClick={setPage} />
+    </CardContainer>
+  );
+}
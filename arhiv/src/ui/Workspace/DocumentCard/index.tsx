@@ -1,4 +1,4 @@
-import { isAsset, isErasedDocument, isProject } from '../../utils/schema';
+import { isAsset, isErasedDocument, isProject, isSavedSearch } from '../../utils/schema';
 import { useSuspenseQuery } from '../../utils/suspense';
 import { copyTextToClipbard } from '../../utils/index';
 import { TASK_DOCUMENT_TYPE } from '../../dto';
@@ -10,6 +10,7 @@ import { DocumentCard } from './DocumentCard';
 import { ErasedDocumentCard } from './ErasedDocumentCard';
 import { AssetCard } from './AssetCard';
 import { ProjectCard } from './ProjectCard';
+import { SavedSearchCard } from './SavedSearchCard';
 
 type DocumentCard = Extract<Card, { variant: 'document' }>;
 
@@ -85,5 +86,22 @@ export function DocumentCardContainer() {
     );
   }
 
+  if (isSavedSearch(document.documentType)) {
+    return (
+      <SavedSearchCard
+        document={document}
+        isUpdating={isUpdating}
+        onForceEditor={() => {
+          controller.pushStack(card.id, {
+            variant: 'document',
+            documentId: card.documentId,
+            forceEditor: true,
+          });
+        }}
+        options={documentActions}
+      />
+    );
+  }
+
   return <DocumentCard document={document} isUpdating={isUpdating} options={documentActions} />;
 }
@@ -13,6 +13,7 @@ import { useDocumentChange } from '../../Workspace/documentChangeUtils';
 import { Pagination } from './Pagination';
 import { CatalogFilter, type Filter } from './CatalogFilter';
 import { CatalogItemBadges } from './CatalogItemBadges';
+import { CatalogItemSnippets } from './CatalogItemSnippets';
 
 export type { Filter };
 
@@ -120,6 +121,8 @@ export function Catalog({
           <div className="empty:hidden mt-1 flex gap-2">
             <CatalogItemBadges documentType={item.documentType} data={item.data} />
           </div>
+
+          <CatalogItemSnippets snippets={item.snippets} />
         </div>
       </div>
     </div>
@@ -0,0 +1,39 @@
+import { DocumentSnippet } from '../../dto';
+
+// the title is already shown in the catalog item
+const TITLE_FIELD = '@title';
+
+const MAX_SNIPPETS = 2;
+
+type Props = {
+  snippets: DocumentSnippet[];
+};
+
+export function CatalogItemSnippets({ snippets }: Props) {
+  const visibleSnippets = snippets
+    .filter((snippet) => snippet.field !== TITLE_FIELD)
+    .slice(0, MAX_SNIPPETS);
+
+  if (visibleSnippets.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className="mt-1 text-sm text-gray-500 break-anywhere">
+      {visibleSnippets.map((snippet) => (
+        <div key={snippet.field}>
+          <span className="font-mono text-xs text-gray-400 mr-2">{snippet.field}</span>
+          {snippet.fragments.map((fragment, index) =>
+            fragment.highlight ? (
+              <mark key={index} className="bg-yellow-300/40 text-inherit">
+                {fragment.text}
+              </mark>
+            ) : (
+              <span key={index}>{fragment.text}</span>
+            ),
+          )}
+        </div>
+      ))}
+    </div>
+  );
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use baza::{
+    AsOf, MergeStrategy, SortKey,
     entities::{DocumentData, DocumentLockKey, Id},
     schema::DataSchema,
 };
@@ -16,17 +17,32 @@ pub enum APIRequest {
     ListDocuments {
         document_types: Vec<String>,
         query: String,
-        page: u8,
+        sort: Option<Vec<SortKey>>,
+        page_size: Option<usize>,
+        page: usize,
+        cursor: Option<String>,
         only_conflicts: bool,
+        as_of: Option<AsOf>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RunSavedSearch {
+        id: Id,
+        page_size: Option<usize>,
+        page: usize,
+        cursor: Option<String>,
+        as_of: Option<AsOf>,
     },
     #[serde(rename_all = "camelCase")]
     GetDocuments {
         ids: Vec<Id>,
         ignore_missing: Option<bool>,
+        as_of: Option<AsOf>,
     },
     GetStatus {},
+    #[serde(rename_all = "camelCase")]
     GetDocument {
         id: Id,
+        as_of: Option<AsOf>,
     },
     ParseMarkup {
         markup: String,
@@ -91,6 +107,42 @@ pub enum APIRequest {
         export_password: SecretString,
     },
     CountConflicts {},
+    GetDocumentConflict {
+        id: Id,
+    },
+    #[serde(rename_all = "camelCase")]
+    ResolveDocumentConflict {
+        id: Id,
+        lock_key: Option<DocumentLockKey>,
+        fields: Vec<ConflictFieldResolution>,
+    },
+    ListMergeRecords {
+        id: Option<Id>,
+    },
+    GetMergePoint {
+        id: Id,
+    },
+    #[serde(rename_all = "camelCase")]
+    UndoMerge {
+        id: Id,
+        lock_key: Option<DocumentLockKey>,
+        branch: Option<usize>,
+        strategy: Option<MergeStrategy>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ListActivity {
+        id: Option<Id>,
+        document_types: Vec<String>,
+        since: Option<Timestamp>,
+        until: Option<Timestamp>,
+        page_size: Option<usize>,
+        page: usize,
+    },
+    FindGarbage {},
+    #[serde(rename_all = "camelCase")]
+    CollectGarbage {
+        erase_unreferenced_assets: bool,
+    },
 }
 
 #[derive(Serialize)]
@@ -101,6 +153,14 @@ pub enum APIResponse {
         documents: Vec<ListDocumentsResult>,
         has_more: bool,
         total: usize,
+        next_cursor: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RunSavedSearch {
+        documents: Vec<ListDocumentsResult>,
+        has_more: bool,
+        total: usize,
+        next_cursor: Option<String>,
     },
     GetDocuments {
         documents: Vec<GetDocumentsResult>,
@@ -167,6 +227,47 @@ pub enum APIResponse {
     CountConflicts {
         conflicts_count: usize,
     },
+    #[serde(rename_all = "camelCase")]
+    GetDocumentConflict {
+        id: Id,
+        document_type: String,
+        base_rev: Option<String>,
+        branches: Vec<ConflictBranchInfo>,
+        fields: Vec<ConflictFieldInfo>,
+    },
+    ResolveDocumentConflict {
+        errors: Option<SaveDocumentErrors>,
+    },
+    ListMergeRecords {
+        records: Vec<MergeRecordInfo>,
+    },
+    #[serde(rename_all = "camelCase")]
+    GetMergePoint {
+        id: Id,
+        merge_rev: String,
+        merged_at: Timestamp,
+        base_rev: Option<String>,
+        branches: Vec<ConflictBranchInfo>,
+    },
+    UndoMerge {},
+    #[serde(rename_all = "camelCase")]
+    ListActivity {
+        events: Vec<ActivityEventInfo>,
+        has_more: bool,
+        total: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    FindGarbage {
+        orphaned_blobs: Vec<OrphanedBlobInfo>,
+        unreferenced_assets: Vec<UnreferencedAssetInfo>,
+        reclaimable_size: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    CollectGarbage {
+        removed_blobs: usize,
+        removed_size: u64,
+        erased_assets: Vec<Id>,
+    },
 }
 
 #[derive(Serialize)]
@@ -186,6 +287,22 @@ pub struct ListDocumentsResult {
     pub data: DocumentData,
     pub cover: Option<Id>,
     pub has_conflict: bool,
+    /// Excerpts of the fields that matched the search query.
+    pub snippets: Vec<DocumentSnippet>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSnippet {
+    pub field: String,
+    pub fragments: Vec<SnippetFragment>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetFragment {
+    pub text: String,
+    pub highlight: bool,
 }
 
 #[derive(Serialize)]
@@ -205,6 +322,92 @@ pub struct SaveDocumentErrors {
     pub field_errors: HashMap<String, Vec<String>>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictBranchInfo {
+    pub rev: String,
+    pub updated_at: Timestamp,
+    pub is_erased: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictFieldInfo {
+    pub field: String,
+    pub base: Option<Value>,
+    pub branches: Vec<Option<Value>>,
+    pub resolution: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ConflictFieldResolution {
+    pub field: String,
+    pub choice: ConflictFieldChoice,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields, tag = "typeName")]
+pub enum ConflictFieldChoice {
+    Base {},
+    Branch { index: usize },
+    Value { value: Value },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeRecordInfo {
+    pub id: Id,
+    pub document_type: String,
+    pub merged_at: Timestamp,
+    pub base_rev: Option<String>,
+    pub revs: Vec<String>,
+    pub decisions: Vec<MergeDecisionInfo>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeDecisionInfo {
+    pub field: Option<String>,
+    pub decision: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEventInfo {
+    pub id: Id,
+    pub rev: String,
+    pub updated_at: Timestamp,
+    pub kind: String,
+    pub document_type: String,
+    pub title: String,
+    pub changes: Vec<FieldChangeInfo>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChangeInfo {
+    pub field: String,
+    pub change: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedBlobInfo {
+    pub id: Id,
+    pub location: String,
+    pub size: u64,
+    pub erased_asset: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreferencedAssetInfo {
+    pub id: Id,
+    pub filename: String,
+    pub size: u64,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DocumentBackref {
@@ -1,4 +1,4 @@
-import { JSONObj, NominalType, Obj } from './utils/index';
+import { JSONObj, JSONValue, NominalType, Obj } from './utils/index';
 import { DataSchema } from './utils/schema';
 
 export type APIRequest =
@@ -6,13 +6,27 @@ export type APIRequest =
       typeName: 'ListDocuments';
       documentTypes: DocumentType[];
       query: string;
+      sort?: string[];
+      pageSize?: number;
       page: number;
+      cursor?: string;
       onlyConflicts: boolean;
+      // ISO8601 time, YYYY-MM-DD date or revision like a:1-b:2
+      asOf?: string;
+    }
+  | {
+      typeName: 'RunSavedSearch';
+      id: DocumentId;
+      pageSize?: number;
+      page: number;
+      cursor?: string;
+      asOf?: string;
     }
   | {
       typeName: 'GetDocuments';
       ids: DocumentId[];
       ignoreMissing?: boolean;
+      asOf?: string;
     }
   | {
       typeName: 'GetStatus';
@@ -20,6 +34,7 @@ export type APIRequest =
   | {
       typeName: 'GetDocument';
       id: DocumentId;
+      asOf?: string;
     }
   | {
       typeName: 'ParseMarkup';
@@ -97,6 +112,47 @@ export type APIRequest =
     }
   | {
       typeName: 'CountConflicts';
+    }
+  | {
+      typeName: 'GetDocumentConflict';
+      id: DocumentId;
+    }
+  | {
+      typeName: 'ResolveDocumentConflict';
+      id: DocumentId;
+      lockKey?: DocumentLockKey;
+      fields: ConflictFieldResolution[];
+    }
+  | {
+      typeName: 'ListMergeRecords';
+      id?: DocumentId;
+    }
+  | {
+      typeName: 'GetMergePoint';
+      id: DocumentId;
+    }
+  | {
+      typeName: 'UndoMerge';
+      id: DocumentId;
+      lockKey?: DocumentLockKey;
+      branch?: number;
+      strategy?: MergeStrategy;
+    }
+  | {
+      typeName: 'ListActivity';
+      id?: DocumentId;
+      documentTypes: DocumentType[];
+      since?: string;
+      until?: string;
+      pageSize?: number;
+      page: number;
+    }
+  | {
+      typeName: 'FindGarbage';
+    }
+  | {
+      typeName: 'CollectGarbage';
+      eraseUnreferencedAssets: boolean;
     };
 
 export type APIResponse =
@@ -105,6 +161,14 @@ export type APIResponse =
       documents: ListDocumentsResult[];
       hasMore: boolean;
       total: number;
+      nextCursor?: string;
+    }
+  | {
+      typeName: 'RunSavedSearch';
+      documents: ListDocumentsResult[];
+      hasMore: boolean;
+      total: number;
+      nextCursor?: string;
     }
   | {
       typeName: 'GetDocuments';
@@ -189,6 +253,51 @@ export type APIResponse =
   | {
       typeName: 'CountConflicts';
       conflictsCount: number;
+    }
+  | {
+      typeName: 'GetDocumentConflict';
+      id: DocumentId;
+      documentType: DocumentType;
+      baseRev?: string;
+      branches: ConflictBranchInfo[];
+      fields: ConflictFieldInfo[];
+    }
+  | {
+      typeName: 'ResolveDocumentConflict';
+      errors?: SaveDocumentErrors;
+    }
+  | {
+      typeName: 'ListMergeRecords';
+      records: MergeRecordInfo[];
+    }
+  | {
+      typeName: 'GetMergePoint';
+      id: DocumentId;
+      mergeRev: string;
+      mergedAt: string;
+      baseRev?: string;
+      branches: ConflictBranchInfo[];
+    }
+  | {
+      typeName: 'UndoMerge';
+    }
+  | {
+      typeName: 'ListActivity';
+      events: ActivityEventInfo[];
+      hasMore: boolean;
+      total: number;
+    }
+  | {
+      typeName: 'FindGarbage';
+      orphanedBlobs: OrphanedBlobInfo[];
+      unreferencedAssets: UnreferencedAssetInfo[];
+      reclaimableSize: number;
+    }
+  | {
+      typeName: 'CollectGarbage';
+      removedBlobs: number;
+      removedSize: number;
+      erasedAssets: DocumentId[];
     };
 
 export type DocumentId = NominalType<string, 'DocumentId'>;
@@ -199,6 +308,7 @@ export type DocumentDTO = Omit<Extract<APIResponse, { typeName: 'GetDocument' }>
 
 export const ERASED_DOCUMENT_TYPE = '' as DocumentType;
 export const ASSET_DOCUMENT_TYPE = 'asset' as DocumentType;
+export const SAVED_SEARCH_DOCUMENT_TYPE = 'saved search' as DocumentType;
 export const PROJECT_DOCUMENT_TYPE = 'project' as DocumentType;
 export const NOTE_DOCUMENT_TYPE = 'note' as DocumentType;
 export const TASK_DOCUMENT_TYPE = 'task' as DocumentType;
@@ -225,6 +335,17 @@ export type ListDocumentsResult<D = DocumentData> = {
   data: D;
   cover?: DocumentId;
   hasConflict: boolean;
+  snippets: DocumentSnippet[];
+};
+
+export type DocumentSnippet = {
+  field: string;
+  fragments: SnippetFragment[];
+};
+
+export type SnippetFragment = {
+  text: string;
+  highlight: boolean;
 };
 
 export type DocumentData = JSONObj;
@@ -242,6 +363,73 @@ export type SaveDocumentErrors = {
   fieldErrors: DocumentFieldErrors;
 };
 
+export type ConflictBranchInfo = {
+  rev: string;
+  updatedAt: string;
+  isErased: boolean;
+};
+
+export type ConflictFieldInfo = {
+  field: string;
+  base?: JSONValue;
+  branches: Array<JSONValue | undefined>;
+  resolution?: JSONValue;
+};
+
+export type ConflictFieldChoice =
+  | { typeName: 'Base' }
+  | { typeName: 'Branch'; index: number }
+  | { typeName: 'Value'; value: JSONValue };
+
+export type ConflictFieldResolution = {
+  field: string;
+  choice: ConflictFieldChoice;
+};
+
+export type MergeStrategy = 'auto' | 'prefer-older' | 'prefer-newer';
+
+export type MergeRecordInfo = {
+  id: DocumentId;
+  documentType: DocumentType;
+  mergedAt: string;
+  baseRev?: string;
+  revs: string[];
+  decisions: MergeDecisionInfo[];
+};
+
+export type MergeDecisionInfo = {
+  field?: string;
+  decision: string;
+};
+
+export type ActivityEventInfo = {
+  id: DocumentId;
+  rev: string;
+  updatedAt: string;
+  kind: 'created' | 'modified' | 'erased';
+  documentType: DocumentType;
+  title: string;
+  changes: FieldChangeInfo[];
+};
+
+export type FieldChangeInfo = {
+  field: string;
+  change: 'added' | 'changed' | 'removed';
+};
+
+export type OrphanedBlobInfo = {
+  id: DocumentId;
+  location: 'storage' | 'state';
+  size: number;
+  erasedAsset: boolean;
+};
+
+export type UnreferencedAssetInfo = {
+  id: DocumentId;
+  filename: string;
+  size: number;
+};
+
 export type DirEntry =
   | {
       typeName: 'Dir';
@@ -420,6 +608,13 @@ export function throwBadMarkupElement(value: MarkupElement) {
   throw new Error(`Unknown MarkupElement: ${value.typeName}`);
 }
 
+export type SavedSearchData = {
+  name: string;
+  query?: string;
+  document_types?: string;
+  sort?: string;
+};
+
 export type ProjectData = {
   name: string;
   description: string;
@@ -3,11 +3,13 @@ import {
   DocumentType,
   ERASED_DOCUMENT_TYPE,
   PROJECT_DOCUMENT_TYPE,
+  SAVED_SEARCH_DOCUMENT_TYPE,
 } from '../dto';
 import { EmptyObj } from './index';
 
 export type DataSchema = {
   modules: DataDescription[];
+  custom_modules: DataDescription[];
 };
 
 export type DataDescription = {
@@ -20,6 +22,7 @@ export type DataDescriptionField = {
   field_type: FieldType;
   mandatory: boolean;
   readonly: boolean;
+  ordered?: boolean;
 };
 
 export type FieldType =
@@ -35,15 +38,19 @@ export type FieldType =
   | { People: EmptyObj }
   | { Countries: EmptyObj };
 
+function getModules(): DataDescription[] {
+  return [...window.CONFIG.schema.modules, ...window.CONFIG.schema.custom_modules];
+}
+
 export function getDocumentTypes(collections: boolean): DocumentType[] {
-  return window.CONFIG.schema.modules
+  return getModules()
     .filter((module) => isModuleCollection(module) === collections)
     .map((module) => module.document_type)
     .sort();
 }
 
 export function getDataDescription(documentType: DocumentType): DataDescription {
-  const dataDescription = window.CONFIG.schema.modules.find(
+  const dataDescription = getModules().find(
     (module) => module.document_type === documentType,
   );
   if (!dataDescription) {
@@ -70,7 +77,7 @@ function isModuleCollectionForDocument(module: DataDescription, documentType: Do
 }
 
 export function getCollectionTypesForDocument(documentType: DocumentType) {
-  return window.CONFIG.schema.modules
+  return getModules()
     .filter((module) => isModuleCollectionForDocument(module, documentType))
     .map((module) => module.document_type);
 }
@@ -83,6 +90,10 @@ export function isAsset(documentType: DocumentType) {
   return documentType === ASSET_DOCUMENT_TYPE;
 }
 
+export function isSavedSearch(documentType: DocumentType) {
+  return documentType === SAVED_SEARCH_DOCUMENT_TYPE;
+}
+
 export function isProject(documentType: DocumentType) {
   return documentType === PROJECT_DOCUMENT_TYPE;
 }
@@ -32,6 +32,12 @@ impl Timestamp {
         Ok(Timestamp(ts))
     }
 
+    pub fn to_iso8601_string(&self) -> String {
+        self.0
+            .format(&format_description::well_known::Rfc3339)
+            .expect("timestamp must be formatted as ISO8601")
+    }
+
     pub fn format_time(&self, fmt: &str) -> Result<String> {
         let format = format_description::parse_borrowed::<3>(fmt)
             .context(anyhow!("Failed to parse format description {fmt}"))?;
@@ -191,6 +197,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn iso8601_string_round_trips() {
+        let ts = timestamp("2023-10-23T11:23:39.5+03:30");
+
+        assert_eq!(ts.to_iso8601_string(), "2023-10-23T11:23:39.5+03:30");
+        assert_eq!(timestamp(&ts.to_iso8601_string()), ts);
+    }
+
     #[test]
     fn default_date_time_format_matches_documented_layout() {
         let ts = timestamp("2023-10-23T11:23:39Z");
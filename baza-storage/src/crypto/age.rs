@@ -95,6 +95,8 @@ impl AgeKey {
     }
 }
 
+/// Age payload is a sequence of independently authenticated 64 KiB chunks, so seeking jumps
+/// straight to the chunk that contains the position and decrypts only that chunk.
 pub struct AgeReader<R: Read> {
     inner: StreamReader<R>,
 }
@@ -257,7 +259,7 @@ pub fn is_no_matching_keys_error(err: &anyhow::Error) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
+    use std::{cell::Cell, io::Cursor, rc::Rc};
 
     use baza_common::{generate_alpanumeric_string, read_all_as_string};
 
@@ -345,6 +347,59 @@ mod tests {
         assert_eq!(&decrypted, &data.as_bytes()[50..]);
     }
 
+    struct CountingReader<R> {
+        inner: R,
+        bytes_read: Rc<Cell<usize>>,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let bytes_read = self.inner.read(buf)?;
+            self.bytes_read.set(self.bytes_read.get() + bytes_read);
+
+            Ok(bytes_read)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_seek_decrypts_only_target_chunk() {
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        let data = generate_alpanumeric_string(16 * CHUNK_SIZE);
+        let key = AgeKey::generate_age_x25519_key();
+
+        let encrypted = {
+            let mut writer = AgeWriter::new(Vec::new(), key.clone()).unwrap();
+            writer.write_all(data.as_bytes()).unwrap();
+            writer.finish().unwrap()
+        };
+
+        let bytes_read = Rc::new(Cell::new(0));
+        let counting_reader = CountingReader {
+            inner: Cursor::new(encrypted),
+            bytes_read: bytes_read.clone(),
+        };
+        let mut reader = AgeReader::new(counting_reader, key).unwrap();
+
+        let pos = 11 * CHUNK_SIZE - 100;
+        reader.seek(SeekFrom::Start(pos as u64)).unwrap();
+
+        // read across the chunk boundary
+        let mut buf = vec![0; 200];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, &data.as_bytes()[pos..pos + 200]);
+
+        // header and two chunks with their authentication tags
+        let bytes_read = bytes_read.get();
+        assert!(bytes_read < 3 * CHUNK_SIZE, "read {bytes_read} bytes");
+    }
+
     #[test]
     fn test_authentication() {
         let data = generate_alpanumeric_string(100);
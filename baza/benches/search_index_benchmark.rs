@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use baza::{
+    SearchEngine,
+    entities::{Document, DocumentData, DocumentType},
+    schema::{DataDescription, DataSchema, Field, FieldType},
+};
+use baza_common::{TempFile, generate_alpanumeric_string};
+use baza_storage::crypto::age::AgeKey;
+
+const NOTE_TYPE: &str = "note";
+const ARCHIVE_SIZES: [usize; 3] = [1_000, 3_000, 10_000];
+
+fn new_schema() -> DataSchema {
+    DataSchema::new(
+        "bench",
+        vec![DataDescription {
+            document_type: NOTE_TYPE.into(),
+            title_format: "${title}".into(),
+            fields: vec![
+                Field {
+                    name: "title".into(),
+                    field_type: FieldType::String {},
+                    mandatory: true,
+                    readonly: false,
+                    ordered: None,
+                },
+                Field {
+                    name: "data".into(),
+                    field_type: FieldType::MarkupString {},
+                    mandatory: false,
+                    readonly: false,
+                    ordered: None,
+                },
+            ],
+        }],
+    )
+}
+
+fn gen_words(count: usize) -> String {
+    (0..count)
+        .map(|_| generate_alpanumeric_string(6))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn new_note() -> Document {
+    let mut data = DocumentData::new();
+    data.set("title", gen_words(4));
+    data.set("data", gen_words(20));
+
+    Document::new_with_data(DocumentType::new(NOTE_TYPE), data)
+}
+
+fn bench_search_index_save(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search_index_save");
+    group.sample_size(10);
+    group.sampling_mode(criterion::SamplingMode::Flat);
+
+    let key = AgeKey::generate_age_x25519_key();
+
+    for archive_size in ARCHIVE_SIZES {
+        let dir = TempFile::new_with_details("search_index_benchmark", "");
+        dir.mkdir().expect("must create dir");
+        let file = format!("{}/search_index.gz.age", dir.path);
+
+        let mut search = SearchEngine::new(new_schema());
+        for _ in 0..archive_size {
+            search
+                .index_document(&new_note(), &[], None)
+                .expect("must index document");
+        }
+        search.write(&file, key.clone()).expect("must write index");
+        search.finish_merge();
+
+        // Saves one edited note; re-indexing the note isn't measured.
+        // Every MAX_SEARCH_INDEX_SEGMENTS-th save also serializes the index for the background
+        // merge, so that is included in the average.
+        group.bench_with_input(
+            BenchmarkId::new("save_one_edit", archive_size),
+            &archive_size,
+            |b, _| {
+                b.iter_custom(|iters| {
+                    let mut total = Duration::ZERO;
+
+                    for _ in 0..iters {
+                        search
+                            .index_document(&new_note(), &[], None)
+                            .expect("must index document");
+
+                        let start_time = Instant::now();
+                        search.write(&file, key.clone()).expect("must write index");
+                        total += start_time.elapsed();
+                    }
+
+                    total
+                })
+            },
+        );
+        search.finish_merge();
+
+        // Writes the whole index, like every save did before delta segments.
+        group.bench_with_input(
+            BenchmarkId::new("full_rewrite", archive_size),
+            &archive_size,
+            |b, _| {
+                b.iter(|| {
+                    search.merge(&file, key.clone()).expect("must merge index");
+                    search.finish_merge();
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_search_index_save);
+criterion_main!(benches);
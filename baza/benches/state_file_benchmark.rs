@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use baza::{
+    BazaInfo, BazaPaths, BazaState,
+    entities::{Document, DocumentData, DocumentType, InstanceId},
+    schema::{DataDescription, DataSchema, Field, FieldType},
+};
+use baza_common::{TempFile, generate_alpanumeric_string};
+use baza_storage::crypto::age::AgeKey;
+
+const NOTE_TYPE: &str = "note";
+const ARCHIVE_SIZES: [usize; 3] = [1_000, 3_000, 10_000];
+
+fn new_schema() -> DataSchema {
+    DataSchema::new(
+        "bench",
+        vec![DataDescription {
+            document_type: NOTE_TYPE.into(),
+            title_format: "${title}".into(),
+            fields: vec![
+                Field {
+                    name: "title".into(),
+                    field_type: FieldType::String {},
+                    mandatory: true,
+                    readonly: false,
+                    ordered: None,
+                },
+                Field {
+                    name: "data".into(),
+                    field_type: FieldType::MarkupString {},
+                    mandatory: false,
+                    readonly: false,
+                    ordered: None,
+                },
+            ],
+        }],
+    )
+}
+
+fn gen_words(count: usize) -> String {
+    (0..count)
+        .map(|_| generate_alpanumeric_string(6))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn new_note() -> Document {
+    let mut data = DocumentData::new();
+    data.set("title", gen_words(4));
+    data.set("data", gen_words(20));
+
+    Document::new_with_data(DocumentType::new(NOTE_TYPE), data)
+}
+
+fn bench_state_save(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state_save");
+    group.sample_size(10);
+    group.sampling_mode(criterion::SamplingMode::Flat);
+
+    let key = AgeKey::generate_age_x25519_key();
+
+    for archive_size in ARCHIVE_SIZES {
+        let dir = TempFile::new_with_details("state_file_benchmark", "");
+        dir.mkdir().expect("must create dir");
+        let paths = BazaPaths::new(
+            format!("{}/storage", dir.path),
+            format!("{}/state", dir.path),
+            format!("{}/downloads", dir.path),
+        );
+        paths.ensure_dirs_exist().expect("must create dirs");
+
+        let schema = new_schema();
+        let info = BazaInfo {
+            storage_version: 1,
+            data_version: schema.get_latest_data_version(),
+        };
+        let mut state = BazaState::new(
+            InstanceId::from_string("bench").expect("must create instance id"),
+            info,
+            schema,
+        );
+        for _ in 0..archive_size {
+            state
+                .stage_document(new_note(), &None)
+                .expect("must stage document");
+        }
+        state.write(&paths, key.clone()).expect("must write state");
+
+        // Saves one edited note, together with its search index changes; staging the note
+        // isn't measured. Every 32nd save also writes the whole state file, and serializes the
+        // search index for the background merge, so that is included in the average.
+        group.bench_with_input(
+            BenchmarkId::new("save_one_edit", archive_size),
+            &archive_size,
+            |b, _| {
+                b.iter_custom(|iters| {
+                    let mut total = Duration::ZERO;
+
+                    for _ in 0..iters {
+                        state
+                            .stage_document(new_note(), &None)
+                            .expect("must stage document");
+
+                        let start_time = Instant::now();
+                        state.write(&paths, key.clone()).expect("must write state");
+                        total += start_time.elapsed();
+                    }
+
+                    total
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_state_save);
+criterion_main!(benches);
@@ -0,0 +1,173 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use tokio::{task::JoinHandle, time::interval};
+
+use baza_common::log;
+
+use crate::BazaManager;
+
+pub type AssetTextTask = JoinHandle<()>;
+
+/// Extracts texts of the assets that weren't processed yet, like the assets received from other
+/// instances, in the background. The baza is locked only to list the assets and to save the
+/// texts, not while the files are parsed.
+pub struct AssetTextService {
+    baza_manager: Arc<BazaManager>,
+    check_interval: Duration,
+}
+
+impl AssetTextService {
+    pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+    // Texts are saved in small batches, so that the progress isn't lost if the app is closed.
+    const BATCH_SIZE: usize = 8;
+
+    pub fn new(baza_manager: Arc<BazaManager>, check_interval: Duration) -> Self {
+        AssetTextService {
+            baza_manager,
+            check_interval,
+        }
+    }
+
+    pub fn start(self) -> AssetTextTask {
+        let task = tokio::spawn(async move {
+            let mut interval = interval(self.check_interval);
+
+            loop {
+                interval.tick().await;
+
+                let baza_manager = self.baza_manager.clone();
+                let result =
+                    tokio::task::spawn_blocking(move || index_pending_asset_texts(&baza_manager))
+                        .await;
+
+                match result {
+                    Ok(Ok(_count)) => {}
+                    Ok(Err(err)) => log::warn!("Failed to extract asset texts: {err:#}"),
+                    Err(err) => {
+                        log::warn!("Asset text extraction task failed: {err}");
+                        break;
+                    }
+                }
+            }
+
+            log::debug!("Asset text task ended");
+        });
+
+        log::info!(
+            "Started asset text service, check interval is {} seconds",
+            self.check_interval.as_secs()
+        );
+
+        task
+    }
+}
+
+/// Returns the number of processed assets.
+fn index_pending_asset_texts(baza_manager: &BazaManager) -> Result<usize> {
+    if !baza_manager.storage_exists()? || !baza_manager.is_unlocked() {
+        return Ok(0);
+    }
+
+    let mut count = 0;
+    loop {
+        let pending = baza_manager
+            .open()?
+            .list_pending_asset_texts(AssetTextService::BATCH_SIZE)?;
+        if pending.is_empty() {
+            break;
+        }
+
+        let texts = pending
+            .into_iter()
+            .map(|pending| {
+                let text = pending.extract_text();
+
+                (pending, text)
+            })
+            .collect::<Vec<_>>();
+
+        let mut baza = baza_manager.open_mut()?;
+        for (pending, text) in texts {
+            baza.set_pending_asset_text(pending, text)?;
+            count += 1;
+        }
+        baza.save_changes()?;
+    }
+
+    if count > 0 {
+        log::info!("Extracted texts of {count} assets");
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use anyhow::Result;
+    use tokio::time::sleep;
+
+    use baza_common::TempFile;
+
+    use crate::{BazaManager, Filter};
+
+    use super::{AssetTextService, index_pending_asset_texts};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_index_pending_asset_texts() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("asset_text_service", "");
+        temp_dir.mkdir()?;
+
+        let manager = Arc::new(BazaManager::new_for_tests(&temp_dir.path));
+
+        let source_file = temp_dir.new_child("notes.txt");
+        source_file.write_str("The Left Hand of Darkness")?;
+
+        // an asset received from another instance has no text yet
+        let asset_id = {
+            let mut baza = manager.open_mut()?;
+            let asset =
+                baza.create_asset_with_filename(&source_file.path, "notes.txt".to_string())?;
+            baza.commit()?;
+            asset.id
+        };
+        // dropping the cached baza waits for the background search index merge
+        manager.clear_cached_baza()?;
+        std::fs::remove_file(&manager.paths.state_asset_texts_file)?;
+        std::fs::remove_file(&manager.paths.state_search_index_file)?;
+
+        let filter = Filter {
+            query: "darkness".to_string(),
+            ..Default::default()
+        };
+        {
+            let baza = manager.open()?;
+            assert_eq!(baza.list_pending_asset_texts(10)?.len(), 1);
+            assert!(baza.list_documents(&filter)?.items.is_empty());
+        }
+
+        // the first check runs right away, in a blocking thread
+        let task = AssetTextService::new(manager.clone(), Duration::from_secs(60)).start();
+        for _ in 0..100 {
+            if manager.open()?.list_pending_asset_texts(10)?.is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        task.abort();
+
+        let baza = manager.open()?;
+        assert!(baza.list_pending_asset_texts(10)?.is_empty());
+        let page = baza.list_documents(&filter)?;
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].get_id(), &asset_id);
+        drop(baza);
+
+        assert_eq!(index_pending_asset_texts(&manager)?, 0);
+
+        Ok(())
+    }
+}
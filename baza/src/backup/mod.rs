@@ -15,7 +15,7 @@ pub use restore::{RestoreCheckReport, RestoreOptions};
 
 use crate::BazaManager;
 
-use self::manifest::{BackupManifestWrite, write_backup_manifest};
+use self::manifest::{BackupManifestWrite, MANIFEST_SUFFIX, write_backup_manifest};
 
 impl BazaManager {
     /// Creates an encrypted backup generation in an existing absolute directory.
@@ -40,7 +40,7 @@ impl BazaManager {
             bail!("Can't backup: there are staged changes");
         }
 
-        let data_dir = format!("{backup_dir}/data");
+        let data_dir = get_backup_data_dir(backup_dir);
         create_dir_if_not_exist(&data_dir)?;
 
         let now = Timestamp::now().format_time("[year]-[month padding:zero]-[day padding:zero]_[hour padding:zero]-[minute padding:zero]-[second padding:zero]").expect("must be valid format");
@@ -113,6 +113,32 @@ impl BazaManager {
     }
 }
 
+fn get_backup_data_dir(backup_dir: &str) -> String {
+    format!("{backup_dir}/data")
+}
+
+/// Returns the path of the BLOB copy in the backup dir, if the backup has a copy of the BLOB.
+pub(crate) fn find_backup_blob(backup_dir: &str, blob_file_name: &str) -> Result<Option<String>> {
+    let backup_blob_path = format!("{}/{blob_file_name}", get_backup_data_dir(backup_dir));
+
+    if file_exists(&backup_blob_path)? {
+        Ok(Some(backup_blob_path))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the manifest of the latest backup generation in the backup dir.
+pub(crate) fn find_latest_backup_manifest(backup_dir: &str) -> Result<Option<String>> {
+    // manifest file names start with the backup timestamp
+    let latest_manifest = list_files(backup_dir)?
+        .into_iter()
+        .filter(|file_path| file_path.ends_with(MANIFEST_SUFFIX))
+        .max();
+
+    Ok(latest_manifest)
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -1,15 +1,17 @@
-use std::{collections::HashMap, fs, io::copy, path::Path};
+use std::{collections::HashMap, fs, path::Path};
 
 use anyhow::{Context, Result, ensure};
 
 use baza_common::{
-    FsTransaction, LockFile, SecretString, TempFile, Timestamp, bytes_to_hex_string,
-    create_file_reader, dir_exists, list_files, path_to_string,
+    FsTransaction, LockFile, SecretString, TempFile, Timestamp, dir_exists, list_files,
+    path_to_string,
 };
 use baza_storage::crypto::age::AgeKey;
 
 use crate::{
     Baza, BazaManager, BazaPaths,
+    baza::read_blob_metadata,
+    baza_state::{BazaState, SearchEngine},
     entities::Id,
     schema::{ASSET_TYPE, Asset, AssetData},
 };
@@ -279,8 +281,16 @@ impl BazaManager {
 
     fn clear_runtime_state(&self, fs_tx: &mut FsTransaction) -> Result<()> {
         fs_tx.remove_file_if_exists(&self.paths.state_file)?;
+        for segment_file in BazaState::list_state_file_segments(&self.paths.state_file)? {
+            fs_tx.remove_file(segment_file)?;
+        }
         fs_tx.remove_file_if_exists(&self.paths.state_search_index_file)?;
+        for segment_file in SearchEngine::list_index_segments(&self.paths.state_search_index_file)?
+        {
+            fs_tx.remove_file(segment_file)?;
+        }
         fs_tx.remove_file_if_exists(&self.paths.state_document_locks_file)?;
+        fs_tx.remove_file_if_exists(&self.paths.state_asset_texts_file)?;
 
         if dir_exists(&self.paths.state_data_dir)? {
             for state_blob in list_files(&self.paths.state_data_dir)? {
@@ -320,24 +330,15 @@ fn verify_asset_blob_plaintext(
 ) -> Result<()> {
     let path = backup.blob_path(blob_artifact)?;
     let blob_key = AgeKey::from_age_x25519_key(asset_data.age_x25519_key.clone())?;
-    let mut reader = baza_storage::crypto::age::AgeReader::new(
-        create_file_reader(&path_to_string(&path))?,
-        blob_key,
-    )?;
-    let mut hashing_reader = baza_common::Sha256HashingReader::new(&mut reader);
-    let size = copy(&mut hashing_reader, &mut std::io::sink())?;
-    let hash = hashing_reader
-        .get_hash()
-        .context("Blob hash must be finalized after full read")?;
-    let hash = bytes_to_hex_string(hash);
+    let metadata = read_blob_metadata(&path_to_string(&path), blob_key)?;
 
     ensure!(
-        size == asset_data.size,
+        metadata.size == asset_data.size,
         "BLOB {} size mismatch",
         blob_artifact.id
     );
     ensure!(
-        hash == asset_data.content_sha256,
+        metadata.content_sha256 == asset_data.content_sha256,
         "BLOB {} plaintext SHA-256 mismatch",
         blob_artifact.id
     );
@@ -0,0 +1,359 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{Context, Result, ensure};
+use serde::Serialize;
+
+use baza_common::Timestamp;
+
+use crate::{
+    BazaStorage, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+    entities::{Document, DocumentData, DocumentType, Id, Revision},
+    merge::{FieldChange, diff_document_fields},
+};
+
+use super::Baza;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityKind {
+    Created,
+    Modified,
+    Erased,
+}
+
+impl fmt::Display for ActivityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            ActivityKind::Created => "created",
+            ActivityKind::Modified => "modified",
+            ActivityKind::Erased => "erased",
+        };
+
+        write!(f, "{value}")
+    }
+}
+
+/// A committed snapshot of a document, compared to the snapshot it was based on.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ActivityEvent {
+    pub id: Id,
+    pub rev: Revision,
+    pub updated_at: Timestamp,
+    pub kind: ActivityKind,
+    pub document_type: DocumentType,
+    pub data: DocumentData,
+    /// Fields changed since the previous snapshot, all fields for the created documents.
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Default)]
+pub struct ActivityFilter {
+    /// Show only events of one document.
+    pub id: Option<Id>,
+    /// Show only events of the documents which last non-erased snapshot has one of the types.
+    /// Erasure may remove all the other snapshots of the document, then its type is unknown and
+    /// the erasure is shown for any types.
+    pub document_types: Vec<DocumentType>,
+    /// Show events committed at or after the time.
+    pub since: Option<Timestamp>,
+    /// Show events committed at or before the time.
+    pub until: Option<Timestamp>,
+    /// Page size, [`DEFAULT_PAGE_SIZE`] if not set.
+    pub page_size: Option<usize>,
+    pub page: usize,
+}
+
+impl ActivityFilter {
+    fn should_show_snapshot(&self, document: &Document) -> bool {
+        if self.id.as_ref().is_some_and(|id| *id != document.id) {
+            return false;
+        }
+
+        if self.since.is_some_and(|since| document.updated_at < since) {
+            return false;
+        }
+
+        if self.until.is_some_and(|until| document.updated_at > until) {
+            return false;
+        }
+
+        true
+    }
+
+    fn should_show_document_type(&self, document_type: Option<&DocumentType>) -> bool {
+        let Some(document_type) = document_type else {
+            return true;
+        };
+
+        self.document_types.is_empty() || self.document_types.contains(document_type)
+    }
+}
+
+/// Returns the type of the latest non-erased snapshot, if it's still in the storage.
+fn get_last_document_type(snapshots: &[Document]) -> Option<&DocumentType> {
+    snapshots
+        .iter()
+        .rev()
+        .map(|document| &document.document_type)
+        .find(|document_type| !document_type.is_erased())
+}
+
+pub struct ActivityPage {
+    /// Events of the page, the most recent first.
+    pub events: Vec<ActivityEvent>,
+    pub has_more: bool,
+    pub total: usize,
+}
+
+impl Baza {
+    /// Lists the committed changes of the documents, the most recent first.
+    pub fn list_activity(&self, filter: &ActivityFilter) -> Result<ActivityPage> {
+        let page_size = filter.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+        ensure!(
+            (1..=MAX_PAGE_SIZE).contains(&page_size),
+            "Page size must be between 1 and {MAX_PAGE_SIZE}"
+        );
+
+        let storage = BazaStorage::read_file(&self.paths.storage_main_db_file, self.key.clone())?;
+
+        let mut documents: HashMap<Id, Vec<Document>> = HashMap::new();
+        for item in storage {
+            let (key, raw_document) = item?;
+
+            if filter.id.as_ref().is_some_and(|id| *id != key.id) {
+                continue;
+            }
+
+            let document: Document = serde_json::from_str(&raw_document)
+                .context(format!("Failed to parse document {}", key.serialize()))?;
+
+            documents.entry(key.id).or_default().push(document);
+        }
+
+        let mut documents = documents.into_values().collect::<Vec<_>>();
+        for snapshots in &mut documents {
+            snapshots.sort_by(|a, b| {
+                a.updated_at
+                    .cmp(&b.updated_at)
+                    .then_with(|| a.rev.history_cmp(&b.rev))
+            });
+        }
+
+        // snapshots are sorted and paginated before building the events, since diffing them
+        // is much slower than comparing their keys
+        let mut entries = Vec::new();
+        for snapshots in &documents {
+            if !filter.should_show_document_type(get_last_document_type(snapshots)) {
+                continue;
+            }
+
+            // each entry is the snapshot together with the preceding snapshots of the document
+            for (pos, document) in snapshots.iter().enumerate() {
+                if filter.should_show_snapshot(document) {
+                    entries.push(&snapshots[..=pos]);
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            let (a, b) = (&a[a.len() - 1], &b[b.len() - 1]);
+
+            b.updated_at
+                .cmp(&a.updated_at)
+                .then_with(|| b.rev.history_cmp(&a.rev))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        let total = entries.len();
+        let start = filter.page.saturating_mul(page_size);
+        let has_more = start.saturating_add(page_size) < total;
+        let events = entries
+            .into_iter()
+            .skip(start)
+            .take(page_size)
+            .map(|snapshots| {
+                let (document, previous_snapshots) = snapshots
+                    .split_last()
+                    .expect("entry must contain the snapshot");
+
+                // the latest of the snapshots that this one is based on
+                let previous = previous_snapshots
+                    .iter()
+                    .rev()
+                    .find(|previous| previous.rev.is_older_than(&document.rev));
+
+                new_activity_event(previous, document)
+            })
+            .collect();
+
+        Ok(ActivityPage {
+            events,
+            has_more,
+            total,
+        })
+    }
+}
+
+/// Older snapshots of the erased documents are removed from the storage, so erasure is the
+/// only known event of the document.
+fn new_activity_event(previous: Option<&Document>, document: &Document) -> ActivityEvent {
+    let (kind, changes) = match previous {
+        _ if document.is_erased() => (ActivityKind::Erased, Vec::new()),
+        Some(previous) => (
+            ActivityKind::Modified,
+            diff_document_fields(&previous.data, &document.data),
+        ),
+        None => (
+            ActivityKind::Created,
+            diff_document_fields(&DocumentData::new(), &document.data),
+        ),
+    };
+
+    ActivityEvent {
+        id: document.id.clone(),
+        rev: document.rev.clone(),
+        updated_at: document.updated_at,
+        kind,
+        document_type: document.document_type.clone(),
+        data: document.data.clone(),
+        changes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use baza_common::{TempFile, Timestamp};
+
+    use crate::{
+        BazaManager,
+        entities::{DocumentType, new_document, new_test_data},
+        merge::{FieldChange, FieldChangeKind},
+    };
+
+    use super::{ActivityFilter, ActivityKind, get_last_document_type};
+
+    #[test]
+    fn test_list_activity() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("list_activity", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut()?;
+
+        let doc = new_document(json!({ "test": "first" }));
+        let id = doc.id.clone();
+        baza.stage_document(doc, &None)?;
+        let erased = new_document(json!({ "test": "erased" }));
+        baza.stage_document(erased.clone(), &None)?;
+        baza.commit()?;
+        let created_at = baza.must_get_document(&id)?.updated_at;
+
+        let mut doc = baza.must_get_document(&id)?.clone();
+        doc.data = new_test_data(json!({ "test": "second" }));
+        baza.stage_document(doc, &None)?;
+        baza.erase_document(&erased.id)?;
+        baza.commit()?;
+
+        // staged changes aren't in the activity
+        let mut doc = baza.must_get_document(&id)?.clone();
+        doc.data = new_test_data(json!({ "test": "staged" }));
+        baza.stage_document(doc, &None)?;
+
+        let page = baza.list_activity(&ActivityFilter::default())?;
+        assert_eq!(page.total, 3);
+        assert!(!page.has_more);
+
+        let events = page
+            .events
+            .iter()
+            .filter(|event| event.id == id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events.iter().map(|event| event.kind).collect::<Vec<_>>(),
+            vec![ActivityKind::Modified, ActivityKind::Created]
+        );
+        assert_eq!(
+            events[0].changes,
+            vec![FieldChange {
+                field: "test".to_string(),
+                kind: FieldChangeKind::Changed,
+            }]
+        );
+        assert_eq!(
+            events[1].changes,
+            vec![FieldChange {
+                field: "test".to_string(),
+                kind: FieldChangeKind::Added,
+            }]
+        );
+
+        let page = baza.list_activity(&ActivityFilter {
+            id: Some(erased.id.clone()),
+            ..Default::default()
+        })?;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.events[0].kind, ActivityKind::Erased);
+        assert!(page.events[0].changes.is_empty());
+
+        let page = baza.list_activity(&ActivityFilter {
+            document_types: vec![DocumentType::new("test_type")],
+            ..Default::default()
+        })?;
+        assert_eq!(page.total, 3);
+
+        // the older snapshots of the erased document were removed, so its type is unknown
+        let page = baza.list_activity(&ActivityFilter {
+            document_types: vec![DocumentType::new("other_type")],
+            ..Default::default()
+        })?;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.events[0].id, erased.id);
+
+        let page = baza.list_activity(&ActivityFilter {
+            until: Some(created_at),
+            ..Default::default()
+        })?;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.events[0].kind, ActivityKind::Created);
+
+        let page = baza.list_activity(&ActivityFilter {
+            since: Some(Timestamp::now()),
+            ..Default::default()
+        })?;
+        assert_eq!(page.total, 0);
+
+        let page = baza.list_activity(&ActivityFilter {
+            page_size: Some(2),
+            page: 1,
+            ..Default::default()
+        })?;
+        assert_eq!(page.total, 3);
+        assert_eq!(page.events.len(), 1);
+        assert!(!page.has_more);
+
+        let page = baza.list_activity(&ActivityFilter {
+            page_size: Some(2),
+            ..Default::default()
+        })?;
+        assert_eq!(page.events.len(), 2);
+        assert!(page.has_more);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_last_document_type() {
+        let document = new_document(json!({ "test": "value" }));
+        let mut erased = document.clone();
+        erased.erase();
+
+        assert_eq!(
+            get_last_document_type(&[document.clone(), erased.clone()]),
+            Some(&document.document_type)
+        );
+        assert_eq!(get_last_document_type(&[erased]), None);
+    }
+}
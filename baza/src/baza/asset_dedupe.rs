@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use baza_common::{Timestamp, log};
+
+use crate::{
+    DocumentHead,
+    entities::{Document, Id},
+    schema::{ASSET_TYPE, Asset},
+};
+
+use super::Baza;
+
+/// An asset that was merged into another asset with the same content.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateAsset {
+    pub id: Id,
+    pub kept_id: Id,
+    pub size: u64,
+}
+
+/// Duplicate assets that asset deduplication merges, or would merge in a dry run.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct AssetsDedupeReport {
+    pub duplicates: Vec<DuplicateAsset>,
+    /// Documents that referenced the duplicates, and now reference the kept assets.
+    pub updated_documents: Vec<Id>,
+}
+
+impl AssetsDedupeReport {
+    /// Total size of the duplicate assets, in bytes.
+    #[must_use]
+    pub fn get_duplicates_size(&self) -> u64 {
+        self.duplicates.iter().map(|duplicate| duplicate.size).sum()
+    }
+}
+
+fn get_asset_time(head: &DocumentHead) -> Timestamp {
+    *head.get_created_at().unwrap_or(head.get_updated_at())
+}
+
+impl Baza {
+    /// Finds an asset with the same content, the oldest one if there are many.
+    pub(crate) fn find_asset_by_content(
+        &self,
+        content_sha256: &str,
+        size: u64,
+    ) -> Result<Option<Asset>> {
+        let oldest_head = self
+            .state
+            .find_assets_by_content(content_sha256)
+            .filter_map(|id| self.get_document(id))
+            .filter(|head| {
+                head.get_single_document()
+                    .data
+                    .get("size")
+                    .and_then(Value::as_u64)
+                    == Some(size)
+            })
+            .min_by(|a, b| {
+                (get_asset_time(a), a.get_id().as_ref())
+                    .cmp(&(get_asset_time(b), b.get_id().as_ref()))
+            });
+
+        oldest_head
+            .map(|head| head.get_single_document().clone().convert())
+            .transpose()
+    }
+
+    fn find_duplicate_assets(&self) -> Result<Vec<DuplicateAsset>> {
+        let mut assets_by_content: HashMap<(String, u64), Vec<(Timestamp, Asset)>> = HashMap::new();
+
+        for head in self.iter_documents() {
+            if !head.get_type().is(ASSET_TYPE) || head.is_conflict() {
+                continue;
+            }
+
+            let asset: Asset = head.get_single_document().clone().convert()?;
+
+            assets_by_content
+                .entry((asset.data.content_sha256.clone(), asset.data.size))
+                .or_default()
+                .push((get_asset_time(head), asset));
+        }
+
+        let mut duplicates = Vec::new();
+        for mut assets in assets_by_content.into_values() {
+            if assets.len() < 2 {
+                continue;
+            }
+
+            // keep the oldest asset
+            assets.sort_by(|(a_time, a), (b_time, b)| {
+                (a_time, a.id.as_ref()).cmp(&(b_time, b.id.as_ref()))
+            });
+
+            let mut assets = assets.into_iter();
+            let (_time, kept) = assets.next().expect("assets must not be empty");
+
+            duplicates.extend(assets.map(|(_time, asset)| DuplicateAsset {
+                id: asset.id,
+                kept_id: kept.id.clone(),
+                size: asset.data.size,
+            }));
+        }
+
+        duplicates.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+        Ok(duplicates)
+    }
+
+    /// Merges assets with the same content into the oldest one: documents that reference the
+    /// duplicates are updated to reference the kept asset, and the duplicates are erased.
+    /// Changes are staged; BLOBs of the duplicates are removed like BLOBs of other erased assets.
+    pub fn dedupe_assets(&mut self, dry_run: bool) -> Result<AssetsDedupeReport> {
+        let duplicates = self.find_duplicate_assets()?;
+
+        let mut updated_documents: HashMap<Id, Document> = HashMap::new();
+        for duplicate in &duplicates {
+            let referencing_ids = self
+                .find_document_backrefs(&duplicate.id)
+                .into_iter()
+                .chain(self.find_document_collections(&duplicate.id))
+                .collect::<HashSet<_>>();
+
+            for id in referencing_ids {
+                let mut document = match updated_documents.remove(&id) {
+                    Some(document) => document,
+                    None => self.must_get_document(&id)?.clone(),
+                };
+
+                self.get_document_expert().replace_refs(
+                    &document.document_type,
+                    &mut document.data,
+                    &duplicate.id,
+                    &duplicate.kept_id,
+                )?;
+
+                updated_documents.insert(id, document);
+            }
+        }
+
+        let mut updated_ids = updated_documents.keys().cloned().collect::<Vec<_>>();
+        updated_ids.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        let report = AssetsDedupeReport {
+            duplicates,
+            updated_documents: updated_ids,
+        };
+
+        if dry_run {
+            return Ok(report);
+        }
+
+        for document in updated_documents.into_values() {
+            self.stage_document(document, &None)?;
+        }
+
+        for duplicate in &report.duplicates {
+            self.erase_document(&duplicate.id)?;
+        }
+
+        if !report.duplicates.is_empty() {
+            log::info!(
+                "Merged {} duplicate assets, updated {} documents",
+                report.duplicates.len(),
+                report.updated_documents.len()
+            );
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use baza_common::TempFile;
+    use baza_storage::crypto::age::AgeKey;
+
+    use crate::{
+        BazaManager,
+        entities::{Document, DocumentType},
+        schema::{ASSET_TYPE, DataDescription, DataSchema, Field, FieldType},
+    };
+
+    fn new_schema() -> DataSchema {
+        DataSchema::new(
+            "test",
+            vec![DataDescription {
+                document_type: "note".into(),
+                title_format: "note".into(),
+                fields: vec![
+                    Field {
+                        name: "description".into(),
+                        field_type: FieldType::MarkupString {},
+                        mandatory: false,
+                        readonly: false,
+                        ordered: None,
+                    },
+                    Field {
+                        name: "cover".into(),
+                        field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
+                        mandatory: false,
+                        readonly: false,
+                        ordered: None,
+                    },
+                ],
+            }],
+        )
+    }
+
+    #[test]
+    fn test_create_asset_reuses_asset_with_same_content() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("create_asset_dedupe", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut()?;
+
+        let source_file = temp_dir.new_child("photo.jpg");
+        source_file.write_str("photo data")?;
+        let other_file = temp_dir.new_child("other.jpg");
+        other_file.write_str("other data")?;
+
+        let asset = baza.create_asset(&source_file.path)?;
+        baza.commit()?;
+
+        let same_asset = baza.create_asset_with_filename(&source_file.path, "copy.jpg".into())?;
+        assert_eq!(same_asset.id, asset.id);
+        assert_eq!(same_asset.data.filename, asset.data.filename);
+        assert!(!baza.has_staged_documents());
+        assert!(baza.paths.list_state_blobs()?.is_empty());
+
+        let other_asset = baza.create_asset(&other_file.path)?;
+        assert_ne!(other_asset.id, asset.id);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedupe_assets() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("dedupe_assets", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests_with_schema(&temp_dir.path, new_schema());
+        let mut baza = manager.open_mut()?;
+
+        let source_file = temp_dir.new_child("photo.jpg");
+        source_file.write_str("photo data")?;
+
+        let asset = baza.create_asset(&source_file.path)?;
+        baza.commit()?;
+
+        // duplicates created before the content was checked on import
+        let mut duplicate = Document::new(DocumentType::new(ASSET_TYPE))
+            .with_data(serde_json::to_value(&asset.data)?);
+        duplicate.data.set("filename", "copy.jpg");
+        let blob_key = AgeKey::from_age_x25519_key(asset.data.age_x25519_key.clone())?;
+        baza.add_blob(&duplicate.id, &source_file.path, blob_key)?;
+        baza.stage_document(duplicate.clone(), &None)?;
+
+        let note = Document::new(DocumentType::new("note")).with_data(json!({
+            "description": format!("![photo](ref:{})", duplicate.id),
+            "cover": duplicate.id,
+        }));
+        baza.stage_document(note.clone(), &None)?;
+        baza.commit()?;
+
+        let report = baza.dedupe_assets(true)?;
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].id, duplicate.id);
+        assert_eq!(report.duplicates[0].kept_id, asset.id);
+        assert_eq!(report.updated_documents, vec![note.id.clone()]);
+        assert_eq!(report.get_duplicates_size(), asset.data.size);
+        assert!(!baza.has_staged_documents());
+
+        baza.dedupe_assets(false)?;
+        baza.commit()?;
+
+        assert!(
+            baza.get_document(&duplicate.id)
+                .unwrap()
+                .is_original_erased()
+        );
+
+        let note = baza.must_get_document(&note.id)?;
+        assert_eq!(note.data.get_str("cover"), Some(asset.id.as_ref()));
+        assert_eq!(
+            note.data.get_str("description"),
+            Some(format!("![photo](ref:{})", asset.id).as_str())
+        );
+
+        assert!(baza.dedupe_assets(false)?.duplicates.is_empty());
+
+        Ok(())
+    }
+}
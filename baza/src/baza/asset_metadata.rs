@@ -0,0 +1,539 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read, Seek, SeekFrom},
+};
+
+use anyhow::{Context, Result};
+use id3::TagLike;
+use image::{ImageDecoder, ImageReader};
+
+use baza_common::log;
+
+use crate::schema::AssetData;
+
+use super::asset_text::{MAX_ASSET_SIZE, catch_parser_panic};
+
+// ID3 tags may embed cover art, so bigger tags are skipped instead of being read into memory.
+const MAX_ID3_TAG_SIZE: u64 = 16 * 1024 * 1024;
+
+// Only the beginning of the audio after the ID3 tag is read, to find the first MPEG frame.
+const MAX_AUDIO_HEADER_LEN: u64 = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AssetMetadataFormat {
+    Image,
+    Mp3,
+    Pdf,
+}
+
+impl AssetMetadataFormat {
+    fn detect(data: &AssetData) -> Option<Self> {
+        match data.media_type.as_str() {
+            "image/jpeg" | "image/png" | "image/gif" | "image/webp" => {
+                Some(AssetMetadataFormat::Image)
+            }
+            "audio/mpeg" => Some(AssetMetadataFormat::Mp3),
+            "application/pdf" => Some(AssetMetadataFormat::Pdf),
+            _ => None,
+        }
+    }
+
+    fn extract(self, file_path: &str, data: &mut AssetData) -> Result<()> {
+        match self {
+            AssetMetadataFormat::Image => extract_image_metadata(file_path, data),
+            AssetMetadataFormat::Mp3 => extract_mp3_metadata(file_path, data),
+            AssetMetadataFormat::Pdf => extract_pdf_metadata(file_path, data),
+        }
+    }
+}
+
+/// Fills the metadata fields of the asset from the source file: image dimensions, EXIF date and
+/// GPS coordinates, audio duration and ID3 tags, document page count. Files that can't be
+/// parsed are kept without the metadata.
+pub(super) fn extract_asset_metadata(file_path: &str, data: &mut AssetData) {
+    let Some(format) = AssetMetadataFormat::detect(data) else {
+        return;
+    };
+
+    // the PDF parser reads the whole file into memory
+    if format == AssetMetadataFormat::Pdf && data.size > MAX_ASSET_SIZE {
+        return;
+    }
+
+    log::debug!("Extracting {format:?} metadata of {file_path}");
+
+    if let Err(err) = format.extract(file_path, data) {
+        log::warn!("Failed to extract metadata of {file_path}: {err:#}");
+    }
+}
+
+fn extract_image_metadata(file_path: &str, data: &mut AssetData) -> Result<()> {
+    let mut decoder = ImageReader::open(file_path)?
+        .with_guessed_format()?
+        .into_decoder()
+        .context("Failed to read image header")?;
+
+    let (width, height) = decoder.dimensions();
+    data.width = Some(width.into());
+    data.height = Some(height.into());
+
+    let Some(exif) = decoder.exif_metadata()? else {
+        return Ok(());
+    };
+
+    let exif = ExifData::parse(&exif)?;
+
+    // the image is displayed rotated by 90 degrees
+    if matches!(exif.orientation, Some(5..=8)) {
+        data.width = Some(height.into());
+        data.height = Some(width.into());
+    }
+
+    data.taken_at = exif.taken_at;
+    data.gps = exif.gps;
+
+    Ok(())
+}
+
+fn extract_pdf_metadata(file_path: &str, data: &mut AssetData) -> Result<()> {
+    let page_count = catch_parser_panic(|| {
+        let document = pdf_extract::Document::load(file_path).context("Failed to parse PDF")?;
+
+        Ok(document.get_pages().len())
+    })?;
+
+    data.page_count = Some(page_count as u64);
+
+    Ok(())
+}
+
+fn extract_mp3_metadata(file_path: &str, data: &mut AssetData) -> Result<()> {
+    let mut file = BufReader::new(File::open(file_path)?);
+
+    let tag_size = read_id3_tag_size(&mut file)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut tag_duration_ms = None;
+    if tag_size > MAX_ID3_TAG_SIZE {
+        log::warn!("Skipping ID3 tag of {file_path}: tag is too big ({tag_size} bytes)");
+    } else if tag_size > 0 {
+        match id3::Tag::read_from2(&mut file) {
+            Ok(tag) => {
+                data.artist = non_empty(tag.artist());
+                data.title = non_empty(tag.title());
+                data.album = non_empty(tag.album());
+                tag_duration_ms = tag.duration().map(u64::from);
+            }
+            Err(err) => log::warn!("Failed to read ID3 tag of {file_path}: {err}"),
+        }
+    }
+
+    let duration_ms = match tag_duration_ms {
+        Some(duration_ms) => Some(duration_ms),
+        None => {
+            file.seek(SeekFrom::Start(tag_size))?;
+
+            let mut audio = Vec::new();
+            file.take(MAX_AUDIO_HEADER_LEN).read_to_end(&mut audio)?;
+
+            estimate_mp3_duration_ms(&audio, data.size.saturating_sub(tag_size))
+        }
+    };
+    data.duration = duration_ms.map(|duration_ms| format_duration(duration_ms / 1000));
+
+    Ok(())
+}
+
+/// Returns the size of the ID3v2 tag at the beginning of the file including the header and the
+/// footer, or 0 if there's no tag.
+fn read_id3_tag_size(reader: &mut impl Read) -> Result<u64> {
+    let mut header = [0; 10];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(0),
+        Err(err) => return Err(err.into()),
+    }
+
+    if !header.starts_with(b"ID3") {
+        return Ok(0);
+    }
+
+    let size = header[6..10]
+        .iter()
+        .fold(0, |size, byte| (size << 7) | u64::from(byte & 0x7f));
+    let has_footer = header[5] & 0x10 != 0;
+
+    Ok(10 + size + if has_footer { 10 } else { 0 })
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToString::to_string)
+}
+
+fn format_duration(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = seconds % 3600 / 60;
+    let seconds = seconds % 60;
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// Fields of the EXIF chunk of the image.
+#[derive(Debug, Default, PartialEq)]
+struct ExifData {
+    orientation: Option<u32>,
+    /// Local time of the camera, like `2024-03-05T10:20:30`.
+    taken_at: Option<String>,
+    /// Decimal latitude and longitude, like `50.450100,30.523400`.
+    gps: Option<String>,
+}
+
+impl ExifData {
+    fn parse(chunk: &[u8]) -> Result<Self> {
+        let chunk = chunk.strip_prefix(b"Exif\0\0").unwrap_or(chunk);
+
+        let exif = exif::Reader::new()
+            .read_raw(chunk.to_vec())
+            .context("Failed to parse EXIF")?;
+        let get_field = |tag| exif.get_field(tag, exif::In::PRIMARY);
+
+        let orientation =
+            get_field(exif::Tag::Orientation).and_then(|field| field.value.get_uint(0));
+
+        let taken_at = get_field(exif::Tag::DateTimeOriginal)
+            .or_else(|| get_field(exif::Tag::DateTime))
+            .and_then(|field| format_exif_date_time(&field.value));
+
+        let get_coordinate = |tag, ref_tag, negative_ref: &[u8]| {
+            let exif::Value::Rational(ref values) = get_field(tag)?.value else {
+                return None;
+            };
+            let [degrees, minutes, seconds] = values.as_slice() else {
+                return None;
+            };
+            let coordinate = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+
+            let exif::Value::Ascii(ref refs) = get_field(ref_tag)?.value else {
+                return None;
+            };
+
+            if refs.first()?.as_slice() == negative_ref {
+                Some(-coordinate)
+            } else {
+                Some(coordinate)
+            }
+        };
+
+        let gps = get_coordinate(exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, b"S")
+            .zip(get_coordinate(
+                exif::Tag::GPSLongitude,
+                exif::Tag::GPSLongitudeRef,
+                b"W",
+            ))
+            .filter(|(latitude, longitude)| latitude.is_finite() && longitude.is_finite())
+            .map(|(latitude, longitude)| format!("{latitude:.6},{longitude:.6}"));
+
+        Ok(ExifData {
+            orientation,
+            taken_at,
+            gps,
+        })
+    }
+}
+
+/// Converts `2024:03:05 10:20:30` into `2024-03-05T10:20:30`.
+fn format_exif_date_time(value: &exif::Value) -> Option<String> {
+    let exif::Value::Ascii(values) = value else {
+        return None;
+    };
+
+    let date_time = exif::DateTime::from_ascii(values.first()?).ok()?;
+
+    // cameras without a clock write zeros
+    if date_time.year == 0 {
+        return None;
+    }
+
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        date_time.year,
+        date_time.month,
+        date_time.day,
+        date_time.hour,
+        date_time.minute,
+        date_time.second
+    ))
+}
+
+const MPEG1_LAYER3_BITRATES: [u64; 15] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+];
+const MPEG2_LAYER3_BITRATES: [u64; 15] =
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// Estimates duration of the MPEG Layer III audio from the frame count of the Xing header of
+/// the VBR files, or from the bitrate of the first frame of the CBR files.
+fn estimate_mp3_duration_ms(audio: &[u8], audio_size: u64) -> Option<u64> {
+    let frame_pos = audio
+        .windows(4)
+        .position(|header| header[0] == 0xFF && header[1] & 0xE0 == 0xE0)?;
+    let header = &audio[frame_pos..frame_pos + 4];
+
+    let is_mpeg1 = match (header[1] >> 3) & 0b11 {
+        0b11 => true,
+        0b10 | 0b00 => false,
+        _ => return None,
+    };
+    let is_layer3 = (header[1] >> 1) & 0b11 == 0b01;
+    if !is_layer3 {
+        return None;
+    }
+
+    let bitrate_kbps = if is_mpeg1 {
+        MPEG1_LAYER3_BITRATES.get((header[2] >> 4) as usize)?
+    } else {
+        MPEG2_LAYER3_BITRATES.get((header[2] >> 4) as usize)?
+    };
+
+    let sample_rate: u64 = match ((header[1] >> 3) & 0b11, (header[2] >> 2) & 0b11) {
+        (0b11, 0) => 44100,
+        (0b11, 1) => 48000,
+        (0b11, 2) => 32000,
+        (0b10, 0) => 22050,
+        (0b10, 1) => 24000,
+        (0b10, 2) => 16000,
+        (0b00, 0) => 11025,
+        (0b00, 1) => 12000,
+        (0b00, 2) => 8000,
+        _ => return None,
+    };
+    let samples_per_frame: u64 = if is_mpeg1 { 1152 } else { 576 };
+
+    let is_mono = header[3] >> 6 == 0b11;
+    let side_info_len = match (is_mpeg1, is_mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+
+    let xing_pos = frame_pos + 4 + side_info_len;
+    if let Some(xing) = audio.get(xing_pos..xing_pos + 12)
+        && (xing.starts_with(b"Xing") || xing.starts_with(b"Info"))
+    {
+        let flags = u32::from_be_bytes(xing[4..8].try_into().ok()?);
+        if flags & 1 != 0 {
+            let frames = u64::from(u32::from_be_bytes(xing[8..12].try_into().ok()?));
+
+            return Some(frames * samples_per_frame * 1000 / sample_rate);
+        }
+    }
+
+    if *bitrate_kbps == 0 {
+        return None;
+    }
+
+    let audio_size = audio_size.saturating_sub(frame_pos as u64);
+
+    Some(audio_size * 8 / bitrate_kbps)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use id3::TagLike;
+
+    use baza_common::{SecretString, TempFile};
+
+    use crate::{BazaManager, schema::AssetData};
+
+    use super::{
+        ExifData, estimate_mp3_duration_ms, extract_mp3_metadata, format_duration,
+        read_id3_tag_size,
+    };
+
+    #[test]
+    fn test_create_asset_extracts_metadata() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("create_asset_metadata", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut()?;
+
+        let image_file = temp_dir.new_child("image.png");
+        image::RgbImage::new(3, 2).save_with_format(&image_file.path, image::ImageFormat::Png)?;
+
+        let asset = baza.create_asset(&image_file.path)?;
+        assert_eq!(asset.data.media_type, "image/png");
+        assert_eq!(asset.data.width, Some(3));
+        assert_eq!(asset.data.height, Some(2));
+        assert_eq!(asset.data.taken_at, None);
+
+        let text_file = temp_dir.new_child("notes.txt");
+        text_file.write_str("notes")?;
+
+        let asset = baza.create_asset(&text_file.path)?;
+        assert_eq!(asset.data.width, None);
+
+        baza.commit()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_exif() -> Result<()> {
+        let mut data = Vec::new();
+        data.extend(b"II*\0");
+        data.extend(8u32.to_le_bytes());
+
+        let push_entry = |data: &mut Vec<u8>, tag: u16, format: u16, count: u32, value: u32| {
+            data.extend(tag.to_le_bytes());
+            data.extend(format.to_le_bytes());
+            data.extend(count.to_le_bytes());
+            data.extend(value.to_le_bytes());
+        };
+
+        // IFD0: orientation, pointers to the Exif and GPS IFDs
+        let exif_ifd_offset = 8 + 2 + 3 * 12 + 4;
+        let date_offset = exif_ifd_offset + 2 + 12 + 4;
+        let gps_ifd_offset = date_offset + 20;
+        let rationals_offset = gps_ifd_offset + 2 + 4 * 12 + 4;
+
+        data.extend(3u16.to_le_bytes());
+        push_entry(&mut data, 0x0112, 3, 1, 6);
+        push_entry(&mut data, 0x8769, 4, 1, exif_ifd_offset);
+        push_entry(&mut data, 0x8825, 4, 1, gps_ifd_offset);
+        data.extend(0u32.to_le_bytes());
+
+        // Exif IFD: DateTimeOriginal
+        data.extend(1u16.to_le_bytes());
+        push_entry(&mut data, 0x9003, 2, 20, date_offset);
+        data.extend(0u32.to_le_bytes());
+        data.extend(b"2024:03:05 10:20:30\0");
+
+        // GPS IFD: 50°27'0.36" N, 30°31'24.24" W
+        data.extend(4u16.to_le_bytes());
+        push_entry(&mut data, 0x0001, 2, 2, u32::from_le_bytes(*b"N\0\0\0"));
+        push_entry(&mut data, 0x0002, 5, 3, rationals_offset);
+        push_entry(&mut data, 0x0003, 2, 2, u32::from_le_bytes(*b"W\0\0\0"));
+        push_entry(&mut data, 0x0004, 5, 3, rationals_offset + 24);
+        data.extend(0u32.to_le_bytes());
+        for (numerator, denominator) in [(50, 1), (27, 1), (36, 100), (30, 1), (31, 1), (2424, 100)]
+        {
+            data.extend(u32::to_le_bytes(numerator));
+            data.extend(u32::to_le_bytes(denominator));
+        }
+
+        let exif = ExifData::parse(&data)?;
+        assert_eq!(
+            exif,
+            ExifData {
+                orientation: Some(6),
+                taken_at: Some("2024-03-05T10:20:30".to_string()),
+                gps: Some("50.450100,-30.523400".to_string()),
+            }
+        );
+
+        assert!(ExifData::parse(b"not exif").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_mp3_metadata() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("extract_mp3_metadata", "");
+        temp_dir.mkdir()?;
+
+        // the cover art makes the tag bigger than the audio header
+        let mut tag = id3::Tag::new();
+        tag.set_artist("Artist");
+        tag.set_title("Title");
+        tag.set_album("Album");
+        tag.add_frame(id3::frame::Picture {
+            mime_type: "image/jpeg".to_string(),
+            picture_type: id3::frame::PictureType::CoverFront,
+            description: String::new(),
+            data: vec![1; 100 * 1024],
+        });
+
+        // MPEG1 Layer III, 128 kbps, 44100 Hz, stereo
+        let mut audio = vec![0xFF, 0xFB, 0x90, 0x00];
+        audio.resize(160_000, 0);
+
+        let extract = |tag: &id3::Tag| -> Result<AssetData> {
+            let mut bytes = Vec::new();
+            tag.write_to(&mut bytes, id3::Version::Id3v24)?;
+            bytes.extend(&audio);
+
+            let file = temp_dir.new_child("track.mp3");
+            std::fs::write(&file.path, &bytes)?;
+
+            let mut data = AssetData::new(
+                "track.mp3".to_string(),
+                "audio/mpeg".to_string(),
+                bytes.len() as u64,
+                String::new(),
+                SecretString::new("key".into()),
+            );
+            extract_mp3_metadata(&file.path, &mut data)?;
+
+            Ok(data)
+        };
+
+        let data = extract(&tag)?;
+        assert_eq!(data.artist.as_deref(), Some("Artist"));
+        assert_eq!(data.title.as_deref(), Some("Title"));
+        assert_eq!(data.album.as_deref(), Some("Album"));
+        assert_eq!(data.duration.as_deref(), Some("0:10"));
+
+        tag.set_duration(185_000);
+        let data = extract(&tag)?;
+        assert_eq!(data.duration.as_deref(), Some("3:05"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_id3_tag_size() -> Result<()> {
+        let mut bytes = Vec::new();
+        id3::Tag::new().write_to(&mut bytes, id3::Version::Id3v24)?;
+        assert_eq!(
+            read_id3_tag_size(&mut bytes.as_slice())?,
+            bytes.len() as u64
+        );
+
+        assert_eq!(read_id3_tag_size(&mut b"no tag here".as_slice())?, 0);
+        assert_eq!(read_id3_tag_size(&mut b"ID3".as_slice())?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimate_mp3_duration() {
+        // MPEG1 Layer III, 128 kbps, 44100 Hz, stereo
+        let mut audio = vec![0xFF, 0xFB, 0x90, 0x00];
+        audio.resize(417, 0);
+        assert_eq!(estimate_mp3_duration_ms(&audio, 160_000), Some(10_000));
+
+        // Xing header with the frame count
+        audio[4 + 32..4 + 32 + 4].copy_from_slice(b"Xing");
+        audio[4 + 32 + 4..4 + 32 + 8].copy_from_slice(&1u32.to_be_bytes());
+        audio[4 + 32 + 8..4 + 32 + 12].copy_from_slice(&3828u32.to_be_bytes());
+        assert_eq!(estimate_mp3_duration_ms(&audio, 160_000), Some(99_996));
+
+        assert_eq!(estimate_mp3_duration_ms(b"not audio", 9), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(5), "0:05");
+        assert_eq!(format_duration(185), "3:05");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+}
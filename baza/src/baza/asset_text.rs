@@ -0,0 +1,433 @@
+use std::{
+    io::{Read, Seek},
+    panic::{UnwindSafe, catch_unwind},
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result, anyhow};
+use regex::Regex;
+
+use baza_common::{create_file_reader, get_mime_from_path, log};
+use baza_storage::crypto::age::{AgeKey, AgeReader};
+
+use crate::{
+    entities::Id,
+    schema::{Asset, AssetData},
+};
+
+use super::Baza;
+
+// Bigger files are skipped, to keep extraction time and memory bounded.
+pub(super) const MAX_ASSET_SIZE: u64 = 64 * 1024 * 1024;
+
+// Only the beginning of a long text is indexed, so that a single book doesn't bloat the index.
+const MAX_TEXT_LEN: usize = 256 * 1024;
+
+// EPUB entries are decompressed only up to this total size, as the archive size doesn't limit
+// the size of its decompressed entries. HTML markup is usually longer than its text.
+const MAX_EPUB_HTML_LEN: u64 = 4 * MAX_TEXT_LEN as u64;
+
+static HIDDEN_ELEMENTS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(script|style|head)\b.*?</(script|style|head)\s*>")
+        .expect("failed to create hidden elements regex")
+});
+
+static TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").expect("failed to create tag regex"));
+
+static ENTITY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").expect("failed to create regex")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AssetTextFormat {
+    Text,
+    Pdf,
+    Epub,
+}
+
+impl AssetTextFormat {
+    fn detect(data: &AssetData) -> Option<Self> {
+        // media type of the plain text files can't be inferred from the contents
+        let media_type = if data.media_type == "application/octet-stream" {
+            get_mime_from_path(&data.filename)
+        } else {
+            data.media_type.clone()
+        };
+
+        match media_type.as_str() {
+            "application/pdf" => Some(AssetTextFormat::Pdf),
+            "application/epub+zip" => Some(AssetTextFormat::Epub),
+            "application/json" | "application/xml" => Some(AssetTextFormat::Text),
+            media_type if media_type.starts_with("text/") => Some(AssetTextFormat::Text),
+            _ => None,
+        }
+    }
+
+    fn extract(self, reader: impl Read + Seek) -> Result<String> {
+        match self {
+            AssetTextFormat::Text => extract_plain_text(reader),
+            AssetTextFormat::Pdf => extract_pdf_text(reader),
+            AssetTextFormat::Epub => extract_epub_text(reader),
+        }
+    }
+}
+
+fn extract_plain_text(mut reader: impl Read) -> Result<String> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    String::from_utf8(bytes).context("Text file must be valid UTF-8")
+}
+
+fn extract_pdf_text(mut reader: impl Read) -> Result<String> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    catch_parser_panic(|| {
+        pdf_extract::extract_text_from_mem(&bytes).context("Failed to extract text from PDF")
+    })
+}
+
+/// Runs a parser of a file from the outside world, and turns its panics into errors, as some
+/// parsers panic on malformed files.
+pub(super) fn catch_parser_panic<T>(parse: impl FnOnce() -> Result<T> + UnwindSafe) -> Result<T> {
+    catch_unwind(parse).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+
+        Err(anyhow!("Parser panicked: {message}"))
+    })
+}
+
+/// Extracts the text of the HTML documents of the book, in the order they are stored in the
+/// archive, which usually matches the reading order.
+fn extract_epub_text(reader: impl Read + Seek) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(reader).context("Failed to open EPUB archive")?;
+
+    let mut text = String::new();
+    let mut html_len = 0;
+    for index in 0..archive.len() {
+        if html_len >= MAX_EPUB_HTML_LEN {
+            break;
+        }
+
+        let file = archive.by_index(index)?;
+
+        let name = file.name().to_lowercase();
+        if !(name.ends_with(".xhtml") || name.ends_with(".html") || name.ends_with(".htm")) {
+            continue;
+        }
+
+        let mut html = Vec::new();
+        file.take(MAX_EPUB_HTML_LEN - html_len)
+            .read_to_end(&mut html)
+            .with_context(|| format!("Failed to read EPUB file {name}"))?;
+        html_len += html.len() as u64;
+
+        // the entry may be cut in the middle of a character
+        text.push_str(&html_to_text(&String::from_utf8_lossy(&html)));
+        text.push('\n');
+
+        if text.len() > MAX_TEXT_LEN {
+            break;
+        }
+    }
+
+    Ok(text)
+}
+
+fn html_to_text(html: &str) -> String {
+    let html = HIDDEN_ELEMENTS_REGEX.replace_all(html, " ");
+    let text = TAG_REGEX.replace_all(&html, " ");
+
+    decode_html_entities(&text)
+}
+
+fn decode_html_entities(text: &str) -> String {
+    ENTITY_REGEX
+        .replace_all(text, |captures: &regex::Captures| {
+            let entity = &captures[1];
+
+            let value = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+
+            value.map_or_else(|| captures[0].to_string(), |value| value.to_string())
+        })
+        .into_owned()
+}
+
+fn truncate_text(mut text: String) -> String {
+    if text.len() > MAX_TEXT_LEN {
+        let mut len = MAX_TEXT_LEN;
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        text.truncate(len);
+    }
+
+    text
+}
+
+/// An asset which file text wasn't extracted yet.
+pub struct PendingAssetText {
+    asset_id: Id,
+    data: AssetData,
+    blob_path: String,
+}
+
+impl PendingAssetText {
+    /// Extracts the text of the asset file. It doesn't need the baza, so that the baza isn't
+    /// locked while big files are parsed. Files that aren't supported or can't be parsed get an
+    /// empty text, so that they aren't processed again.
+    #[must_use]
+    pub fn extract_text(&self) -> String {
+        let asset_id = &self.asset_id;
+
+        match AssetTextFormat::detect(&self.data) {
+            Some(format) if self.data.size <= MAX_ASSET_SIZE => {
+                log::debug!("Extracting {format:?} text of asset {asset_id}");
+
+                self.open_blob()
+                    .and_then(|reader| format.extract(reader))
+                    .map(truncate_text)
+                    .unwrap_or_else(|err| {
+                        log::warn!("Failed to extract text of asset {asset_id}: {err:#}");
+
+                        String::new()
+                    })
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn open_blob(&self) -> Result<impl Read + Seek + use<>> {
+        let blob_key = AgeKey::from_age_x25519_key(self.data.age_x25519_key.clone())?;
+        let file_reader = create_file_reader(&self.blob_path)?;
+
+        AgeReader::new(file_reader, blob_key)
+    }
+}
+
+impl Baza {
+    fn get_pending_asset_text(&self, asset_id: &Id) -> Result<Option<PendingAssetText>> {
+        if self.state.get_asset_text(asset_id).is_some() {
+            return Ok(None);
+        }
+
+        let Some(blob_path) = self.get_blob_path(asset_id)? else {
+            return Ok(None);
+        };
+
+        let asset: Asset = self.get_asset(asset_id)?.context("Asset not found")?;
+
+        Ok(Some(PendingAssetText {
+            asset_id: asset.id,
+            data: asset.data,
+            blob_path,
+        }))
+    }
+
+    /// Lists up to `limit` assets which texts weren't extracted yet, like the assets received
+    /// from other instances. Assets without BLOBs are skipped.
+    pub fn list_pending_asset_texts(&self, limit: usize) -> Result<Vec<PendingAssetText>> {
+        let mut pending = Vec::new();
+
+        for asset_id in self.state.list_assets_without_text() {
+            if pending.len() == limit {
+                break;
+            }
+
+            if let Some(pending_asset_text) = self.get_pending_asset_text(&asset_id)? {
+                pending.push(pending_asset_text);
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// Caches the extracted text for the search index. The text is dropped if the asset file was
+    /// replaced during the extraction.
+    pub fn set_pending_asset_text(
+        &mut self,
+        pending: PendingAssetText,
+        text: String,
+    ) -> Result<()> {
+        let asset_id = &pending.asset_id;
+
+        let Some(asset) = self.get_asset(asset_id)? else {
+            return Ok(());
+        };
+
+        if self.state.get_asset_text(asset_id).is_some()
+            || asset.data.content_sha256 != pending.data.content_sha256
+        {
+            log::debug!("Asset {asset_id} changed while its text was extracted");
+            return Ok(());
+        }
+
+        self.state
+            .set_asset_text(asset_id, &pending.data.content_sha256, text)
+    }
+
+    /// Extracts the text of the asset file for the search index, unless it's already cached.
+    pub fn index_asset_text(&mut self, asset_id: &Id) -> Result<()> {
+        let Some(pending) = self.get_pending_asset_text(asset_id)? else {
+            return Ok(());
+        };
+
+        let text = pending.extract_text();
+
+        self.set_pending_asset_text(pending, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use baza_common::SecretString;
+
+    use crate::schema::AssetData;
+
+    use super::{
+        AssetTextFormat, MAX_EPUB_HTML_LEN, MAX_TEXT_LEN, catch_parser_panic, html_to_text,
+        truncate_text,
+    };
+
+    fn asset_data(filename: &str, media_type: &str) -> AssetData {
+        AssetData::new(
+            filename.to_string(),
+            media_type.to_string(),
+            0,
+            String::new(),
+            SecretString::new("key".into()),
+        )
+    }
+
+    #[test]
+    fn test_detect_asset_text_format() {
+        let detect =
+            |filename, media_type| AssetTextFormat::detect(&asset_data(filename, media_type));
+
+        assert_eq!(
+            detect("notes.md", "application/octet-stream"),
+            Some(AssetTextFormat::Text)
+        );
+        assert_eq!(detect("notes", "text/plain"), Some(AssetTextFormat::Text));
+        assert_eq!(
+            detect("book.pdf", "application/pdf"),
+            Some(AssetTextFormat::Pdf)
+        );
+        assert_eq!(
+            detect("book.epub", "application/epub+zip"),
+            Some(AssetTextFormat::Epub)
+        );
+        assert_eq!(detect("photo.jpg", "image/jpeg"), None);
+        assert_eq!(detect("data.bin", "application/octet-stream"), None);
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let text = html_to_text(
+            "<html><head><title>Ignored</title><style>p { color: red; }</style></head>\
+             <body><p>Tom &amp; Jerry&#39;s&nbsp;<b>tale</b> &#x263A; &unknown;</p>\
+             <script>alert(1)</script></body></html>",
+        );
+
+        assert_eq!(
+            text.split_whitespace().collect::<Vec<_>>().join(" "),
+            "Tom & Jerry's tale ☺ &unknown;"
+        );
+    }
+
+    #[test]
+    fn test_extract_epub_text() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+
+        writer.start_file("mimetype", options).unwrap();
+        writer.write_all(b"application/epub+zip").unwrap();
+        writer.start_file("OEBPS/chapter1.xhtml", options).unwrap();
+        writer.write_all(b"<p>First chapter</p>").unwrap();
+        writer.start_file("OEBPS/chapter2.xhtml", options).unwrap();
+        writer.write_all(b"<p>Second chapter</p>").unwrap();
+
+        let archive = writer.finish().unwrap();
+
+        let text = AssetTextFormat::Epub
+            .extract(Cursor::new(archive.into_inner()))
+            .unwrap();
+
+        assert_eq!(
+            text.split_whitespace().collect::<Vec<_>>().join(" "),
+            "First chapter Second chapter"
+        );
+    }
+
+    #[test]
+    fn test_extract_epub_text_limits_decompressed_size() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        writer.start_file("OEBPS/chapter1.xhtml", options).unwrap();
+        writer.write_all(b"<p>First chapter</p>").unwrap();
+        // compresses into a few KiB
+        writer.start_file("OEBPS/bomb.xhtml", options).unwrap();
+        let chunk = vec![b'a'; 1024 * 1024];
+        for _ in 0..8 {
+            writer.write_all(&chunk).unwrap();
+        }
+        writer.start_file("OEBPS/chapter2.xhtml", options).unwrap();
+        writer.write_all(b"<p>Second chapter</p>").unwrap();
+
+        let archive = writer.finish().unwrap().into_inner();
+        assert!(archive.len() < 64 * 1024);
+
+        let text = AssetTextFormat::Epub.extract(Cursor::new(archive)).unwrap();
+
+        assert!(text.trim_start().starts_with("First chapter"));
+        assert!(text.len() <= MAX_EPUB_HTML_LEN as usize + 64);
+        assert!(!text.contains("Second chapter"));
+    }
+
+    #[test]
+    fn test_truncate_text() {
+        let text = "є".repeat(MAX_TEXT_LEN);
+
+        let truncated = truncate_text(text);
+        assert!(truncated.len() <= MAX_TEXT_LEN);
+        assert!(truncated.chars().all(|char| char == 'є'));
+
+        assert_eq!(truncate_text("short".to_string()), "short");
+    }
+
+    #[test]
+    fn test_catch_parser_panic() {
+        assert_eq!(catch_parser_panic(|| Ok(1)).unwrap(), 1);
+
+        let result = catch_parser_panic(|| -> anyhow::Result<()> { panic!("malformed file") });
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Parser panicked: malformed file"
+        );
+    }
+}
@@ -1,14 +1,14 @@
 use std::{
     collections::HashSet,
     fs::remove_file,
-    io::{Read, Seek, copy},
+    io::{self, Read, Seek, copy},
 };
 
 use anyhow::{Context, Result, ensure};
 
 use baza_common::{
-    Sha256HashingWriter, bytes_to_hex_string, create_file_reader, create_file_writer, file_exists,
-    log,
+    Sha256HashingReader, Sha256HashingWriter, bytes_to_hex_string, create_file_reader,
+    create_file_writer, file_exists, log,
 };
 use baza_storage::crypto::age::{AgeKey, AgeReader, AgeWriter};
 
@@ -20,7 +20,7 @@ use crate::{
 use super::Baza;
 
 impl Baza {
-    fn get_blob_path(&self, asset_id: &Id) -> Result<Option<String>> {
+    pub(super) fn get_blob_path(&self, asset_id: &Id) -> Result<Option<String>> {
         let blob_path = self.paths.get_state_blob_path(asset_id);
 
         if file_exists(&blob_path)? {
@@ -56,7 +56,7 @@ impl Baza {
         asset_id: &Id,
         file_path: &str,
         blob_key: AgeKey,
-    ) -> Result<BlobWriteMetadata> {
+    ) -> Result<BlobMetadata> {
         ensure!(
             !self.blob_exists(asset_id)?,
             "BLOB {asset_id} already exists"
@@ -77,7 +77,7 @@ impl Baza {
         Ok(())
     }
 
-    fn remove_state_blob(&mut self, asset_id: &Id) -> Result<()> {
+    pub(super) fn remove_state_blob(&mut self, asset_id: &Id) -> Result<()> {
         log::debug!("Removing state BLOB {asset_id}");
         let file_path = self.paths.get_state_blob_path(asset_id);
 
@@ -172,8 +172,8 @@ impl Baza {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-/// Metadata derived from the plaintext stream while it is encrypted into a blob.
-pub(crate) struct BlobWriteMetadata {
+/// Size and SHA-256 of the plaintext stream of a blob.
+pub(crate) struct BlobMetadata {
     pub size: u64,
     pub content_sha256: String,
 }
@@ -183,7 +183,7 @@ pub(crate) fn write_and_encrypt_blob(
     file_path: &str,
     blob_path: &str,
     key: AgeKey,
-) -> Result<BlobWriteMetadata> {
+) -> Result<BlobMetadata> {
     let file_writer = create_file_writer(blob_path, false)?;
     let age_writer = AgeWriter::new(file_writer, key)?;
     let mut hashing_writer = Sha256HashingWriter::new(age_writer);
@@ -196,12 +196,28 @@ pub(crate) fn write_and_encrypt_blob(
     let (content_hash, age_writer) = hashing_writer.finish();
     age_writer.finish()?;
 
-    Ok(BlobWriteMetadata {
+    Ok(BlobMetadata {
         size,
         content_sha256: bytes_to_hex_string(&content_hash),
     })
 }
 
+/// Decrypts a blob and computes metadata of its plaintext stream.
+pub(crate) fn read_blob_metadata(blob_path: &str, key: AgeKey) -> Result<BlobMetadata> {
+    let mut age_reader = AgeReader::new(create_file_reader(blob_path)?, key)?;
+    let mut hashing_reader = Sha256HashingReader::new(&mut age_reader);
+
+    let size = copy(&mut hashing_reader, &mut io::sink()).context("Failed to decrypt BLOB")?;
+    let content_hash = hashing_reader
+        .get_hash()
+        .context("BLOB hash must be finalized after full read")?;
+
+    Ok(BlobMetadata {
+        size,
+        content_sha256: bytes_to_hex_string(content_hash),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
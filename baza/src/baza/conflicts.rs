@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result, bail, ensure};
+use serde_json::Value;
+
+use baza_common::{Timestamp, log};
+
+use crate::{
+    entities::{Document, DocumentLockKey, DocumentType, Id, Revision},
+    merge::MergeExpert,
+};
+
+use super::{Baza, StagingError};
+
+/// An original snapshot of a conflicted document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictBranch {
+    pub rev: Revision,
+    pub updated_at: Timestamp,
+    pub is_erased: bool,
+}
+
+/// A field which value differs between the conflict branches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictField {
+    pub field: String,
+    /// The value in the common ancestor of the branches, if it's known.
+    pub base: Option<Value>,
+    /// Values in the branches, in the order of `DocumentConflict::branches`.
+    pub branches: Vec<Option<Value>>,
+    /// The value in the staged resolution.
+    pub resolution: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentConflict {
+    pub id: Id,
+    pub document_type: DocumentType,
+    /// The revision of the common ancestor of the branches, if there's a single one.
+    pub base_rev: Option<Revision>,
+    /// Branches from the oldest to the newest.
+    pub branches: Vec<ConflictBranch>,
+    pub fields: Vec<ConflictField>,
+}
+
+/// How to resolve a conflicting field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldResolution {
+    /// Take the value of the common ancestor.
+    Base,
+    /// Take the value of the branch, by index in `DocumentConflict::branches`.
+    Branch(usize),
+    /// Use the provided value; `null` removes the field.
+    Value(Value),
+}
+
+impl Baza {
+    /// Reads the original branches of a conflicted document, and their common ancestor from the
+    /// storage.
+    fn get_conflict_snapshots(&self, id: &Id) -> Result<(Option<Document>, Vec<Document>)> {
+        let head = self
+            .get_document(id)
+            .with_context(|| format!("Can't find document {id}"))?;
+        ensure!(head.is_conflict(), "Document {id} is not conflicted");
+
+        let mut branches = head.iter_original_snapshots().cloned().collect::<Vec<_>>();
+        branches.sort_by(|a, b| {
+            a.updated_at
+                .cmp(&b.updated_at)
+                .then_with(|| a.rev.history_cmp(&b.rev))
+        });
+
+        let snapshots = self.list_document_snapshots(id)?;
+        let branch_revs = branches
+            .iter()
+            .map(|document| &document.rev)
+            .collect::<HashSet<_>>();
+        let base_rev =
+            Revision::find_base_rev(&branch_revs, snapshots.iter().map(|document| &document.rev));
+        let base = base_rev.and_then(|base_rev| {
+            snapshots
+                .iter()
+                .find(|document| &document.rev == base_rev)
+                .cloned()
+        });
+
+        Ok((base, branches))
+    }
+
+    /// Lists the fields which values differ between the branches of a conflicted document,
+    /// with the values in the common ancestor, in each branch, and in the staged resolution.
+    pub fn get_document_conflict(&self, id: &Id) -> Result<DocumentConflict> {
+        let (base, branches) = self.get_conflict_snapshots(id)?;
+
+        self.build_document_conflict(id, base.as_ref(), &branches)
+    }
+
+    fn build_document_conflict(
+        &self,
+        id: &Id,
+        base: Option<&Document>,
+        branches: &[Document],
+    ) -> Result<DocumentConflict> {
+        let document_type = branches
+            .iter()
+            .find(|document| !document.is_erased())
+            .map(|document| document.document_type.clone())
+            .unwrap_or_else(DocumentType::erased);
+
+        let staged = self
+            .get_document(id)
+            .and_then(|head| head.get_staged_document());
+
+        let mut fields = Vec::new();
+        if !document_type.is_erased() {
+            for field in self.get_schema().iter_fields(&document_type)? {
+                let values = branches
+                    .iter()
+                    .map(|document| document.data.get(&field.name).cloned())
+                    .collect::<Vec<_>>();
+
+                if values.iter().all(|value| value == &values[0]) {
+                    continue;
+                }
+
+                fields.push(ConflictField {
+                    field: field.name.clone(),
+                    base: base.and_then(|base| base.data.get(&field.name).cloned()),
+                    branches: values,
+                    resolution: staged.and_then(|staged| staged.data.get(&field.name).cloned()),
+                });
+            }
+        }
+
+        Ok(DocumentConflict {
+            id: id.clone(),
+            document_type,
+            base_rev: base.map(|base| base.rev.clone()),
+            branches: branches
+                .iter()
+                .map(|document| ConflictBranch {
+                    rev: document.rev.clone(),
+                    updated_at: document.updated_at,
+                    is_erased: document.is_erased(),
+                })
+                .collect(),
+            fields,
+        })
+    }
+
+    /// Applies the per-field choices on top of the staged resolution, or of the automatic merge
+    /// if there's no staged resolution, and stages the result. The conflict is cleared on commit.
+    pub fn resolve_document_conflict(
+        &mut self,
+        id: &Id,
+        resolutions: &HashMap<String, FieldResolution>,
+        lock_key: &Option<DocumentLockKey>,
+    ) -> std::result::Result<&Document, StagingError> {
+        log::debug!("Resolving conflict of document {id}");
+
+        let document = self.build_conflict_resolution(id, resolutions)?;
+
+        self.stage_document(document, lock_key)
+    }
+
+    fn build_conflict_resolution(
+        &self,
+        id: &Id,
+        resolutions: &HashMap<String, FieldResolution>,
+    ) -> Result<Document> {
+        let (base, branches) = self.get_conflict_snapshots(id)?;
+        let conflict = self.build_document_conflict(id, base.as_ref(), &branches)?;
+
+        let staged = self
+            .get_document(id)
+            .and_then(|head| head.get_staged_document())
+            .cloned();
+        let mut document = match staged {
+            Some(staged) => staged,
+            None => MergeExpert::new(self.get_schema().clone())
+                .merge_originals(base.clone(), branches.iter().collect())?,
+        };
+        ensure!(
+            !document.is_erased(),
+            "Staged resolution of document {id} erases it; reset the document first"
+        );
+
+        for (field, resolution) in resolutions {
+            ensure!(
+                conflict.fields.iter().any(|item| &item.field == field),
+                "Field '{field}' of document {id} isn't conflicting"
+            );
+
+            let value = match resolution {
+                FieldResolution::Base => {
+                    let base = base.as_ref().context("Conflict has no common ancestor")?;
+
+                    base.data.get(field).cloned()
+                }
+                FieldResolution::Branch(index) => {
+                    let Some(branch) = branches.get(*index) else {
+                        bail!(
+                            "Branch index {index} is out of range, document {id} has {} branches",
+                            branches.len()
+                        );
+                    };
+
+                    branch.data.get(field).cloned()
+                }
+                FieldResolution::Value(value) => {
+                    Some(value.clone()).filter(|value| !value.is_null())
+                }
+            };
+
+            match value {
+                Some(value) => document.data.set(field, value),
+                None => document.data.remove(field),
+            }
+        }
+
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use baza_common::TempFile;
+
+    use crate::{
+        BazaManager,
+        entities::{Document, new_document},
+    };
+
+    use super::FieldResolution;
+
+    /// Creates a baza with a document that was concurrently changed on two devices.
+    fn new_conflict(temp_dir: &TempFile) -> (BazaManager, Document, Document, Document) {
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+
+        let base = new_document(json!({ "test": "base" })).with_rev(json!({ "a": 1 }));
+        let left = base
+            .clone()
+            .with_data(json!({ "test": "left" }))
+            .with_rev(json!({ "a": 2 }));
+        let right = base
+            .clone()
+            .with_data(json!({ "test": "right" }))
+            .with_rev(json!({ "a": 1, "b": 1 }));
+
+        {
+            let baza = manager.open().unwrap();
+            baza.create_storage_file(
+                &manager.paths.get_storage_file("db1"),
+                &[base.clone(), left.clone()],
+            );
+            baza.create_storage_file(
+                &manager.paths.get_storage_file("db2"),
+                &[base.clone(), right.clone()],
+            );
+        }
+        manager.clear_cached_baza().unwrap();
+
+        (manager, base, left, right)
+    }
+
+    #[test]
+    fn test_get_document_conflict() {
+        let temp_dir = TempFile::new_with_details("conflicts", "");
+        temp_dir.mkdir().unwrap();
+        let (manager, base, left, right) = new_conflict(&temp_dir);
+
+        let baza = manager.open().unwrap();
+        let conflict = baza.get_document_conflict(&base.id).unwrap();
+
+        assert_eq!(conflict.base_rev, Some(base.rev.clone()));
+        assert_eq!(conflict.branches.len(), 2);
+        assert_eq!(conflict.fields.len(), 1);
+
+        let field = &conflict.fields[0];
+        assert_eq!(field.field, "test");
+        assert_eq!(field.base, Some(json!("base")));
+
+        let branch_values = conflict
+            .branches
+            .iter()
+            .map(|branch| {
+                if branch.rev == left.rev {
+                    Some(json!("left"))
+                } else {
+                    assert_eq!(branch.rev, right.rev);
+                    Some(json!("right"))
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(field.branches, branch_values);
+
+        // the automatic merge is staged as the resolution
+        assert!(field.resolution.is_some());
+    }
+
+    #[test]
+    fn test_resolve_document_conflict() {
+        let temp_dir = TempFile::new_with_details("conflicts", "");
+        temp_dir.mkdir().unwrap();
+        let (manager, base, _left, _right) = new_conflict(&temp_dir);
+
+        let mut baza = manager.open_mut().unwrap();
+        let conflict = baza.get_document_conflict(&base.id).unwrap();
+        let branch_value = conflict.fields[0].branches[1].clone().unwrap();
+
+        let resolve = |resolution| HashMap::from([("test".to_string(), resolution)]);
+
+        assert!(
+            baza.resolve_document_conflict(
+                &base.id,
+                &HashMap::from([("ref".to_string(), FieldResolution::Base)]),
+                &None
+            )
+            .is_err(),
+            "Only conflicting fields could be resolved"
+        );
+        assert!(
+            baza.resolve_document_conflict(&base.id, &resolve(FieldResolution::Branch(2)), &None)
+                .is_err()
+        );
+
+        let document = baza
+            .resolve_document_conflict(&base.id, &resolve(FieldResolution::Base), &None)
+            .unwrap();
+        assert_eq!(document.data.get("test"), Some(&json!("base")));
+
+        let document = baza
+            .resolve_document_conflict(&base.id, &resolve(FieldResolution::Branch(1)), &None)
+            .unwrap();
+        assert_eq!(document.data.get("test"), Some(&branch_value));
+
+        // the resolution is applied on top of the automatic merge after reset
+        baza.reset_document(&base.id, &None).unwrap();
+        let document = baza
+            .resolve_document_conflict(
+                &base.id,
+                &resolve(FieldResolution::Value(json!("custom"))),
+                &None,
+            )
+            .unwrap();
+        assert_eq!(document.data.get("test"), Some(&json!("custom")));
+
+        baza.commit().unwrap();
+
+        let head = baza.get_document(&base.id).unwrap();
+        assert!(!head.is_conflict());
+        assert_eq!(
+            head.get_single_document().data.get("test"),
+            Some(&json!("custom"))
+        );
+        assert!(baza.get_document_conflict(&base.id).is_err());
+    }
+}
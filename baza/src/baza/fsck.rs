@@ -0,0 +1,470 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+
+use baza_common::{get_file_name, log};
+use baza_storage::crypto::age::AgeKey;
+
+use crate::{
+    BazaStorage,
+    backup::{find_backup_blob, find_latest_backup_manifest},
+    entities::{Document, DocumentKey, Id, Revision},
+    schema::{ASSET_TYPE, Asset, AssetData},
+};
+
+use super::{Baza, ValidationError, blobs::read_blob_metadata};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FsckIssueKind {
+    Missing,
+    Corrupt,
+    Mismatched,
+    Orphaned,
+}
+
+impl fmt::Display for FsckIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            FsckIssueKind::Missing => "missing",
+            FsckIssueKind::Corrupt => "corrupt",
+            FsckIssueKind::Mismatched => "mismatched",
+            FsckIssueKind::Orphaned => "orphaned",
+        };
+
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FsckIssue {
+    pub kind: FsckIssueKind,
+    /// Id of the asset of a BLOB issue, or of the document of a snapshot issue. `None` for the
+    /// issues of the whole storage file.
+    pub id: Option<Id>,
+    /// Revision of the snapshot of a snapshot issue.
+    pub rev: Option<Revision>,
+    pub details: String,
+    /// BLOB copies in the backups that match the asset, or manifests of the latest backups to
+    /// restore the storage from.
+    pub repair_from: Vec<String>,
+}
+
+/// Integrity issues of the storage file, snapshots and BLOBs.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct FsckReport {
+    pub checked_snapshots: usize,
+    pub checked_blobs: usize,
+    pub issues: Vec<FsckIssue>,
+}
+
+impl FsckReport {
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    #[must_use]
+    pub fn count_issues(&self, kind: FsckIssueKind) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.kind == kind)
+            .count()
+    }
+}
+
+struct AssetBlob {
+    id: Id,
+    data: AssetData,
+    blob_path: Option<String>,
+    backup_blob_paths: Vec<String>,
+}
+
+impl Baza {
+    /// Checks integrity of the archive: every snapshot in the storage file must match its index
+    /// key, the latest snapshots of the documents must match the schema, every committed snapshot
+    /// must be in the storage file, and every asset BLOB must decrypt into the content of the
+    /// asset. BLOBs are verified in parallel.
+    ///
+    /// Older snapshots aren't validated, as they may have been valid under an older schema.
+    ///
+    /// Backup dirs are searched for the BLOB copies and the backups that could repair the issues.
+    pub fn fsck(&self, backup_dirs: &[String]) -> Result<FsckReport> {
+        let mut backup_manifests = Vec::new();
+        for backup_dir in backup_dirs {
+            backup_manifests.extend(find_latest_backup_manifest(backup_dir)?);
+        }
+
+        let mut report = FsckReport::default();
+
+        self.check_storage(&mut report, &backup_manifests)?;
+        self.check_blobs(&mut report, backup_dirs)?;
+
+        for blob in self.find_orphaned_blobs()? {
+            let owner = if blob.erased_asset {
+                "an erased asset"
+            } else {
+                "an unknown document"
+            };
+
+            report.issues.push(FsckIssue {
+                kind: FsckIssueKind::Orphaned,
+                id: Some(blob.id),
+                rev: None,
+                details: format!(
+                    "{} BLOB of {owner} isn't used by any asset, garbage collection removes it",
+                    blob.location
+                ),
+                repair_from: Vec::new(),
+            });
+        }
+
+        log::info!(
+            "Fsck: checked {} snapshots and {} BLOBs, found {} issues",
+            report.checked_snapshots,
+            report.checked_blobs,
+            report.issues.len()
+        );
+
+        Ok(report)
+    }
+
+    fn check_storage(&self, report: &mut FsckReport, backup_manifests: &[String]) -> Result<()> {
+        let storage = BazaStorage::read_file(&self.paths.storage_main_db_file, self.key.clone())
+            .context("Failed to read storage file")?;
+        let storage_keys = storage.index.iter().cloned().collect::<HashSet<_>>();
+        let latest_keys = find_latest_keys(&storage_keys);
+
+        let new_snapshot_issue = |kind, key: &DocumentKey, details: String| FsckIssue {
+            kind,
+            id: Some(key.id.clone()),
+            rev: Some(key.rev.clone()),
+            details,
+            repair_from: backup_manifests.to_vec(),
+        };
+
+        for item in storage {
+            let (key, raw_document) = match item {
+                Ok(item) => item,
+                Err(err) => {
+                    report.issues.push(FsckIssue {
+                        kind: FsckIssueKind::Corrupt,
+                        id: None,
+                        rev: None,
+                        details: format!("Failed to read storage file: {err:#}"),
+                        repair_from: backup_manifests.to_vec(),
+                    });
+                    break;
+                }
+            };
+            report.checked_snapshots += 1;
+
+            let document: Document = match serde_json::from_str(&raw_document) {
+                Ok(document) => document,
+                Err(err) => {
+                    report.issues.push(new_snapshot_issue(
+                        FsckIssueKind::Corrupt,
+                        &key,
+                        format!("Failed to parse snapshot: {err}"),
+                    ));
+                    continue;
+                }
+            };
+
+            if DocumentKey::for_document(&document) != key {
+                report.issues.push(new_snapshot_issue(
+                    FsckIssueKind::Mismatched,
+                    &key,
+                    "Snapshot doesn't match its storage index key".to_string(),
+                ));
+                continue;
+            }
+
+            if !latest_keys.contains(&key) {
+                continue;
+            }
+
+            if let Err(err) = self.validate_snapshot(&document) {
+                report.issues.push(new_snapshot_issue(
+                    FsckIssueKind::Mismatched,
+                    &key,
+                    format!(
+                        "Snapshot doesn't match the schema: {}",
+                        format_validation_error(&err)
+                    ),
+                ));
+            }
+        }
+
+        for head in self.iter_documents() {
+            for rev in head.iter_original_revs() {
+                let key = DocumentKey::new(head.get_id().clone(), rev.clone());
+
+                if !storage_keys.contains(&key) {
+                    report.issues.push(new_snapshot_issue(
+                        FsckIssueKind::Missing,
+                        &key,
+                        "Committed snapshot is missing in the storage file".to_string(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_blobs(&self, report: &mut FsckReport, backup_dirs: &[String]) -> Result<()> {
+        let mut assets = Vec::new();
+        for head in self.iter_documents() {
+            // staged erasure of an asset keeps the BLOB, as reset would restore the asset
+            let Some(document) = head
+                .iter_all_snapshots()
+                .find(|document| document.document_type.is(ASSET_TYPE))
+            else {
+                continue;
+            };
+            let asset: Asset = document.clone().convert()?;
+
+            let storage_blob_path = self.paths.get_storage_blob_path(&asset.id);
+            let blob_file_name = get_file_name(&storage_blob_path);
+            let mut backup_blob_paths = Vec::new();
+            for backup_dir in backup_dirs {
+                backup_blob_paths.extend(find_backup_blob(backup_dir, blob_file_name)?);
+            }
+
+            assets.push(AssetBlob {
+                blob_path: self.get_blob_path(&asset.id)?,
+                backup_blob_paths,
+                id: asset.id,
+                data: asset.data,
+            });
+        }
+
+        report.checked_blobs = assets.len();
+
+        let issues = assets
+            .into_par_iter()
+            .filter_map(check_asset_blob)
+            .collect::<Vec<_>>();
+        report.issues.extend(issues);
+
+        Ok(())
+    }
+}
+
+/// Returns the keys of the snapshots that aren't older than any other snapshot of the document.
+fn find_latest_keys(keys: &HashSet<DocumentKey>) -> HashSet<&DocumentKey> {
+    let mut revs_by_id: HashMap<&Id, Vec<&Revision>> = HashMap::new();
+    for key in keys {
+        revs_by_id.entry(&key.id).or_default().push(&key.rev);
+    }
+
+    keys.iter()
+        .filter(|key| {
+            revs_by_id[&key.id]
+                .iter()
+                .all(|rev| !key.rev.is_older_than(rev))
+        })
+        .collect()
+}
+
+fn check_asset_blob(asset: AssetBlob) -> Option<FsckIssue> {
+    let (kind, details) = match &asset.blob_path {
+        None => (FsckIssueKind::Missing, "BLOB file is missing".to_string()),
+        Some(blob_path) => match verify_blob(blob_path, &asset.data) {
+            Ok(None) => return None,
+            Ok(Some(mismatch)) => (FsckIssueKind::Mismatched, mismatch),
+            Err(err) => (FsckIssueKind::Corrupt, format!("{err:#}")),
+        },
+    };
+
+    let repair_from = asset
+        .backup_blob_paths
+        .into_iter()
+        .filter(|backup_blob_path| matches!(verify_blob(backup_blob_path, &asset.data), Ok(None)))
+        .collect();
+
+    Some(FsckIssue {
+        kind,
+        id: Some(asset.id),
+        rev: None,
+        details,
+        repair_from,
+    })
+}
+
+/// Returns the description of a mismatch if the BLOB doesn't decrypt into the asset content.
+fn verify_blob(blob_path: &str, asset_data: &AssetData) -> Result<Option<String>> {
+    let blob_key = AgeKey::from_age_x25519_key(asset_data.age_x25519_key.clone())?;
+    let metadata = read_blob_metadata(blob_path, blob_key)?;
+
+    if metadata.size != asset_data.size {
+        return Ok(Some(format!(
+            "BLOB size {} doesn't match asset size {}",
+            metadata.size, asset_data.size
+        )));
+    }
+
+    if metadata.content_sha256 != asset_data.content_sha256 {
+        return Ok(Some(
+            "BLOB SHA-256 doesn't match asset content_sha256".to_string(),
+        ));
+    }
+
+    Ok(None)
+}
+
+fn format_validation_error(err: &ValidationError) -> String {
+    match err {
+        ValidationError::FieldError { errors } => {
+            let mut errors = errors
+                .iter()
+                .map(|(field, errors)| format!("field '{field}': {}", errors.join(", ")))
+                .collect::<Vec<_>>();
+            errors.sort();
+
+            errors.join("; ")
+        }
+        ValidationError::DocumentError { errors } => errors.join("; "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+    use serde_json::json;
+
+    use baza_common::{TempFile, get_file_name};
+    use baza_storage::crypto::age::AgeKey;
+
+    use crate::{
+        BazaManager, BazaStorage,
+        baza::blobs::write_and_encrypt_blob,
+        entities::{DocumentKey, Id, Revision, new_document},
+    };
+
+    use super::FsckIssueKind;
+
+    #[test]
+    fn test_fsck() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("fsck", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut()?;
+
+        let mut asset_ids = Vec::new();
+        for data in ["valid", "corrupt", "missing", "mismatched"] {
+            let file = temp_dir.new_child("asset");
+            file.write_str(data)?;
+            asset_ids.push(baza.create_asset(&file.path)?.id);
+        }
+        let doc = new_document(json!({ "test": "value" }));
+        baza.stage_document(doc.clone(), &None)?;
+        baza.commit()?;
+
+        let report = baza.fsck(&[])?;
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!(report.checked_blobs, 4);
+        assert!(report.checked_snapshots > 0);
+
+        let [_valid_id, corrupt_id, missing_id, mismatched_id] = asset_ids.as_slice() else {
+            unreachable!()
+        };
+
+        // back up the BLOB before removing it
+        let backup_dir = format!("{}/backup", temp_dir.path);
+        fs::create_dir_all(format!("{backup_dir}/data"))?;
+        let missing_blob_path = baza.paths.get_storage_blob_path(missing_id);
+        let backup_blob_path = format!("{backup_dir}/data/{}", get_file_name(&missing_blob_path));
+        fs::copy(&missing_blob_path, &backup_blob_path)?;
+        fs::remove_file(&missing_blob_path)?;
+
+        let corrupt_blob_path = baza.paths.get_storage_blob_path(corrupt_id);
+        let mut data = fs::read(&corrupt_blob_path)?;
+        let len = data.len();
+        data[len - 10] ^= 2;
+        fs::write(&corrupt_blob_path, data)?;
+
+        let mismatched_blob_path = baza.paths.get_storage_blob_path(mismatched_id);
+        let mismatched_asset = baza.get_asset(mismatched_id)?.unwrap();
+        let other_file = temp_dir.new_child("other");
+        other_file.write_str("other content")?;
+        fs::remove_file(&mismatched_blob_path)?;
+        write_and_encrypt_blob(
+            &other_file.path,
+            &mismatched_blob_path,
+            AgeKey::from_age_x25519_key(mismatched_asset.data.age_x25519_key)?,
+        )?;
+
+        fs::write(baza.paths.get_state_blob_path(&Id::new()), "orphaned")?;
+
+        // replace the committed snapshot with one that doesn't match the schema
+        let committed = baza.must_get_document(&doc.id)?.clone();
+        let mut invalid = committed.clone();
+        invalid.id = Id::new();
+        invalid.data.set("unknown", "value");
+
+        // older snapshots are validated only against the index key
+        let mut outdated = new_document(json!({ "test": "old" }));
+        outdated.rev = Revision::from_safe_string("a:1")?;
+        outdated.data.set("unknown", "value");
+        let mut latest = outdated.clone();
+        latest.rev = Revision::from_safe_string("a:2")?;
+        latest.data.remove("unknown");
+
+        let db_file = baza.paths.storage_main_db_file.clone();
+        let patched_db_file = format!("{}/patched.gz.age", temp_dir.path);
+        BazaStorage::read_file(&db_file, baza.key.clone())?.rewrite_and_save_to_file(
+            &patched_db_file,
+            [
+                (DocumentKey::for_document(&committed), None),
+                (DocumentKey::for_document(&invalid), Some(invalid.clone())),
+                (DocumentKey::for_document(&outdated), Some(outdated.clone())),
+                (DocumentKey::for_document(&latest), Some(latest.clone())),
+            ]
+            .into(),
+        )?;
+        fs::rename(&patched_db_file, &db_file)?;
+
+        let report = baza.fsck(std::slice::from_ref(&backup_dir))?;
+        assert_eq!(report.issues.len(), 6, "{:?}", report.issues);
+
+        let find_issue = |id: &Id| {
+            report
+                .issues
+                .iter()
+                .find(|issue| issue.id.as_ref() == Some(id))
+                .unwrap()
+        };
+
+        let issue = find_issue(missing_id);
+        assert_eq!(issue.kind, FsckIssueKind::Missing);
+        assert_eq!(issue.repair_from, vec![backup_blob_path]);
+
+        let issue = find_issue(corrupt_id);
+        assert_eq!(issue.kind, FsckIssueKind::Corrupt);
+        assert!(issue.repair_from.is_empty());
+
+        assert_eq!(find_issue(mismatched_id).kind, FsckIssueKind::Mismatched);
+
+        let issue = find_issue(&doc.id);
+        assert_eq!(issue.kind, FsckIssueKind::Missing);
+        assert_eq!(issue.rev.as_ref(), Some(&committed.rev));
+
+        let issue = find_issue(&invalid.id);
+        assert_eq!(issue.kind, FsckIssueKind::Mismatched);
+        assert!(issue.details.contains("unknown"), "{}", issue.details);
+
+        assert_eq!(report.count_issues(FsckIssueKind::Orphaned), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use std::{collections::HashSet, fmt};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use baza_common::{FsTransaction, get_file_size, log};
+
+use crate::{BazaStorage, entities::Id, schema::ASSET_TYPE};
+
+use super::Baza;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BlobLocation {
+    Storage,
+    State,
+}
+
+impl fmt::Display for BlobLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            BlobLocation::Storage => "storage",
+            BlobLocation::State => "state",
+        };
+
+        write!(f, "{value}")
+    }
+}
+
+/// A BLOB file that no asset document uses.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedBlob {
+    pub id: Id,
+    pub location: BlobLocation,
+    /// Size of the encrypted file, in bytes.
+    pub size: u64,
+    /// The BLOB belongs to an erased asset, rather than to an unknown document.
+    pub erased_asset: bool,
+}
+
+/// An asset that no document references.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UnreferencedAsset {
+    pub id: Id,
+    pub filename: String,
+    /// Size of the encrypted BLOB file, in bytes.
+    pub size: u64,
+}
+
+/// Garbage that garbage collection removes, or would remove.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct GarbageReport {
+    pub orphaned_blobs: Vec<OrphanedBlob>,
+    pub unreferenced_assets: Vec<UnreferencedAsset>,
+}
+
+impl GarbageReport {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.orphaned_blobs.is_empty() && self.unreferenced_assets.is_empty()
+    }
+
+    #[must_use]
+    pub fn get_orphaned_blobs_size(&self) -> u64 {
+        self.orphaned_blobs.iter().map(|blob| blob.size).sum()
+    }
+
+    #[must_use]
+    pub fn get_unreferenced_assets_size(&self) -> u64 {
+        self.unreferenced_assets
+            .iter()
+            .map(|asset| asset.size)
+            .sum()
+    }
+}
+
+impl Baza {
+    /// Ids of the documents that are assets in any of their snapshots, including the original
+    /// snapshots of staged changes, as resetting the changes would restore them.
+    fn collect_asset_ids(&self) -> HashSet<&Id> {
+        self.iter_documents()
+            .filter(|head| {
+                head.iter_all_snapshots()
+                    .any(|document| document.document_type.is(ASSET_TYPE))
+            })
+            .map(|head| head.get_id())
+            .collect()
+    }
+
+    /// Ids of the documents in the db files that weren't merged into the main db file yet, like
+    /// the db files of other devices that arrived by file sync. Their BLOBs may arrive first.
+    fn collect_unmerged_document_ids(&self) -> Result<HashSet<Id>> {
+        let mut ids = HashSet::new();
+
+        for db_file in self.paths.list_storage_db_files()? {
+            if db_file == self.paths.storage_main_db_file {
+                continue;
+            }
+
+            let storage = BazaStorage::read_file(&db_file, self.key.clone())
+                .with_context(|| format!("Failed to read db file {db_file}"))?;
+
+            ids.extend(storage.index.iter().map(|key| key.id.clone()));
+        }
+
+        Ok(ids)
+    }
+
+    pub(super) fn find_orphaned_blobs(&self) -> Result<Vec<OrphanedBlob>> {
+        let asset_ids = self.collect_asset_ids();
+        let unmerged_ids = self.collect_unmerged_document_ids()?;
+
+        let mut orphaned_blobs = Vec::new();
+        for (location, blob_ids) in [
+            (BlobLocation::Storage, self.paths.list_storage_blobs()?),
+            (BlobLocation::State, self.paths.list_state_blobs()?),
+        ] {
+            for id in blob_ids {
+                if asset_ids.contains(&id) {
+                    continue;
+                }
+
+                // storage BLOBs are shared with other devices, and may belong to their new assets
+                if location == BlobLocation::Storage && unmerged_ids.contains(&id) {
+                    continue;
+                }
+
+                let size = get_file_size(&self.get_blob_file_path(&id, location))?;
+                let erased_asset = self
+                    .get_document(&id)
+                    .is_some_and(|head| head.is_original_erased() || head.is_staged_erased());
+
+                orphaned_blobs.push(OrphanedBlob {
+                    id,
+                    location,
+                    size,
+                    erased_asset,
+                });
+            }
+        }
+
+        orphaned_blobs.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+        Ok(orphaned_blobs)
+    }
+
+    fn find_unreferenced_assets(&self) -> Result<Vec<UnreferencedAsset>> {
+        let mut unreferenced_assets = Vec::new();
+        for head in self.iter_documents() {
+            let document = head.get_single_document();
+            if !document.document_type.is(ASSET_TYPE) || head.is_conflict() {
+                continue;
+            }
+
+            if !self.find_document_backrefs(&document.id).is_empty()
+                || !self.find_document_collections(&document.id).is_empty()
+            {
+                continue;
+            }
+
+            let size = match self.get_blob_path(&document.id)? {
+                Some(blob_path) => get_file_size(&blob_path)?,
+                None => 0,
+            };
+
+            unreferenced_assets.push(UnreferencedAsset {
+                id: document.id.clone(),
+                filename: document
+                    .data
+                    .get_str("filename")
+                    .unwrap_or_default()
+                    .to_string(),
+                size,
+            });
+        }
+
+        unreferenced_assets.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+        Ok(unreferenced_assets)
+    }
+
+    fn get_blob_file_path(&self, id: &Id, location: BlobLocation) -> String {
+        match location {
+            BlobLocation::Storage => self.paths.get_storage_blob_path(id),
+            BlobLocation::State => self.paths.get_state_blob_path(id),
+        }
+    }
+
+    /// Finds BLOB files that no asset uses, including BLOBs of the erased assets, and assets
+    /// that no document references. Storage BLOBs of the documents in the db files that weren't
+    /// merged yet are never orphaned.
+    pub fn find_garbage(&self) -> Result<GarbageReport> {
+        Ok(GarbageReport {
+            orphaned_blobs: self.find_orphaned_blobs()?,
+            unreferenced_assets: self.find_unreferenced_assets()?,
+        })
+    }
+
+    /// Removes the orphaned BLOB files in one file system transaction. With
+    /// `erase_unreferenced_assets`, also erases assets that no document references; their BLOBs
+    /// are removed like BLOBs of other erased assets.
+    pub fn collect_garbage(&mut self, erase_unreferenced_assets: bool) -> Result<GarbageReport> {
+        let mut report = self.find_garbage()?;
+
+        if !report.orphaned_blobs.is_empty() {
+            let mut fs_tx = FsTransaction::new();
+            for blob in &report.orphaned_blobs {
+                fs_tx.remove_file(self.get_blob_file_path(&blob.id, blob.location))?;
+            }
+            fs_tx.commit().context("Failed to remove orphaned BLOBs")?;
+
+            log::info!(
+                "Removed {} orphaned BLOBs, {} bytes",
+                report.orphaned_blobs.len(),
+                report.get_orphaned_blobs_size()
+            );
+        }
+
+        if !erase_unreferenced_assets {
+            report.unreferenced_assets.clear();
+            return Ok(report);
+        }
+
+        for asset in &report.unreferenced_assets {
+            self.erase_document(&asset.id)?;
+        }
+
+        if !report.unreferenced_assets.is_empty() {
+            log::info!(
+                "Erased {} unreferenced assets",
+                report.unreferenced_assets.len()
+            );
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use baza_common::{TempFile, file_exists};
+
+    use crate::{
+        Baza, BazaManager,
+        entities::{Document, DocumentType, Id, new_document},
+        schema::{ASSET_TYPE, DataDescription, DataSchema, Field, FieldType},
+    };
+
+    use super::BlobLocation;
+
+    fn new_schema() -> DataSchema {
+        DataSchema::new(
+            "test",
+            vec![DataDescription {
+                document_type: "note".into(),
+                title_format: "note".into(),
+                fields: vec![Field {
+                    name: "cover".into(),
+                    field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
+                    mandatory: false,
+                    readonly: false,
+                    ordered: None,
+                }],
+            }],
+        )
+    }
+
+    #[test]
+    fn test_collect_garbage() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("collect_garbage", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests_with_schema(&temp_dir.path, new_schema());
+        let mut baza = manager.open_mut()?;
+
+        let create_asset = |baza: &mut Baza, data: &str| -> Result<Id> {
+            let file = temp_dir.new_child("asset");
+            file.write_str(data)?;
+
+            Ok(baza.create_asset(&file.path)?.id)
+        };
+
+        let referenced_id = create_asset(&mut baza, "referenced")?;
+        let unreferenced_id = create_asset(&mut baza, "unreferenced")?;
+        let erased_id = create_asset(&mut baza, "erased")?;
+        let note =
+            Document::new(DocumentType::new("note")).with_data(json!({ "cover": referenced_id }));
+        baza.stage_document(note, &None)?;
+        baza.commit()?;
+
+        baza.erase_document(&erased_id)?;
+        baza.commit()?;
+
+        // BLOB of an asset that isn't in the documents
+        let unknown_id = Id::new();
+        let unknown_blob_path = baza.paths.get_state_blob_path(&unknown_id);
+        std::fs::write(&unknown_blob_path, "unknown")?;
+
+        // staged changes keep the BLOB of the committed asset
+        baza.erase_document(&referenced_id)?;
+
+        let report = baza.find_garbage()?;
+        assert_eq!(report.orphaned_blobs.len(), 2);
+        let erased_blob = report
+            .orphaned_blobs
+            .iter()
+            .find(|blob| blob.id == erased_id)
+            .unwrap();
+        assert_eq!(erased_blob.location, BlobLocation::Storage);
+        assert!(erased_blob.erased_asset);
+        let unknown_blob = report
+            .orphaned_blobs
+            .iter()
+            .find(|blob| blob.id == unknown_id)
+            .unwrap();
+        assert_eq!(unknown_blob.location, BlobLocation::State);
+        assert!(!unknown_blob.erased_asset);
+        assert_eq!(
+            report.get_orphaned_blobs_size(),
+            unknown_blob.size + erased_blob.size
+        );
+
+        assert_eq!(report.unreferenced_assets.len(), 1);
+        assert_eq!(report.unreferenced_assets[0].id, unreferenced_id);
+        assert!(report.get_unreferenced_assets_size() > 0);
+
+        baza.reset_all_documents()?;
+
+        let report = baza.collect_garbage(false)?;
+        assert_eq!(report.orphaned_blobs.len(), 2);
+        assert!(report.unreferenced_assets.is_empty());
+        assert!(!file_exists(&unknown_blob_path)?);
+        assert!(!baza.blob_exists(&erased_id)?);
+        assert!(baza.blob_exists(&referenced_id)?);
+        assert!(baza.blob_exists(&unreferenced_id)?);
+
+        let report = baza.collect_garbage(true)?;
+        assert!(report.orphaned_blobs.is_empty());
+        assert_eq!(report.unreferenced_assets.len(), 1);
+        assert!(
+            baza.get_document(&unreferenced_id)
+                .unwrap()
+                .is_staged_erased()
+        );
+        baza.commit()?;
+
+        let report = baza.find_garbage()?;
+        assert_eq!(report.orphaned_blobs.len(), 1);
+        assert_eq!(report.orphaned_blobs[0].id, unreferenced_id);
+        assert!(report.unreferenced_assets.is_empty());
+
+        // BLOB of an asset of another device, that arrived before its db file was merged
+        let synced_asset = new_document(json!({})).with_rev(json!({ "b": 1 }));
+        baza.create_storage_file(
+            &baza.paths.get_storage_file("device2"),
+            std::slice::from_ref(&synced_asset),
+        );
+        let synced_blob_path = baza.paths.get_storage_blob_path(&synced_asset.id);
+        std::fs::write(&synced_blob_path, "synced")?;
+
+        let report = baza.collect_garbage(false)?;
+        assert_eq!(report.orphaned_blobs.len(), 1);
+        assert_eq!(report.orphaned_blobs[0].id, unreferenced_id);
+        assert!(file_exists(&synced_blob_path)?);
+
+        Ok(())
+    }
+}
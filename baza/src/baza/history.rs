@@ -0,0 +1,551 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use baza_common::{FsTransaction, Timestamp, get_file_size, log};
+
+use crate::{
+    BazaStorage,
+    baza_storage::StoragePatch,
+    entities::{DocumentKey, DocumentType, Id, InstanceId, LatestRevComputer, Revision},
+    schema::{
+        HISTORY_SETTINGS_TYPE, HistoryRetentionPolicy, HistorySettingsData,
+        build_history_retention_policy,
+    },
+};
+
+use super::Baza;
+
+/// Snapshots that pruning of the document history removes, or would remove in a dry run.
+#[derive(Serialize, Debug, PartialEq, Eq, Default)]
+pub struct HistoryPruneReport {
+    pub total_snapshots: usize,
+    pub pruned_snapshots: usize,
+    /// Number of documents that lose some of their snapshots.
+    pub pruned_documents: usize,
+    /// Size of the main storage file before pruning, in bytes.
+    pub storage_size: u64,
+    /// Size of the main storage file after pruning, in bytes. In a dry run it is an estimate,
+    /// as the size of the encryption header varies.
+    pub pruned_storage_size: u64,
+}
+
+impl HistoryPruneReport {
+    #[must_use]
+    pub fn get_reclaimed_size(&self) -> u64 {
+        self.storage_size.saturating_sub(self.pruned_storage_size)
+    }
+}
+
+#[derive(Deserialize)]
+struct SnapshotTime {
+    updated_at: Timestamp,
+}
+
+struct SnapshotInfo {
+    rev: Revision,
+    updated_at: Timestamp,
+}
+
+/// Counts the size of the rewritten storage in a dry run.
+#[derive(Default)]
+struct SizeCounter(u64);
+
+impl Write for SizeCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Baza {
+    /// Builds the history retention policy from the history settings documents, ordered by
+    /// document id. Invalid settings documents are ignored.
+    pub fn get_history_retention_policy(&self) -> Option<HistoryRetentionPolicy> {
+        let mut documents = self
+            .state
+            .iter_documents()
+            .map(|head| head.get_single_document())
+            .filter(|document| document.document_type.is(HISTORY_SETTINGS_TYPE))
+            .collect::<Vec<_>>();
+
+        documents.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+        let settings = documents
+            .into_iter()
+            .filter_map(
+                |document| match document.clone().convert::<HistorySettingsData>() {
+                    Ok(settings) => Some(settings.data),
+                    Err(err) => {
+                        log::warn!("Ignoring history settings {}: {err:#}", document.id);
+                        None
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+
+        build_history_retention_policy(&settings)
+    }
+
+    /// Removes old document snapshots from the main storage according to the history retention
+    /// policy. Snapshots that other instances may still need as merge bases are always kept.
+    /// With `dry_run`, only reports what would be removed.
+    pub fn prune_history(&mut self, dry_run: bool) -> Result<HistoryPruneReport> {
+        let db_file = self.paths.storage_main_db_file.clone();
+
+        let storage_size = get_file_size(&db_file)?;
+        let mut report = HistoryPruneReport {
+            storage_size,
+            pruned_storage_size: storage_size,
+            ..Default::default()
+        };
+
+        let Some(policy) = self.get_history_retention_policy() else {
+            log::info!("Prune history: no history settings, keeping all snapshots");
+
+            let storage = BazaStorage::read_file(&db_file, self.key.clone())?;
+            report.total_snapshots = storage.index.len();
+
+            return Ok(report);
+        };
+
+        let storage = BazaStorage::read_file(&db_file, self.key.clone())?;
+        report.total_snapshots = storage.index.len();
+
+        let mut documents: HashMap<Id, Vec<SnapshotInfo>> = HashMap::new();
+        for item in storage {
+            let (key, raw_document) = item?;
+
+            let time: SnapshotTime = serde_json::from_str(&raw_document)
+                .context(format!("Failed to parse document {}", key.serialize()))?;
+
+            documents.entry(key.id).or_default().push(SnapshotInfo {
+                rev: key.rev,
+                updated_at: time.updated_at,
+            });
+        }
+
+        let pruned_keys = select_pruned_snapshots(
+            &policy,
+            &documents,
+            |id| self.state.get_document(id).map(|head| head.get_type()),
+            Timestamp::now(),
+        );
+
+        report.pruned_snapshots = pruned_keys.len();
+        report.pruned_documents = pruned_keys
+            .iter()
+            .map(|key| &key.id)
+            .collect::<HashSet<_>>()
+            .len();
+
+        if pruned_keys.is_empty() {
+            log::info!("Prune history: nothing to prune");
+            return Ok(report);
+        }
+
+        let patch = pruned_keys
+            .into_iter()
+            .map(|key| (key, None))
+            .collect::<StoragePatch>();
+
+        if dry_run {
+            let storage = BazaStorage::read_file(&db_file, self.key.clone())?;
+
+            let mut size_counter = SizeCounter::default();
+            storage.rewrite(&mut size_counter, patch)?;
+            report.pruned_storage_size = size_counter.0;
+
+            return Ok(report);
+        }
+
+        let mut fs_tx = FsTransaction::new();
+
+        let old_db_file = fs_tx.move_to_backup(db_file.clone())?;
+        let storage = BazaStorage::read_file(&old_db_file, self.key.clone())?;
+        storage.rewrite_and_save_to_file(&db_file, patch)?;
+
+        fs_tx.commit()?;
+
+        report.pruned_storage_size = get_file_size(&db_file)?;
+
+        // update snapshots count in the state
+        let storage = BazaStorage::read_file(&db_file, self.key.clone())?;
+        let mut snapshot_counts: HashMap<&Id, usize> = HashMap::new();
+        for key in storage.index.iter() {
+            *snapshot_counts.entry(&key.id).or_insert(0) += 1;
+        }
+        for (id, snapshots_count) in snapshot_counts {
+            self.state.update_snapshots_count(id, snapshots_count)?;
+        }
+        self.save_changes()?;
+
+        log::info!(
+            "Prune history: removed {} of {} snapshots, reclaimed {} bytes",
+            report.pruned_snapshots,
+            report.total_snapshots,
+            report.get_reclaimed_size()
+        );
+
+        Ok(report)
+    }
+}
+
+/// Returns the latest revision that all known instances have seen: every instance's commit
+/// revision includes the revisions it knew about.
+fn get_revision_known_to_all<'r>(revs: impl Iterator<Item = &'r Revision>) -> Revision {
+    let mut latest_instance_revs: HashMap<&InstanceId, &Revision> = HashMap::new();
+
+    for rev in revs {
+        for instance_id in rev.iter_instance_ids() {
+            let latest = latest_instance_revs.entry(instance_id).or_insert(rev);
+
+            if rev.get_version(instance_id) > latest.get_version(instance_id) {
+                *latest = rev;
+            }
+        }
+    }
+
+    Revision::min_all(latest_instance_revs.into_values())
+}
+
+/// Selects snapshots to prune. A snapshot is kept if:
+/// * the document has conflicting latest snapshots, or its type is pinned;
+/// * some instance hasn't seen it yet, or it is the latest snapshot all instances have seen,
+///   so it could be a base for merging future changes of other instances;
+/// * it is one of the `keep_last` latest snapshots of the document;
+/// * it is the latest snapshot of a day within the last `keep_daily_days` days.
+fn select_pruned_snapshots<'d>(
+    policy: &HistoryRetentionPolicy,
+    documents: &HashMap<Id, Vec<SnapshotInfo>>,
+    get_document_type: impl Fn(&Id) -> Option<&'d DocumentType>,
+    now: Timestamp,
+) -> Vec<DocumentKey> {
+    let known_rev = get_revision_known_to_all(
+        documents
+            .values()
+            .flat_map(|snapshots| snapshots.iter().map(|snapshot| &snapshot.rev)),
+    );
+
+    let mut pruned_keys = Vec::new();
+
+    for (id, snapshots) in documents {
+        let Some(document_type) = get_document_type(id) else {
+            continue;
+        };
+        if policy.is_pinned(document_type) {
+            continue;
+        }
+
+        let mut latest = LatestRevComputer::new();
+        latest.update(snapshots.iter().map(|snapshot| &snapshot.rev));
+        if latest.get().len() > 1 {
+            continue;
+        }
+
+        let is_known = |rev: &Revision| !rev.is_concurrent_or_newer_than(&known_rev);
+
+        let mut latest_known = LatestRevComputer::new();
+        latest_known.update(
+            snapshots
+                .iter()
+                .map(|snapshot| &snapshot.rev)
+                .filter(|rev| is_known(rev)),
+        );
+        let latest_known = latest_known.get();
+
+        // from the newest to the oldest
+        let mut snapshots = snapshots.iter().collect::<Vec<_>>();
+        snapshots.sort_by(|a, b| b.rev.history_cmp(&a.rev));
+
+        let oldest_pos = snapshots.len() - 1;
+
+        let mut days = HashSet::new();
+        for (pos, snapshot) in snapshots.into_iter().enumerate() {
+            let age_days = (now - snapshot.updated_at).whole_days();
+            let is_daily = u64::try_from(age_days).unwrap_or_default() < policy.keep_daily_days
+                && days.insert(get_day(&snapshot.updated_at));
+
+            let keep = is_daily
+                || pos < policy.keep_last
+                || !is_known(&snapshot.rev)
+                || latest_known.contains(&snapshot.rev)
+                // the oldest snapshot keeps the creation time of the document
+                || pos == oldest_pos;
+
+            if !keep {
+                pruned_keys.push(DocumentKey::new(id.clone(), snapshot.rev.clone()));
+            }
+        }
+    }
+
+    pruned_keys
+}
+
+fn get_day(timestamp: &Timestamp) -> String {
+    timestamp
+        .format_time("[year]-[month]-[day]")
+        .expect("day format must be valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs, time::Duration};
+
+    use anyhow::Result;
+    use serde_json::{Value, json};
+
+    use baza_common::{TempFile, Timestamp, get_file_size};
+
+    use crate::{
+        BazaManager,
+        entities::{
+            Document, DocumentKey, DocumentType, Id, Revision, new_document, new_test_data,
+        },
+        schema::{HISTORY_SETTINGS_TYPE, HistoryRetentionPolicy},
+    };
+
+    use super::{SnapshotInfo, select_pruned_snapshots};
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn snapshot(rev: Value, updated_at: Timestamp) -> SnapshotInfo {
+        SnapshotInfo {
+            rev: Revision::from_value(rev).unwrap(),
+            updated_at,
+        }
+    }
+
+    fn select(
+        policy: &HistoryRetentionPolicy,
+        documents: &HashMap<Id, Vec<SnapshotInfo>>,
+        now: Timestamp,
+    ) -> Vec<DocumentKey> {
+        let document_type = DocumentType::new("test_type");
+
+        let mut keys = select_pruned_snapshots(policy, documents, |_| Some(&document_type), now);
+        keys.sort_by(|a, b| a.rev.history_cmp(&b.rev));
+
+        keys
+    }
+
+    #[test]
+    fn test_select_pruned_snapshots() -> Result<()> {
+        let start = Timestamp::MIN;
+        let now = start + 20 * DAY;
+
+        let id: Id = "test".into();
+        let documents = HashMap::from([(
+            id.clone(),
+            vec![
+                snapshot(json!({ "a": 1 }), start),
+                snapshot(json!({ "a": 2 }), start + 16 * DAY),
+                snapshot(json!({ "a": 3 }), start + 17 * DAY),
+                snapshot(json!({ "a": 4 }), start + 17 * DAY + DAY / 2),
+                snapshot(json!({ "a": 5 }), start + 19 * DAY),
+            ],
+        )]);
+
+        let mut policy = HistoryRetentionPolicy {
+            keep_last: 1,
+            keep_daily_days: 0,
+            pinned_types: Default::default(),
+        };
+
+        let key = |rev: Value| DocumentKey::new(id.clone(), Revision::from_value(rev).unwrap());
+
+        // the oldest snapshot is always kept
+        assert_eq!(
+            select(&policy, &documents, now),
+            vec![
+                key(json!({ "a": 2 })),
+                key(json!({ "a": 3 })),
+                key(json!({ "a": 4 })),
+            ]
+        );
+
+        // the last snapshot of a day is kept
+        policy.keep_daily_days = 5;
+        assert_eq!(
+            select(&policy, &documents, now),
+            vec![key(json!({ "a": 3 }))]
+        );
+
+        policy.pinned_types = [DocumentType::new("test_type")].into();
+        assert!(select(&policy, &documents, now).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_snapshots_unknown_to_other_instances() -> Result<()> {
+        let now = Timestamp::MIN;
+
+        let policy = HistoryRetentionPolicy {
+            keep_last: 0,
+            keep_daily_days: 0,
+            pinned_types: Default::default(),
+        };
+
+        let id: Id = "test".into();
+        let other_id: Id = "other".into();
+
+        // instance "b" last committed after it has seen { "a": 3 }
+        let documents = HashMap::from([
+            (
+                id.clone(),
+                vec![
+                    snapshot(json!({ "a": 1 }), now),
+                    snapshot(json!({ "a": 2 }), now),
+                    snapshot(json!({ "a": 3 }), now),
+                    snapshot(json!({ "a": 4 }), now),
+                    snapshot(json!({ "a": 5 }), now),
+                ],
+            ),
+            (
+                other_id.clone(),
+                vec![snapshot(json!({ "a": 3, "b": 1 }), now)],
+            ),
+        ]);
+
+        assert_eq!(
+            select(&policy, &documents, now),
+            vec![DocumentKey::new(
+                id.clone(),
+                Revision::from_value(json!({ "a": 2 }))?
+            )]
+        );
+
+        // all snapshots of conflicting documents are kept
+        let documents = HashMap::from([(
+            id.clone(),
+            vec![
+                snapshot(json!({ "a": 1 }), now),
+                snapshot(json!({ "a": 2 }), now),
+                snapshot(json!({ "a": 3 }), now),
+                snapshot(json!({ "a": 2, "b": 1 }), now),
+            ],
+        )]);
+        assert!(select(&policy, &documents, now).is_empty());
+
+        Ok(())
+    }
+
+    /// Deterministic pseudo-random text, so that snapshots don't shrink much when compressed.
+    fn incompressible_text(seed: u64) -> String {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+
+        (0..8 * 1024)
+            .map(|_| {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+
+                char::from(b'a' + (state % 26) as u8)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_prune_history() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("prune_history", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut()?;
+
+        let doc = new_document(json!({ "test": incompressible_text(0) }));
+        let id = doc.id.clone();
+        baza.stage_document(doc, &None)?;
+        baza.commit()?;
+
+        for seed in 1..=4 {
+            let mut doc = baza.must_get_document(&id)?.clone();
+            doc.data = new_test_data(json!({ "test": incompressible_text(seed) }));
+            baza.stage_document(doc, &None)?;
+            baza.commit()?;
+        }
+
+        // without history settings all snapshots are kept
+        let report = baza.prune_history(false)?;
+        assert_eq!(report.total_snapshots, 5);
+        assert_eq!(report.pruned_snapshots, 0);
+
+        let settings = Document::new(DocumentType::new(HISTORY_SETTINGS_TYPE))
+            .with_data(json!({ "keep_last": 2, "pinned_types": "Klingon" }));
+        assert!(baza.stage_document(settings.clone(), &None).is_err());
+
+        let settings = settings.with_data(json!({ "keep_last": 2 }));
+        baza.stage_document(settings, &None)?;
+        baza.commit()?;
+
+        let storage_size = get_file_size(&baza.paths.storage_main_db_file)?;
+
+        let dry_run_report = baza.prune_history(true)?;
+        assert_eq!(dry_run_report.total_snapshots, 6);
+        assert_eq!(dry_run_report.pruned_snapshots, 2);
+        assert_eq!(dry_run_report.pruned_documents, 1);
+        assert_eq!(dry_run_report.storage_size, storage_size);
+        // each pruned snapshot takes more than 4 KiB even when compressed
+        assert!(dry_run_report.get_reclaimed_size() > 8 * 1024);
+        assert_eq!(baza.list_document_snapshots(&id)?.len(), 5);
+        assert_eq!(
+            get_file_size(&baza.paths.storage_main_db_file)?,
+            storage_size
+        );
+
+        let report = baza.prune_history(false)?;
+        assert_eq!(report.pruned_snapshots, 2);
+        assert_eq!(report.storage_size, storage_size);
+        assert!(report.get_reclaimed_size() > 8 * 1024);
+
+        // the estimate differs only by the size of the encryption header
+        assert!(
+            report
+                .pruned_storage_size
+                .abs_diff(dry_run_report.pruned_storage_size)
+                < 256
+        );
+
+        // the oldest snapshot and the last 2 snapshots are kept
+        let snapshots = baza.list_document_snapshots(&id)?;
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|snapshot| snapshot.data.clone())
+                .collect::<Vec<_>>(),
+            [0, 3, 4]
+                .map(|seed| new_test_data(json!({ "test": incompressible_text(seed) })))
+                .to_vec()
+        );
+        assert_eq!(baza.get_document(&id).unwrap().get_snapshots_count(), 3);
+
+        assert_eq!(baza.prune_history(false)?.pruned_snapshots, 0);
+
+        // created_at is restored from the oldest snapshot when the state is rebuilt
+        let created_at = *baza.get_document(&id).unwrap().get_created_at().unwrap();
+        assert_eq!(created_at, snapshots[0].updated_at);
+        drop(baza);
+
+        manager.clear_cached_baza()?;
+        fs::remove_file(&manager.paths.state_file)?;
+
+        let baza = manager.open()?;
+        assert_eq!(
+            baza.get_document(&id).unwrap().get_created_at(),
+            Some(&created_at)
+        );
+
+        Ok(())
+    }
+}
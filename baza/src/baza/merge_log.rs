@@ -0,0 +1,239 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    io::Write,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use baza_common::{Timestamp, create_file_reader, create_file_writer, file_exists, log, read_all};
+use baza_storage::{AgeGzReader, AgeGzWriter, crypto::age::AgeKey};
+
+use crate::{
+    entities::{DocumentType, Id, Revision},
+    merge::MergeDecision,
+};
+
+use super::Baza;
+
+// Older records are dropped, so that the log doesn't grow forever.
+const MAX_MERGE_RECORDS: usize = 10_000;
+
+/// An automatic resolution of a document conflict.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MergeRecord {
+    pub id: Id,
+    pub document_type: DocumentType,
+    pub merged_at: Timestamp,
+    /// The revision of the common ancestor of the branches, if it's known.
+    pub base_rev: Option<Revision>,
+    /// Revisions of the merged branches, from the oldest to the newest.
+    pub revs: Vec<Revision>,
+    pub decisions: Vec<MergeDecision>,
+}
+
+impl MergeRecord {
+    /// Identifies the merge: the document and the merged revisions, regardless of their order.
+    fn get_merge_key(&self) -> (Id, BTreeSet<String>) {
+        let revs = self.revs.iter().map(Revision::to_safe_string).collect();
+
+        (self.id.clone(), revs)
+    }
+}
+
+fn read_merge_log(file: &str, key: AgeKey) -> Result<Vec<MergeRecord>> {
+    if !file_exists(file)? {
+        return Ok(Vec::new());
+    }
+
+    log::debug!("Reading merge log from file {file}");
+
+    let reader = create_file_reader(file)?;
+    let agegz_reader = AgeGzReader::new(reader, key)?;
+
+    let bytes = read_all(agegz_reader)?;
+
+    postcard::from_bytes(&bytes).context("Failed to parse merge log")
+}
+
+fn write_merge_log(file: &str, key: AgeKey, records: &[MergeRecord]) -> Result<()> {
+    log::debug!("Writing merge log to file {file}");
+
+    let writer = create_file_writer(file, true)?;
+    let mut agegz_writer = AgeGzWriter::new(writer, key)?;
+
+    let bytes = postcard::to_allocvec(records).context("Failed to serialize merge log")?;
+    agegz_writer.write_all(&bytes)?;
+
+    let mut writer = agegz_writer.finish()?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+impl Baza {
+    /// Lists automatic conflict resolutions from the newest to the oldest, optionally only for
+    /// the given document.
+    pub fn list_merge_records(&self, id: Option<&Id>) -> Result<Vec<MergeRecord>> {
+        let mut records = read_merge_log(&self.paths.state_merge_log_file, self.key.clone())?;
+
+        if let Some(id) = id {
+            records.retain(|record| &record.id == id);
+        }
+        records.reverse();
+
+        Ok(records)
+    }
+
+    /// Appends records to the merge log. The same merge could be performed again when the state
+    /// is rebuilt, so already logged merges are skipped.
+    pub(super) fn append_merge_records(&self, new_records: Vec<MergeRecord>) -> Result<()> {
+        if new_records.is_empty() {
+            return Ok(());
+        }
+
+        let file = &self.paths.state_merge_log_file;
+
+        let mut records = match read_merge_log(file, self.key.clone()) {
+            Ok(records) => records,
+            Err(err) => {
+                // keep the unreadable log, so that it could be recovered manually
+                let now = Timestamp::now()
+                    .format_time("[year]-[month padding:zero]-[day padding:zero]_[hour padding:zero]-[minute padding:zero]-[second padding:zero]")
+                    .expect("must be valid format");
+                let unreadable_file = format!("{file}-{now}-unreadable");
+                std::fs::rename(file, &unreadable_file).with_context(|| {
+                    format!("Failed to move unreadable merge log {file} to {unreadable_file}")
+                })?;
+
+                log::warn!(
+                    "Failed to read merge log, moved it to {unreadable_file} and starting a new one: {err:#}"
+                );
+
+                Vec::new()
+            }
+        };
+
+        let mut merge_keys = records
+            .iter()
+            .map(MergeRecord::get_merge_key)
+            .collect::<HashSet<_>>();
+
+        let count = records.len();
+        for record in new_records {
+            if merge_keys.insert(record.get_merge_key()) {
+                records.push(record);
+            }
+        }
+
+        let added = records.len() - count;
+        if added == 0 {
+            return Ok(());
+        }
+
+        if records.len() > MAX_MERGE_RECORDS {
+            records.drain(..records.len() - MAX_MERGE_RECORDS);
+        }
+
+        log::info!("Logged {added} automatic merges");
+
+        write_merge_log(file, self.key.clone(), &records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use baza_common::{TempFile, list_files};
+
+    use crate::{
+        BazaManager,
+        entities::new_document,
+        merge::{MergeDecision, MergeDecisionKind},
+    };
+
+    #[test]
+    fn test_log_automatic_merges() {
+        let temp_dir = TempFile::new_with_details("merge_log", "");
+        temp_dir.mkdir().unwrap();
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+
+        let base = new_document(json!({ "test": "base" })).with_rev(json!({ "a": 1 }));
+        let left = base
+            .clone()
+            .with_data(json!({ "test": "base left" }))
+            .with_rev(json!({ "a": 2 }));
+        let right = base
+            .clone()
+            .with_data(json!({ "test": "base", "ref": "other" }))
+            .with_rev(json!({ "a": 1, "b": 1 }));
+
+        {
+            let baza = manager.open().unwrap();
+            assert!(baza.list_merge_records(None).unwrap().is_empty());
+
+            baza.create_storage_file(
+                &manager.paths.get_storage_file("db1"),
+                &[base.clone(), left.clone()],
+            );
+            baza.create_storage_file(
+                &manager.paths.get_storage_file("db2"),
+                &[base.clone(), right.clone()],
+            );
+        }
+        manager.clear_cached_baza().unwrap();
+
+        let baza = manager.open().unwrap();
+        let records = baza.list_merge_records(Some(&base.id)).unwrap();
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record.base_rev, Some(base.rev.clone()));
+        assert_eq!(record.revs.len(), 2);
+        assert!(record.revs.contains(&left.rev));
+        assert!(record.revs.contains(&right.rev));
+
+        let (test_kind, ref_kind) = if record.revs[0] == left.rev {
+            (MergeDecisionKind::TookLeft, MergeDecisionKind::TookRight)
+        } else {
+            (MergeDecisionKind::TookRight, MergeDecisionKind::TookLeft)
+        };
+        assert_eq!(
+            record.decisions,
+            vec![
+                MergeDecision {
+                    field: Some("test".to_string()),
+                    kind: test_kind,
+                },
+                MergeDecision {
+                    field: Some("ref".to_string()),
+                    kind: ref_kind,
+                },
+            ]
+        );
+
+        // the same merge isn't logged twice
+        baza.append_merge_records(records.clone()).unwrap();
+        assert_eq!(baza.list_merge_records(None).unwrap(), records);
+
+        // unreadable log is moved aside rather than overwritten
+        let merge_log_file = &baza.paths.state_merge_log_file;
+        std::fs::write(merge_log_file, "corrupted").unwrap();
+        baza.append_merge_records(records.clone()).unwrap();
+        assert_eq!(baza.list_merge_records(None).unwrap(), records);
+
+        let unreadable_files = list_files(&baza.paths.state_dir)
+            .unwrap()
+            .into_iter()
+            .filter(|file| {
+                file.starts_with(merge_log_file.as_str()) && file.ends_with("-unreadable")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(unreadable_files.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(&unreadable_files[0]).unwrap(),
+            "corrupted"
+        );
+    }
+}
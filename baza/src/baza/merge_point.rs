@@ -0,0 +1,262 @@
+use anyhow::{Context, Result, bail, ensure};
+
+use baza_common::{Timestamp, log};
+
+use crate::{
+    entities::{Document, DocumentLockKey, Id, LatestRevComputer, Revision},
+    merge::{MergeExpert, MergeStrategy},
+};
+
+use super::{Baza, ConflictBranch};
+
+/// A committed snapshot that merged concurrent branches of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergePoint {
+    pub id: Id,
+    /// The revision of the committed merge.
+    pub merge_rev: Revision,
+    pub merged_at: Timestamp,
+    /// The revision of the common ancestor of the branches, if there's a single one.
+    pub base_rev: Option<Revision>,
+    /// Merged branches from the oldest to the newest.
+    pub branches: Vec<ConflictBranch>,
+}
+
+struct MergeSnapshots {
+    merge: Document,
+    base: Option<Document>,
+    branches: Vec<Document>,
+}
+
+impl Baza {
+    /// Finds the latest committed snapshot which revision follows several concurrent revisions,
+    /// together with these revisions and their common ancestor.
+    fn find_merge_snapshots(&self, id: &Id) -> Result<MergeSnapshots> {
+        let snapshots = self.list_document_snapshots(id)?;
+
+        for merge in snapshots.iter().rev() {
+            let mut latest = LatestRevComputer::new();
+            latest.update(
+                snapshots
+                    .iter()
+                    .map(|document| &document.rev)
+                    .filter(|rev| rev.is_older_than(&merge.rev)),
+            );
+            let branch_revs = latest.get();
+
+            if branch_revs.len() < 2 {
+                continue;
+            }
+
+            let base_rev = Revision::find_base_rev(
+                &branch_revs,
+                snapshots.iter().map(|document| &document.rev),
+            );
+            let base = base_rev.and_then(|base_rev| {
+                snapshots
+                    .iter()
+                    .find(|document| &document.rev == base_rev)
+                    .cloned()
+            });
+
+            // snapshots are already sorted from the oldest to the newest
+            let branches = snapshots
+                .iter()
+                .filter(|document| branch_revs.contains(&document.rev))
+                .cloned()
+                .collect();
+
+            return Ok(MergeSnapshots {
+                merge: merge.clone(),
+                base,
+                branches,
+            });
+        }
+
+        bail!("Document {id} has no committed merges")
+    }
+
+    /// Returns the latest committed merge of concurrent branches of the document.
+    pub fn find_merge_point(&self, id: &Id) -> Result<MergePoint> {
+        let snapshots = self.find_merge_snapshots(id)?;
+
+        Ok(MergePoint {
+            id: id.clone(),
+            merge_rev: snapshots.merge.rev,
+            merged_at: snapshots.merge.updated_at,
+            base_rev: snapshots.base.map(|base| base.rev),
+            branches: snapshots
+                .branches
+                .iter()
+                .map(|document| ConflictBranch {
+                    rev: document.rev.clone(),
+                    updated_at: document.updated_at,
+                    is_erased: document.is_erased(),
+                })
+                .collect(),
+        })
+    }
+
+    fn ensure_not_conflicted(&self, id: &Id) -> Result<()> {
+        let head = self
+            .get_document(id)
+            .with_context(|| format!("Can't find document {id}"))?;
+        ensure!(
+            !head.is_conflict(),
+            "Document {id} is conflicted; resolve the conflict first"
+        );
+
+        Ok(())
+    }
+
+    /// Stages the data of one of the branches of the latest merge, by index in
+    /// `MergePoint::branches`.
+    pub fn revert_document_to_merge_branch(
+        &mut self,
+        id: &Id,
+        branch: usize,
+        lock_key: &Option<DocumentLockKey>,
+    ) -> Result<&Document> {
+        self.ensure_not_conflicted(id)?;
+
+        let merge_point = self.find_merge_point(id)?;
+        let Some(branch) = merge_point.branches.get(branch) else {
+            bail!(
+                "Branch index {branch} is out of range, merge of document {id} has {} branches",
+                merge_point.branches.len()
+            );
+        };
+
+        log::debug!(
+            "Reverting document {id} to merge branch {}",
+            branch.rev.to_safe_string()
+        );
+
+        self.revert_document_to_snapshot(id, &branch.rev.clone(), lock_key)
+    }
+
+    /// Merges the branches of the latest merge again with the given strategy, and stages the
+    /// result.
+    pub fn remerge_document(
+        &mut self,
+        id: &Id,
+        strategy: MergeStrategy,
+        lock_key: &Option<DocumentLockKey>,
+    ) -> Result<&Document> {
+        self.ensure_not_conflicted(id)?;
+
+        log::debug!("Merging branches of document {id} again using {strategy} strategy");
+
+        let snapshots = self.find_merge_snapshots(id)?;
+        let merged = MergeExpert::new(self.get_schema().clone())
+            .with_strategy(strategy)
+            .merge_originals(snapshots.base, snapshots.branches.iter().collect())?;
+        ensure!(
+            !merged.is_erased(),
+            "All branches of document {id} erased it; erase the document instead"
+        );
+
+        let mut document = self.must_get_document(id)?.clone();
+        ensure!(
+            document.document_type == merged.document_type,
+            "Can't merge document {id} of type {} as type {}",
+            document.document_type,
+            merged.document_type
+        );
+        document.data = merged.data;
+
+        Ok(self.stage_document(document, lock_key)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use baza_common::TempFile;
+
+    use crate::{BazaManager, entities::new_document, merge::MergeStrategy};
+
+    #[test]
+    fn test_undo_merge() {
+        let temp_dir = TempFile::new_with_details("merge_point", "");
+        temp_dir.mkdir().unwrap();
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+
+        let base = new_document(json!({ "test": "base" })).with_rev(json!({ "a": 1 }));
+        let left = base
+            .clone()
+            .with_data(json!({ "test": "base left" }))
+            .with_rev(json!({ "a": 2 }));
+        let right = base
+            .clone()
+            .with_data(json!({ "test": "base right" }))
+            .with_rev(json!({ "a": 1, "b": 1 }));
+
+        {
+            let baza = manager.open().unwrap();
+            baza.create_storage_file(
+                &manager.paths.get_storage_file("db1"),
+                &[base.clone(), left.clone()],
+            );
+            baza.create_storage_file(
+                &manager.paths.get_storage_file("db2"),
+                &[base.clone(), right.clone()],
+            );
+        }
+        manager.clear_cached_baza().unwrap();
+
+        let mut baza = manager.open_mut().unwrap();
+        assert!(baza.find_merge_point(&base.id).is_err());
+        assert!(
+            baza.remerge_document(&base.id, MergeStrategy::Auto, &None)
+                .is_err(),
+            "Unresolved conflict can't be merged again"
+        );
+
+        baza.commit().unwrap();
+
+        let merge_point = baza.find_merge_point(&base.id).unwrap();
+        assert_eq!(merge_point.base_rev, Some(base.rev.clone()));
+        assert_eq!(merge_point.branches.len(), 2);
+        assert_eq!(
+            merge_point.merge_rev,
+            baza.get_document(&base.id)
+                .unwrap()
+                .get_single_document()
+                .rev
+        );
+
+        let branch_value = |index: usize| {
+            if merge_point.branches[index].rev == left.rev {
+                json!("base left")
+            } else {
+                json!("base right")
+            }
+        };
+
+        let document = baza
+            .revert_document_to_merge_branch(&base.id, 0, &None)
+            .unwrap();
+        assert_eq!(document.data.get("test"), Some(&branch_value(0)));
+        assert!(
+            baza.revert_document_to_merge_branch(&base.id, 2, &None)
+                .is_err()
+        );
+
+        let document = baza
+            .remerge_document(&base.id, MergeStrategy::PreferNewer, &None)
+            .unwrap();
+        assert_eq!(document.data.get("test"), Some(&branch_value(1)));
+
+        let document = baza
+            .remerge_document(&base.id, MergeStrategy::Auto, &None)
+            .unwrap();
+        let value = document.data.get_str("test").unwrap();
+        assert!(value.contains("left") && value.contains("right"));
+
+        // the merge point stays the same until the next merge
+        baza.commit().unwrap();
+        assert_eq!(baza.find_merge_point(&base.id).unwrap(), merge_point);
+    }
+}
@@ -1,5 +1,16 @@
+mod activity;
+mod asset_dedupe;
+mod asset_metadata;
+mod asset_text;
 mod blobs;
+mod conflicts;
+mod fsck;
+mod gc;
+mod history;
+mod merge_log;
+mod merge_point;
 mod stats;
+mod time_travel;
 mod validator;
 
 use std::{
@@ -11,11 +22,14 @@ use std::{
 use anyhow::{Context, Result, bail, ensure};
 use thiserror::Error;
 
-use baza_common::{FsTransaction, Timestamp, file_exists, get_file_name, get_media_type, log};
+use baza_common::{
+    FsTransaction, Timestamp, bytes_to_hex_string, create_file_reader, file_exists,
+    get_file_hash_sha256, get_file_name, get_media_type, log,
+};
 use baza_storage::crypto::age::AgeKey;
 
 use crate::{
-    BazaInfo, BazaState, BazaStorage, DocumentHead, Filter, ListPage, Locks,
+    BazaInfo, BazaState, BazaStorage, DocumentExpert, DocumentHead, Filter, ListPage, Locks,
     baza_paths::BazaPaths,
     baza_storage::{STORAGE_VERSION, create_storage_patch},
     entities::{
@@ -23,12 +37,30 @@ use crate::{
         LatestRevComputer, Revision,
     },
     merge::MergeExpert,
-    schema::{ASSET_TYPE, Asset, AssetData, DataSchema},
+    schema::{
+        ASSET_TYPE, Asset, AssetData, DataSchema, SAVED_SEARCH_TYPE, SavedSearch,
+        TYPE_DEFINITION_TYPE,
+    },
 };
 
+pub use activity::{ActivityEvent, ActivityFilter, ActivityKind, ActivityPage};
+pub use asset_dedupe::{AssetsDedupeReport, DuplicateAsset};
+pub use asset_text::PendingAssetText;
+pub use conflicts::{ConflictBranch, ConflictField, DocumentConflict, FieldResolution};
+pub use fsck::{FsckIssue, FsckIssueKind, FsckReport};
+pub use gc::{BlobLocation, GarbageReport, OrphanedBlob, UnreferencedAsset};
+pub use history::HistoryPruneReport;
+pub use merge_log::MergeRecord;
+pub use merge_point::MergePoint;
 pub use stats::{BLOBSCount, DocumentsCount};
+use time_travel::AsOfStateCache;
+pub use time_travel::{AsOf, BazaView};
 pub use validator::ValidationError;
 
+pub(crate) use blobs::read_blob_metadata;
+
+use asset_metadata::extract_asset_metadata;
+
 use super::baza_storage::DocumentsIndex;
 
 #[derive(Error, Debug)]
@@ -43,6 +75,7 @@ pub struct Baza {
     state_file_modification_time: Timestamp,
     paths: BazaPaths,
     key: AgeKey,
+    as_of_state: AsOfStateCache,
     #[cfg(test)]
     commit_test_action: Option<(CommitCheckpoint, CommitTestAction)>,
 }
@@ -105,6 +138,7 @@ impl Baza {
             state_file_modification_time,
             paths,
             key,
+            as_of_state: AsOfStateCache::default(),
             #[cfg(test)]
             commit_test_action: None,
         })
@@ -132,6 +166,7 @@ impl Baza {
             state_file_modification_time,
             paths,
             key,
+            as_of_state: AsOfStateCache::default(),
             #[cfg(test)]
             commit_test_action: None,
         })
@@ -143,12 +178,15 @@ impl Baza {
         let mut storage =
             BazaStorage::read_file(&self.paths.storage_main_db_file, self.key.clone())?;
 
-        let latest_snapshots_count = update_state_from_storage(&mut self.state, &mut storage)?;
+        let (latest_snapshots_count, merge_records) =
+            update_state_from_storage(&mut self.state, &mut storage)?;
 
         if latest_snapshots_count > 0 {
             log::info!("Got {latest_snapshots_count} latest snapshots from the storage");
         }
 
+        self.append_merge_records(merge_records)?;
+
         self.save_changes()?;
 
         Ok(())
@@ -186,10 +224,15 @@ impl Baza {
         self.state.get_single_latest_revision()
     }
 
+    /// Returns the schema extended with the custom document types defined in this baza.
     pub fn get_schema(&self) -> &DataSchema {
         self.state.get_schema()
     }
 
+    pub fn get_document_expert(&self) -> DocumentExpert<'_> {
+        DocumentExpert::new(self.get_schema())
+    }
+
     pub fn get_storage_dir(&self) -> &str {
         &self.paths.storage_dir
     }
@@ -257,6 +300,19 @@ impl Baza {
     pub fn erase_document(&mut self, id: &Id) -> Result<()> {
         log::debug!("Erasing document {id}");
 
+        let document = self.must_get_document(id)?;
+        if document.document_type.is(TYPE_DEFINITION_TYPE) {
+            let defined_type =
+                DocumentType::new(document.data.get_str("document_type").unwrap_or_default());
+
+            ensure!(
+                !self
+                    .iter_documents()
+                    .any(|head| head.get_single_document().document_type == defined_type),
+                "Can't erase definition of document type '{defined_type}' while documents of this type exist"
+            );
+        }
+
         self.state.erase_document(id)
     }
 
@@ -303,6 +359,22 @@ impl Baza {
         result
     }
 
+    /// Builds a list filter for the first page of the saved search results.
+    pub fn get_saved_search_filter(&self, id: &Id) -> Result<Filter> {
+        let document = self.must_get_document(id)?;
+        ensure!(
+            document.document_type.is(SAVED_SEARCH_TYPE),
+            "Document {id} isn't a saved search"
+        );
+
+        let saved_search: SavedSearch = document.clone().convert()?;
+
+        saved_search
+            .data
+            .to_filter(self.get_schema())
+            .with_context(|| format!("Saved search {id} is invalid"))
+    }
+
     /// Reads all committed snapshots for one document from the encrypted storage history.
     pub fn list_document_snapshots(&self, id: &Id) -> Result<Vec<Document>> {
         let mut storage =
@@ -389,6 +461,8 @@ impl Baza {
         Ok(Some(asset))
     }
 
+    /// Returns a decrypting reader of the asset BLOB. Seeking decrypts only the chunk that
+    /// contains the position, so HTTP range requests don't decrypt the preceding data.
     pub fn get_asset_data(&self, asset_id: &Id) -> Result<impl Read + Seek + use<>> {
         let asset = self.get_asset(asset_id)?.context("Asset not found")?;
 
@@ -405,6 +479,7 @@ impl Baza {
     }
 
     /// Creates an encrypted asset from a local file using an application-provided display name.
+    /// If there's an asset with the same content already, it is returned instead.
     ///
     /// The application layer supplies names from uploads, URLs, or HTTP headers;
     /// the storage core owns blob encryption, media metadata, and document staging.
@@ -421,6 +496,20 @@ impl Baza {
         );
 
         let media_type = get_media_type(file_path)?;
+
+        // check for an asset with the same content before encrypting the file
+        let content_sha256 =
+            bytes_to_hex_string(&get_file_hash_sha256(create_file_reader(file_path)?)?);
+        let size = std::fs::metadata(file_path)?.len();
+        if let Some(asset) = self.find_asset_by_content(&content_sha256, size)? {
+            log::info!(
+                "Reusing asset {} with the same content as {file_path}",
+                asset.id
+            );
+
+            return Ok(asset);
+        }
+
         let blob_key = AgeKey::generate_age_x25519_key();
 
         let age_x25519_key = blob_key.serialize();
@@ -428,21 +517,28 @@ impl Baza {
         let asset_id = Id::new();
         let metadata = self.add_blob(&asset_id, file_path, blob_key)?;
 
-        let mut asset = Document::new_with_data(
-            DocumentType::new(ASSET_TYPE),
-            AssetData {
-                filename,
-                media_type,
-                size: metadata.size,
-                content_sha256: metadata.content_sha256,
-                age_x25519_key,
-            },
+        if metadata.content_sha256 != content_sha256 {
+            self.remove_state_blob(&asset_id)?;
+            bail!("Asset source {file_path} changed while it was being added");
+        }
+
+        let mut data = AssetData::new(
+            filename,
+            media_type,
+            metadata.size,
+            metadata.content_sha256,
+            age_x25519_key,
         );
+        extract_asset_metadata(file_path, &mut data);
+
+        let mut asset = Document::new_with_data(DocumentType::new(ASSET_TYPE), data);
         asset.id = asset_id;
 
         let document = asset.into_document()?;
         let document = self.stage_document(document, &None)?.clone();
 
+        self.index_asset_text(&document.id)?;
+
         log::info!("Created asset {} from {file_path}", document.id);
 
         document.convert()
@@ -530,8 +626,11 @@ impl Baza {
         storage.rewrite_and_save_to_file(&self.paths.storage_main_db_file, patch)?;
         self.run_commit_test_action(CommitCheckpoint::DbWritten)?;
 
-        // backup state file
+        // backup state file; the whole state is written into a new state file
         fs_tx.move_to_backup(self.paths.state_file.clone())?;
+        for segment_file in BazaState::list_state_file_segments(&self.paths.state_file)? {
+            fs_tx.move_to_backup(segment_file)?;
+        }
         self.run_commit_test_action(CommitCheckpoint::StateBackedUp)?;
 
         // write changes to state file
@@ -638,12 +737,13 @@ fn create_index_map(index: &DocumentsIndex) -> HashMap<Id, (HashSet<Revision>, O
     result
 }
 
+/// Returns the number of new snapshots, and the records of automatically merged conflicts.
 fn update_state_from_storage<R: Read>(
     state: &mut BazaState,
     storage: &mut BazaStorage<R>,
-) -> Result<usize> {
+) -> Result<(usize, Vec<MergeRecord>)> {
     if state.has_staged_documents() {
-        return Ok(0);
+        return Ok((0, Vec::new()));
     }
 
     let storage_info = storage.get_info()?;
@@ -684,27 +784,62 @@ fn update_state_from_storage<R: Read>(
                 acc
             });
 
+    // the first snapshot of a document in the storage is its oldest one
+    let mut first_snapshot_keys = HashMap::new();
+    for key in storage.index.iter() {
+        let has_created_at = state
+            .get_document(&key.id)
+            .is_some_and(|head| head.get_created_at().is_some());
+
+        if !has_created_at && !first_snapshot_keys.contains_key(&key.id) {
+            first_snapshot_keys.insert(key.id.clone(), key.clone());
+        }
+    }
+    let mut pending_first_snapshot_keys = first_snapshot_keys
+        .values()
+        .cloned()
+        .collect::<HashSet<_>>();
+
     // read necessary snapshots from the storage
     let mut latest_snapshots = HashMap::new();
-    while !latest_snapshot_keys.is_empty() {
+    let mut created_times = HashMap::new();
+    while !latest_snapshot_keys.is_empty() || !pending_first_snapshot_keys.is_empty() {
         let (ref key, ref raw_document) = storage.next().context("No records in the storage")??;
 
-        if !latest_snapshot_keys.contains(key) {
+        let is_latest = latest_snapshot_keys.remove(key);
+        let is_first = pending_first_snapshot_keys.remove(key);
+        if !is_latest && !is_first {
             continue;
         }
 
         let document: Document =
             serde_json::from_str(raw_document).context("Failed to parse raw document")?;
 
-        latest_snapshots.insert(key.clone(), document);
+        if is_first {
+            created_times.insert(key.id.clone(), document.updated_at);
+        }
 
-        latest_snapshot_keys.remove(key);
+        if is_latest {
+            latest_snapshots.insert(key.clone(), document);
+        }
     }
 
-    let merge_expert = MergeExpert::new(state.get_schema().clone());
+    // insert type definitions first, so that documents of custom types could be merged & indexed
+    let mut outdated_documents = storage_index_map.into_iter().collect::<Vec<_>>();
+    outdated_documents.sort_by_cached_key(|(id, (max_revs, _base_rev))| {
+        let is_type_definition = max_revs.iter().any(|rev| {
+            latest_snapshots
+                .get(&DocumentKey::new(id.clone(), rev.clone()))
+                .is_some_and(|document| document.document_type.is(TYPE_DEFINITION_TYPE))
+        });
+
+        !is_type_definition
+    });
+
     let mut latest_snapshots_count = 0;
+    let mut merge_records = Vec::new();
 
-    for (id, (max_revs, base_rev)) in storage_index_map {
+    for (id, (max_revs, base_rev)) in outdated_documents {
         let snapshots = max_revs.into_iter().map(|rev| {
             let key = DocumentKey::new(id.clone(), rev.clone());
 
@@ -731,8 +866,27 @@ fn update_state_from_storage<R: Read>(
                 latest_snapshots.remove(&key).expect("Snapshot is missing")
             });
 
-            let merged = merge_expert
-                .merge_originals(base, document_head.iter_original_snapshots().collect())?;
+            let base_rev = base.as_ref().map(|base| base.rev.clone());
+
+            let mut originals = document_head.iter_original_snapshots().collect::<Vec<_>>();
+            originals.sort_by_key(|document| document.updated_at);
+            let revs = originals
+                .iter()
+                .map(|document| document.rev.clone())
+                .collect();
+
+            let merge_expert = MergeExpert::new(state.get_schema().clone());
+            let (merged, decisions) =
+                merge_expert.merge_originals_with_decisions(base, originals)?;
+
+            merge_records.push(MergeRecord {
+                id: id.clone(),
+                document_type: merged.document_type.clone(),
+                merged_at: Timestamp::now(),
+                base_rev,
+                revs,
+                decisions,
+            });
 
             document_head.modify(merged)?;
         }
@@ -751,7 +905,11 @@ fn update_state_from_storage<R: Read>(
         state.update_snapshots_count(id, snapshots_count)?;
     }
 
-    Ok(latest_snapshots_count)
+    for (id, created_at) in created_times {
+        state.update_created_at(&id, created_at)?;
+    }
+
+    Ok((latest_snapshots_count, merge_records))
 }
 
 #[cfg(test)]
@@ -759,19 +917,33 @@ mod commit_tests;
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use serde_json::json;
 
     use baza_common::TempFile;
     use baza_storage::crypto::age::AgeKey;
 
     use crate::{
-        BazaManager, BazaState, DocumentHead,
+        BazaManager, BazaState, DocumentHead, Filter,
         baza_storage::create_test_storage,
-        entities::{new_document, new_test_data},
+        entities::{Document, DocumentType, new_document, new_test_data},
+        schema::{SAVED_SEARCH_TYPE, SEARCH_SETTINGS_TYPE, TYPE_DEFINITION_TYPE},
     };
 
     use super::update_state_from_storage;
 
+    fn new_type_definition(document_type: &str) -> Document {
+        Document::new(DocumentType::new(TYPE_DEFINITION_TYPE)).with_data(json!({
+            "document_type": document_type,
+            "title_format": "${name}",
+            "fields": json!([
+                { "name": "name", "field_type": { "String": {} }, "mandatory": true, "readonly": false },
+                { "name": "ingredients", "field_type": { "MarkupString": {} }, "mandatory": false, "readonly": false },
+            ]).to_string(),
+        }))
+    }
+
     #[test]
     fn test_update_state_from_storage() {
         let key = AgeKey::generate_age_x25519_key();
@@ -800,8 +972,10 @@ mod tests {
             ],
         );
 
-        let changes = update_state_from_storage(&mut state, &mut storage).unwrap();
+        let (changes, merge_records) = update_state_from_storage(&mut state, &mut storage).unwrap();
         assert_eq!(changes, 3);
+        assert_eq!(merge_records.len(), 1);
+        assert_eq!(merge_records[0].id, doc_a.id);
 
         {
             let head = state.get_document(&doc_a.id).unwrap();
@@ -856,6 +1030,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_document_created_at() {
+        let temp_dir = TempFile::new_with_details("document_created_at", "");
+        temp_dir.mkdir().unwrap();
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let id = {
+            let mut baza = manager.open_mut().unwrap();
+
+            let doc = new_document(json!({ "test": "first" }));
+            let id = doc.id.clone();
+            baza.stage_document(doc, &None).unwrap();
+            baza.commit().unwrap();
+
+            let mut doc = baza.must_get_document(&id).unwrap().clone();
+            doc.data = new_test_data(json!({ "test": "second" }));
+            baza.stage_document(doc, &None).unwrap();
+            baza.commit().unwrap();
+
+            id
+        };
+
+        let first_snapshot = manager
+            .open()
+            .unwrap()
+            .list_document_snapshots(&id)
+            .unwrap()[0]
+            .clone();
+        let created_at = *manager
+            .open()
+            .unwrap()
+            .get_document(&id)
+            .unwrap()
+            .get_created_at()
+            .expect("created_at must be known");
+        assert_eq!(created_at, first_snapshot.updated_at);
+
+        // check if created_at is restored from the storage when the state is rebuilt
+        manager.clear_cached_baza().unwrap();
+        fs::remove_file(&manager.paths.state_file).unwrap();
+
+        let baza = manager.open().unwrap();
+        let head = baza.get_document(&id).unwrap();
+        assert_eq!(head.get_created_at(), Some(&created_at));
+        assert_ne!(head.get_updated_at(), &created_at);
+    }
+
     #[test]
     fn test_revert_document_to_snapshot_stages_historical_data() {
         let temp_dir = TempFile::new_with_details("document_revert", "");
@@ -888,4 +1109,234 @@ mod tests {
         let current = baza.must_get_document(&id).unwrap();
         assert_eq!(current.data, new_test_data(json!({ "test": "second" })));
     }
+
+    #[test]
+    fn test_custom_document_types() {
+        let temp_dir = TempFile::new_with_details("custom_types", "");
+        temp_dir.mkdir().unwrap();
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut().unwrap();
+
+        let recipe = Document::new(DocumentType::new("recipe")).with_data(json!({
+            "name": "Borscht",
+            "ingredients": "beetroot, cabbage",
+        }));
+
+        assert!(baza.stage_document(recipe.clone(), &None).is_err());
+
+        let definition = new_type_definition("recipe");
+        baza.stage_document(definition.clone(), &None).unwrap();
+        assert!(baza.get_schema().is_custom_type(&recipe.document_type));
+
+        baza.stage_document(recipe.clone(), &None).unwrap();
+        assert_eq!(
+            baza.get_document_expert()
+                .get_title(&recipe.document_type, &recipe.data)
+                .unwrap(),
+            "Borscht"
+        );
+
+        let page = baza
+            .list_documents(&Filter {
+                query: "beetroot".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].get_id(), &recipe.id);
+
+        assert!(baza.erase_document(&definition.id).is_err());
+
+        baza.commit().unwrap();
+        drop(baza);
+
+        let manager = BazaManager::new(manager.paths.clone(), manager.get_schema().clone());
+        manager.unlock("test password".into()).unwrap();
+        let baza = manager.open().unwrap();
+
+        assert!(baza.get_schema().is_custom_type(&recipe.document_type));
+        assert!(baza.get_document(&recipe.id).is_some());
+    }
+
+    #[test]
+    fn test_reject_invalid_type_definition() {
+        let temp_dir = TempFile::new_with_details("custom_types", "");
+        temp_dir.mkdir().unwrap();
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut().unwrap();
+
+        // built-in type
+        assert!(
+            baza.stage_document(new_type_definition("test_type"), &None)
+                .is_err()
+        );
+
+        let mut definition = new_type_definition("recipe");
+        definition.data.set("fields", "not json");
+        assert!(baza.stage_document(definition, &None).is_err());
+
+        let mut definition = new_type_definition("recipe");
+        definition.data.set("title_format", "${unknown}");
+        assert!(baza.stage_document(definition, &None).is_err());
+
+        baza.stage_document(new_type_definition("recipe"), &None)
+            .unwrap();
+
+        // already defined by another document
+        assert!(
+            baza.stage_document(new_type_definition("recipe"), &None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_saved_search() {
+        let temp_dir = TempFile::new_with_details("saved_search", "");
+        temp_dir.mkdir().unwrap();
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut().unwrap();
+
+        let doc_a = new_document(json!({ "test": "value a" }));
+        let doc_b = new_document(json!({ "test": "value b" }));
+        let other = new_document(json!({ "test": "other" }));
+        for document in [&doc_a, &doc_b, &other] {
+            baza.stage_document(document.clone(), &None).unwrap();
+        }
+
+        let new_saved_search = |query: &str, sort: &str| {
+            Document::new(DocumentType::new(SAVED_SEARCH_TYPE)).with_data(json!({
+                "name": "Values",
+                "query": query,
+                "document_types": "test_type",
+                "sort": sort,
+            }))
+        };
+
+        assert!(
+            baza.stage_document(new_saved_search("unknown:value", ""), &None)
+                .is_err()
+        );
+        assert!(
+            baza.stage_document(new_saved_search("", "unknown"), &None)
+                .is_err()
+        );
+
+        let saved_search = new_saved_search("test:value", "test:desc");
+        baza.stage_document(saved_search.clone(), &None).unwrap();
+
+        let filter = baza.get_saved_search_filter(&saved_search.id).unwrap();
+        let page = baza.list_documents(&filter).unwrap();
+        let ids = page
+            .items
+            .iter()
+            .map(|head| head.get_id().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![doc_b.id.clone(), doc_a.id.clone()]);
+
+        assert!(baza.get_saved_search_filter(&doc_a.id).is_err());
+    }
+
+    #[test]
+    fn test_search_settings() {
+        let temp_dir = TempFile::new_with_details("search_settings", "");
+        temp_dir.mkdir().unwrap();
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+
+        let doc = new_document(json!({ "test": "car" }));
+        let new_search_settings = |stemming: &str| {
+            Document::new(DocumentType::new(SEARCH_SETTINGS_TYPE)).with_data(json!({
+                "stemming": stemming,
+                "synonyms": "car, automobile",
+            }))
+        };
+
+        {
+            let mut baza = manager.open_mut().unwrap();
+            baza.stage_document(doc.clone(), &None).unwrap();
+
+            assert!(
+                baza.stage_document(new_search_settings("Klingon"), &None)
+                    .is_err()
+            );
+            baza.stage_document(new_search_settings("English"), &None)
+                .unwrap();
+            baza.save_changes().unwrap();
+        }
+
+        // the search index is reused with the same settings after reopening
+        let baza = manager.open().unwrap();
+        let filter = Filter {
+            document_types: vec![DocumentType::new("test_type")],
+            query: "automobile".to_string(),
+            ..Default::default()
+        };
+        let page = baza.list_documents(&filter).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].get_id(), &doc.id);
+    }
+
+    #[test]
+    fn test_search_in_asset_contents() {
+        let temp_dir = TempFile::new_with_details("asset_contents", "");
+        temp_dir.mkdir().unwrap();
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+
+        let source_file = temp_dir.new_child("notes.txt");
+        source_file.write_str("The Left Hand of Darkness").unwrap();
+
+        let filter = Filter {
+            query: "darkness".to_string(),
+            ..Default::default()
+        };
+
+        let asset_id = {
+            let mut baza = manager.open_mut().unwrap();
+            let asset = baza
+                .create_asset_with_filename(&source_file.path, "notes.txt".to_string())
+                .unwrap();
+            baza.save_changes().unwrap();
+
+            let page = baza.list_documents(&filter).unwrap();
+            assert_eq!(page.items.len(), 1);
+            assert_eq!(page.items[0].get_id(), &asset.id);
+            assert_eq!(page.snippets[&asset.id][0].field, "@content");
+
+            asset.id
+        };
+
+        // the extracted text is cached and isn't lost when the search index is rebuilt;
+        // dropping the cached baza waits for the background search index merge
+        manager.clear_cached_baza().unwrap();
+        fs::remove_file(&manager.paths.state_search_index_file).unwrap();
+        let baza = manager.open().unwrap();
+        assert_eq!(
+            baza.state.get_asset_text(&asset_id),
+            Some("The Left Hand of Darkness")
+        );
+        let page = baza.list_documents(&filter).unwrap();
+        assert_eq!(page.items.len(), 1);
+    }
+
+    #[test]
+    fn test_update_state_from_storage_with_custom_types() {
+        let key = AgeKey::generate_age_x25519_key();
+
+        let definition = new_type_definition("recipe").with_rev(json!({ "a": 1 }));
+        let recipe = Document::new(DocumentType::new("recipe"))
+            .with_data(json!({ "name": "Borscht" }))
+            .with_rev(json!({ "a": 1 }));
+
+        let mut state = BazaState::new_test_state();
+        let mut storage = create_test_storage(key, &[recipe.clone(), definition.clone()]);
+
+        update_state_from_storage(&mut state, &mut storage).unwrap();
+
+        assert!(state.get_schema().is_custom_type(&recipe.document_type));
+        assert!(state.get_document(&recipe.id).is_some());
+    }
 }
@@ -0,0 +1,447 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use anyhow::{Context, Result, ensure};
+use serde::{Deserialize, Serialize};
+
+use baza_common::{Timestamp, get_file_modification_time};
+
+use crate::{
+    BazaState, BazaStorage, DocumentExpert, DocumentHead, Filter, ListPage,
+    entities::{Document, Id, Revision},
+    schema::{DataSchema, TYPE_DEFINITION_TYPE},
+};
+
+use super::Baza;
+
+/// A point in the archive history, serialized as an ISO8601 time, a `YYYY-MM-DD` date or a
+/// revision safe string like `a:1-b:2`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AsOf {
+    Time(Timestamp),
+    Revision(Revision),
+}
+
+impl AsOf {
+    /// Checks if the snapshot was committed at or before this point in history.
+    fn includes(&self, document: &Document) -> bool {
+        match self {
+            AsOf::Time(time) => document.updated_at <= *time,
+            AsOf::Revision(rev) => !document.rev.is_concurrent_or_newer_than(rev),
+        }
+    }
+}
+
+impl FromStr for AsOf {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        ensure!(!value.is_empty(), "As-of value must not be empty");
+
+        if let Ok(time) = Timestamp::parse_iso8601_time(value) {
+            return Ok(AsOf::Time(time));
+        }
+
+        // a date means the end of that day in UTC
+        if let Ok(time) = Timestamp::parse_iso8601_time(&format!("{value}T23:59:59.999999999Z")) {
+            return Ok(AsOf::Time(time));
+        }
+
+        let rev = Revision::from_safe_string(value).with_context(|| {
+            format!("Failed to parse '{value}' as a time, a date or a revision")
+        })?;
+
+        Ok(AsOf::Revision(rev))
+    }
+}
+
+impl fmt::Display for AsOf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsOf::Time(time) => write!(f, "{}", time.to_iso8601_string()),
+            AsOf::Revision(rev) => write!(f, "{}", rev.to_safe_string()),
+        }
+    }
+}
+
+impl TryFrom<String> for AsOf {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<AsOf> for String {
+    fn from(value: AsOf) -> Self {
+        value.to_string()
+    }
+}
+
+enum ViewState<'b> {
+    Current(&'b BazaState),
+    AsOf(Arc<BazaState>),
+}
+
+/// The last state read as of some point in history. Reading the state means reading the whole
+/// storage history, so it's reused while the storage file doesn't change.
+#[derive(Default)]
+pub(super) struct AsOfStateCache {
+    last: Mutex<Option<CachedAsOfState>>,
+}
+
+struct CachedAsOfState {
+    as_of: AsOf,
+    storage_modification_time: Timestamp,
+    state: Arc<BazaState>,
+}
+
+/// A read-only view of the documents, either the current ones, or the committed documents as
+/// they were at some point in history.
+pub struct BazaView<'b> {
+    as_of: Option<AsOf>,
+    state: ViewState<'b>,
+}
+
+impl BazaView<'_> {
+    fn get_state(&self) -> &BazaState {
+        match &self.state {
+            ViewState::Current(state) => state,
+            ViewState::AsOf(state) => state,
+        }
+    }
+
+    /// Returns the point in history, or `None` for the current documents.
+    pub fn get_as_of(&self) -> Option<&AsOf> {
+        self.as_of.as_ref()
+    }
+
+    /// Returns the schema extended with the custom document types defined at that point.
+    pub fn get_schema(&self) -> &DataSchema {
+        self.get_state().get_schema()
+    }
+
+    pub fn get_document_expert(&self) -> DocumentExpert<'_> {
+        DocumentExpert::new(self.get_schema())
+    }
+
+    pub fn get_document(&self, id: &Id) -> Option<&DocumentHead> {
+        self.get_state().get_document(id)
+    }
+
+    pub fn must_get_document(&self, id: &Id) -> Result<&Document> {
+        let document = self.get_state().must_get_document(id);
+
+        if let Some(as_of) = &self.as_of {
+            return document.with_context(|| format!("Document {id} didn't exist as of {as_of}"));
+        }
+
+        document
+    }
+
+    pub fn iter_documents(&self) -> impl Iterator<Item = &DocumentHead> {
+        self.get_state().iter_documents()
+    }
+
+    pub fn list_documents(&self, filter: &Filter) -> Result<ListPage<'_>> {
+        self.get_state().list_documents(filter)
+    }
+
+    pub fn find_document_backrefs(&self, id: &Id) -> HashSet<Id> {
+        self.get_state().find_document_backrefs(id)
+    }
+
+    pub fn find_document_collections(&self, id: &Id) -> HashSet<Id> {
+        self.get_state().find_document_collections(id)
+    }
+}
+
+/// Snapshots of a document committed at or before the point in history.
+struct SnapshotsAsOf {
+    latest: Vec<Document>,
+    count: usize,
+    created_at: Timestamp,
+}
+
+impl Baza {
+    /// Opens a read-only view of the documents. Without `as_of` the view shows the current
+    /// documents, including the staged ones.
+    pub fn open_view(&self, as_of: Option<AsOf>) -> Result<BazaView<'_>> {
+        let Some(as_of) = as_of else {
+            return Ok(BazaView {
+                as_of: None,
+                state: ViewState::Current(&self.state),
+            });
+        };
+
+        let state = self.get_state_as_of(&as_of)?;
+
+        Ok(BazaView {
+            as_of: Some(as_of),
+            state: ViewState::AsOf(state),
+        })
+    }
+
+    fn get_state_as_of(&self, as_of: &AsOf) -> Result<Arc<BazaState>> {
+        let storage_modification_time =
+            get_file_modification_time(&self.paths.storage_main_db_file)?;
+
+        // the cache is rebuilt if a thread panicked while holding the lock
+        let mut last = self
+            .as_of_state
+            .last
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(cached) = last.as_ref()
+            && cached.as_of == *as_of
+            && cached.storage_modification_time == storage_modification_time
+        {
+            return Ok(cached.state.clone());
+        }
+
+        let state = Arc::new(self.read_state_as_of(as_of)?);
+        *last = Some(CachedAsOfState {
+            as_of: as_of.clone(),
+            storage_modification_time,
+            state: state.clone(),
+        });
+
+        Ok(state)
+    }
+
+    /// Reconstructs the committed documents as of the given time or revision from the storage
+    /// history. Staged documents are ignored, and concurrent snapshots are kept as conflicts.
+    ///
+    /// Asset texts are taken from the current state, so the content of the assets that had
+    /// other files at that point isn't searchable.
+    fn read_state_as_of(&self, as_of: &AsOf) -> Result<BazaState> {
+        let storage = BazaStorage::read_file(&self.paths.storage_main_db_file, self.key.clone())?;
+
+        let mut documents: HashMap<Id, SnapshotsAsOf> = HashMap::new();
+        for item in storage {
+            let (key, raw_document) = item?;
+
+            let document: Document = serde_json::from_str(&raw_document)
+                .context(format!("Failed to parse document {}", key.serialize()))?;
+
+            if !as_of.includes(&document) {
+                continue;
+            }
+
+            let Some(snapshots) = documents.get_mut(&key.id) else {
+                documents.insert(
+                    key.id,
+                    SnapshotsAsOf {
+                        created_at: document.updated_at,
+                        latest: vec![document],
+                        count: 1,
+                    },
+                );
+                continue;
+            };
+
+            snapshots.count += 1;
+            snapshots.created_at = snapshots.created_at.min(document.updated_at);
+
+            if snapshots
+                .latest
+                .iter()
+                .any(|latest| document.rev.is_older_than(&latest.rev))
+            {
+                continue;
+            }
+            snapshots
+                .latest
+                .retain(|latest| !latest.rev.is_older_than(&document.rev));
+            snapshots.latest.push(document);
+        }
+
+        // custom types are defined by the type definitions of that time
+        let schema = self.get_schema().with_custom_types(Vec::new());
+        let mut state = BazaState::new(
+            self.get_instance_id().clone(),
+            self.get_info().clone(),
+            schema,
+        );
+        state.copy_asset_texts_from(&self.state);
+
+        // insert type definitions first, so that documents of custom types could be indexed
+        let mut documents = documents.into_iter().collect::<Vec<_>>();
+        documents.sort_by_cached_key(|(_id, snapshots)| {
+            let is_type_definition = snapshots
+                .latest
+                .iter()
+                .any(|document| document.document_type.is(TYPE_DEFINITION_TYPE));
+
+            !is_type_definition
+        });
+
+        for (id, snapshots) in documents {
+            state.insert_document_head(DocumentHead::new(snapshots.latest.into_iter())?)?;
+            state.update_snapshots_count(&id, snapshots.count)?;
+            state.update_created_at(&id, snapshots.created_at)?;
+        }
+
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
+    use serde_json::json;
+
+    use baza_common::{TempFile, Timestamp};
+
+    use crate::{
+        BazaManager, Filter,
+        entities::{Revision, new_document, new_test_data},
+    };
+
+    use super::AsOf;
+
+    #[test]
+    fn test_parse_as_of() -> Result<()> {
+        let time = Timestamp::parse_iso8601_time("2024-03-05T10:20:30Z")?;
+        assert_eq!("2024-03-05T10:20:30Z".parse::<AsOf>()?, AsOf::Time(time));
+
+        let end_of_day = Timestamp::parse_iso8601_time("2024-03-05T23:59:59.999999999Z")?;
+        assert_eq!("2024-03-05".parse::<AsOf>()?, AsOf::Time(end_of_day));
+
+        let rev = Revision::from_safe_string("a:1-b:2")?;
+        assert_eq!("a:1-b:2".parse::<AsOf>()?, AsOf::Revision(rev.clone()));
+
+        assert_eq!(AsOf::Revision(rev).to_string(), "a:1-b:2");
+        assert_eq!(
+            AsOf::Time(time).to_string().parse::<AsOf>()?,
+            AsOf::Time(time)
+        );
+
+        assert!("".parse::<AsOf>().is_err());
+        assert!("last march".parse::<AsOf>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_view() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("open_view", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut()?;
+
+        let doc = new_document(json!({ "test": "first" }));
+        let id = doc.id.clone();
+        baza.stage_document(doc, &None)?;
+        baza.commit()?;
+
+        let first = baza.must_get_document(&id)?.clone();
+
+        let mut doc = first.clone();
+        doc.data = new_test_data(json!({ "test": "second" }));
+        baza.stage_document(doc, &None)?;
+        let other = new_document(json!({ "test": "other" }));
+        baza.stage_document(other.clone(), &None)?;
+        baza.commit()?;
+
+        // staged changes aren't visible in the views
+        let mut doc = baza.must_get_document(&id)?.clone();
+        doc.data = new_test_data(json!({ "test": "third" }));
+        baza.stage_document(doc, &None)?;
+
+        for as_of in [
+            AsOf::Revision(first.rev.clone()),
+            AsOf::Time(first.updated_at),
+        ] {
+            let view = baza.open_view(Some(as_of))?;
+
+            assert_eq!(view.iter_documents().count(), 1);
+            assert!(view.get_document(&other.id).is_none());
+            assert!(view.must_get_document(&other.id).is_err());
+
+            let head = view.get_document(&id).unwrap();
+            assert_eq!(head.get_snapshots_count(), 1);
+            assert_eq!(head.get_created_at(), Some(&first.updated_at));
+            assert_eq!(view.must_get_document(&id)?.data, first.data);
+
+            let page = view.list_documents(&Filter {
+                query: "second".to_string(),
+                ..Default::default()
+            })?;
+            assert_eq!(page.total, 0);
+        }
+
+        let view = baza.open_view(Some(AsOf::Time(Timestamp::now())))?;
+        assert_eq!(view.iter_documents().count(), 2);
+        assert_eq!(view.get_document(&id).unwrap().get_snapshots_count(), 2);
+        assert_eq!(
+            view.must_get_document(&id)?.data,
+            new_test_data(json!({ "test": "second" }))
+        );
+
+        let page = view.list_documents(&Filter {
+            query: "second".to_string(),
+            ..Default::default()
+        })?;
+        assert_eq!(page.total, 1);
+
+        let view = baza.open_view(Some(AsOf::Time(Timestamp::MIN)))?;
+        assert_eq!(view.iter_documents().count(), 0);
+
+        let view = baza.open_view(None)?;
+        assert_eq!(
+            view.must_get_document(&id)?.data,
+            new_test_data(json!({ "test": "third" }))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_as_of_is_cached() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("state_as_of_cache", "");
+        temp_dir.mkdir()?;
+
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+        let mut baza = manager.open_mut()?;
+
+        let source_file = temp_dir.new_child("notes.txt");
+        source_file.write_str("The Left Hand of Darkness")?;
+        let asset = baza.create_asset_with_filename(&source_file.path, "notes.txt".to_string())?;
+        baza.commit()?;
+
+        let as_of: AsOf = "2999-01-01".parse()?;
+        let state = baza.get_state_as_of(&as_of)?;
+        assert!(Arc::ptr_eq(&state, &baza.get_state_as_of(&as_of)?));
+
+        // asset texts are searchable in the view
+        let view = baza.open_view(Some(as_of.clone()))?;
+        let page = view.list_documents(&Filter {
+            query: "darkness".to_string(),
+            ..Default::default()
+        })?;
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].get_id(), &asset.id);
+
+        // the state is read again after the storage changes
+        let doc = new_document(json!({ "test": "other" }));
+        baza.stage_document(doc.clone(), &None)?;
+        baza.commit()?;
+
+        let new_state = baza.get_state_as_of(&as_of)?;
+        assert!(!Arc::ptr_eq(&state, &new_state));
+        assert!(new_state.get_document(&doc.id).is_some());
+
+        Ok(())
+    }
+}
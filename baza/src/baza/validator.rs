@@ -4,7 +4,10 @@ use anyhow::{Result, anyhow, bail, ensure};
 
 use crate::{
     entities::{Document, Id},
-    schema::Field,
+    schema::{
+        Field, HISTORY_SETTINGS_TYPE, HistorySettingsData, SAVED_SEARCH_TYPE, SEARCH_SETTINGS_TYPE,
+        SavedSearchData, SearchSettingsData, TYPE_DEFINITION_TYPE, TypeDefinitionData,
+    },
 };
 
 use super::Baza;
@@ -86,11 +89,11 @@ impl Baza {
 
         let schema = self.get_schema().clone();
         for field in schema.iter_fields(&document.document_type)? {
-            let value = document.data.get(field.name);
+            let value = document.data.get(&field.name);
 
             // ensure readonly field didn't change
             if let Some(prev_document) = prev_document {
-                let prev_value = prev_document.data.get(field.name);
+                let prev_value = prev_document.data.get(&field.name);
 
                 if field.readonly && value != prev_value {
                     track_err::<()>(
@@ -135,6 +138,57 @@ impl Baza {
             }
         }
 
+        if errors.is_empty() && document.document_type.is(TYPE_DEFINITION_TYPE) {
+            let validation_result = self.validate_type_definition(document);
+
+            if let Err(err) = validation_result {
+                errors
+                    .entry("fields".to_string())
+                    .or_default()
+                    .push(format!("{err:#}"));
+            }
+        }
+
+        if errors.is_empty() && document.document_type.is(SAVED_SEARCH_TYPE) {
+            self.validate_saved_search(document, &mut errors);
+        }
+
+        if errors.is_empty() && document.document_type.is(SEARCH_SETTINGS_TYPE) {
+            validate_search_settings(document, &mut errors);
+        }
+
+        if errors.is_empty() && document.document_type.is(HISTORY_SETTINGS_TYPE) {
+            self.validate_history_settings(document, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::FieldError { errors })
+        }
+    }
+
+    /// Validates a committed snapshot against the schema. Refs aren't checked, as older snapshots
+    /// may reference documents that were erased since then.
+    pub fn validate_snapshot(
+        &self,
+        document: &Document,
+    ) -> std::result::Result<(), ValidationError> {
+        if document.is_erased() {
+            return Ok(());
+        }
+
+        self.validate_fields_presence(document)?;
+
+        let mut errors: FieldValidationErrors = Default::default();
+        for field in self.get_schema().iter_fields(&document.document_type)? {
+            track_err(
+                &mut errors,
+                field,
+                field.validate(document.data.get(&field.name)),
+            );
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -142,7 +196,76 @@ impl Baza {
         }
     }
 
-    fn validate_ref(&self, id: &Id, expected_document_types: Option<&[&str]>) -> Result<()> {
+    fn validate_type_definition(&self, document: &Document) -> Result<()> {
+        let definition = document
+            .clone()
+            .convert::<TypeDefinitionData>()?
+            .data
+            .to_data_description()?;
+
+        let is_defined_elsewhere = self.iter_documents().any(|head| {
+            let other = head.get_single_document();
+
+            other.id != document.id
+                && other.document_type.is(TYPE_DEFINITION_TYPE)
+                && other.data.get_str("document_type") == Some(definition.document_type.as_str())
+        });
+        ensure!(
+            !is_defined_elsewhere,
+            "document type '{}' is already defined",
+            definition.document_type
+        );
+
+        self.get_schema().validate_custom_type(&definition)
+    }
+
+    fn validate_saved_search(&self, document: &Document, errors: &mut FieldValidationErrors) {
+        let saved_search = match document.clone().convert::<SavedSearchData>() {
+            Ok(saved_search) => saved_search.data,
+            Err(err) => {
+                errors
+                    .entry("name".to_string())
+                    .or_default()
+                    .push(format!("{err:#}"));
+                return;
+            }
+        };
+
+        let schema = self.get_schema();
+        let results = [
+            (
+                "document_types",
+                saved_search.validate_document_types(schema),
+            ),
+            ("query", saved_search.validate_query(schema)),
+            ("sort", saved_search.validate_sort(schema)),
+        ];
+
+        for (field, result) in results {
+            if let Err(err) = result {
+                errors
+                    .entry(field.to_string())
+                    .or_default()
+                    .push(format!("{err:#}"));
+            }
+        }
+    }
+
+    fn validate_history_settings(&self, document: &Document, errors: &mut FieldValidationErrors) {
+        let result = document
+            .clone()
+            .convert::<HistorySettingsData>()
+            .and_then(|settings| settings.data.validate_pinned_types(self.get_schema()));
+
+        if let Err(err) = result {
+            errors
+                .entry("pinned_types".to_string())
+                .or_default()
+                .push(format!("{err:#}"));
+        }
+    }
+
+    fn validate_ref(&self, id: &Id, expected_document_types: Option<&[String]>) -> Result<()> {
         let document = if let Some(head) = self.get_document(id) {
             head.get_single_document()
         } else {
@@ -159,7 +282,9 @@ impl Baza {
             && !document_types.is_empty()
         {
             ensure!(
-                document_types.contains(&document.document_type.as_ref()),
+                document_types
+                    .iter()
+                    .any(|document_type| document.document_type.is(document_type)),
                 "document '{}' expected to be '{}' but has type '{}'",
                 id,
                 document_types.join(", "),
@@ -240,6 +365,20 @@ impl Baza {
     }
 }
 
+fn validate_search_settings(document: &Document, errors: &mut FieldValidationErrors) {
+    let result = document
+        .clone()
+        .convert::<SearchSettingsData>()
+        .and_then(|settings| settings.data.get_stemming_languages());
+
+    if let Err(err) = result {
+        errors
+            .entry("stemming".to_string())
+            .or_default()
+            .push(format!("{err:#}"));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -262,13 +401,14 @@ mod tests {
             DataSchema::new(
                 "test",
                 vec![DataDescription {
-                    document_type: "test_type",
-                    title_format: "title",
+                    document_type: "test_type".into(),
+                    title_format: "title".into(),
                     fields: vec![Field {
-                        name: "test",
+                        name: "test".into(),
                         field_type: FieldType::String {},
                         mandatory: true,
                         readonly: false,
+                        ordered: None,
                     }],
                 }],
             ),
@@ -299,13 +439,14 @@ mod tests {
             DataSchema::new(
                 "test",
                 vec![DataDescription {
-                    document_type: "test_type",
-                    title_format: "title",
+                    document_type: "test_type".into(),
+                    title_format: "title".into(),
                     fields: vec![Field {
-                        name: "test",
+                        name: "test".into(),
                         field_type: FieldType::String {},
                         mandatory: false,
                         readonly: true,
+                        ordered: None,
                     }],
                 }],
             ),
@@ -352,13 +493,14 @@ mod tests {
             DataSchema::new(
                 "test",
                 vec![DataDescription {
-                    document_type: "test_type",
-                    title_format: "title",
+                    document_type: "test_type".into(),
+                    title_format: "title".into(),
                     fields: vec![Field {
-                        name: "ref",
-                        field_type: FieldType::Ref(&["test_type"]),
+                        name: "ref".into(),
+                        field_type: FieldType::Ref(vec!["test_type".into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     }],
                 }],
             ),
@@ -390,18 +532,19 @@ mod tests {
                 "test",
                 vec![
                     DataDescription {
-                        document_type: "test_type",
-                        title_format: "title",
+                        document_type: "test_type".into(),
+                        title_format: "title".into(),
                         fields: vec![],
                     },
                     DataDescription {
-                        document_type: "tag",
-                        title_format: "title",
+                        document_type: "tag".into(),
+                        title_format: "title".into(),
                         fields: vec![Field {
-                            name: "items",
-                            field_type: FieldType::RefList(&[]),
+                            name: "items".into(),
+                            field_type: FieldType::RefList(vec![]),
                             mandatory: false,
                             readonly: false,
+                            ordered: None,
                         }],
                     },
                 ],
@@ -435,23 +578,24 @@ mod tests {
                 "test",
                 vec![
                     DataDescription {
-                        document_type: "test_type",
-                        title_format: "title",
+                        document_type: "test_type".into(),
+                        title_format: "title".into(),
                         fields: vec![],
                     },
                     DataDescription {
-                        document_type: "other_type",
-                        title_format: "title",
+                        document_type: "other_type".into(),
+                        title_format: "title".into(),
                         fields: vec![],
                     },
                     DataDescription {
-                        document_type: "collection",
-                        title_format: "title",
+                        document_type: "collection".into(),
+                        title_format: "title".into(),
                         fields: vec![Field {
-                            name: "items",
-                            field_type: FieldType::RefList(&["other_type"]),
+                            name: "items".into(),
+                            field_type: FieldType::RefList(vec!["other_type".into()]),
                             mandatory: false,
                             readonly: false,
+                            ordered: None,
                         }],
                     },
                 ],
@@ -4,7 +4,7 @@ use anyhow::{Context, Result, ensure};
 
 use baza_common::{
     FsTransaction, bytes_to_hex_string, create_file_reader, create_file_writer, file_exists,
-    generate_alpanumeric_string, get_file_hash_sha256, log,
+    get_file_hash_sha256, log,
 };
 use baza_storage::crypto::age::{AgeKey, AgeReader};
 
@@ -64,14 +64,17 @@ impl BazaManager {
             source_info.storage_version
         );
 
-        if source_info.data_version == TARGET_DATA_VERSION {
-            let removed_state = if local_state.data_version != Some(TARGET_DATA_VERSION) {
+        if source_info.data_version >= TARGET_DATA_VERSION {
+            let removed_state = if local_state.data_version != Some(source_info.data_version) {
                 self.ensure_local_state_can_migrate(&local_state)?;
                 self.remove_local_state_artifacts_transactionally()?
             } else {
                 false
             };
-            log::info!("Storage is already at data version {TARGET_DATA_VERSION}");
+            log::info!(
+                "Storage is already at data version {}",
+                source_info.data_version
+            );
             return Ok(removed_state);
         }
 
@@ -111,51 +114,6 @@ impl BazaManager {
         Ok(true)
     }
 
-    fn ensure_local_state_can_migrate(
-        &self,
-        local_state: &crate::baza_state::LocalStateMigrationStatus,
-    ) -> Result<()> {
-        ensure!(
-            !local_state.has_staged_documents,
-            "Can't migrate while local state has staged documents. Open this storage with the previous Arhiv version, commit or reset local changes, then upgrade again."
-        );
-        ensure!(
-            !local_state.has_document_locks,
-            "Can't migrate while local state has document locks. Open this storage with the previous Arhiv version, clear locks or reset local state, then upgrade again."
-        );
-
-        let state_blobs = self.paths.list_state_blobs()?;
-        ensure!(
-            state_blobs.is_empty(),
-            "Can't migrate while local state has {} blob(s). Open this storage with the previous Arhiv version, commit or reset local asset changes, then upgrade again.",
-            state_blobs.len()
-        );
-
-        Ok(())
-    }
-
-    fn read_common_storage_info(&self, db_files: &[String], key: AgeKey) -> Result<BazaInfo> {
-        let mut infos = db_files
-            .iter()
-            .map(|db_file| {
-                let mut storage = BazaStorage::read_file(db_file, key.clone())
-                    .with_context(|| format!("Failed to read storage info from {db_file}"))?;
-
-                storage.get_info().cloned()
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        let first_info = infos.pop().context("No existing db files found")?;
-        for info in infos {
-            ensure!(
-                info == first_info,
-                "Can't migrate storage files with mixed BazaInfo values"
-            );
-        }
-
-        Ok(first_info)
-    }
-
     fn write_migrated_asset_hash_storage(
         &self,
         db_file: &str,
@@ -207,7 +165,8 @@ impl BazaManager {
             storage_version: source_info.storage_version,
             data_version: TARGET_DATA_VERSION,
         };
-        let migrated_file = self.new_migration_temp_file(db_file);
+        let migrated_file =
+            self.new_migration_temp_file(db_file, SOURCE_DATA_VERSION, TARGET_DATA_VERSION);
         let mut writer = create_file_writer(&migrated_file, false)
             .with_context(|| format!("Failed to create migrated storage file {migrated_file}"))?;
         create_storage(&mut writer, key, target_info, &documents)
@@ -257,37 +216,6 @@ impl BazaManager {
 
         Ok(content_sha256)
     }
-
-    fn new_migration_temp_file(&self, db_file: &str) -> String {
-        format!(
-            "{db_file}.v{SOURCE_DATA_VERSION}-to-v{TARGET_DATA_VERSION}-{}.tmp",
-            generate_alpanumeric_string(10)
-        )
-    }
-
-    fn remove_local_state_artifacts_transactionally(&self) -> Result<bool> {
-        let mut fs_tx = FsTransaction::new();
-        let removed = self.remove_local_state_artifacts(&mut fs_tx)?;
-        fs_tx.commit()?;
-
-        Ok(removed)
-    }
-
-    fn remove_local_state_artifacts(&self, fs_tx: &mut FsTransaction) -> Result<bool> {
-        let mut removed = false;
-        for file in [
-            &self.paths.state_file,
-            &self.paths.state_search_index_file,
-            &self.paths.state_document_locks_file,
-        ] {
-            if file_exists(file)? {
-                fs_tx.remove_file(file)?;
-                removed = true;
-            }
-        }
-
-        Ok(removed)
-    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,394 @@
+use std::{collections::HashSet, io::Write, time::Instant};
+
+use anyhow::{Context, Result, ensure};
+use serde::Serialize;
+
+use baza_common::{FsTransaction, create_file_writer, log};
+use baza_storage::crypto::age::AgeKey;
+
+use crate::{
+    BazaInfo, BazaState, BazaStorage,
+    baza_storage::create_storage,
+    entities::{Document, DocumentKey, DocumentType, Id},
+};
+
+use super::{BASE_DATA_VERSION, BazaManager};
+
+#[derive(Debug, Serialize)]
+pub struct MigratedDocument {
+    pub id: Id,
+    pub document_type: DocumentType,
+    pub snapshots: usize,
+    pub fields: Vec<String>,
+}
+
+/// Documents that pending data migrations change, and errors that would prevent the migration.
+#[derive(Debug, Serialize)]
+pub struct DataMigrationReport {
+    pub source_data_version: u8,
+    pub target_data_version: u8,
+    pub migrations: Vec<String>,
+    pub documents: Vec<MigratedDocument>,
+    pub errors: Vec<String>,
+}
+
+impl DataMigrationReport {
+    fn add_document(&mut self, document: &Document, fields: Vec<String>) {
+        let entry = if let Some(entry) = self
+            .documents
+            .iter_mut()
+            .find(|item| item.id == document.id)
+        {
+            entry
+        } else {
+            self.documents.push(MigratedDocument {
+                id: document.id.clone(),
+                document_type: document.document_type.clone(),
+                snapshots: 0,
+                fields: vec![],
+            });
+
+            self.documents.last_mut().expect("document was just added")
+        };
+
+        entry.snapshots += 1;
+        for field in fields {
+            if !entry.fields.contains(&field) {
+                entry.fields.push(field);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty() && self.errors.is_empty()
+    }
+}
+
+impl BazaManager {
+    /// Lists documents that pending declarative data migrations would change, without
+    /// modifying the storage.
+    pub fn dry_run_data_migrations(&self) -> Result<DataMigrationReport> {
+        ensure!(self.storage_exists()?, "Storage doesn't exist");
+
+        let _lock = self.wait_for_file_lock()?;
+        let key = self.acquire_state_read_lock()?.get_key()?.clone();
+
+        let db_files = self.paths.list_storage_db_files()?;
+        ensure!(!db_files.is_empty(), "No existing db files found");
+
+        let source_info = self.read_common_storage_info(&db_files, key.clone())?;
+
+        let mut report = self.new_data_migration_report(&source_info);
+        let mut seen_snapshots = HashSet::new();
+        for db_file in &db_files {
+            self.migrate_storage_documents(db_file, key.clone(), &mut report, &mut seen_snapshots)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Applies declarative data migrations newer than the storage data version.
+    ///
+    /// Like other data migrations, this requires a clean local state. Returns `true` when
+    /// storage and local state artifacts changed.
+    pub(super) fn apply_data_migrations_with_key(&self, key: &AgeKey) -> Result<bool> {
+        let latest_data_version = self.schema.get_latest_data_version();
+
+        let db_files = self.paths.list_storage_db_files()?;
+        ensure!(!db_files.is_empty(), "No existing db files found");
+
+        let source_info = self.read_common_storage_info(&db_files, key.clone())?;
+        if source_info.data_version >= latest_data_version {
+            return Ok(false);
+        }
+
+        ensure!(
+            source_info.data_version >= BASE_DATA_VERSION,
+            "Unsupported data migration path: {} to {}",
+            source_info.data_version,
+            latest_data_version
+        );
+
+        log::info!(
+            "Preparing data migration from version {} to {latest_data_version}",
+            source_info.data_version
+        );
+
+        let local_state = BazaState::read_local_migration_status(&self.paths, key.clone())
+            .context("Failed to inspect local state before migration")?;
+        self.ensure_local_state_can_migrate(&local_state)?;
+
+        let started_at = Instant::now();
+        let mut report = self.new_data_migration_report(&source_info);
+        let mut seen_snapshots = HashSet::new();
+        let migrated_files = db_files
+            .iter()
+            .map(|db_file| {
+                let documents = self.migrate_storage_documents(
+                    db_file,
+                    key.clone(),
+                    &mut report,
+                    &mut seen_snapshots,
+                )?;
+                ensure!(
+                    report.errors.is_empty(),
+                    "Failed to migrate storage file {db_file}: {}",
+                    report.errors.join("; ")
+                );
+
+                let target_info = BazaInfo {
+                    storage_version: source_info.storage_version,
+                    data_version: latest_data_version,
+                };
+                let migrated_file = self.new_migration_temp_file(
+                    db_file,
+                    source_info.data_version,
+                    latest_data_version,
+                );
+                let mut writer = create_file_writer(&migrated_file, false).with_context(|| {
+                    format!("Failed to create migrated storage file {migrated_file}")
+                })?;
+                create_storage(&mut writer, key.clone(), target_info, &documents).with_context(
+                    || format!("Failed to write migrated storage file {migrated_file}"),
+                )?;
+                writer.flush()?;
+
+                Ok(migrated_file)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut fs_tx = FsTransaction::new();
+        for (db_file, migrated_file) in db_files.iter().zip(migrated_files.iter()) {
+            fs_tx.move_file(migrated_file, db_file, false)?;
+        }
+        self.remove_local_state_artifacts(&mut fs_tx)?;
+        fs_tx.commit()?;
+
+        log::info!(
+            "Migrated {} storage file(s) from data version {} to {}, changed {} document(s) in {:?}",
+            db_files.len(),
+            source_info.data_version,
+            latest_data_version,
+            report.documents.len(),
+            started_at.elapsed()
+        );
+
+        Ok(true)
+    }
+
+    fn new_data_migration_report(&self, source_info: &BazaInfo) -> DataMigrationReport {
+        DataMigrationReport {
+            source_data_version: source_info.data_version,
+            target_data_version: self
+                .schema
+                .get_latest_data_version()
+                .max(source_info.data_version),
+            migrations: self
+                .schema
+                .get_pending_migrations(source_info.data_version)
+                .map(|migration| format!("v{}: {}", migration.data_version, migration.description))
+                .collect(),
+            documents: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn migrate_storage_documents(
+        &self,
+        db_file: &str,
+        key: AgeKey,
+        report: &mut DataMigrationReport,
+        seen_snapshots: &mut HashSet<DocumentKey>,
+    ) -> Result<Vec<Document>> {
+        let mut storage = BazaStorage::read_file(db_file, key)?;
+        let data_version = storage.get_info()?.data_version;
+
+        let expected_documents = storage.index.len();
+        let mut documents = Vec::with_capacity(expected_documents);
+
+        while let Some(item) = storage.next_parsed() {
+            let (key, mut document) = item?;
+
+            let mut changed_fields = Vec::new();
+            for migration in self.schema.get_pending_migrations(data_version) {
+                match migration.apply(&mut document) {
+                    Ok(fields) => changed_fields.extend(fields),
+                    Err(err) => report.errors.push(format!("{err:#}")),
+                }
+            }
+
+            if !changed_fields.is_empty() && seen_snapshots.insert(key) {
+                report.add_document(&document, changed_fields);
+            }
+
+            documents.push(document);
+        }
+
+        ensure!(
+            documents.len() == expected_documents,
+            "Storage file {db_file} yielded {} documents, expected {}",
+            documents.len(),
+            expected_documents
+        );
+
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use baza_common::{TempFile, create_file_writer};
+    use baza_storage::crypto::age::AgeKey;
+
+    use crate::{
+        BazaInfo, BazaPaths, BazaStorage,
+        baza_storage::{STORAGE_VERSION, create_storage},
+        entities::{Document, Id, new_document},
+        schema::{DataMigration, DataSchema, FieldMigration, FieldType, TypeMigration},
+    };
+
+    use super::BazaManager;
+
+    fn storage_key(manager: &BazaManager) -> AgeKey {
+        manager
+            .acquire_state_read_lock()
+            .unwrap()
+            .get_key()
+            .unwrap()
+            .clone()
+    }
+
+    fn read_main_storage(manager: &BazaManager) -> (BazaInfo, Vec<Document>) {
+        let mut storage =
+            BazaStorage::read_file(&manager.paths.storage_main_db_file, storage_key(manager))
+                .unwrap();
+        let info = storage.get_info().unwrap().clone();
+
+        (info, storage.get_all().unwrap())
+    }
+
+    fn new_migrated_schema() -> DataSchema {
+        DataSchema::new_test_schema().with_migrations(vec![DataMigration {
+            data_version: 3,
+            description: "Store test values as strings".into(),
+            types: vec![TypeMigration {
+                document_type: "test_type".into(),
+                fields: vec![
+                    FieldMigration::Coerce {
+                        field: "test".into(),
+                        field_type: FieldType::String {},
+                    },
+                    FieldMigration::Drop {
+                        field: "legacy".into(),
+                    },
+                ],
+            }],
+        }])
+    }
+
+    /// Creates a v2 storage with documents that don't match the current schema, and returns a
+    /// manager that uses the migrated schema.
+    fn create_v2_storage(temp_dir: &TempFile, test_value: serde_json::Value) -> (BazaManager, Id) {
+        let manager = BazaManager::new_for_tests(&temp_dir.path);
+
+        let id = {
+            let mut baza = manager.open_mut().unwrap();
+            let document = new_document(json!({ "test": "value" }));
+            baza.stage_document(document.clone(), &None).unwrap();
+            baza.commit().unwrap();
+
+            document.id
+        };
+
+        let (info, mut documents) = read_main_storage(&manager);
+        for document in &mut documents {
+            if document.id == id {
+                document.data.set("test", test_value.clone());
+                document.data.set("legacy", "old value");
+            }
+        }
+
+        let mut writer = create_file_writer(&manager.paths.storage_main_db_file, true).unwrap();
+        create_storage(&mut writer, storage_key(&manager), info, &documents).unwrap();
+        drop(writer);
+
+        let manager = BazaManager::new(
+            BazaPaths::new_for_tests(&temp_dir.path),
+            new_migrated_schema(),
+        );
+        manager.unlock("test password".into()).unwrap();
+
+        (manager, id)
+    }
+
+    #[test]
+    fn test_applies_data_migrations() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("baza_field_migrations", "");
+        temp_dir.mkdir()?;
+
+        let (manager, id) = create_v2_storage(&temp_dir, json!(42));
+
+        let baza = manager.open()?;
+        let document = baza
+            .get_document(&id)
+            .expect("document exists")
+            .get_single_document();
+        assert_eq!(document.data.get_str("test"), Some("42"));
+        assert!(document.data.get("legacy").is_none());
+        drop(baza);
+
+        let (info, _) = read_main_storage(&manager);
+        assert_eq!(
+            info,
+            BazaInfo {
+                storage_version: STORAGE_VERSION,
+                data_version: 3,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dry_run_data_migrations() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("baza_field_migrations_dry_run", "");
+        temp_dir.mkdir()?;
+
+        let (manager, id) = create_v2_storage(&temp_dir, json!(42));
+        let storage_before = read_main_storage(&manager);
+
+        let report = manager.dry_run_data_migrations()?;
+        assert_eq!(report.source_data_version, 2);
+        assert_eq!(report.target_data_version, 3);
+        assert_eq!(report.migrations, vec!["v3: Store test values as strings"]);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.documents.len(), 1);
+        assert_eq!(report.documents[0].id, id);
+        assert_eq!(report.documents[0].fields, vec!["test", "legacy"]);
+
+        assert_eq!(read_main_storage(&manager), storage_before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_migration_failure_keeps_storage() -> Result<()> {
+        let temp_dir = TempFile::new_with_details("baza_field_migrations_failure", "");
+        temp_dir.mkdir()?;
+
+        let (manager, _id) = create_v2_storage(&temp_dir, json!({ "nested": true }));
+        let storage_before = read_main_storage(&manager);
+
+        let report = manager.dry_run_data_migrations()?;
+        assert_eq!(report.errors.len(), 1);
+
+        assert!(manager.open().is_err());
+        assert_eq!(read_main_storage(&manager), storage_before);
+
+        Ok(())
+    }
+}
@@ -1,16 +1,126 @@
 mod asset_content_sha256;
+mod field_migrations;
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail, ensure};
+
+use baza_common::{FsTransaction, file_exists, generate_alpanumeric_string};
 use baza_storage::crypto::age::AgeKey;
 
+use crate::{
+    BazaInfo, BazaStorage,
+    baza_state::{BazaState, LocalStateMigrationStatus, SearchEngine},
+};
+
+pub use field_migrations::{DataMigrationReport, MigratedDocument};
+
 use super::BazaManager;
 
+/// Data version produced by the built-in asset content hash migration.
+const BASE_DATA_VERSION: u8 = 2;
+
 impl BazaManager {
     /// Applies required data migrations before normal state loading enforces the latest schema.
     pub(super) fn migrate_to_latest_data_version_if_needed(&self, key: &AgeKey) -> Result<bool> {
-        match self.schema.get_latest_data_version() {
-            2 => self.migrate_data_v1_to_v2_asset_content_sha256_with_key(key),
-            latest => bail!("No data migration path is registered for data version {latest}"),
+        let latest = self.schema.get_latest_data_version();
+        if latest < BASE_DATA_VERSION {
+            bail!("No data migration path is registered for data version {latest}");
         }
+
+        let mut changed = self.migrate_data_v1_to_v2_asset_content_sha256_with_key(key)?;
+        changed |= self.apply_data_migrations_with_key(key)?;
+
+        Ok(changed)
+    }
+
+    fn ensure_local_state_can_migrate(
+        &self,
+        local_state: &LocalStateMigrationStatus,
+    ) -> Result<()> {
+        ensure!(
+            !local_state.has_staged_documents,
+            "Can't migrate while local state has staged documents. Open this storage with the previous Arhiv version, commit or reset local changes, then upgrade again."
+        );
+        ensure!(
+            !local_state.has_document_locks,
+            "Can't migrate while local state has document locks. Open this storage with the previous Arhiv version, clear locks or reset local state, then upgrade again."
+        );
+
+        let state_blobs = self.paths.list_state_blobs()?;
+        ensure!(
+            state_blobs.is_empty(),
+            "Can't migrate while local state has {} blob(s). Open this storage with the previous Arhiv version, commit or reset local asset changes, then upgrade again.",
+            state_blobs.len()
+        );
+
+        Ok(())
+    }
+
+    fn read_common_storage_info(&self, db_files: &[String], key: AgeKey) -> Result<BazaInfo> {
+        let mut infos = db_files
+            .iter()
+            .map(|db_file| {
+                let mut storage = BazaStorage::read_file(db_file, key.clone())
+                    .with_context(|| format!("Failed to read storage info from {db_file}"))?;
+
+                storage.get_info().cloned()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let first_info = infos.pop().context("No existing db files found")?;
+        for info in infos {
+            ensure!(
+                info == first_info,
+                "Can't migrate storage files with mixed BazaInfo values"
+            );
+        }
+
+        Ok(first_info)
+    }
+
+    fn new_migration_temp_file(
+        &self,
+        db_file: &str,
+        source_version: u8,
+        target_version: u8,
+    ) -> String {
+        format!(
+            "{db_file}.v{source_version}-to-v{target_version}-{}.tmp",
+            generate_alpanumeric_string(10)
+        )
+    }
+
+    fn remove_local_state_artifacts_transactionally(&self) -> Result<bool> {
+        let mut fs_tx = FsTransaction::new();
+        let removed = self.remove_local_state_artifacts(&mut fs_tx)?;
+        fs_tx.commit()?;
+
+        Ok(removed)
+    }
+
+    fn remove_local_state_artifacts(&self, fs_tx: &mut FsTransaction) -> Result<bool> {
+        let mut removed = false;
+        for file in [
+            &self.paths.state_file,
+            &self.paths.state_search_index_file,
+            &self.paths.state_document_locks_file,
+            &self.paths.state_asset_texts_file,
+        ] {
+            if file_exists(file)? {
+                fs_tx.remove_file(file)?;
+                removed = true;
+            }
+        }
+
+        let segment_files = BazaState::list_state_file_segments(&self.paths.state_file)?
+            .into_iter()
+            .chain(SearchEngine::list_index_segments(
+                &self.paths.state_search_index_file,
+            )?);
+        for segment_file in segment_files {
+            fs_tx.remove_file(segment_file)?;
+            removed = true;
+        }
+
+        Ok(removed)
     }
 }
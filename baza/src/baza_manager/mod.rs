@@ -9,7 +9,7 @@ use anyhow::{Context, Result, anyhow, ensure};
 use baza_common::{FsTransaction, LockFile, SecretString, Timestamp, log};
 use baza_storage::crypto::age::AgeKey;
 
-use crate::{DocumentExpert, schema::DataSchema};
+use crate::schema::DataSchema;
 
 use super::{
     BazaInfo, BazaStorage,
@@ -19,6 +19,7 @@ use super::{
 
 use self::manager_state::BazaManagerState;
 pub use self::manager_state::StorageKeyUnlockResult;
+pub use self::migration::{DataMigrationReport, MigratedDocument};
 
 pub struct BazaManager {
     schema: DataSchema,
@@ -212,14 +213,11 @@ impl BazaManager {
         manager
     }
 
+    /// Returns the built-in schema. Use [`crate::Baza::get_schema`] to include custom document types.
     pub fn get_schema(&self) -> &DataSchema {
         &self.schema
     }
 
-    pub fn get_document_expert(&self) -> DocumentExpert<'_> {
-        DocumentExpert::new(&self.schema)
-    }
-
     pub fn get_state_dir(&self) -> &str {
         &self.paths.state_dir
     }
@@ -7,7 +7,7 @@ use baza_common::{
     get_file_name, list_files,
 };
 
-use crate::entities::Id;
+use crate::{BazaState, entities::Id};
 
 const BLOB_EXT: &str = ".age";
 
@@ -27,6 +27,8 @@ pub struct BazaPaths {
     pub state_file: String,
     pub state_search_index_file: String,
     pub state_document_locks_file: String,
+    pub state_asset_texts_file: String,
+    pub state_merge_log_file: String,
     pub state_data_dir: String,
 
     pub downloads_dir: String,
@@ -46,6 +48,8 @@ impl BazaPaths {
         let state_file = format!("{state_dir}/state.gz.age");
         let state_search_index_file = format!("{state_dir}/search_index.gz.age");
         let state_document_locks_file = format!("{state_dir}/document_locks.age");
+        let state_asset_texts_file = format!("{state_dir}/asset_texts.gz.age");
+        let state_merge_log_file = format!("{state_dir}/merge_log.gz.age");
         let state_data_dir = format!("{state_dir}/data");
 
         let lock_file = format!("{state_dir}/baza.lock");
@@ -64,6 +68,8 @@ impl BazaPaths {
             state_data_dir,
             state_search_index_file,
             state_document_locks_file,
+            state_asset_texts_file,
+            state_merge_log_file,
 
             downloads_dir,
 
@@ -170,8 +176,20 @@ impl BazaPaths {
         file_exists(&self.state_file)
     }
 
+    /// Returns the latest modification time of the state file and its delta segments.
     pub fn read_state_file_modification_time(&self) -> Result<Timestamp> {
-        get_file_modification_time(&self.state_file)
+        let mut modification_time = get_file_modification_time(&self.state_file)?;
+
+        for segment_file in BazaState::list_state_file_segments(&self.state_file)? {
+            // segments of the previous generations could be removed concurrently
+            let Ok(metadata) = std::fs::metadata(&segment_file) else {
+                continue;
+            };
+
+            modification_time = modification_time.max(metadata.modified()?.into());
+        }
+
+        Ok(modification_time)
     }
 
     pub fn storage_main_db_file_exists(&self) -> Result<bool> {
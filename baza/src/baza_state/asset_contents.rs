@@ -0,0 +1,131 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::Id;
+
+use super::{BazaState, DocumentHead, asset_texts::get_asset_content_sha256};
+
+/// Index of assets by the SHA-256 of their content. It isn't persisted: it's built when the
+/// state is read, and updated when documents change.
+#[derive(Debug, Default)]
+pub(super) struct AssetContentsIndex {
+    content_by_id: HashMap<Id, String>,
+    ids_by_content: HashMap<String, HashSet<Id>>,
+}
+
+impl AssetContentsIndex {
+    pub fn new<'h>(heads: impl Iterator<Item = &'h DocumentHead>) -> Self {
+        let mut index = AssetContentsIndex::default();
+
+        for head in heads {
+            index.update(head.get_id(), get_asset_content_sha256(head));
+        }
+
+        index
+    }
+
+    fn update(&mut self, id: &Id, content_sha256: Option<&str>) {
+        if self.content_by_id.get(id).map(String::as_str) == content_sha256 {
+            return;
+        }
+
+        if let Some(old_content_sha256) = self.content_by_id.remove(id)
+            && let Some(ids) = self.ids_by_content.get_mut(&old_content_sha256)
+        {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.ids_by_content.remove(&old_content_sha256);
+            }
+        }
+
+        if let Some(content_sha256) = content_sha256 {
+            self.content_by_id
+                .insert(id.clone(), content_sha256.to_string());
+            self.ids_by_content
+                .entry(content_sha256.to_string())
+                .or_default()
+                .insert(id.clone());
+        }
+    }
+}
+
+impl BazaState {
+    pub(super) fn update_asset_contents_index(&mut self, id: &Id) {
+        let content_sha256 = self
+            .file
+            .documents
+            .get(id)
+            .and_then(get_asset_content_sha256);
+
+        self.asset_contents.update(id, content_sha256);
+    }
+
+    /// Returns IDs of the assets with the given content SHA-256.
+    pub fn find_assets_by_content(&self, content_sha256: &str) -> impl Iterator<Item = &Id> {
+        self.asset_contents
+            .ids_by_content
+            .get(content_sha256)
+            .into_iter()
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        BazaState,
+        entities::{Document, DocumentType, Id},
+        schema::ASSET_TYPE,
+    };
+
+    fn new_asset(content_sha256: &str) -> Document {
+        Document::new(DocumentType::new(ASSET_TYPE)).with_data(json!({
+            "filename": "file.txt",
+            "media_type": "text/plain",
+            "size": 4,
+            "content_sha256": content_sha256,
+            "age_x25519_key": "key",
+        }))
+    }
+
+    fn find(state: &BazaState, content_sha256: &str) -> Vec<Id> {
+        let mut ids = state
+            .find_assets_by_content(content_sha256)
+            .cloned()
+            .collect::<Vec<_>>();
+        ids.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        ids
+    }
+
+    #[test]
+    fn test_find_assets_by_content() {
+        let mut state = BazaState::new_test_state();
+
+        let asset1 = new_asset("hash1");
+        let asset2 = new_asset("hash1");
+        let mut expected = vec![asset1.id.clone(), asset2.id.clone()];
+        expected.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        state.stage_document(asset1.clone(), &None).unwrap();
+        state.stage_document(asset2.clone(), &None).unwrap();
+        assert_eq!(find(&state, "hash1"), expected);
+        assert!(find(&state, "hash2").is_empty());
+
+        // content change
+        let mut updated_asset1 = asset1.clone();
+        updated_asset1.data.set("content_sha256", "hash2");
+        state.stage_document(updated_asset1, &None).unwrap();
+        assert_eq!(find(&state, "hash1"), vec![asset2.id.clone()]);
+        assert_eq!(find(&state, "hash2"), vec![asset1.id.clone()]);
+
+        // erased assets aren't indexed
+        state.erase_document(&asset2.id).unwrap();
+        assert!(find(&state, "hash1").is_empty());
+
+        // reset of a new document removes it
+        state.reset_document(&asset1.id, &None).unwrap();
+        assert!(find(&state, "hash2").is_empty());
+    }
+}
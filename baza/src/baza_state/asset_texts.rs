@@ -0,0 +1,152 @@
+use anyhow::{Context, Result, ensure};
+
+use crate::{entities::Id, schema::ASSET_TYPE};
+
+use super::{BazaState, DocumentHead, asset_texts_file::AssetText};
+
+/// Returns the hash of the asset file, or `None` if the document isn't an asset.
+pub(super) fn get_asset_content_sha256(head: &DocumentHead) -> Option<&str> {
+    let document = head.get_single_document();
+    if !document.document_type.is(ASSET_TYPE) {
+        return None;
+    }
+
+    document.data.get_str("content_sha256")
+}
+
+impl BazaState {
+    /// Returns the text extracted from the asset file, if it's cached.
+    pub fn get_asset_text(&self, id: &Id) -> Option<&str> {
+        let content_sha256 = get_asset_content_sha256(self.get_document(id)?)?;
+
+        self.asset_texts.get_text(id, content_sha256)
+    }
+
+    /// Lists assets which file text hasn't been extracted yet.
+    pub fn list_assets_without_text(&self) -> Vec<Id> {
+        let mut ids = self
+            .iter_documents()
+            .filter(|head| get_asset_content_sha256(head).is_some())
+            .map(DocumentHead::get_id)
+            .filter(|id| self.get_asset_text(id).is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        ids.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        ids
+    }
+
+    /// Caches the text extracted from the asset file with the given hash, and re-indexes the
+    /// asset. An empty text marks files that have no text to extract.
+    pub fn set_asset_text(&mut self, id: &Id, content_sha256: &str, text: String) -> Result<()> {
+        let head = self.get_document(id).context("Asset must exist")?;
+        ensure!(
+            get_asset_content_sha256(head) == Some(content_sha256),
+            "Asset {id} file has changed"
+        );
+
+        self.asset_texts.set_text(
+            id.clone(),
+            AssetText {
+                content_sha256: content_sha256.to_string(),
+                text,
+            },
+        );
+
+        let collections = self.find_document_collections(id);
+        self.index_document(id, &collections)
+    }
+
+    /// Copies the cached asset texts of another state. A text is used only while the asset
+    /// has the same file it was extracted from.
+    pub(crate) fn copy_asset_texts_from(&mut self, other: &BazaState) {
+        self.asset_texts = other.asset_texts.clone();
+    }
+
+    /// Drops the texts of erased assets, and of the files that were replaced.
+    pub(super) fn prune_asset_texts(&mut self) {
+        let documents = &self.file.documents;
+
+        self.asset_texts.retain(|id, asset_text| {
+            documents.get(id).and_then(get_asset_content_sha256)
+                == Some(asset_text.content_sha256.as_str())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::{
+        BazaState,
+        entities::{Document, DocumentType, Id},
+        schema::ASSET_TYPE,
+    };
+
+    use super::super::Filter;
+
+    fn asset_data(content_sha256: &str) -> Value {
+        json!({
+            "filename": "notes.txt",
+            "media_type": "text/plain",
+            "size": 1,
+            "content_sha256": content_sha256,
+            "age_x25519_key": "key",
+        })
+    }
+
+    fn search(state: &BazaState, query: &str) -> Vec<Id> {
+        let filter = Filter {
+            query: query.to_string(),
+            ..Default::default()
+        };
+
+        state
+            .list_documents(&filter)
+            .unwrap()
+            .items
+            .iter()
+            .map(|head| head.get_id().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_search_in_asset_text() {
+        let mut state = BazaState::new_test_state();
+
+        let asset = Document::new(DocumentType::new(ASSET_TYPE)).with_data(asset_data("sha1"));
+        state.stage_document(asset.clone(), &None).unwrap();
+        assert_eq!(state.list_assets_without_text(), vec![asset.id.clone()]);
+        assert!(search(&state, "earthsea").is_empty());
+
+        state
+            .set_asset_text(&asset.id, "sha1", "A Wizard of Earthsea".to_string())
+            .unwrap();
+        assert!(state.list_assets_without_text().is_empty());
+        assert_eq!(search(&state, "earthsea"), vec![asset.id.clone()]);
+        assert_eq!(search(&state, "@content:wizard"), vec![asset.id.clone()]);
+
+        // the text of another file isn't accepted
+        assert!(
+            state
+                .set_asset_text(&asset.id, "sha2", "other".to_string())
+                .is_err()
+        );
+
+        // the cached text is used when the index is rebuilt
+        state.rebuild_search_index().unwrap();
+        assert_eq!(search(&state, "earthsea"), vec![asset.id.clone()]);
+
+        // the text is dropped when the asset file is replaced
+        let asset = asset.with_data(asset_data("sha2"));
+        state.stage_document(asset.clone(), &None).unwrap();
+        assert!(search(&state, "earthsea").is_empty());
+        assert_eq!(state.list_assets_without_text(), vec![asset.id.clone()]);
+
+        state.prune_asset_texts();
+        assert!(state.asset_texts.is_modified());
+        assert!(state.get_asset_text(&asset.id).is_none());
+    }
+}
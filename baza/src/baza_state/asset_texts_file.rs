@@ -0,0 +1,99 @@
+use std::{collections::HashMap, io::Write};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use baza_common::{create_file_reader, create_file_writer, log, read_all};
+use baza_storage::crypto::age::AgeKey;
+use baza_storage::{AgeGzReader, AgeGzWriter};
+
+use crate::entities::Id;
+
+/// Text extracted from the asset file, together with the hash of the file it was extracted from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AssetText {
+    pub content_sha256: String,
+    pub text: String,
+}
+
+/// A cache of the text extracted from asset files, so that files aren't decrypted and parsed
+/// again when the search index is rebuilt.
+#[derive(Clone)]
+pub struct AssetTextsFile {
+    texts: HashMap<Id, AssetText>,
+    modified: bool,
+}
+
+impl AssetTextsFile {
+    pub fn new() -> AssetTextsFile {
+        AssetTextsFile {
+            texts: Default::default(),
+            modified: false,
+        }
+    }
+
+    pub fn read(file: &str, key: AgeKey) -> Result<Self> {
+        log::debug!("Reading asset texts from file {file}");
+
+        let reader = create_file_reader(file)?;
+        let agegz_reader = AgeGzReader::new(reader, key)?;
+
+        let bytes = read_all(agegz_reader)?;
+        let texts = postcard::from_bytes(&bytes).context("Failed to parse AssetTexts")?;
+
+        Ok(AssetTextsFile {
+            texts,
+            modified: false,
+        })
+    }
+
+    pub fn write(&mut self, file: &str, key: AgeKey) -> Result<()> {
+        log::debug!("Writing asset texts to file {file}");
+
+        let writer = create_file_writer(file, true)?;
+        let mut agegz_writer = AgeGzWriter::new(writer, key)?;
+
+        let bytes = postcard::to_allocvec(&self.texts).context("Failed to serialize AssetTexts")?;
+        agegz_writer.write_all(&bytes)?;
+
+        let mut writer = agegz_writer.finish()?;
+        writer.flush()?;
+
+        self.modified = false;
+
+        Ok(())
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Returns the text of the asset, if it was extracted from the file with the given hash.
+    pub fn get_text(&self, id: &Id, content_sha256: &str) -> Option<&str> {
+        self.texts
+            .get(id)
+            .filter(|asset_text| asset_text.content_sha256 == content_sha256)
+            .map(|asset_text| asset_text.text.as_str())
+    }
+
+    pub fn set_text(&mut self, id: Id, asset_text: AssetText) {
+        if self.texts.get(&id) == Some(&asset_text) {
+            return;
+        }
+
+        self.texts.insert(id, asset_text);
+        self.modified = true;
+    }
+
+    /// Removes texts of the assets that don't match the `is_actual` check.
+    pub fn retain(&mut self, mut is_actual: impl FnMut(&Id, &AssetText) -> bool) {
+        let count = self.texts.len();
+
+        self.texts
+            .retain(|id, asset_text| is_actual(id, asset_text));
+
+        if self.texts.len() != count {
+            self.modified = true;
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+
+use baza_common::log;
+
+use crate::{
+    entities::Document,
+    schema::{DataDescription, TYPE_DEFINITION_TYPE, TypeDefinitionData},
+};
+
+use super::{BazaState, DocumentHead};
+
+/// Parses runtime-defined document types from the type definition documents.
+///
+/// Definitions are ordered by document id, so all instances resolve duplicates the same way.
+pub(super) fn collect_custom_types<'h>(
+    heads: impl Iterator<Item = &'h DocumentHead>,
+) -> Vec<DataDescription> {
+    let mut definitions = heads
+        .map(DocumentHead::get_single_document)
+        .filter(|document| document.document_type.is(TYPE_DEFINITION_TYPE))
+        .collect::<Vec<_>>();
+
+    definitions.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+    definitions
+        .into_iter()
+        .filter_map(|document| match parse_type_definition(document) {
+            Ok(definition) => Some(definition),
+            Err(err) => {
+                log::warn!("Ignoring type definition {}: {err:#}", document.id);
+                None
+            }
+        })
+        .collect()
+}
+
+pub(super) fn parse_type_definition(document: &Document) -> Result<DataDescription> {
+    let definition = document.clone().convert::<TypeDefinitionData>()?;
+
+    definition.data.to_data_description()
+}
+
+pub(super) fn is_type_definition(head: Option<&DocumentHead>) -> bool {
+    head.is_some_and(|head| {
+        head.iter_all_snapshots()
+            .any(|document| document.document_type.is(TYPE_DEFINITION_TYPE))
+    })
+}
+
+impl BazaState {
+    /// Re-reads custom types from the type definition documents, and re-indexes all documents
+    /// if the resulting schema changed.
+    pub(super) fn refresh_schema(&mut self) -> Result<()> {
+        let schema = self
+            .schema
+            .with_custom_types(collect_custom_types(self.iter_documents()));
+
+        if schema.fingerprint()? == self.schema.fingerprint()? {
+            return Ok(());
+        }
+
+        log::info!("Custom document types changed, re-indexing documents");
+
+        self.schema = schema;
+        self.search.reset(self.schema.clone());
+
+        let ids = self.file.documents.keys().cloned().collect::<Vec<_>>();
+        for id in ids {
+            let head = self
+                .file
+                .documents
+                .remove(&id)
+                .expect("document must exist");
+
+            self.update_document_refs(&head)?;
+
+            self.file.documents.insert(id, head);
+        }
+
+        self.rebuild_search_index()?;
+
+        self.file.mark_all_modified();
+        log::trace!("State modified: refreshed schema");
+
+        Ok(())
+    }
+}
@@ -12,6 +12,9 @@ pub struct DocumentHead {
     original: HashSet<Document>,
     staged: Option<Document>,
     snapshots_count: usize,
+    /// Time of the first committed snapshot, unknown for states created before it was tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<Timestamp>,
 }
 
 impl DocumentHead {
@@ -22,6 +25,7 @@ impl DocumentHead {
             original: HashSet::with_capacity(0),
             staged: Some(document),
             snapshots_count: 0,
+            created_at: None,
         }
     }
 
@@ -29,6 +33,7 @@ impl DocumentHead {
         ensure!(document.is_committed(), "Document must be committed");
 
         Ok(Self {
+            created_at: Some(document.updated_at),
             original: HashSet::from_iter([document]),
             staged: None,
             snapshots_count: 1,
@@ -59,6 +64,7 @@ impl DocumentHead {
 
             original,
             staged: None,
+            created_at: None,
         })
     }
 
@@ -101,6 +107,15 @@ impl DocumentHead {
             .updated_at
     }
 
+    /// Returns the time of the first committed snapshot, if known.
+    pub fn get_created_at(&self) -> Option<&Timestamp> {
+        self.created_at.as_ref()
+    }
+
+    pub(super) fn set_created_at(&mut self, created_at: Timestamp) {
+        self.created_at = Some(created_at);
+    }
+
     pub fn get_original_revs(&self) -> HashSet<&Revision> {
         self.iter_original_revs().collect()
     }
@@ -173,6 +188,9 @@ impl DocumentHead {
 
         let mut result = DocumentHead::new_committed(staged_document)?;
         result.snapshots_count = self.snapshots_count + 1;
+        if !self.original.is_empty() {
+            result.created_at = self.created_at;
+        }
 
         Ok(result)
     }
@@ -12,20 +12,35 @@ use crate::{
     entities::{
         Document, DocumentLock, DocumentLockKey, Id, InstanceId, LatestRevComputer, Revision,
     },
-    schema::DataSchema,
+    schema::{DataSchema, SEARCH_SETTINGS_TYPE, TYPE_DEFINITION_TYPE},
 };
 
+mod asset_contents;
+mod asset_texts;
+mod asset_texts_file;
+mod custom_types;
 mod document_head;
 mod document_locks_file;
 mod query;
 mod refs;
 mod search;
+mod search_refs;
+mod search_settings;
+mod segments;
+mod sort;
 mod state_file;
+mod structured_query;
 
+use asset_contents::AssetContentsIndex;
+use asset_texts_file::AssetTextsFile;
+use custom_types::{collect_custom_types, is_type_definition};
 pub use document_head::DocumentHead;
 use document_locks_file::DocumentLocksFile;
 pub use document_locks_file::Locks;
-pub use query::{Filter, ListPage};
+pub use query::{DEFAULT_PAGE_SIZE, Filter, ListPage, MAX_PAGE_SIZE, SearchSnippet};
+use search_settings::{collect_search_tokenizer, is_search_settings};
+pub use sort::{SortField, SortKey};
+pub use structured_query::{Comparison, Predicate, StructuredQuery};
 
 use super::baza_paths::BazaPaths;
 
@@ -34,6 +49,8 @@ pub struct BazaState {
     schema: DataSchema,
     search: SearchEngine,
     document_locks: DocumentLocksFile,
+    asset_texts: AssetTextsFile,
+    asset_contents: AssetContentsIndex,
 }
 
 /// Local state facts used by migrations that must inspect state without normal version gates.
@@ -45,11 +62,16 @@ pub(crate) struct LocalStateMigrationStatus {
 
 impl BazaState {
     pub fn new(instance_id: InstanceId, info: BazaInfo, schema: DataSchema) -> Self {
+        let mut search = SearchEngine::new(schema.clone());
+        search.set_tokenizer(collect_search_tokenizer(std::iter::empty()));
+
         BazaState {
             file: BazaStateFile::new(instance_id, info),
-            search: SearchEngine::new(schema.clone()),
+            search,
             schema,
             document_locks: DocumentLocksFile::new(),
+            asset_texts: AssetTextsFile::new(),
+            asset_contents: AssetContentsIndex::default(),
         }
     }
 
@@ -65,33 +87,27 @@ impl BazaState {
     pub fn read(paths: &BazaPaths, key: AgeKey, schema: DataSchema) -> Result<Self> {
         let file = BazaStateFile::read(&paths.state_file, key.clone())?;
 
-        let search =
+        let schema = schema.with_custom_types(collect_custom_types(file.documents.values()));
+
+        let (mut search, mut rebuild_search_index) =
             match SearchEngine::read(&paths.state_search_index_file, key.clone(), schema.clone()) {
-                Ok(search) => search,
+                Ok(search) => (search, false),
                 Err(err) => {
                     log::info!(
                         "Search index will be rebuilt because it could not be reused: {err:#}"
                     );
 
-                    let mut search = SearchEngine::new(schema.clone());
-
-                    let start_time = Instant::now();
-                    for head in file.documents.values() {
-                        search.index_document(head.get_single_document())?;
-                    }
-                    let duration = start_time.elapsed();
-                    log::info!(
-                        "Built search index of {} documents in {:?}",
-                        file.documents.len(),
-                        duration
-                    );
-
-                    search.write(&paths.state_search_index_file, key.clone())?;
-
-                    search
+                    (SearchEngine::new(schema.clone()), true)
                 }
             };
 
+        if search.set_tokenizer(collect_search_tokenizer(file.documents.values()))
+            && !rebuild_search_index
+        {
+            log::info!("Search index will be rebuilt because search settings changed");
+            rebuild_search_index = true;
+        }
+
         let locks = match DocumentLocksFile::read(&paths.state_document_locks_file, key.clone()) {
             Ok(locks) => locks,
             Err(err) => {
@@ -105,12 +121,42 @@ impl BazaState {
             }
         };
 
-        Ok(BazaState {
+        let asset_texts = match AssetTextsFile::read(&paths.state_asset_texts_file, key.clone()) {
+            Ok(asset_texts) => asset_texts,
+            Err(err) => {
+                log::debug!("Failed to read asset texts file: {err}");
+
+                AssetTextsFile::new()
+            }
+        };
+
+        let asset_contents = AssetContentsIndex::new(file.documents.values());
+
+        let mut state = BazaState {
             file,
             search,
             schema,
             document_locks: locks,
-        })
+            asset_texts,
+            asset_contents,
+        };
+
+        if rebuild_search_index {
+            let start_time = Instant::now();
+            state.rebuild_search_index()?;
+            let duration = start_time.elapsed();
+            log::info!(
+                "Built search index of {} documents in {:?}",
+                state.file.documents.len(),
+                duration
+            );
+
+            state
+                .search
+                .write(&paths.state_search_index_file, key.clone())?;
+        }
+
+        Ok(state)
     }
 
     /// Reads only the local state fields needed for migration preflight.
@@ -145,6 +191,11 @@ impl BazaState {
         })
     }
 
+    /// Lists the delta segment files stored next to the state file.
+    pub(crate) fn list_state_file_segments(state_file: &str) -> Result<Vec<String>> {
+        BazaStateFile::list_segments(state_file)
+    }
+
     pub fn write(&mut self, paths: &BazaPaths, key: AgeKey) -> Result<()> {
         if self.file.modified {
             self.file.write(&paths.state_file, key.clone())?;
@@ -158,14 +209,22 @@ impl BazaState {
 
         if self.document_locks.is_modified() {
             self.document_locks
-                .write(&paths.state_document_locks_file, key)?;
+                .write(&paths.state_document_locks_file, key.clone())?;
+        }
+
+        self.prune_asset_texts();
+        if self.asset_texts.is_modified() {
+            self.asset_texts.write(&paths.state_asset_texts_file, key)?;
         }
 
         Ok(())
     }
 
     pub fn is_modified(&self) -> bool {
-        self.file.modified || self.search.is_modified() || self.document_locks.is_modified()
+        self.file.modified
+            || self.search.is_modified()
+            || self.document_locks.is_modified()
+            || self.asset_texts.is_modified()
     }
 
     pub fn get_info(&self) -> &BazaInfo {
@@ -235,8 +294,14 @@ impl BazaState {
 
         self.document_locks.check_document_lock(&id, lock_key)?;
 
+        let search_dependencies = self.capture_search_dependencies(&id);
         let current_value = self.file.documents.remove(&id);
 
+        let affects_schema = document.document_type.is(TYPE_DEFINITION_TYPE)
+            || is_type_definition(current_value.as_ref());
+        let affects_search_settings = document.document_type.is(SEARCH_SETTINGS_TYPE)
+            || is_search_settings(current_value.as_ref());
+
         let updated_head = if let Some(mut document_head) = current_value {
             document_head.modify(document)?;
             document_head
@@ -245,12 +310,19 @@ impl BazaState {
         };
 
         self.update_document_refs(&updated_head)?;
-        self.search
-            .index_document(updated_head.get_single_document())?;
         self.file.documents.insert(id.clone(), updated_head);
-        self.file.modified = true;
+        self.update_asset_contents_index(&id);
+        self.update_search_index(&id, search_dependencies)?;
+        self.file.mark_modified(&id);
         log::trace!("State modified: staged document");
 
+        if affects_schema {
+            self.refresh_schema()?;
+        }
+        if affects_search_settings {
+            self.refresh_search_tokenizer()?;
+        }
+
         let document = self
             .get_document(&id)
             .context("Document must exist")?
@@ -259,24 +331,46 @@ impl BazaState {
         Ok(document)
     }
 
-    pub fn insert_document_head(&mut self, head: DocumentHead) -> Result<()> {
-        let current_value = self.file.documents.remove(head.get_id());
+    pub fn insert_document_head(&mut self, mut head: DocumentHead) -> Result<()> {
+        let id = head.get_id().clone();
 
-        if let Some(document_head) = current_value {
+        let search_dependencies = self.capture_search_dependencies(&id);
+        let current_value = self.file.documents.remove(&id);
+
+        if let Some(document_head) = &current_value {
             ensure!(
                 !document_head.is_staged(),
                 "Can't insert into staged document"
             );
+
+            if head.get_created_at().is_none()
+                && let Some(created_at) = document_head.get_created_at()
+            {
+                head.set_created_at(*created_at);
+            }
         }
 
+        let affects_schema =
+            is_type_definition(Some(&head)) || is_type_definition(current_value.as_ref());
+        let affects_search_settings =
+            is_search_settings(Some(&head)) || is_search_settings(current_value.as_ref());
+
         self.update_document_refs(&head)?;
-        self.search.index_document(head.get_single_document())?;
 
-        self.file.documents.insert(head.get_id().clone(), head);
-        self.file.modified = true;
+        self.file.documents.insert(id.clone(), head);
+        self.update_asset_contents_index(&id);
+        self.update_search_index(&id, search_dependencies)?;
+        self.file.mark_modified(&id);
 
         log::trace!("State modified: inserted document head");
 
+        if affects_schema {
+            self.refresh_schema()?;
+        }
+        if affects_search_settings {
+            self.refresh_search_tokenizer()?;
+        }
+
         Ok(())
     }
 
@@ -306,13 +400,25 @@ impl BazaState {
 
         if head.get_snapshots_count() != snapshots_count {
             head.update_snapshots_count(snapshots_count);
-            self.file.modified = true;
+            self.file.mark_modified(id);
             log::trace!("State modified: updated snapshots count");
         }
 
         Ok(())
     }
 
+    pub(super) fn update_created_at(&mut self, id: &Id, created_at: Timestamp) -> Result<()> {
+        let head = self.get_mut_document(id).context("must find document")?;
+
+        if head.get_created_at() != Some(&created_at) {
+            head.set_created_at(created_at);
+            self.file.mark_modified(id);
+            log::trace!("State modified: updated creation time");
+        }
+
+        Ok(())
+    }
+
     pub fn iter_documents(&self) -> impl Iterator<Item = &DocumentHead> {
         self.file.documents.values()
     }
@@ -351,24 +457,33 @@ impl BazaState {
     pub fn reset_document(&mut self, id: &Id, lock_key: &Option<DocumentLockKey>) -> Result<()> {
         self.document_locks.check_document_lock(id, lock_key)?;
 
+        let search_dependencies = self.capture_search_dependencies(id);
         let (id, document) = self
             .file
             .documents
             .remove_entry(id)
             .context("Document doesn't exist")?;
+        let affects_schema = is_type_definition(Some(&document));
+        let affects_search_settings = is_search_settings(Some(&document));
         self.remove_document_refs(&id);
-        self.search.remove_document_index(&id);
 
         if let Some(updated_head) = document.reset() {
             self.update_document_refs(&updated_head)?;
-            self.search
-                .index_document(updated_head.get_single_document())?;
-            self.file.documents.insert(id, updated_head);
+            self.file.documents.insert(id.clone(), updated_head);
         }
+        self.update_asset_contents_index(&id);
+        self.update_search_index(&id, search_dependencies)?;
 
-        self.file.modified = true;
+        self.file.mark_modified(&id);
         log::trace!("State modified: reset document");
 
+        if affects_schema {
+            self.refresh_schema()?;
+        }
+        if affects_search_settings {
+            self.refresh_search_tokenizer()?;
+        }
+
         Ok(())
     }
 
@@ -388,6 +503,7 @@ impl BazaState {
         let new_rev = self.calculate_next_revision();
 
         for id in ids {
+            let search_dependencies = self.capture_search_dependencies(&id);
             let (id, document_head) = self
                 .file
                 .documents
@@ -397,12 +513,11 @@ impl BazaState {
             let updated_head = document_head.commit(new_rev.clone())?;
 
             self.update_document_refs(&updated_head)?;
-            self.search
-                .index_document(updated_head.get_single_document())?;
-            self.file.documents.insert(id, updated_head);
+            self.file.documents.insert(id.clone(), updated_head);
+            self.file.mark_modified(&id);
+            self.update_search_index(&id, search_dependencies)?;
         }
 
-        self.file.modified = true;
         log::trace!("State modified: commit");
 
         Ok(())
@@ -1,14 +1,33 @@
-use anyhow::Result;
+use std::collections::HashMap;
 
-use crate::entities::DocumentType;
+use anyhow::{Context, Result, ensure};
+use serde::Serialize;
 
-use super::{BazaState, DocumentHead};
+use crate::{
+    DocumentExpert,
+    entities::{DocumentType, Id},
+    full_text_search::{FieldMatch, Snippet},
+};
+
+use super::{
+    BazaState, DocumentHead, StructuredQuery,
+    search::{ID_FIELD_NAME, REFS_FIELD_NAME},
+    sort::{ListCursor, SortKey, SortValues, compare_sort_values, get_sort_values},
+};
 
 #[derive(Default)]
 pub struct Filter {
     pub document_types: Vec<DocumentType>,
+    /// Free text and field predicates, see [`StructuredQuery`].
     pub query: String,
-    pub page: u8,
+    /// Sort keys, most recently modified documents first if empty.
+    /// Search results are ordered by relevance unless sort keys are provided.
+    pub sort: Vec<SortKey>,
+    /// Page size, [`DEFAULT_PAGE_SIZE`] if not set.
+    pub page_size: Option<usize>,
+    pub page: usize,
+    /// Cursor returned in [`ListPage::next_cursor`]; replaces `page`.
+    pub cursor: Option<String>,
     pub only_conflicts: bool,
 }
 
@@ -38,6 +57,17 @@ impl Filter {
             true
         }
     }
+
+    fn get_page_size(&self) -> Result<usize> {
+        let page_size = self.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+
+        ensure!(
+            (1..=MAX_PAGE_SIZE).contains(&page_size),
+            "Page size must be between 1 and {MAX_PAGE_SIZE}"
+        );
+
+        Ok(page_size)
+    }
 }
 
 #[derive(Debug)]
@@ -45,63 +75,192 @@ pub struct ListPage<'d> {
     pub items: Vec<&'d DocumentHead>,
     pub has_more: bool,
     pub total: usize,
+    /// Cursor for the next page, if there is one.
+    pub next_cursor: Option<String>,
+    /// Snippets of the fields where the page items matched the free text query.
+    pub snippets: HashMap<Id, Vec<SearchSnippet>>,
+}
+
+/// An excerpt of the document field that matched the search query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchSnippet {
+    pub field: String,
+    #[serde(flatten)]
+    pub snippet: Snippet,
 }
 
-const PAGE_SIZE: usize = 10;
+pub const DEFAULT_PAGE_SIZE: usize = 10;
+pub const MAX_PAGE_SIZE: usize = 1000;
 
 impl BazaState {
     pub fn list_documents(&self, filter: &Filter) -> Result<ListPage<'_>> {
-        let page_start = (filter.page as usize) * PAGE_SIZE;
-
-        if filter.query.trim().is_empty() {
-            let mut filtered_documents = self
-                .iter_documents()
-                .filter(|head| filter.should_show_document(head))
-                .collect::<Vec<_>>();
+        let page_size = filter.get_page_size()?;
 
-            // sort by modification time
-            filtered_documents.sort_by(|a, b| b.get_updated_at().cmp(a.get_updated_at()));
+        for sort_key in &filter.sort {
+            sort_key.validate(&self.schema)?;
+        }
 
-            let total = filtered_documents.len();
-            let page_end = page_start + PAGE_SIZE;
-            let paginated_documents = filtered_documents
-                .into_iter()
-                .skip(page_start)
-                .take(PAGE_SIZE)
-                .collect::<Vec<_>>();
-
-            Ok(ListPage {
-                has_more: page_end < total,
-                total,
-                items: paginated_documents,
+        let query = StructuredQuery::parse(&filter.query, &self.schema)?;
+        let should_show_document = |head: &DocumentHead| {
+            filter.should_show_document(head)
+                && query.matches(head.get_single_document(), &self.schema)
+        };
+
+        let has_text_query = !query.text.trim().is_empty();
+        let mut field_matches = HashMap::new();
+        let filtered_documents = if has_text_query {
+            self.search
+                .search_with_matches(&query.text)
+                .map(|(id, fields)| {
+                    let head = self
+                        .get_document(&id)
+                        .expect("Document returned by search engine must exist");
+                    field_matches.insert(id, fields);
+
+                    head
+                })
+                .filter(|head| should_show_document(head))
+                .collect::<Vec<_>>()
+        } else {
+            self.iter_documents()
+                .filter(|head| should_show_document(head))
+                .collect::<Vec<_>>()
+        };
+
+        // search results are already ordered by relevance
+        let sort = if !filter.sort.is_empty() {
+            Some(filter.sort.clone())
+        } else if has_text_query {
+            None
+        } else {
+            Some(SortKey::default_sort())
+        };
+
+        let document_expert = DocumentExpert::new(&self.schema);
+        let mut documents = filtered_documents
+            .into_iter()
+            .map(|head| {
+                let values = sort
+                    .as_ref()
+                    .map(|sort| get_sort_values(head, sort, &document_expert, &self.schema))
+                    .unwrap_or_default();
+
+                (head, values)
             })
+            .collect::<Vec<(&DocumentHead, SortValues)>>();
+
+        if let Some(sort) = &sort {
+            documents.sort_by(|a, b| {
+                compare_sort_values(sort, (&a.1, a.0.get_id()), (&b.1, b.0.get_id()))
+            });
+        }
+
+        let total = documents.len();
+        let page_start = match filter
+            .cursor
+            .as_deref()
+            .map(ListCursor::decode)
+            .transpose()?
+        {
+            Some(cursor) => match &sort {
+                Some(sort) => documents.partition_point(|(head, values)| {
+                    compare_sort_values(sort, (values, head.get_id()), (&cursor.values, &cursor.id))
+                        .is_le()
+                }),
+                None => documents
+                    .iter()
+                    .position(|(head, _)| head.get_id() == &cursor.id)
+                    .map_or(cursor.position, |position| position + 1),
+            },
+            None => filter.page.saturating_mul(page_size),
+        }
+        .min(total);
+        let page_end = page_start.saturating_add(page_size).min(total);
+
+        let has_more = page_end < total;
+        let next_cursor = if has_more {
+            let (head, values) = &documents[page_end - 1];
+
+            Some(
+                ListCursor {
+                    values: values.clone(),
+                    id: head.get_id().clone(),
+                    position: page_end,
+                }
+                .encode()?,
+            )
         } else {
-            let results = self
-                .search
-                .search(&filter.query)
-                .map(|id| {
-                    self.get_document(&id)
-                        .expect("Document returned by search engine must exist")
-                })
-                .filter(|doc| filter.should_show_document(doc))
-                .collect::<Vec<_>>();
+            None
+        };
+
+        let items = documents[page_start..page_end]
+            .iter()
+            .map(|(head, _)| *head)
+            .collect::<Vec<_>>();
+
+        let mut snippets = HashMap::new();
+        for head in &items {
+            let id = head.get_id();
+            let Some(fields) = field_matches.get(id) else {
+                continue;
+            };
 
-            let total = results.len();
+            let document_snippets = self.build_search_snippets(id, fields)?;
+            if !document_snippets.is_empty() {
+                snippets.insert(id.clone(), document_snippets);
+            }
+        }
 
-            let has_more = (page_start + PAGE_SIZE) < total;
+        Ok(ListPage {
+            items,
+            has_more,
+            total,
+            next_cursor,
+            snippets,
+        })
+    }
 
-            let items = results
-                .into_iter()
-                .skip(page_start)
-                .take(PAGE_SIZE)
-                .collect::<Vec<_>>();
-
-            Ok(ListPage {
-                items,
-                has_more,
-                total,
-            })
+    fn build_search_snippets(&self, id: &Id, fields: &[FieldMatch]) -> Result<Vec<SearchSnippet>> {
+        let document = self
+            .get_document(id)
+            .context("Document must exist")?
+            .get_single_document();
+
+        let refs_titles = if fields.iter().any(|field| field.field == REFS_FIELD_NAME) {
+            self.collect_refs_titles(id, &self.find_document_collections(id))
+        } else {
+            vec![]
+        };
+        let search_fields =
+            self.search
+                .extract_search_fields(document, &refs_titles, self.get_asset_text(id))?;
+
+        let mut snippets: Vec<SearchSnippet> = Vec::new();
+        for field in fields {
+            // the id is already shown with every document
+            if field.field == ID_FIELD_NAME {
+                continue;
+            }
+
+            let Some(snippet) = search_fields
+                .get(&field.field)
+                .and_then(|text| Snippet::build(text, &field.positions))
+            else {
+                continue;
+            };
+
+            // the title is often the same as one of the fields
+            if snippets.iter().any(|item| item.snippet == snippet) {
+                continue;
+            }
+
+            snippets.push(SearchSnippet {
+                field: field.field.clone(),
+                snippet,
+            });
         }
+
+        Ok(snippets)
     }
 }
 
@@ -178,7 +337,7 @@ mod tests {
 
         // Add more documents to test pagination
         state.insert_snapshots(
-            (0..PAGE_SIZE)
+            (0..DEFAULT_PAGE_SIZE)
                 .map(|_| new_document(json!({ "test": "value" })).with_rev(json!({ "a": 1 })))
                 .collect(),
         );
@@ -191,7 +350,7 @@ mod tests {
             };
 
             let result = state.list_documents(&filter).unwrap();
-            assert_eq!(result.items.len(), PAGE_SIZE);
+            assert_eq!(result.items.len(), DEFAULT_PAGE_SIZE);
             assert!(result.has_more);
             assert_eq!(result.total, 12);
 
@@ -225,7 +384,7 @@ mod tests {
             };
 
             let result = state.list_documents(&filter).unwrap();
-            assert_eq!(result.items.len(), PAGE_SIZE);
+            assert_eq!(result.items.len(), DEFAULT_PAGE_SIZE);
             assert!(result.has_more);
             assert_eq!(result.total, 12);
 
@@ -242,6 +401,177 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_list_documents_with_predicates() {
+        let mut state = BazaState::new_test_state();
+
+        let doc1 = new_document(json!({ "test": "first value" })).with_rev(json!({ "a": 1 }));
+        let doc2 = new_document(json!({ "test": "second value", "ref": doc1.id }))
+            .with_rev(json!({ "a": 1 }));
+        let doc3 = new_document(json!({ "test": "other" })).with_rev(json!({ "a": 1 }));
+        state.insert_snapshots(vec![doc1.clone(), doc2.clone(), doc3]);
+
+        {
+            let filter = Filter {
+                query: format!("type:test_type ref:{}", doc1.id),
+                ..Default::default()
+            };
+
+            let result = state.list_documents(&filter).unwrap();
+            assert_eq!(result.total, 1);
+            assert_eq!(result.items[0].get_id(), &doc2.id);
+        }
+
+        // Check if predicates combine with full-text search
+        {
+            let filter = Filter {
+                query: "value -test:second".to_string(),
+                ..Default::default()
+            };
+
+            let result = state.list_documents(&filter).unwrap();
+            assert_eq!(result.total, 1);
+            assert_eq!(result.items[0].get_id(), &doc1.id);
+        }
+
+        // Check if search terms could be scoped to a field
+        {
+            let filter = Filter {
+                query: "@test:first @title:val*".to_string(),
+                ..Default::default()
+            };
+
+            let result = state.list_documents(&filter).unwrap();
+            assert_eq!(result.total, 1);
+            assert_eq!(result.items[0].get_id(), &doc1.id);
+        }
+
+        // Check if invalid predicates are reported
+        {
+            let filter = Filter {
+                query: "unknown:value".to_string(),
+                ..Default::default()
+            };
+
+            assert!(state.list_documents(&filter).is_err());
+        }
+    }
+
+    #[test]
+    fn test_list_documents_sorted() {
+        let mut state = BazaState::new_test_state();
+
+        let doc_b = new_document(json!({ "test": "b" })).with_rev(json!({ "a": 1 }));
+        let doc_a = new_document(json!({ "test": "A" })).with_rev(json!({ "a": 1 }));
+        let doc_empty = new_document(json!({})).with_rev(json!({ "a": 1 }));
+        state.insert_snapshots(vec![doc_b.clone(), doc_a.clone(), doc_empty.clone()]);
+
+        let list_ids = |sort: &str| {
+            let filter = Filter {
+                sort: vec![sort.parse().unwrap()],
+                ..Default::default()
+            };
+
+            state
+                .list_documents(&filter)
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|head| head.get_id().clone())
+                .collect::<Vec<_>>()
+        };
+
+        // missing values go last in both directions
+        assert_eq!(
+            list_ids("test"),
+            vec![doc_a.id.clone(), doc_b.id.clone(), doc_empty.id.clone()]
+        );
+        assert_eq!(
+            list_ids("test:desc"),
+            vec![doc_b.id.clone(), doc_a.id.clone(), doc_empty.id.clone()]
+        );
+
+        // sort by relevance or by a field when searching
+        {
+            let filter = Filter {
+                query: "b".to_string(),
+                sort: vec!["@title:desc".parse().unwrap()],
+                ..Default::default()
+            };
+
+            let result = state.list_documents(&filter).unwrap();
+            assert_eq!(result.items[0].get_id(), &doc_b.id);
+        }
+
+        {
+            let filter = Filter {
+                sort: vec!["unknown".parse().unwrap()],
+                ..Default::default()
+            };
+
+            assert!(state.list_documents(&filter).is_err());
+        }
+    }
+
+    #[test]
+    fn test_list_documents_with_cursor() {
+        let mut state = BazaState::new_test_state();
+
+        let documents = (0..5)
+            .map(|i| {
+                new_document(json!({ "test": format!("value {i}") })).with_rev(json!({ "a": 1 }))
+            })
+            .collect::<Vec<_>>();
+        state.insert_snapshots(documents.clone());
+
+        let filter = Filter {
+            sort: vec!["test".parse().unwrap()],
+            page_size: Some(2),
+            ..Default::default()
+        };
+
+        let page = state.list_documents(&filter).unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[1].get_id(), &documents[1].id);
+        assert!(page.has_more);
+        let cursor = page.next_cursor.clone().unwrap();
+
+        // documents added before the cursor don't shift the next page
+        state.insert_snapshots(vec![
+            new_document(json!({ "test": "value 0.5" })).with_rev(json!({ "a": 1 })),
+        ]);
+
+        let filter = Filter {
+            cursor: Some(cursor),
+            ..filter
+        };
+        let page = state.list_documents(&filter).unwrap();
+        assert_eq!(
+            page.items
+                .iter()
+                .map(|head| head.get_id().clone())
+                .collect::<Vec<_>>(),
+            vec![documents[2].id.clone(), documents[3].id.clone()]
+        );
+        assert_eq!(page.total, 6);
+
+        let filter = Filter {
+            cursor: page.next_cursor.clone(),
+            ..filter
+        };
+        let page = state.list_documents(&filter).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].get_id(), &documents[4].id);
+        assert!(!page.has_more);
+        assert!(page.next_cursor.is_none());
+
+        let filter = Filter {
+            page_size: Some(0),
+            ..Default::default()
+        };
+        assert!(state.list_documents(&filter).is_err());
+    }
+
     #[test]
     fn test_list_conflics() {
         let mut state = BazaState::new_test_state();
@@ -268,4 +598,43 @@ mod tests {
             assert!(result.items[0].is_conflict());
         }
     }
+
+    #[test]
+    fn test_list_documents_snippets() {
+        let mut state = BazaState::new_test_state();
+
+        let doc1 = new_document(json!({ "test": "The quick brown fox" }));
+        let doc2 = new_document(json!({ "test": "A lazy dog" }));
+        state.insert_snapshots(vec![doc1.clone(), doc2.clone()]);
+
+        let filter = Filter {
+            query: "brown".to_string(),
+            ..Default::default()
+        };
+        let page = state.list_documents(&filter).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.snippets.len(), 1);
+
+        // the title has the same snippet as the field
+        let snippets = &page.snippets[&doc1.id];
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].field, "@title");
+        assert_eq!(
+            snippets[0].snippet.render("[", "]"),
+            "The quick [brown] fox"
+        );
+
+        // ids aren't included in snippets
+        let filter = Filter {
+            query: format!("@id:{}", doc2.id),
+            ..Default::default()
+        };
+        let page = state.list_documents(&filter).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert!(page.snippets.is_empty());
+
+        // no snippets without free text query
+        let page = state.list_documents(&Default::default()).unwrap();
+        assert!(page.snippets.is_empty());
+    }
 }
@@ -1,12 +1,16 @@
-use std::{collections::HashMap, io::Write, time::Instant};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Instant,
+};
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
 
-use crate::full_text_search::{FTSEngine, FieldBoost};
-use baza_common::{create_file_reader, create_file_writer, log, read_all};
+use crate::full_text_search::{FTSEngine, FieldBoost, FieldMatch, Tokenizer};
+use baza_common::log;
 use baza_storage::crypto::age::AgeKey;
-use baza_storage::{AgeGzReader, AgeGzWriter};
 
 use crate::{
     DocumentExpert,
@@ -14,22 +18,41 @@ use crate::{
     schema::DataSchema,
 };
 
-const TITLE_FIELD_NAME: &str = "@title";
-const ID_FIELD_NAME: &str = "@id";
-const SEARCH_INDEX_FORMAT_VERSION: u8 = 1;
-// v5 uses field-local BM25 document-length normalization.
-const SEARCH_ALGORITHM_VERSION: u8 = 5;
+use super::segments::{
+    get_next_generation, get_segment_file, list_segment_files, read_encrypted_file,
+    select_generation_segments, write_base_file, write_encrypted_file,
+};
+
+pub(super) const TITLE_FIELD_NAME: &str = "@title";
+pub(super) const ID_FIELD_NAME: &str = "@id";
+pub(super) const REFS_FIELD_NAME: &str = "@refs";
+pub(super) const CONTENT_FIELD_NAME: &str = "@content";
+// v2 stores index changes in delta segments on top of the base index file.
+const SEARCH_INDEX_FORMAT_VERSION: u8 = 2;
+// v8 indexes the text extracted from the asset files.
+const SEARCH_ALGORITHM_VERSION: u8 = 8;
+// Segments are merged into the base index file once there are too many of them,
+// so that reading the index doesn't have to replay a long history of changes.
+const MAX_SEARCH_INDEX_SEGMENTS: usize = 32;
 
 #[derive(Serialize, Deserialize)]
-struct SearchIndexFile {
+struct SearchIndexHeader {
     format_version: u8,
     search_version: u8,
     data_version: u8,
     schema_fingerprint: String,
-    fts: FTSEngine,
 }
 
-impl SearchIndexFile {
+impl SearchIndexHeader {
+    fn new(schema: &DataSchema) -> Result<Self> {
+        Ok(Self {
+            format_version: SEARCH_INDEX_FORMAT_VERSION,
+            search_version: SEARCH_ALGORITHM_VERSION,
+            data_version: schema.get_latest_data_version(),
+            schema_fingerprint: schema.fingerprint()?,
+        })
+    }
+
     fn validate(&self, schema: &DataSchema) -> Result<()> {
         if self.format_version != SEARCH_INDEX_FORMAT_VERSION {
             log::info!(
@@ -73,40 +96,127 @@ impl SearchIndexFile {
     }
 }
 
+/// The base index file. Each merge into the base file starts a new generation of segments.
+#[derive(Serialize, Deserialize)]
+struct SearchIndexFile {
+    header: SearchIndexHeader,
+    generation: u64,
+    fts: FTSEngine,
+}
+
 #[derive(Serialize)]
 struct SearchIndexFileRef<'fts> {
-    format_version: u8,
-    search_version: u8,
-    data_version: u8,
-    schema_fingerprint: String,
+    header: SearchIndexHeader,
+    generation: u64,
     fts: &'fts FTSEngine,
 }
 
-impl<'fts> SearchIndexFileRef<'fts> {
-    fn new(schema: &DataSchema, fts: &'fts FTSEngine) -> Result<Self> {
-        Ok(Self {
-            format_version: SEARCH_INDEX_FORMAT_VERSION,
-            search_version: SEARCH_ALGORITHM_VERSION,
-            data_version: schema.get_latest_data_version(),
-            schema_fingerprint: schema.fingerprint()?,
-            fts,
-        })
+/// A delta segment with the index changes made since the previous segment of the generation.
+#[derive(Serialize, Deserialize)]
+struct SearchIndexSegment {
+    header: SearchIndexHeader,
+    changes: Vec<SearchIndexChange>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SearchIndexChange {
+    Index {
+        document_id: String,
+        fields: HashMap<String, String>,
+        boost_fields: HashMap<String, FieldBoost>,
+    },
+    Remove {
+        document_id: String,
+    },
+}
+
+impl SearchIndexChange {
+    fn apply(&self, fts: &mut FTSEngine) {
+        match self {
+            SearchIndexChange::Index {
+                document_id,
+                fields,
+                boost_fields,
+            } => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| (field.as_str(), value.as_str()))
+                    .collect();
+                let boost_fields = boost_fields
+                    .iter()
+                    .map(|(field, boost)| (field.as_str(), *boost))
+                    .collect();
+
+                fts.index_document(document_id.clone(), fields, boost_fields);
+            }
+            SearchIndexChange::Remove { document_id } => {
+                fts.remove_document(document_id);
+            }
+        }
     }
 }
 
 pub struct SearchEngine {
-    fts: FTSEngine,
+    // shared with the background merge, which serializes a snapshot of the index
+    fts: Arc<FTSEngine>,
     schema: DataSchema,
     modified: bool,
+
+    // generation of the base index file, None if the index must be merged into a new base file
+    generation: Option<u64>,
+    segments_count: usize,
+    // changes that aren't yet written to a segment
+    changes: Vec<SearchIndexChange>,
+    merge: Option<JoinHandle<Result<()>>>,
 }
 
 impl SearchEngine {
     pub fn new(schema: DataSchema) -> Self {
         SearchEngine {
-            fts: FTSEngine::new(),
+            fts: Arc::new(FTSEngine::new()),
             schema,
             modified: false,
+            generation: None,
+            segments_count: 0,
+            changes: Vec::new(),
+            merge: None,
+        }
+    }
+
+    /// Lists the delta segment files stored next to the index file.
+    pub(crate) fn list_index_segments(file: &str) -> Result<Vec<String>> {
+        let segments = list_segment_files(file)?
+            .into_iter()
+            .map(|(_, _, segment_file)| segment_file)
+            .collect();
+
+        Ok(segments)
+    }
+
+    /// Drops the index, so that documents could be re-indexed using the updated schema.
+    pub fn reset(&mut self, schema: DataSchema) {
+        self.fts = Arc::new(FTSEngine::with_tokenizer(self.fts.get_tokenizer().clone()));
+        self.schema = schema;
+        self.modified = true;
+        self.generation = None;
+        self.segments_count = 0;
+        self.changes.clear();
+    }
+
+    /// Replaces the tokenizer. If it changed, drops the index, so that documents could be
+    /// re-indexed using the new tokenizer, and returns `true`.
+    pub fn set_tokenizer(&mut self, tokenizer: Tokenizer) -> bool {
+        if self.fts.get_tokenizer() == &tokenizer {
+            return false;
         }
+
+        self.fts = Arc::new(FTSEngine::with_tokenizer(tokenizer));
+        self.modified = true;
+        self.generation = None;
+        self.segments_count = 0;
+        self.changes.clear();
+
+        true
     }
 
     pub fn read(file: &str, key: AgeKey, schema: DataSchema) -> Result<Self> {
@@ -114,75 +224,195 @@ impl SearchEngine {
 
         let start_time = Instant::now();
 
-        let reader = create_file_reader(file)?;
-        let agegz_reader = AgeGzReader::new(reader, key)?;
+        // segments must be listed before reading the base file, see write_base_file()
+        let segments = list_segment_files(file)?;
 
-        let bytes = read_all(agegz_reader)?;
+        let bytes = read_encrypted_file(file, key.clone())?;
         let index_file: SearchIndexFile =
             postcard::from_bytes(&bytes).context("Failed to parse SearchIndexFile")?;
-        index_file.validate(&schema)?;
+        index_file.header.validate(&schema)?;
+
+        let mut fts = index_file.fts;
+
+        let segments = select_generation_segments(segments, index_file.generation)
+            .context("Search index segments don't match the search index file")?;
+
+        for segment_file in &segments {
+            let bytes = read_encrypted_file(segment_file, key.clone())
+                .with_context(|| format!("Failed to read search index segment {segment_file}"))?;
+            let segment: SearchIndexSegment =
+                postcard::from_bytes(&bytes).context("Failed to parse SearchIndexSegment")?;
+            segment.header.validate(&schema)?;
+
+            for change in &segment.changes {
+                change.apply(&mut fts);
+            }
+        }
 
         let duration = start_time.elapsed();
         log::info!(
-            "Read search index from file in {:?}: format_version={}, search_version={}, data_version={}, schema_fingerprint={}",
+            "Read search index from file in {:?}: format_version={}, search_version={}, data_version={}, schema_fingerprint={}, generation={}, segments={}",
             duration,
-            index_file.format_version,
-            index_file.search_version,
-            index_file.data_version,
-            index_file.schema_fingerprint
+            index_file.header.format_version,
+            index_file.header.search_version,
+            index_file.header.data_version,
+            index_file.header.schema_fingerprint,
+            index_file.generation,
+            segments.len()
         );
 
         Ok(SearchEngine {
-            fts: index_file.fts,
+            fts: Arc::new(fts),
             schema,
             modified: false,
+            generation: Some(index_file.generation),
+            segments_count: segments.len(),
+            changes: Vec::new(),
+            merge: None,
         })
     }
 
+    /// Appends the index changes to a new segment, or merges the whole index into a new base
+    /// file in the background if there are too many segments.
     pub fn write(&mut self, file: &str, key: AgeKey) -> Result<()> {
-        log::debug!("Writing search index to file {file}");
+        if self.merge.as_ref().is_some_and(JoinHandle::is_finished) {
+            self.finish_merge();
+        }
 
-        let start_time = Instant::now();
+        match self.generation {
+            Some(generation) if self.segments_count < MAX_SEARCH_INDEX_SEGMENTS => {
+                if !self.changes.is_empty() {
+                    let segment_file = get_segment_file(file, generation, self.segments_count);
+                    self.write_segment(&segment_file, key)?;
+                    self.segments_count += 1;
+                }
+            }
+            _ => {
+                self.merge(file, key)?;
+            }
+        }
+
+        self.modified = false;
 
-        let writer = create_file_writer(file, true)?;
-        let mut agegz_writer = AgeGzWriter::new(writer, key)?;
+        Ok(())
+    }
 
-        let index_file = SearchIndexFileRef::new(&self.schema, &self.fts)?;
-        postcard::to_io(&index_file, &mut agegz_writer)
-            .context("Failed to serialize SearchIndexFile")?;
+    fn write_segment(&mut self, segment_file: &str, key: AgeKey) -> Result<()> {
+        log::debug!("Writing search index segment to file {segment_file}");
 
-        let mut writer = agegz_writer.finish()?;
-        writer.flush()?;
+        let start_time = Instant::now();
 
-        self.modified = false;
+        let segment = SearchIndexSegment {
+            header: SearchIndexHeader::new(&self.schema)?,
+            changes: std::mem::take(&mut self.changes),
+        };
+        let bytes =
+            postcard::to_allocvec(&segment).context("Failed to serialize SearchIndexSegment")?;
+        write_encrypted_file(segment_file, key, &bytes)?;
 
         let duration = start_time.elapsed();
         log::info!(
-            "Wrote search index to file in {:?}: format_version={}, search_version={}, data_version={}, schema_fingerprint={}",
-            duration,
-            index_file.format_version,
-            index_file.search_version,
-            index_file.data_version,
-            index_file.schema_fingerprint
+            "Wrote search index segment with {} changes to file in {:?}",
+            segment.changes.len(),
+            duration
         );
 
         Ok(())
     }
 
-    pub fn index_document(&mut self, document: &Document) -> Result<()> {
+    /// Starts writing the whole index into a new base file in the background.
+    pub fn merge(&mut self, file: &str, key: AgeKey) -> Result<()> {
+        self.finish_merge();
+
+        // the new generation must be newer than any segments left on disk
+        let generation = get_next_generation(file, self.generation)?;
+        let header = SearchIndexHeader::new(&self.schema)?;
+
+        // the index is copied only if it's modified before the merge finishes
+        let fts = self.fts.clone();
+
+        self.generation = Some(generation);
+        self.segments_count = 0;
+        self.changes.clear();
+        self.modified = false;
+
+        let file = file.to_string();
+        self.merge = Some(thread::spawn(move || {
+            let index_file = SearchIndexFileRef {
+                header,
+                generation,
+                fts: &fts,
+            };
+            let bytes = postcard::to_allocvec(&index_file)
+                .context("Failed to serialize SearchIndexFile")?;
+
+            write_base_file(&file, key, generation, &bytes)
+        }));
+
+        Ok(())
+    }
+
+    /// Waits for the background merge to finish. If it failed, the next write merges again.
+    pub fn finish_merge(&mut self) {
+        let Some(merge) = self.merge.take() else {
+            return;
+        };
+
+        let result = merge
+            .join()
+            .unwrap_or_else(|_| Err(anyhow!("Search index merge thread panicked")));
+
+        if let Err(err) = result {
+            log::error!("Failed to merge search index: {err:#}");
+            self.generation = None;
+        }
+    }
+
+    /// Indexes the document fields, together with `refs_titles`: the titles of the documents
+    /// it references and of the collections it belongs to, and the `content` text extracted
+    /// from the asset file.
+    pub fn index_document(
+        &mut self,
+        document: &Document,
+        refs_titles: &[String],
+        content: Option<&str>,
+    ) -> Result<()> {
+        let fields = self.extract_search_fields(document, refs_titles, content)?;
+
+        let mut boost_fields = HashMap::new();
+        boost_fields.insert(TITLE_FIELD_NAME.to_string(), FieldBoost::new(1.9)?);
+        boost_fields.insert(ID_FIELD_NAME.to_string(), FieldBoost::new(2.0)?);
+        boost_fields.insert(CONTENT_FIELD_NAME.to_string(), FieldBoost::new(0.5)?);
+
+        self.apply_change(SearchIndexChange::Index {
+            document_id: document.id.to_string(),
+            fields,
+            boost_fields,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the indexed text of the document fields, by field name.
+    pub(super) fn extract_search_fields(
+        &self,
+        document: &Document,
+        refs_titles: &[String],
+        content: Option<&str>,
+    ) -> Result<HashMap<String, String>> {
         let mut fields = HashMap::new();
 
         let document_expert = DocumentExpert::new(&self.schema);
         let title = document_expert.get_title(&document.document_type, &document.data)?;
-        fields.insert(TITLE_FIELD_NAME, title.as_str());
-        fields.insert(ID_FIELD_NAME, &document.id);
-
-        let mut boost_fields = HashMap::new();
-        boost_fields.insert(TITLE_FIELD_NAME, FieldBoost::new(1.9)?);
-        boost_fields.insert(ID_FIELD_NAME, FieldBoost::new(2.0)?);
+        fields.insert(TITLE_FIELD_NAME.to_string(), title);
+        fields.insert(ID_FIELD_NAME.to_string(), document.id.to_string());
+        fields.insert(REFS_FIELD_NAME.to_string(), refs_titles.join("\n"));
+        if let Some(content) = content {
+            fields.insert(CONTENT_FIELD_NAME.to_string(), content.to_string());
+        }
 
         for field in self.schema.iter_fields(&document.document_type)? {
-            let value = if let Some(value) = document.data.get(field.name) {
+            let value = if let Some(value) = document.data.get(&field.name) {
                 value
             } else {
                 continue;
@@ -194,19 +424,25 @@ impl SearchEngine {
                 continue;
             };
 
-            fields.insert(field.name, search_data);
+            fields.insert(field.name.clone(), search_data.to_string());
         }
 
-        self.fts
-            .index_document(document.id.to_string(), fields, boost_fields);
-
-        self.modified = true;
-
-        Ok(())
+        Ok(fields)
     }
 
     pub fn remove_document_index(&mut self, id: &Id) {
-        self.fts.remove_document(id);
+        self.apply_change(SearchIndexChange::Remove {
+            document_id: id.to_string(),
+        });
+    }
+
+    fn apply_change(&mut self, change: SearchIndexChange) {
+        change.apply(Arc::make_mut(&mut self.fts));
+
+        // a new base file will include all the changes anyway
+        if self.generation.is_some() {
+            self.changes.push(change);
+        }
 
         self.modified = true;
     }
@@ -217,11 +453,26 @@ impl SearchEngine {
         ids.into_iter().map(|id| id.into())
     }
 
+    /// Same as [`SearchEngine::search`], but also returns the fields that matched the query.
+    pub fn search_with_matches(&self, query: &str) -> impl Iterator<Item = (Id, Vec<FieldMatch>)> {
+        let results = self.fts.search_with_matches(query);
+
+        results
+            .into_iter()
+            .map(|result| (result.document_id.into(), result.fields))
+    }
+
     pub fn is_modified(&self) -> bool {
         self.modified
     }
 }
 
+impl Drop for SearchEngine {
+    fn drop(&mut self) {
+        self.finish_merge();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -238,9 +489,18 @@ mod tests {
     };
 
     use super::{
-        SEARCH_ALGORITHM_VERSION, SEARCH_INDEX_FORMAT_VERSION, SearchEngine, SearchIndexFile,
+        MAX_SEARCH_INDEX_SEGMENTS, SEARCH_ALGORITHM_VERSION, SEARCH_INDEX_FORMAT_VERSION,
+        SearchEngine, SearchIndexFile, SearchIndexHeader, get_segment_file, list_segment_files,
     };
 
+    fn new_index_dir() -> (TempFile, String) {
+        let dir = TempFile::new_with_details("search_index", "");
+        dir.mkdir().unwrap();
+        let file = format!("{}/search_index.gz.age", dir.path);
+
+        (dir, file)
+    }
+
     fn write_search_index_file(
         file: &str,
         key: AgeKey,
@@ -252,10 +512,13 @@ mod tests {
         let writer = create_file_writer(file, true).unwrap();
         let mut agegz_writer = AgeGzWriter::new(writer, key).unwrap();
         let index_file = SearchIndexFile {
-            format_version,
-            search_version,
-            data_version,
-            schema_fingerprint: schema.fingerprint().unwrap(),
+            header: SearchIndexHeader {
+                format_version,
+                search_version,
+                data_version,
+                schema_fingerprint: schema.fingerprint().unwrap(),
+            },
+            generation: 0,
             fts: FTSEngine::new(),
         };
 
@@ -270,7 +533,7 @@ mod tests {
         let document =
             new_document(json!({ "test": "searchable body" })).with_id(Id::from("knownsearchid"));
 
-        search.index_document(&document).unwrap();
+        search.index_document(&document, &[], None).unwrap();
 
         assert_eq!(
             search.search("knownsearchid").collect::<Vec<_>>(),
@@ -291,17 +554,40 @@ mod tests {
         }))
         .with_id(Id::from("ownerdoc"));
 
-        search.index_document(&document).unwrap();
+        search.index_document(&document, &[], None).unwrap();
 
         assert!(search.search("referenceddoc").next().is_none());
     }
 
+    #[test]
+    fn test_indexes_refs_titles() {
+        let mut search = SearchEngine::new(DataSchema::new_test_schema());
+        let document = new_document(json!({ "test": "owner" }));
+
+        search
+            .index_document(
+                &document,
+                &["Left Hand".to_string(), "Tag".to_string()],
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(
+            search.search("hand").collect::<Vec<_>>(),
+            vec![document.id.clone()]
+        );
+        assert_eq!(
+            search.search("@refs:tag").collect::<Vec<_>>(),
+            vec![document.id.clone()]
+        );
+    }
+
     #[test]
     fn test_index_document_rejects_invalid_searchable_field_type() {
         let mut search = SearchEngine::new(DataSchema::new_test_schema());
         let document = new_document(json!({ "test": 123 }));
 
-        let err = search.index_document(&document).unwrap_err();
+        let err = search.index_document(&document, &[], None).unwrap_err();
 
         assert!(err.to_string().contains("failed to extract field test"));
     }
@@ -314,8 +600,9 @@ mod tests {
         let document = new_document(json!({ "test": "roundtrip searchable" }));
 
         let mut search = SearchEngine::new(schema.clone());
-        search.index_document(&document).unwrap();
+        search.index_document(&document, &[], None).unwrap();
         search.write(&file.path, key.clone()).unwrap();
+        search.finish_merge();
 
         let search = SearchEngine::read(&file.path, key, schema).unwrap();
 
@@ -325,6 +612,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_search_index_appends_segments() {
+        let schema = DataSchema::new_test_schema();
+        let key = AgeKey::generate_age_x25519_key();
+        let (_dir, file) = new_index_dir();
+        let document1 = new_document(json!({ "test": "first" }));
+        let document2 = new_document(json!({ "test": "second" }));
+
+        let mut search = SearchEngine::new(schema.clone());
+        search.index_document(&document1, &[], None).unwrap();
+        search.write(&file, key.clone()).unwrap();
+        search.finish_merge();
+
+        search.index_document(&document2, &[], None).unwrap();
+        search.remove_document_index(&document1.id);
+        search.write(&file, key.clone()).unwrap();
+
+        // writes without changes don't create segments
+        search.write(&file, key.clone()).unwrap();
+        assert_eq!(list_segment_files(&file).unwrap().len(), 1);
+
+        let search = SearchEngine::read(&file, key, schema).unwrap();
+
+        assert!(search.search("first").next().is_none());
+        assert_eq!(
+            search.search("second").collect::<Vec<_>>(),
+            vec![document2.id.clone()]
+        );
+    }
+
+    #[test]
+    fn test_search_index_merges_segments() {
+        let schema = DataSchema::new_test_schema();
+        let key = AgeKey::generate_age_x25519_key();
+        let (_dir, file) = new_index_dir();
+
+        let mut search = SearchEngine::new(schema.clone());
+        search.write(&file, key.clone()).unwrap();
+
+        let mut ids = Vec::new();
+        for _ in 0..=MAX_SEARCH_INDEX_SEGMENTS {
+            let document = new_document(json!({ "test": "merged" }));
+            search.index_document(&document, &[], None).unwrap();
+            search.write(&file, key.clone()).unwrap();
+            ids.push(document.id);
+        }
+        search.finish_merge();
+
+        assert!(list_segment_files(&file).unwrap().is_empty());
+
+        let search = SearchEngine::read(&file, key, schema).unwrap();
+
+        assert_eq!(search.search("merged").count(), ids.len());
+    }
+
+    #[test]
+    fn test_search_index_read_rejects_incomplete_segments() {
+        let schema = DataSchema::new_test_schema();
+        let key = AgeKey::generate_age_x25519_key();
+        let (_dir, file) = new_index_dir();
+
+        let mut search = SearchEngine::new(schema.clone());
+        search.write(&file, key.clone()).unwrap();
+        search.finish_merge();
+        for _ in 0..2 {
+            let document = new_document(json!({ "test": "segment" }));
+            search.index_document(&document, &[], None).unwrap();
+            search.write(&file, key.clone()).unwrap();
+        }
+
+        // segment of a newer generation means the base file is stale
+        std::fs::copy(get_segment_file(&file, 0, 0), get_segment_file(&file, 1, 0)).unwrap();
+        assert!(SearchEngine::read(&file, key.clone(), schema.clone()).is_err());
+        std::fs::remove_file(get_segment_file(&file, 1, 0)).unwrap();
+
+        std::fs::remove_file(get_segment_file(&file, 0, 0)).unwrap();
+        let err = match SearchEngine::read(&file, key, schema) {
+            Ok(_) => panic!("Search index read should reject missing segment"),
+            Err(err) => err,
+        };
+
+        assert!(format!("{err:#}").contains("Segment 0 is missing"));
+    }
+
     #[test]
     fn test_search_index_read_rejects_schema_fingerprint_mismatch() {
         let key = AgeKey::generate_age_x25519_key();
@@ -332,14 +703,15 @@ mod tests {
         let document = new_document(json!({ "test": "schema mismatch" }));
 
         let mut search = SearchEngine::new(DataSchema::new_test_schema());
-        search.index_document(&document).unwrap();
+        search.index_document(&document, &[], None).unwrap();
         search.write(&file.path, key.clone()).unwrap();
+        search.finish_merge();
 
         let changed_schema = DataSchema::new(
             "test",
             vec![DataDescription {
-                document_type: "different_type",
-                title_format: "${test}",
+                document_type: "different_type".into(),
+                title_format: "${test}".into(),
                 fields: vec![],
             }],
         );
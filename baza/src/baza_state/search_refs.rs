@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+
+use crate::{DocumentExpert, entities::Id, schema::ASSET_TYPE};
+
+use super::{BazaState, asset_texts::get_asset_content_sha256};
+
+/// The parts of a document that other documents include in their search index, captured before
+/// the document is modified.
+pub(super) struct SearchDependencies {
+    title: Option<String>,
+    collection: HashSet<Id>,
+}
+
+impl BazaState {
+    pub(super) fn capture_search_dependencies(&self, id: &Id) -> SearchDependencies {
+        SearchDependencies {
+            title: self.get_search_title(id),
+            collection: self.get_collection_members(id),
+        }
+    }
+
+    /// Re-indexes the modified document, and the documents that include its title in their
+    /// search index: the documents that reference it and the members of the collection.
+    pub(super) fn update_search_index(
+        &mut self,
+        id: &Id,
+        before: SearchDependencies,
+    ) -> Result<()> {
+        let collection = self.get_collection_members(id);
+
+        let mut dependents = before
+            .collection
+            .symmetric_difference(&collection)
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        if self.get_search_title(id) != before.title {
+            dependents.extend(collection);
+            dependents.extend(self.find_document_backrefs(id));
+        }
+        dependents.remove(id);
+
+        if self.get_document(id).is_some() {
+            let collections = self.find_document_collections(id);
+            self.index_document(id, &collections)?;
+        } else {
+            self.search.remove_document_index(id);
+        }
+
+        for dependent in dependents {
+            if self.get_document(&dependent).is_none() {
+                continue;
+            }
+
+            let collections = self.find_document_collections(&dependent);
+            self.index_document(&dependent, &collections)?;
+        }
+
+        Ok(())
+    }
+
+    /// Indexes all documents; document refs must be up to date.
+    pub(super) fn rebuild_search_index(&mut self) -> Result<()> {
+        // member id -> collection ids
+        let mut collections: HashMap<Id, HashSet<Id>> = HashMap::new();
+        for head in self.iter_documents() {
+            let collection_id = head.get_id();
+
+            for member_id in self.get_collection_members(collection_id) {
+                collections
+                    .entry(member_id)
+                    .or_default()
+                    .insert(collection_id.clone());
+            }
+        }
+
+        let ids = self.file.documents.keys().cloned().collect::<Vec<_>>();
+        let no_collections = HashSet::new();
+        for id in ids {
+            let document_collections = collections.get(&id).unwrap_or(&no_collections);
+
+            self.index_document(&id, document_collections)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn index_document(&mut self, id: &Id, collections: &HashSet<Id>) -> Result<()> {
+        let refs_titles = self.collect_refs_titles(id, collections);
+
+        let head = self.file.documents.get(id).context("Document must exist")?;
+
+        let content = get_asset_content_sha256(head)
+            .and_then(|content_sha256| self.asset_texts.get_text(id, content_sha256));
+
+        self.search
+            .index_document(head.get_single_document(), &refs_titles, content)
+    }
+
+    /// Collects titles of the documents referenced by the document, and of the collections
+    /// that include it. Assets are skipped, their titles are just file names.
+    pub(super) fn collect_refs_titles(&self, id: &Id, collections: &HashSet<Id>) -> Vec<String> {
+        let mut ids = collections.iter().collect::<HashSet<_>>();
+        if let Some(refs) = self.get_document_refs(id) {
+            ids.extend(&refs.documents);
+        }
+        ids.remove(id);
+
+        let mut ids = ids.into_iter().collect::<Vec<_>>();
+        ids.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        ids.into_iter()
+            .filter(|ref_id| {
+                self.get_document(ref_id)
+                    .is_some_and(|head| !head.get_single_document().document_type.is(ASSET_TYPE))
+            })
+            .filter_map(|ref_id| self.get_search_title(ref_id))
+            .collect()
+    }
+
+    fn get_search_title(&self, id: &Id) -> Option<String> {
+        let document = self.get_document(id)?.get_single_document();
+        if document.is_erased() {
+            return None;
+        }
+
+        let expert = DocumentExpert::new(&self.schema);
+
+        expert
+            .get_title(&document.document_type, &document.data)
+            .ok()
+    }
+
+    fn get_collection_members(&self, id: &Id) -> HashSet<Id> {
+        self.get_document_refs(id)
+            .map(|refs| refs.collection.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        BazaInfo, BazaState,
+        entities::{Document, DocumentType, Id, InstanceId, new_document},
+        schema::{DataDescription, DataSchema, Field, FieldType},
+    };
+
+    use super::super::Filter;
+
+    fn new_state() -> BazaState {
+        let test_type = DataSchema::new_test_schema()
+            .get_data_description(&DocumentType::new("test_type"))
+            .unwrap()
+            .clone();
+        let schema = DataSchema::new(
+            "test",
+            vec![
+                test_type,
+                DataDescription {
+                    document_type: "tag".into(),
+                    title_format: "${title}".into(),
+                    fields: vec![
+                        Field {
+                            name: "title".into(),
+                            field_type: FieldType::String {},
+                            mandatory: false,
+                            readonly: false,
+                            ordered: None,
+                        },
+                        Field {
+                            name: "items".into(),
+                            field_type: FieldType::RefList(vec![]),
+                            mandatory: false,
+                            readonly: false,
+                            ordered: None,
+                        },
+                    ],
+                },
+            ],
+        );
+
+        BazaState::new(
+            InstanceId::from_string("test").unwrap(),
+            BazaInfo::new_test_info(),
+            schema,
+        )
+    }
+
+    fn new_tag(title: &str, items: &[&Id]) -> Document {
+        Document::new(DocumentType::new("tag")).with_data(json!({
+            "title": title,
+            "items": items,
+        }))
+    }
+
+    fn search(state: &BazaState, query: &str) -> Vec<Id> {
+        let filter = Filter {
+            query: query.to_string(),
+            ..Default::default()
+        };
+
+        let mut ids = state
+            .list_documents(&filter)
+            .unwrap()
+            .items
+            .iter()
+            .map(|head| head.get_id().clone())
+            .collect::<Vec<_>>();
+        ids.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        ids
+    }
+
+    #[test]
+    fn test_search_in_referenced_document_titles() {
+        let mut state = new_state();
+
+        let author = new_document(json!({ "test": "Ursula" }));
+        let book = new_document(json!({ "test": "Earthsea", "ref": author.id }));
+        state.stage_document(author.clone(), &None).unwrap();
+        state.stage_document(book.clone(), &None).unwrap();
+
+        assert_eq!(search(&state, "@refs:ursula"), vec![book.id.clone()]);
+
+        // renaming the referenced document re-indexes the dependent document
+        let renamed_author = author.clone().with_data(json!({ "test": "Le Guin" }));
+        state.stage_document(renamed_author, &None).unwrap();
+
+        assert!(search(&state, "ursula").is_empty());
+        assert_eq!(search(&state, "@refs:guin"), vec![book.id.clone()]);
+    }
+
+    #[test]
+    fn test_search_in_collection_titles() {
+        let mut state = new_state();
+
+        let doc1 = new_document(json!({ "test": "first" }));
+        let doc2 = new_document(json!({ "test": "second" }));
+        state.stage_document(doc1.clone(), &None).unwrap();
+        state.stage_document(doc2.clone(), &None).unwrap();
+
+        let tag = new_tag("Fantasy", &[&doc1.id]);
+        state.stage_document(tag.clone(), &None).unwrap();
+        assert_eq!(search(&state, "@refs:fantasy"), vec![doc1.id.clone()]);
+
+        // changing collection members re-indexes added and removed members
+        let tag = tag.with_data(json!({ "title": "Fantasy", "items": [doc2.id] }));
+        state.stage_document(tag.clone(), &None).unwrap();
+        assert_eq!(search(&state, "@refs:fantasy"), vec![doc2.id.clone()]);
+
+        state.commit().unwrap();
+        state.rebuild_search_index().unwrap();
+        assert_eq!(search(&state, "@refs:fantasy"), vec![doc2.id.clone()]);
+
+        state.erase_document(&tag.id).unwrap();
+        assert!(search(&state, "@refs:fantasy").is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+
+use baza_common::log;
+
+use crate::{
+    full_text_search::Tokenizer,
+    schema::{SEARCH_SETTINGS_TYPE, SearchSettingsData, build_search_tokenizer},
+};
+
+use super::{BazaState, DocumentHead};
+
+/// Builds the search tokenizer from the search settings documents.
+///
+/// Settings are ordered by document id, so all instances build the same tokenizer.
+pub(super) fn collect_search_tokenizer<'h>(
+    heads: impl Iterator<Item = &'h DocumentHead>,
+) -> Tokenizer {
+    let mut documents = heads
+        .map(DocumentHead::get_single_document)
+        .filter(|document| document.document_type.is(SEARCH_SETTINGS_TYPE))
+        .collect::<Vec<_>>();
+
+    documents.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+    let settings = documents
+        .into_iter()
+        .filter_map(
+            |document| match document.clone().convert::<SearchSettingsData>() {
+                Ok(settings) => Some(settings.data),
+                Err(err) => {
+                    log::warn!("Ignoring search settings {}: {err:#}", document.id);
+                    None
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+
+    build_search_tokenizer(&settings)
+}
+
+pub(super) fn is_search_settings(head: Option<&DocumentHead>) -> bool {
+    head.is_some_and(|head| {
+        head.iter_all_snapshots()
+            .any(|document| document.document_type.is(SEARCH_SETTINGS_TYPE))
+    })
+}
+
+impl BazaState {
+    /// Re-reads the search tokenizer from the search settings documents, and re-indexes all
+    /// documents if it changed.
+    pub(super) fn refresh_search_tokenizer(&mut self) -> Result<()> {
+        let tokenizer = collect_search_tokenizer(self.iter_documents());
+
+        if !self.search.set_tokenizer(tokenizer) {
+            return Ok(());
+        }
+
+        log::info!("Search settings changed, re-indexing documents");
+
+        self.rebuild_search_index()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        BazaState,
+        entities::{Document, DocumentType, Id, new_document},
+        schema::SEARCH_SETTINGS_TYPE,
+    };
+
+    use super::super::Filter;
+
+    fn search(state: &BazaState, query: &str) -> Vec<Id> {
+        let filter = Filter {
+            document_types: vec![DocumentType::new("test_type")],
+            query: query.to_string(),
+            ..Default::default()
+        };
+
+        state
+            .list_documents(&filter)
+            .unwrap()
+            .items
+            .iter()
+            .map(|head| head.get_id().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_stemming_is_disabled_by_default() {
+        let mut state = BazaState::new_test_state();
+
+        let doc1 = new_document(json!({ "test": "Connected books" }));
+        let doc2 = new_document(json!({ "test": "Читаю книги" }));
+        state.stage_document(doc1.clone(), &None).unwrap();
+        state.stage_document(doc2.clone(), &None).unwrap();
+
+        assert_eq!(search(&state, "books connected"), vec![doc1.id.clone()]);
+        assert!(search(&state, "connection").is_empty());
+        assert_eq!(search(&state, "книги"), vec![doc2.id.clone()]);
+        assert!(search(&state, "книгою").is_empty());
+    }
+
+    #[test]
+    fn test_search_settings_are_applied() {
+        let mut state = BazaState::new_test_state();
+
+        let doc = new_document(json!({ "test": "Книги about cars" }));
+        state.stage_document(doc.clone(), &None).unwrap();
+        assert!(search(&state, "automobile").is_empty());
+        assert!(search(&state, "книгою").is_empty());
+
+        let settings = Document::new(DocumentType::new(SEARCH_SETTINGS_TYPE)).with_data(json!({
+            "stemming": "English",
+            "synonyms": "car, automobile",
+        }));
+        state.stage_document(settings.clone(), &None).unwrap();
+
+        // documents are re-indexed with the new settings
+        assert_eq!(search(&state, "automobile"), vec![doc.id.clone()]);
+        // Cyrillic words are stemmed only if Ukrainian is enabled
+        assert!(search(&state, "книгою").is_empty());
+
+        let mut updated_settings = settings.clone();
+        updated_settings.data.set("stemming", "English, Ukrainian");
+        state.stage_document(updated_settings, &None).unwrap();
+        assert_eq!(search(&state, "книгою"), vec![doc.id.clone()]);
+
+        state.erase_document(&settings.id).unwrap();
+        assert!(search(&state, "automobile").is_empty());
+        assert!(search(&state, "книгою").is_empty());
+    }
+}
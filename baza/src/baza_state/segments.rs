@@ -0,0 +1,128 @@
+use std::{io::Write, path::Path, time::Instant};
+
+use anyhow::{Context, Result, ensure};
+
+use baza_common::{
+    AtomicFileWriter, create_file_reader, dir_exists, get_file_name, list_files, log, read_all,
+    remove_file_if_exists,
+};
+use baza_storage::crypto::age::AgeKey;
+use baza_storage::{AgeGzReader, AgeGzWriter};
+
+// Files that are saved often, like the state file and the search index, are stored as a base
+// file plus delta segments with the changes made since the base file was written. Each write of
+// the base file starts a new generation of segments.
+
+/// Segment files are stored next to the base file, as `<base file>.<generation>.<seq>`.
+pub(super) fn get_segment_file(file: &str, generation: u64, seq: usize) -> String {
+    format!("{file}.{generation}.{seq}")
+}
+
+/// Lists segment files of all generations, as `(generation, seq, file)`.
+pub(super) fn list_segment_files(file: &str) -> Result<Vec<(u64, usize, String)>> {
+    let dir = Path::new(file)
+        .parent()
+        .and_then(Path::to_str)
+        .context("Base file must have a parent dir")?;
+    if !dir_exists(dir)? {
+        return Ok(Vec::new());
+    }
+
+    let prefix = format!("{}.", get_file_name(file));
+
+    let segments = list_files(dir)?
+        .into_iter()
+        .filter_map(|segment_file| {
+            let (generation, seq) = get_file_name(&segment_file)
+                .strip_prefix(&prefix)?
+                .split_once('.')?;
+            let generation = generation.parse().ok()?;
+            let seq = seq.parse().ok()?;
+
+            Some((generation, seq, segment_file))
+        })
+        .collect();
+
+    Ok(segments)
+}
+
+/// Returns a generation that is newer than the current one and than any segments left on disk.
+pub(super) fn get_next_generation(file: &str, current_generation: Option<u64>) -> Result<u64> {
+    let generation = list_segment_files(file)?
+        .into_iter()
+        .map(|(generation, _, _)| generation)
+        .chain(current_generation)
+        .max()
+        .map_or(0, |generation| generation + 1);
+
+    Ok(generation)
+}
+
+/// Returns the segment files of the base file generation, ordered by seq.
+///
+/// Segments must be listed before reading the base file, see [`write_base_file`].
+pub(super) fn select_generation_segments(
+    mut segments: Vec<(u64, usize, String)>,
+    generation: u64,
+) -> Result<Vec<String>> {
+    ensure!(
+        segments
+            .iter()
+            .all(|(segment_generation, _, _)| *segment_generation <= generation),
+        "Base file is older than its segments"
+    );
+
+    segments.retain(|(segment_generation, _, _)| *segment_generation == generation);
+    segments.sort_by_key(|(_, seq, _)| *seq);
+
+    segments
+        .into_iter()
+        .enumerate()
+        .map(|(expected_seq, (_, seq, segment_file))| {
+            ensure!(seq == expected_seq, "Segment {expected_seq} is missing");
+
+            Ok(segment_file)
+        })
+        .collect()
+}
+
+pub(super) fn read_encrypted_file(file: &str, key: AgeKey) -> Result<Vec<u8>> {
+    let reader = create_file_reader(file)?;
+    let agegz_reader = AgeGzReader::new(reader, key)?;
+
+    let bytes = read_all(agegz_reader)?;
+
+    Ok(bytes)
+}
+
+pub(super) fn write_encrypted_file(file: &str, key: AgeKey, data: &[u8]) -> Result<()> {
+    let writer = AtomicFileWriter::create(file)?;
+    let mut agegz_writer = AgeGzWriter::new(writer, key)?;
+    agegz_writer.write_all(data)?;
+
+    let mut writer = agegz_writer.finish()?;
+    writer.flush()?;
+    writer.commit()
+}
+
+/// Writes the base file, and then removes segments of the previous generations.
+///
+/// Readers list segments before reading the base file, so they either see the new base file,
+/// or fail to read the removed segments, or find segments of a newer generation than the base
+/// file.
+pub(super) fn write_base_file(file: &str, key: AgeKey, generation: u64, data: &[u8]) -> Result<()> {
+    let start_time = Instant::now();
+
+    write_encrypted_file(file, key, data)?;
+
+    for (segment_generation, _, segment_file) in list_segment_files(file)? {
+        if segment_generation < generation {
+            remove_file_if_exists(&segment_file)?;
+        }
+    }
+
+    let duration = start_time.elapsed();
+    log::info!("Wrote base file {file} in {duration:?}, generation={generation}");
+
+    Ok(())
+}
@@ -0,0 +1,280 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use anyhow::{Context, Result, anyhow, ensure};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use baza_common::{Timestamp, decode_url_safe_base64, to_url_safe_base64};
+
+use crate::{
+    DocumentExpert,
+    entities::Id,
+    schema::{DataSchema, FieldType},
+};
+
+use super::DocumentHead;
+
+const UPDATED_AT: &str = "@updated_at";
+const CREATED_AT: &str = "@created_at";
+const TITLE: &str = "@title";
+const SNAPSHOTS_COUNT: &str = "@snapshots_count";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortField {
+    UpdatedAt,
+    /// Falls back to the modification time if the creation time is unknown.
+    CreatedAt,
+    Title,
+    SnapshotsCount,
+    Field(String),
+}
+
+/// A sort key, serialized as `<field>[:asc|:desc]`, e.g. `rating:desc` or `@title`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl SortKey {
+    #[must_use]
+    pub fn new(field: SortField, descending: bool) -> Self {
+        SortKey { field, descending }
+    }
+
+    /// Most recently modified documents first.
+    #[must_use]
+    pub fn default_sort() -> Vec<SortKey> {
+        vec![SortKey::new(SortField::UpdatedAt, true)]
+    }
+
+    pub fn validate(&self, schema: &DataSchema) -> Result<()> {
+        if let SortField::Field(name) = &self.field {
+            ensure!(
+                schema.find_fields(name).next().is_some(),
+                "Can't sort by unknown field '{name}'"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (field, descending) = match value.rsplit_once(':') {
+            Some((field, "asc")) => (field, false),
+            Some((field, "desc")) => (field, true),
+            Some((_, order)) => return Err(anyhow!("Unknown sort order '{order}'")),
+            None => (value, false),
+        };
+
+        let field = match field {
+            UPDATED_AT => SortField::UpdatedAt,
+            CREATED_AT => SortField::CreatedAt,
+            TITLE => SortField::Title,
+            SNAPSHOTS_COUNT => SortField::SnapshotsCount,
+            "" => return Err(anyhow!("Sort field must not be empty")),
+            field if field.starts_with('@') => return Err(anyhow!("Unknown sort field '{field}'")),
+            field => SortField::Field(field.to_string()),
+        };
+
+        Ok(SortKey { field, descending })
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = match &self.field {
+            SortField::UpdatedAt => UPDATED_AT,
+            SortField::CreatedAt => CREATED_AT,
+            SortField::Title => TITLE,
+            SortField::SnapshotsCount => SNAPSHOTS_COUNT,
+            SortField::Field(field) => field,
+        };
+        let order = if self.descending { "desc" } else { "asc" };
+
+        write!(f, "{field}:{order}")
+    }
+}
+
+impl TryFrom<String> for SortKey {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<SortKey> for String {
+    fn from(value: SortKey) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum SortValue {
+    Number(u64),
+    Text(String),
+    Time(Timestamp),
+}
+
+/// Sort values of a document, one per sort key. `None` is a missing value.
+pub(super) type SortValues = Vec<Option<SortValue>>;
+
+pub(super) fn get_sort_values(
+    head: &DocumentHead,
+    sort: &[SortKey],
+    document_expert: &DocumentExpert,
+    schema: &DataSchema,
+) -> SortValues {
+    let document = head.get_single_document();
+
+    sort.iter()
+        .map(|key| match &key.field {
+            SortField::UpdatedAt => Some(SortValue::Time(*head.get_updated_at())),
+            SortField::CreatedAt => Some(SortValue::Time(
+                *head.get_created_at().unwrap_or(head.get_updated_at()),
+            )),
+            SortField::Title => document_expert
+                .get_title(&document.document_type, &document.data)
+                .ok()
+                .map(|title| SortValue::Text(title.to_lowercase())),
+            SortField::SnapshotsCount => Some(SortValue::Number(head.get_snapshots_count() as u64)),
+            SortField::Field(name) => {
+                let field = schema
+                    .iter_fields(&document.document_type)
+                    .ok()?
+                    .find(|field| &field.name == name)?;
+
+                get_field_sort_value(&field.field_type, document.data.get(name)?)
+            }
+        })
+        .collect()
+}
+
+fn get_field_sort_value(field_type: &FieldType, value: &Value) -> Option<SortValue> {
+    let result = match field_type {
+        FieldType::NaturalNumber {} => SortValue::Number(value.as_u64()?),
+        FieldType::Flag {} => SortValue::Number(value.as_bool()?.into()),
+        FieldType::Enum(options) => {
+            let value = value.as_str()?;
+            let position = options.iter().position(|option| option == value)?;
+
+            SortValue::Number(position as u64)
+        }
+        FieldType::RefList(_) => SortValue::Number(value.as_array()?.len() as u64),
+        FieldType::String {}
+        | FieldType::MarkupString {}
+        | FieldType::Ref(_)
+        | FieldType::Date {}
+        | FieldType::Duration {}
+        | FieldType::People {}
+        | FieldType::Countries {} => {
+            let value = value.as_str()?;
+            if value.is_empty() {
+                return None;
+            }
+
+            SortValue::Text(value.to_lowercase())
+        }
+    };
+
+    Some(result)
+}
+
+/// Compares documents by sort values, then by id, so that the order is total.
+/// Missing values always go last.
+pub(super) fn compare_sort_values(
+    sort: &[SortKey],
+    a: (&SortValues, &Id),
+    b: (&SortValues, &Id),
+) -> Ordering {
+    for (key, (a_value, b_value)) in sort.iter().zip(a.0.iter().zip(b.0.iter())) {
+        let ordering = match (a_value, b_value) {
+            (Some(a_value), Some(b_value)) if key.descending => b_value.cmp(a_value),
+            (Some(a_value), Some(b_value)) => a_value.cmp(b_value),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+
+    a.1.as_ref().cmp(b.1.as_ref())
+}
+
+/// Position of the last document of a page, used to fetch the next page.
+///
+/// Sorted lists resume right after the sort values of the last document, so that documents
+/// added or removed before it don't shift the following pages.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub(super) struct ListCursor {
+    pub values: SortValues,
+    pub id: Id,
+    pub position: usize,
+}
+
+impl ListCursor {
+    pub fn encode(&self) -> Result<String> {
+        let data = serde_json::to_vec(self).context("Failed to serialize list cursor")?;
+
+        Ok(to_url_safe_base64(&data))
+    }
+
+    pub fn decode(value: &str) -> Result<Self> {
+        let data = decode_url_safe_base64(value).context("Invalid list cursor")?;
+
+        serde_json::from_slice(&data).context("Invalid list cursor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::Id;
+
+    use super::{ListCursor, SortField, SortKey, SortValue};
+
+    #[test]
+    fn test_parse_sort_key() {
+        assert_eq!(
+            "@updated_at:desc".parse::<SortKey>().unwrap(),
+            SortKey::new(SortField::UpdatedAt, true)
+        );
+        assert_eq!(
+            "rating".parse::<SortKey>().unwrap(),
+            SortKey::new(SortField::Field("rating".into()), false)
+        );
+        assert_eq!(
+            "@snapshots_count:asc"
+                .parse::<SortKey>()
+                .unwrap()
+                .to_string(),
+            "@snapshots_count:asc"
+        );
+
+        assert!("@unknown".parse::<SortKey>().is_err());
+        assert!("rating:up".parse::<SortKey>().is_err());
+        assert!(":desc".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn test_list_cursor() {
+        let cursor = ListCursor {
+            values: vec![Some(SortValue::Text("title".into())), None],
+            id: Id::new(),
+            position: 10,
+        };
+
+        let encoded = cursor.encode().unwrap();
+        assert_eq!(ListCursor::decode(&encoded).unwrap(), cursor);
+
+        assert!(ListCursor::decode("not a cursor").is_err());
+    }
+}
@@ -1,18 +1,30 @@
-use std::{collections::HashMap, io::Write, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use baza_common::{create_file_reader, create_file_writer, log};
+use baza_common::{file_exists, log};
 use baza_storage::crypto::age::AgeKey;
-use baza_storage::{AgeGzReader, AgeGzWriter};
 
 use crate::{
     BazaInfo,
     entities::{DocumentKey, Id, InstanceId, Refs},
 };
 
-use super::DocumentHead;
+use super::{
+    DocumentHead,
+    segments::{
+        get_next_generation, get_segment_file, list_segment_files, read_encrypted_file,
+        select_generation_segments, write_base_file, write_encrypted_file,
+    },
+};
+
+// Segments are merged into the base state file once there are too many of them,
+// so that reading the state doesn't have to replay a long history of changes.
+const MAX_STATE_SEGMENTS: usize = 32;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct BazaStateFile {
@@ -21,8 +33,47 @@ pub struct BazaStateFile {
     pub documents: HashMap<Id, DocumentHead>,
     pub refs: HashMap<DocumentKey, Refs>,
 
+    // generation of the delta segments written on top of this file
+    #[serde(default)]
+    generation: u64,
+
     #[serde(skip)]
     pub modified: bool,
+
+    // documents changed since the last write, None if the whole file must be written
+    #[serde(skip)]
+    changed_ids: Option<HashSet<Id>>,
+    #[serde(skip)]
+    segments_count: usize,
+}
+
+/// A delta segment with the documents changed since the previous segment of the generation.
+#[derive(Deserialize)]
+struct BazaStateSegment {
+    // None if the document was removed
+    documents: HashMap<Id, Option<DocumentHead>>,
+    refs: HashMap<DocumentKey, Refs>,
+}
+
+#[derive(Serialize)]
+struct BazaStateSegmentRef<'s> {
+    documents: HashMap<&'s Id, Option<&'s DocumentHead>>,
+    refs: HashMap<&'s DocumentKey, &'s Refs>,
+}
+
+impl BazaStateSegment {
+    fn apply(self, file: &mut BazaStateFile) {
+        file.refs
+            .retain(|key, _| !self.documents.contains_key(&key.id));
+        file.refs.extend(self.refs);
+
+        for (id, head) in self.documents {
+            match head {
+                Some(head) => file.documents.insert(id, head),
+                None => file.documents.remove(&id),
+            };
+        }
+    }
 }
 
 impl BazaStateFile {
@@ -32,41 +83,147 @@ impl BazaStateFile {
             documents: HashMap::new(),
             refs: HashMap::new(),
             instance_id,
+            generation: 0,
             modified: true,
+            changed_ids: None,
+            segments_count: 0,
         }
     }
 
+    /// Lists the delta segment files stored next to the state file.
+    pub fn list_segments(file: &str) -> Result<Vec<String>> {
+        let segments = list_segment_files(file)?
+            .into_iter()
+            .map(|(_, _, segment_file)| segment_file)
+            .collect();
+
+        Ok(segments)
+    }
+
     pub fn read(file: &str, key: AgeKey) -> Result<Self> {
         log::debug!("Reading state from file {file}");
 
         let start_time = Instant::now();
 
-        let reader = create_file_reader(file)?;
-        let agegz_reader = AgeGzReader::new(reader, key)?;
+        // segments must be listed before reading the base file, see write_base_file()
+        let segments = list_segment_files(file)?;
+
+        let bytes = read_encrypted_file(file, key.clone())?;
+        let mut state_file: BazaStateFile =
+            serde_json::from_slice(&bytes).context("Failed to parse BazaStateFile")?;
+
+        let segments = select_generation_segments(segments, state_file.generation)
+            .context("State segments don't match the state file")?;
 
-        let file: BazaStateFile =
-            serde_json::from_reader(agegz_reader).context("Failed to parse BazaStateFile")?;
+        for segment_file in &segments {
+            let bytes = read_encrypted_file(segment_file, key.clone())
+                .with_context(|| format!("Failed to read state segment {segment_file}"))?;
+            let segment: BazaStateSegment =
+                serde_json::from_slice(&bytes).context("Failed to parse BazaStateSegment")?;
+
+            segment.apply(&mut state_file);
+        }
+        state_file.segments_count = segments.len();
+        state_file.changed_ids = Some(HashSet::new());
 
         let duration = start_time.elapsed();
-        log::info!("Read state from file in {:?}", duration);
+        log::info!(
+            "Read state from file in {:?}: generation={}, segments={}",
+            duration,
+            state_file.generation,
+            segments.len()
+        );
 
-        Ok(file)
+        Ok(state_file)
     }
 
-    pub fn write(&self, file: &str, key: AgeKey) -> Result<()> {
-        log::debug!("Writing state to file {file}");
+    /// Records that the document was changed, so that it's written to the next segment.
+    pub fn mark_modified(&mut self, id: &Id) {
+        self.modified = true;
+
+        if let Some(changed_ids) = &mut self.changed_ids {
+            changed_ids.insert(id.clone());
+        }
+    }
+
+    /// Records that all the documents were changed, so that the whole file is written.
+    pub fn mark_all_modified(&mut self) {
+        self.modified = true;
+        self.changed_ids = None;
+    }
+
+    /// Appends the changed documents to a new segment, or writes the whole state into a new base
+    /// file if there are too many segments or changes, or if the base file doesn't exist.
+    pub fn write(&mut self, file: &str, key: AgeKey) -> Result<()> {
+        // if the write fails, the next write rewrites the whole file
+        let changed_ids = self.changed_ids.take();
+
+        match changed_ids {
+            Some(changed_ids)
+                if self.segments_count < MAX_STATE_SEGMENTS
+                    && changed_ids.len() <= self.documents.len() / 2
+                    && file_exists(file)? =>
+            {
+                if !changed_ids.is_empty() {
+                    self.write_segment(file, key, &changed_ids)?;
+                }
+            }
+            _ => {
+                self.write_base(file, key)?;
+            }
+        }
+
+        self.changed_ids = Some(HashSet::new());
+
+        Ok(())
+    }
+
+    fn write_segment(&mut self, file: &str, key: AgeKey, changed_ids: &HashSet<Id>) -> Result<()> {
+        let segment_file = get_segment_file(file, self.generation, self.segments_count);
+        log::debug!("Writing state segment to file {segment_file}");
 
         let start_time = Instant::now();
 
-        let writer = create_file_writer(file, true)?;
+        let segment = BazaStateSegmentRef {
+            documents: changed_ids
+                .iter()
+                .map(|id| (id, self.documents.get(id)))
+                .collect(),
+            refs: changed_ids
+                .iter()
+                .filter_map(|id| self.documents.get(id))
+                .flat_map(DocumentHead::iter_all_snapshots)
+                .filter_map(|snapshot| {
+                    self.refs
+                        .get_key_value(&DocumentKey::for_document(snapshot))
+                })
+                .collect(),
+        };
+        let bytes = serde_json::to_vec(&segment).context("Failed to serialize BazaStateSegment")?;
+        write_encrypted_file(&segment_file, key, &bytes)?;
+        self.segments_count += 1;
+
+        let duration = start_time.elapsed();
+        log::info!(
+            "Wrote state segment with {} documents to file in {:?}",
+            changed_ids.len(),
+            duration
+        );
 
-        let mut agegz_writer = AgeGzWriter::new(writer, key)?;
+        Ok(())
+    }
+
+    fn write_base(&mut self, file: &str, key: AgeKey) -> Result<()> {
+        log::debug!("Writing state to file {file}");
+
+        let start_time = Instant::now();
 
-        serde_json::to_writer(&mut agegz_writer, &self)
-            .context("Failed to serialize BazaStateFile")?;
+        // the new generation must be newer than any segments left on disk
+        self.generation = get_next_generation(file, Some(self.generation))?;
+        self.segments_count = 0;
 
-        let mut writer = agegz_writer.finish()?;
-        writer.flush()?;
+        let bytes = serde_json::to_vec(&self).context("Failed to serialize BazaStateFile")?;
+        write_base_file(file, key, self.generation, &bytes)?;
 
         let duration = start_time.elapsed();
         log::info!("Wrote state to file in {:?}", duration);
@@ -74,3 +231,99 @@ impl BazaStateFile {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use baza_common::TempFile;
+    use baza_storage::crypto::age::AgeKey;
+
+    use crate::{
+        BazaInfo,
+        entities::{InstanceId, new_document},
+    };
+
+    use super::{BazaStateFile, DocumentHead, MAX_STATE_SEGMENTS, list_segment_files};
+
+    fn new_state_dir() -> (TempFile, String) {
+        let dir = TempFile::new_with_details("state_file", "");
+        dir.mkdir().unwrap();
+        let file = format!("{}/state.gz.age", dir.path);
+
+        (dir, file)
+    }
+
+    fn add_document(state_file: &mut BazaStateFile) {
+        let document = new_document(json!({ "test": "value" }));
+        let id = document.id.clone();
+
+        state_file
+            .documents
+            .insert(id.clone(), DocumentHead::new_staged(document));
+        state_file.mark_modified(&id);
+    }
+
+    #[test]
+    fn test_state_file_appends_segments() {
+        let key = AgeKey::generate_age_x25519_key();
+        let (_dir, file) = new_state_dir();
+
+        let mut state_file = BazaStateFile::new(
+            InstanceId::from_string("test").unwrap(),
+            BazaInfo::new_test_info(),
+        );
+        for _ in 0..4 {
+            add_document(&mut state_file);
+        }
+        state_file.write(&file, key.clone()).unwrap();
+        assert!(list_segment_files(&file).unwrap().is_empty());
+
+        add_document(&mut state_file);
+        state_file.write(&file, key.clone()).unwrap();
+
+        let removed_id = state_file.documents.keys().next().unwrap().clone();
+        state_file.documents.remove(&removed_id);
+        state_file.mark_modified(&removed_id);
+        state_file.write(&file, key.clone()).unwrap();
+
+        // writes without changes don't create segments
+        state_file.write(&file, key.clone()).unwrap();
+        assert_eq!(list_segment_files(&file).unwrap().len(), 2);
+
+        let read_state_file = BazaStateFile::read(&file, key.clone()).unwrap();
+        assert_eq!(read_state_file.documents, state_file.documents);
+        assert_eq!(read_state_file.refs, state_file.refs);
+        assert!(!read_state_file.documents.contains_key(&removed_id));
+    }
+
+    #[test]
+    fn test_state_file_merges_segments() {
+        let key = AgeKey::generate_age_x25519_key();
+        let (_dir, file) = new_state_dir();
+
+        let mut state_file = BazaStateFile::new(
+            InstanceId::from_string("test").unwrap(),
+            BazaInfo::new_test_info(),
+        );
+        for _ in 0..MAX_STATE_SEGMENTS * 2 {
+            add_document(&mut state_file);
+        }
+        state_file.write(&file, key.clone()).unwrap();
+
+        for _ in 0..MAX_STATE_SEGMENTS {
+            add_document(&mut state_file);
+            state_file.write(&file, key.clone()).unwrap();
+        }
+        assert_eq!(list_segment_files(&file).unwrap().len(), MAX_STATE_SEGMENTS);
+
+        // the next write merges segments into a new base file
+        add_document(&mut state_file);
+        state_file.write(&file, key.clone()).unwrap();
+        assert!(list_segment_files(&file).unwrap().is_empty());
+
+        let read_state_file = BazaStateFile::read(&file, key).unwrap();
+        assert_eq!(read_state_file.documents, state_file.documents);
+        assert_eq!(read_state_file.generation, 2);
+    }
+}
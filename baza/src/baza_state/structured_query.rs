@@ -0,0 +1,522 @@
+use std::cmp::Ordering;
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use serde_json::Value;
+
+use crate::{
+    entities::{Document, DocumentType},
+    schema::{DataSchema, Field, FieldType},
+};
+
+use super::search::{CONTENT_FIELD_NAME, ID_FIELD_NAME, REFS_FIELD_NAME, TITLE_FIELD_NAME};
+
+const TYPE_KEY: &str = "type";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// `field:value`: equality, or substring match for text fields
+    Matches,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn is_ordering(self) -> bool {
+        self != Comparison::Matches
+    }
+
+    fn check(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Matches => ordering.is_eq(),
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Predicate {
+    pub field: String,
+    pub comparison: Comparison,
+    pub value: String,
+    pub negated: bool,
+}
+
+/// A parsed list/search query, like `type:book status:InProgress rating>=Good "Le Guin"`.
+///
+/// Everything that isn't a `field<op>value` predicate is a free-text query for the full-text
+/// search engine. `@field:term` on searchable text fields, `@title:term`, `@id:term`,
+/// `@refs:term` and `@content:term` stay in the free text, as search terms that match only that
+/// field.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StructuredQuery {
+    pub text: String,
+    pub document_types: Vec<DocumentType>,
+    pub predicates: Vec<Predicate>,
+}
+
+impl StructuredQuery {
+    pub fn parse(query: &str, schema: &DataSchema) -> Result<Self> {
+        let mut result = StructuredQuery::default();
+        let mut text_terms = Vec::new();
+
+        for token in split_query(query) {
+            let Some((negated, field, comparison, value)) = parse_predicate(&token) else {
+                text_terms.push(token);
+                continue;
+            };
+
+            if let Some(search_field) = get_search_field(field, comparison, schema)? {
+                // the search engine names schema fields without the @
+                let prefix = if negated { "-" } else { "" };
+                let term = &token[prefix.len() + field.len() + 1..];
+                text_terms.push(format!("{prefix}{search_field}:{term}"));
+                continue;
+            }
+
+            if field == TYPE_KEY {
+                ensure!(
+                    !negated && comparison == Comparison::Matches,
+                    "Only type:<document type> is supported"
+                );
+
+                let document_type = DocumentType::new(value);
+                schema.get_data_description(&document_type)?;
+                result.document_types.push(document_type);
+                continue;
+            }
+
+            let predicate = Predicate {
+                field: field.to_string(),
+                comparison,
+                value,
+                negated,
+            };
+            predicate.validate(schema)?;
+
+            result.predicates.push(predicate);
+        }
+
+        result.text = text_terms.join(" ");
+
+        Ok(result)
+    }
+
+    pub fn has_predicates(&self) -> bool {
+        !self.document_types.is_empty() || !self.predicates.is_empty()
+    }
+
+    pub fn matches(&self, document: &Document, schema: &DataSchema) -> bool {
+        if !self.document_types.is_empty() && !self.document_types.contains(&document.document_type)
+        {
+            return false;
+        }
+
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(document, schema))
+    }
+}
+
+impl Predicate {
+    /// Checks that the value is valid for at least one document type that has the field.
+    fn validate(&self, schema: &DataSchema) -> Result<()> {
+        let mut last_error = None;
+        let mut fields_count = 0;
+
+        for field in schema.find_fields(&self.field) {
+            fields_count += 1;
+
+            match self.compare(field, None) {
+                Ok(_) => return Ok(()),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        ensure!(fields_count > 0, "Unknown field '{}' in query", self.field);
+
+        Err(last_error
+            .unwrap_or_else(|| anyhow!("invalid value"))
+            .context(format!(
+                "Invalid query predicate for field '{}'",
+                self.field
+            )))
+    }
+
+    fn matches(&self, document: &Document, schema: &DataSchema) -> bool {
+        let field = schema
+            .iter_fields(&document.document_type)
+            .ok()
+            .and_then(|mut fields| fields.find(|field| field.name == self.field));
+
+        let Some(field) = field else {
+            return false;
+        };
+
+        let value = document.data.get(&self.field);
+
+        // values that aren't valid for this document type never match
+        let matches = self.compare(field, value).unwrap_or(false);
+
+        matches != self.negated
+    }
+
+    fn compare(&self, field: &Field, value: Option<&Value>) -> Result<bool> {
+        let comparison = self.comparison;
+        let expected = self.value.as_str();
+
+        let result = match &field.field_type {
+            FieldType::String {}
+            | FieldType::MarkupString {}
+            | FieldType::People {}
+            | FieldType::Countries {}
+            | FieldType::Duration {} => {
+                let Some(value) = value.and_then(Value::as_str) else {
+                    return Ok(false);
+                };
+
+                let value = value.to_lowercase();
+                let expected = expected.to_lowercase();
+
+                if comparison.is_ordering() {
+                    comparison.check(value.as_str().cmp(expected.as_str()))
+                } else {
+                    value.contains(&expected)
+                }
+            }
+            FieldType::Date {} => {
+                let Some(value) = value.and_then(Value::as_str) else {
+                    return Ok(false);
+                };
+
+                if comparison.is_ordering() {
+                    comparison.check(value.cmp(expected))
+                } else {
+                    value.starts_with(expected)
+                }
+            }
+            FieldType::NaturalNumber {} => {
+                let expected: u64 = expected
+                    .parse()
+                    .with_context(|| format!("'{expected}' isn't a natural number"))?;
+
+                let Some(value) = value.and_then(Value::as_u64) else {
+                    return Ok(false);
+                };
+
+                comparison.check(value.cmp(&expected))
+            }
+            FieldType::Flag {} => {
+                ensure!(!comparison.is_ordering(), "flags can't be compared");
+
+                let expected = match expected.to_lowercase().as_str() {
+                    "true" | "yes" => true,
+                    "false" | "no" => false,
+                    _ => bail!("'{expected}' isn't a flag value"),
+                };
+
+                let value = value.and_then(Value::as_bool).unwrap_or_default();
+
+                value == expected
+            }
+            FieldType::Enum(options) => {
+                let position = |option: &str| {
+                    options
+                        .iter()
+                        .position(|item| item.eq_ignore_ascii_case(option))
+                };
+
+                let expected = position(expected)
+                    .with_context(|| format!("'{expected}' isn't one of {}", options.join(", ")))?;
+
+                let Some(value) = value.and_then(Value::as_str).and_then(position) else {
+                    return Ok(false);
+                };
+
+                comparison.check(value.cmp(&expected))
+            }
+            FieldType::Ref(_) => {
+                ensure!(!comparison.is_ordering(), "refs can't be compared");
+
+                value.and_then(Value::as_str) == Some(expected)
+            }
+            FieldType::RefList(_) => {
+                ensure!(!comparison.is_ordering(), "refs can't be compared");
+
+                value
+                    .and_then(Value::as_array)
+                    .is_some_and(|items| items.iter().any(|item| item.as_str() == Some(expected)))
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+/// Returns the name of the indexed field if the `@field:term` token should be matched by the
+/// full-text search engine.
+fn get_search_field<'f>(
+    field: &'f str,
+    comparison: Comparison,
+    schema: &DataSchema,
+) -> Result<Option<&'f str>> {
+    let Some(name) = field.strip_prefix('@') else {
+        return Ok(None);
+    };
+
+    ensure!(
+        comparison == Comparison::Matches,
+        "Only {field}:<term> is supported"
+    );
+
+    if [
+        TITLE_FIELD_NAME,
+        ID_FIELD_NAME,
+        REFS_FIELD_NAME,
+        CONTENT_FIELD_NAME,
+    ]
+    .contains(&field)
+    {
+        return Ok(Some(field));
+    }
+
+    let mut fields = schema.find_fields(name).peekable();
+    ensure!(
+        fields.peek().is_some() && fields.all(Field::is_searchable),
+        "Unknown search field '{field}', expected a searchable text field, {TITLE_FIELD_NAME}, {ID_FIELD_NAME}, {REFS_FIELD_NAME} or {CONTENT_FIELD_NAME}"
+    );
+
+    Ok(Some(name))
+}
+
+/// Splits the query by whitespace, keeping double-quoted parts together.
+fn split_query(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+
+        if c.is_whitespace() && !in_quotes {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+            continue;
+        }
+
+        token.push(c);
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn parse_predicate(token: &str) -> Option<(bool, &str, Comparison, String)> {
+    let (negated, token) = match token.strip_prefix('-') {
+        Some(token) => (true, token),
+        None => (false, token),
+    };
+
+    // search fields like @title start with @
+    let name_start = usize::from(token.starts_with('@'));
+    let field_end = name_start
+        + token[name_start..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+    let (field, rest) = token.split_at(field_end);
+    let name = &field[name_start..];
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (comparison, value) = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        (":", Comparison::Matches),
+    ]
+    .into_iter()
+    .find_map(|(operator, comparison)| {
+        rest.strip_prefix(operator).map(|value| (comparison, value))
+    })?;
+
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+
+    if value.is_empty() {
+        return None;
+    }
+
+    Some((negated, field, comparison, value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        entities::{Document, DocumentType},
+        schema::{DataDescription, DataSchema, Field, FieldType},
+    };
+
+    use super::{Comparison, Predicate, StructuredQuery};
+
+    fn new_schema() -> DataSchema {
+        let field = |name: &str, field_type: FieldType| Field {
+            name: name.into(),
+            field_type,
+            mandatory: false,
+            readonly: false,
+            ordered: None,
+        };
+
+        DataSchema::new(
+            "test",
+            vec![DataDescription {
+                document_type: "book".into(),
+                title_format: "${title}".into(),
+                fields: vec![
+                    field("title", FieldType::String {}),
+                    field("authors", FieldType::People {}),
+                    field(
+                        "rating",
+                        FieldType::Enum(vec!["Bad".into(), "Good".into(), "Great".into()]),
+                    ),
+                    field("pages", FieldType::NaturalNumber {}),
+                    field("finished", FieldType::Flag {}),
+                    field("published", FieldType::Date {}),
+                    field("collections", FieldType::RefList(vec![])),
+                ],
+            }],
+        )
+    }
+
+    fn new_book(data: serde_json::Value) -> Document {
+        Document::new(DocumentType::new("book")).with_data(data)
+    }
+
+    fn matches(query: &str, document: &Document) -> bool {
+        let schema = new_schema();
+
+        StructuredQuery::parse(query, &schema)
+            .unwrap()
+            .matches(document, &schema)
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let schema = new_schema();
+
+        let query = StructuredQuery::parse(
+            r#"type:book rating>=Good authors:"Le Guin" -finished:yes dark "left hand""#,
+            &schema,
+        )
+        .unwrap();
+
+        assert_eq!(query.text, r#"dark "left hand""#);
+        assert_eq!(query.document_types, vec![DocumentType::new("book")]);
+        assert_eq!(
+            query.predicates,
+            vec![
+                Predicate {
+                    field: "rating".into(),
+                    comparison: Comparison::GreaterOrEqual,
+                    value: "Good".into(),
+                    negated: false,
+                },
+                Predicate {
+                    field: "authors".into(),
+                    comparison: Comparison::Matches,
+                    value: "Le Guin".into(),
+                    negated: false,
+                },
+                Predicate {
+                    field: "finished".into(),
+                    comparison: Comparison::Matches,
+                    value: "yes".into(),
+                    negated: true,
+                },
+            ]
+        );
+
+        let query = StructuredQuery::parse("just text: 12:30", &schema).unwrap();
+        assert_eq!(query.text, "just text: 12:30");
+        assert!(!query.has_predicates());
+    }
+
+    #[test]
+    fn test_parse_search_fields() {
+        let schema = new_schema();
+
+        let query = StructuredQuery::parse(
+            r#"@authors:"Le Guin" -@title:dark @title:left* @id:abc @refs:tag authors:guin"#,
+            &schema,
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.text,
+            r#"authors:"Le Guin" -@title:dark @title:left* @id:abc @refs:tag"#
+        );
+        assert_eq!(query.predicates.len(), 1);
+
+        assert!(StructuredQuery::parse("@unknown:value", &schema).is_err());
+        assert!(StructuredQuery::parse("@rating:Good", &schema).is_err());
+        assert!(StructuredQuery::parse("@title>value", &schema).is_err());
+    }
+
+    #[test]
+    fn test_parse_query_errors() {
+        let schema = new_schema();
+
+        assert!(StructuredQuery::parse("type:unknown", &schema).is_err());
+        assert!(StructuredQuery::parse("unknown:value", &schema).is_err());
+        assert!(StructuredQuery::parse("rating:Average", &schema).is_err());
+        assert!(StructuredQuery::parse("pages>many", &schema).is_err());
+        assert!(StructuredQuery::parse("finished>yes", &schema).is_err());
+        assert!(StructuredQuery::parse("collections>id", &schema).is_err());
+    }
+
+    #[test]
+    fn test_match_predicates() {
+        let book = new_book(json!({
+            "title": "The Left Hand of Darkness",
+            "authors": "Ursula K. Le Guin",
+            "rating": "Great",
+            "pages": 304,
+            "published": "1969-03-01",
+            "collections": ["collection1"],
+        }));
+
+        assert!(matches("type:book", &book));
+        assert!(matches(r#"authors:"le guin""#, &book));
+        assert!(!matches("authors:Tolkien", &book));
+        assert!(matches("authors>=Ursula", &book));
+        assert!(matches("rating>=Good", &book));
+        assert!(matches("rating:great", &book));
+        assert!(!matches("rating<Great", &book));
+        assert!(matches("pages>300 pages<=304", &book));
+        assert!(!matches("pages:300", &book));
+        assert!(matches("finished:no", &book));
+        assert!(matches("-finished:yes", &book));
+        assert!(matches("published:1969 published<1970", &book));
+        assert!(!matches("published>=1970", &book));
+        assert!(matches("collections:collection1", &book));
+        assert!(!matches("-collections:collection1", &book));
+
+        let unrated = new_book(json!({ "title": "Unrated" }));
+        assert!(!matches("rating>=Bad", &unrated));
+        assert!(matches("-rating:Great", &unrated));
+    }
+}
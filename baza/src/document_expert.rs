@@ -22,7 +22,7 @@ impl<'s> DocumentExpert<'s> {
         let mut refs = Refs::default();
 
         for field in self.schema.iter_fields(document_type)? {
-            if let Some(value) = data.get(field.name) {
+            if let Some(value) = data.get(&field.name) {
                 refs.documents.extend(field.extract_refs(value));
                 refs.collection.extend(field.extract_collection_refs(value));
             }
@@ -31,16 +31,42 @@ impl<'s> DocumentExpert<'s> {
         Ok(refs)
     }
 
+    /// Replaces the references to one document with the references to another one.
+    /// Returns `true` if the data changed.
+    pub fn replace_refs(
+        &self,
+        document_type: &DocumentType,
+        data: &mut DocumentData,
+        old_id: &Id,
+        new_id: &Id,
+    ) -> Result<bool> {
+        let mut changed = false;
+
+        for field in self.schema.iter_fields(document_type)? {
+            let new_value = data
+                .get(&field.name)
+                .and_then(|value| field.replace_refs(value, old_id, new_id));
+
+            if let Some(new_value) = new_value {
+                data.set(&field.name, new_value);
+                changed = true;
+            }
+        }
+
+        Ok(changed)
+    }
+
     pub fn get_title(&self, document_type: &DocumentType, data: &DocumentData) -> Result<String> {
         let mut title_fields = HashMap::new();
         for field in self.schema.iter_fields(document_type)? {
             if field.could_be_in_title() {
-                title_fields.insert(field.name, value_as_string(data.get(field.name)));
+                title_fields.insert(field.name.as_str(), value_as_string(data.get(&field.name)));
             }
         }
 
         render_template_with_vars(
-            self.schema
+            &self
+                .schema
                 .get_data_description(document_type)?
                 .title_format,
             &title_fields,
@@ -73,7 +99,7 @@ impl<'s> DocumentExpert<'s> {
                 return Ok(None);
             };
 
-        Ok(document.data.get_str(cover_field.name).map(From::from))
+        Ok(document.data.get_str(&cover_field.name).map(From::from))
     }
 
     pub fn is_editable(&self, document_type: &DocumentType) -> Result<bool> {
@@ -111,7 +137,7 @@ impl<'s> DocumentExpert<'s> {
 
             has_collection_field = true;
 
-            let Some(field_ids) = collection.data.get_ref_list(field.name)? else {
+            let Some(field_ids) = collection.data.get_ref_list(&field.name)? else {
                 continue;
             };
 
@@ -156,7 +182,7 @@ impl<'s> DocumentExpert<'s> {
         let field =
             self.find_collection_field_for(&collection.document_type, &document.document_type)?;
 
-        collection.data.add_to_ref_list(field.name, &document.id)?;
+        collection.data.add_to_ref_list(&field.name, &document.id)?;
 
         Ok(())
     }
@@ -165,7 +191,7 @@ impl<'s> DocumentExpert<'s> {
         &self,
         collection: &Document,
         id: &Id,
-    ) -> Result<&'static str> {
+    ) -> Result<&str> {
         let mut fields = Vec::new();
         let mut has_collection_field = false;
 
@@ -176,7 +202,7 @@ impl<'s> DocumentExpert<'s> {
 
             has_collection_field = true;
 
-            let Some(field_ids) = collection.data.get_ref_list(field.name)? else {
+            let Some(field_ids) = collection.data.get_ref_list(&field.name)? else {
                 continue;
             };
 
@@ -184,7 +210,7 @@ impl<'s> DocumentExpert<'s> {
                 .into_iter()
                 .any(|field_id| field_id == id.as_ref())
             {
-                fields.push(field.name);
+                fields.push(&field.name);
             }
         }
 
@@ -270,7 +296,7 @@ impl<'s> DocumentExpert<'s> {
 #[cfg(test)]
 mod tests {
     use crate::{
-        entities::{Document, DocumentData, DocumentType},
+        entities::{Document, DocumentData, DocumentType, Id},
         schema::{ASSET_TYPE, DataDescription, DataSchema, Field, FieldType},
     };
 
@@ -294,38 +320,43 @@ mod tests {
         let schema = DataSchema::new(
             "test",
             vec![DataDescription {
-                document_type: "test_type",
-                title_format: "${title}",
+                document_type: "test_type".into(),
+                title_format: "${title}".into(),
                 fields: vec![
                     Field {
-                        name: "title",
+                        name: "title".into(),
                         field_type: FieldType::String {},
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                     Field {
-                        name: "cover",
-                        field_type: FieldType::Ref(&[ASSET_TYPE]),
+                        name: "cover".into(),
+                        field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                     Field {
-                        name: "related",
-                        field_type: FieldType::Ref(&["note"]),
+                        name: "related".into(),
+                        field_type: FieldType::Ref(vec!["note".into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                     Field {
-                        name: "gallery",
-                        field_type: FieldType::RefList(&[ASSET_TYPE]),
+                        name: "gallery".into(),
+                        field_type: FieldType::RefList(vec![ASSET_TYPE.into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                     Field {
-                        name: "mixed_refs",
-                        field_type: FieldType::RefList(&["note", ASSET_TYPE]),
+                        name: "mixed_refs".into(),
+                        field_type: FieldType::RefList(vec!["note".into(), ASSET_TYPE.into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                 ],
             }],
@@ -335,29 +366,99 @@ mod tests {
         let fields = expert
             .asset_ref_fields(&DocumentType::new("test_type"))
             .unwrap();
-        let field_names = fields.iter().map(|field| field.name).collect::<Vec<_>>();
+        let field_names = fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<_>>();
 
         assert_eq!(field_names, ["cover", "gallery", "mixed_refs"]);
     }
 
+    #[test]
+    fn replace_refs_updates_ref_fields_and_markup_links() {
+        let schema = DataSchema::new(
+            "test",
+            vec![DataDescription {
+                document_type: "test_type".into(),
+                title_format: "${title}".into(),
+                fields: vec![
+                    Field {
+                        name: "description".into(),
+                        field_type: FieldType::MarkupString {},
+                        mandatory: false,
+                        readonly: false,
+                        ordered: None,
+                    },
+                    Field {
+                        name: "cover".into(),
+                        field_type: FieldType::Ref(vec![ASSET_TYPE.into()]),
+                        mandatory: false,
+                        readonly: false,
+                        ordered: None,
+                    },
+                    Field {
+                        name: "gallery".into(),
+                        field_type: FieldType::RefList(vec![ASSET_TYPE.into()]),
+                        mandatory: false,
+                        readonly: false,
+                        ordered: None,
+                    },
+                ],
+            }],
+        );
+        let expert = DocumentExpert::new(&schema);
+        let document_type = DocumentType::new("test_type");
+
+        let old_id = Id::new();
+        let new_id = Id::new();
+        let other_id = Id::new();
+
+        let mut data = DocumentData::new();
+        data.set("description", format!("See ![photo](ref:{old_id})"));
+        data.set("cover", &old_id);
+        data.set("gallery", vec![&new_id, &other_id, &old_id]);
+
+        assert!(
+            expert
+                .replace_refs(&document_type, &mut data, &old_id, &new_id)
+                .unwrap()
+        );
+        assert_eq!(
+            data.get_str("description"),
+            Some(format!("See ![photo](ref:{new_id})").as_str())
+        );
+        assert_eq!(data.get_str("cover"), Some(new_id.as_ref()));
+        assert_eq!(
+            data.get_ref_list("gallery").unwrap(),
+            Some(vec![new_id.as_ref(), other_id.as_ref()])
+        );
+
+        assert!(
+            !expert
+                .replace_refs(&document_type, &mut data, &old_id, &new_id)
+                .unwrap()
+        );
+    }
+
     #[test]
     fn reorder_collection_member_rejects_out_of_bounds_position() {
         let schema = DataSchema::new(
             "test",
             vec![
                 DataDescription {
-                    document_type: "collection",
-                    title_format: "${title}",
+                    document_type: "collection".into(),
+                    title_format: "${title}".into(),
                     fields: vec![Field {
-                        name: "members",
-                        field_type: FieldType::RefList(&["member"]),
+                        name: "members".into(),
+                        field_type: FieldType::RefList(vec!["member".into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     }],
                 },
                 DataDescription {
-                    document_type: "member",
-                    title_format: "${title}",
+                    document_type: "member".into(),
+                    title_format: "${title}".into(),
                     fields: vec![],
                 },
             ],
@@ -380,13 +481,14 @@ mod tests {
         let schema = DataSchema::new(
             "test",
             vec![DataDescription {
-                document_type: "collection",
-                title_format: "${title}",
+                document_type: "collection".into(),
+                title_format: "${title}".into(),
                 fields: vec![Field {
-                    name: "members",
-                    field_type: FieldType::RefList(&["member"]),
+                    name: "members".into(),
+                    field_type: FieldType::RefList(vec!["member".into()]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 }],
             }],
         );
@@ -411,13 +513,14 @@ mod tests {
         let schema = DataSchema::new(
             "test",
             vec![DataDescription {
-                document_type: "collection",
-                title_format: "${title}",
+                document_type: "collection".into(),
+                title_format: "${title}".into(),
                 fields: vec![Field {
-                    name: "members",
-                    field_type: FieldType::RefList(&[]),
+                    name: "members".into(),
+                    field_type: FieldType::RefList(vec![]),
                     mandatory: false,
                     readonly: false,
+                    ordered: None,
                 }],
             }],
         );
@@ -440,20 +543,22 @@ mod tests {
         let schema = DataSchema::new(
             "test",
             vec![DataDescription {
-                document_type: "collection",
-                title_format: "${title}",
+                document_type: "collection".into(),
+                title_format: "${title}".into(),
                 fields: vec![
                     Field {
-                        name: "primary",
-                        field_type: FieldType::RefList(&["member"]),
+                        name: "primary".into(),
+                        field_type: FieldType::RefList(vec!["member".into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                     Field {
-                        name: "secondary",
-                        field_type: FieldType::RefList(&["member"]),
+                        name: "secondary".into(),
+                        field_type: FieldType::RefList(vec!["member".into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                 ],
             }],
@@ -479,8 +584,8 @@ mod tests {
         let schema = DataSchema::new(
             "test",
             vec![DataDescription {
-                document_type: "note",
-                title_format: "${title}",
+                document_type: "note".into(),
+                title_format: "${title}".into(),
                 fields: vec![],
             }],
         );
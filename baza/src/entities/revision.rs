@@ -47,6 +47,10 @@ impl Revision {
         self.0.get(id).copied().unwrap_or_default()
     }
 
+    pub fn iter_instance_ids(&self) -> impl Iterator<Item = &InstanceId> {
+        self.0.keys()
+    }
+
     pub fn set_version(&mut self, id: &InstanceId, version: u32) {
         if version == 0 {
             self.0.remove(id);
@@ -263,6 +267,25 @@ impl Revision {
         })
     }
 
+    /// Component-wise minimum of the revisions, i.e. the latest revision older than or equal
+    /// to all of them. Returns the initial revision if there are no revisions.
+    #[must_use]
+    pub fn min_all<'r>(mut revs: impl Iterator<Item = &'r Revision>) -> Revision {
+        let Some(first) = revs.next() else {
+            return Revision::initial();
+        };
+
+        revs.fold(first.clone(), |mut acc, rev| {
+            acc.0.retain(|key, value| {
+                *value = (*value).min(rev.get_version(key));
+
+                *value > 0
+            });
+
+            acc
+        })
+    }
+
     #[must_use]
     pub fn compute_next_rev<'r>(
         revs: impl Iterator<Item = &'r Revision>,
@@ -592,6 +615,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_revision_min_all() -> Result<()> {
+        let rev1 = Revision::from_value(json!({ "a": 1, "b": 3 }))?;
+        let rev2 = Revision::from_value(json!({ "a": 2, "b": 2, "c": 1 }))?;
+
+        assert_eq!(
+            Revision::min_all([rev1.clone(), rev2].iter()),
+            Revision::from_value(json!({ "a": 1, "b": 2 }))?
+        );
+        assert_eq!(Revision::min_all([rev1.clone()].iter()), rev1);
+        assert_eq!(Revision::min_all([].iter()), Revision::initial());
+
+        Ok(())
+    }
+
     #[test]
     fn test_revision_compute_next_rev() -> Result<()> {
         let rev1 = Revision::from_value(json!({ "a": 1, "b": 1 }))?;
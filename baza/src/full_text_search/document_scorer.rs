@@ -11,12 +11,12 @@ pub(super) struct DocumentScore {
 }
 
 #[derive(Default)]
-pub(super) struct DocumentScorer<'term, 'doc> {
-    // query term -> selected term match
-    term_matches: HashMap<&'term str, TermMatch<'doc>>,
+pub(super) struct DocumentScorer<'doc> {
+    // query clause -> selected term match
+    term_matches: HashMap<usize, TermMatch<'doc>>,
 
-    // query term -> score
-    term_scores: HashMap<&'term str, f64>,
+    // query clause -> score
+    term_scores: HashMap<usize, f64>,
 }
 
 struct TermMatch<'doc> {
@@ -24,29 +24,29 @@ struct TermMatch<'doc> {
     matches: &'doc DocumentTermMatches,
 }
 
-impl<'term, 'doc> DocumentScorer<'term, 'doc> {
+impl<'doc> DocumentScorer<'doc> {
     pub fn terms_count(&self) -> usize {
         self.term_matches.len()
     }
 
-    /// Update score of term, if it's bigger than current score
+    /// Update score of query clause, if it's bigger than current score.
+    /// Alternatives of a clause (`a OR b`) compete for the same score.
     pub fn update_term_score(
         &mut self,
-        term: &'term str,
         query_position: usize,
         score: f64,
         matches: &'doc DocumentTermMatches,
     ) {
-        if let Some(current_score) = self.term_scores.get(term) {
-            // we need max score per query term
+        if let Some(current_score) = self.term_scores.get(&query_position) {
+            // we need max score per query clause
             if *current_score >= score {
                 return;
             }
         }
 
-        self.term_scores.insert(term, score);
+        self.term_scores.insert(query_position, score);
         self.term_matches.insert(
-            term,
+            query_position,
             TermMatch {
                 query_position,
                 matches,
@@ -134,9 +134,9 @@ impl<'term, 'doc> DocumentScorer<'term, 'doc> {
         self.term_matches
             .values()
             .filter(|term_match| {
-                field_boosts
-                    .keys()
-                    .any(|field| term_match.matches.get(field).is_some())
+                field_boosts.iter().any(|(field, boost)| {
+                    boost.is_boosted() && term_match.matches.get(field).is_some()
+                })
             })
             .count()
     }
@@ -181,7 +181,7 @@ impl ProximityScore {
     }
 }
 
-fn has_exact_phrase(positions_by_query_term: &[&[usize]]) -> bool {
+pub(super) fn has_exact_phrase(positions_by_query_term: &[&[usize]]) -> bool {
     let Some(first_term_positions) = positions_by_query_term.first() else {
         return false;
     };
@@ -1,9 +1,13 @@
 mod document_scorer;
+mod query;
+mod snippet;
+mod stemmer;
 mod tokenizer;
 
 use std::{
+    borrow::Cow,
     cmp::Reverse,
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet, hash_map::Entry},
 };
 
 use anyhow::{Result, ensure};
@@ -11,28 +15,35 @@ use ordermap::OrderMap;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use strsim::damerau_levenshtein;
-use tokenizer::{tokenize_with_offsets, tokenize_with_positions};
 
 use baza_common::log;
 
-use self::document_scorer::DocumentScorer;
+use self::{
+    document_scorer::{DocumentScorer, has_exact_phrase},
+    query::{MatchMode, QueryItem, SearchQuery},
+};
+pub use self::{snippet::Snippet, stemmer::StemmingLanguage, tokenizer::Tokenizer};
 
 // These are common bm25 parameter values
 const B: f64 = 0.75;
 const K1: f64 = 1.2;
 
+// Quoted phrases are scored like the exact ordered phrase proximity boost, so that a phrase hit
+// outranks the same terms scattered across the document.
+const PHRASE_MULTIPLIER: f64 = 2.0;
+
 // Bound fuzzy expansion so broad queries do not dilute ranking or scale with vocabulary size.
 const MAX_MATCHED_TERMS_PER_QUERY_TERM: usize = 32;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct FieldBoost(f64);
 
 impl FieldBoost {
     pub fn new(value: f64) -> Result<Self> {
         ensure!(
-            (1.0..=2.0).contains(&value),
-            "Field boost must be in range [1, 2], got {value}"
+            (0.1..=2.0).contains(&value),
+            "Field boost must be in range [0.1, 2], got {value}"
         );
 
         Ok(FieldBoost(value))
@@ -41,15 +52,31 @@ impl FieldBoost {
     fn value(&self) -> f64 {
         self.0
     }
+
+    /// Boosts below 1 lower the weight of secondary fields, like extracted file contents.
+    fn is_boosted(&self) -> bool {
+        self.0 > 1.0
+    }
 }
 
 type FieldId = usize;
 
+struct ItemMatch<'doc> {
+    score: f64,
+    // only the scoped field for field-scoped query items
+    matches: Cow<'doc, DocumentTermMatches>,
+}
+
+// document_id -> best match of a query item
+type ItemMatches<'doc> = HashMap<&'doc String, ItemMatch<'doc>>;
+
 // (interned) field -> token position[]; positions are used for proximity scoring.
 type DocumentTermMatches = HashMap<FieldId, Vec<usize>>;
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct FTSEngine {
+    tokenizer: Tokenizer,
+
     // cache field names
     fields: Vec<String>,
 
@@ -75,6 +102,17 @@ impl FTSEngine {
         Default::default()
     }
 
+    pub fn with_tokenizer(tokenizer: Tokenizer) -> Self {
+        FTSEngine {
+            tokenizer,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
     pub fn index_document(
         &mut self,
         document_id: String,
@@ -88,16 +126,17 @@ impl FTSEngine {
         for (field, value) in document {
             let field = self.get_or_intern_field(field);
 
-            let field_terms = tokenize_with_positions(value);
-            if field_terms.is_empty() {
+            let field_terms = self.tokenizer.tokenize_with_positions(value);
+            // synonyms share positions with the original words
+            let Some(field_len) = field_terms.last().map(|(_, position)| position + 1) else {
                 continue;
-            }
+            };
 
-            doc_term_count += field_terms.len();
+            doc_term_count += field_len;
             self.doc_field_term_count
                 .entry(document_id.clone())
                 .or_default()
-                .insert(field, field_terms.len());
+                .insert(field, field_len);
 
             for (term, token_position) in field_terms {
                 let term_matches = self.terms_index.entry(term).or_default();
@@ -262,143 +301,251 @@ impl FTSEngine {
         terms
     }
 
-    pub fn search(&self, query: &str) -> Vec<&String> {
-        let mut seen_query_terms = HashSet::new();
-        let query_terms = tokenize_with_offsets(query)
-            .into_iter()
-            .filter_map(|(term, _)| seen_query_terms.insert(term.clone()).then_some(term))
-            .collect::<Vec<_>>();
+    fn get_term_candidates(&self, query_term: &str, mode: MatchMode) -> Vec<TermCandidate<'_>> {
+        match mode {
+            MatchMode::Fuzzy => self.get_fuzzy_terms(query_term),
+            MatchMode::Prefix => {
+                let mut terms = self.get_fuzzy_terms(query_term);
+                terms.retain(|candidate| candidate.kind != TermCandidateKind::Fuzzy);
 
-        // return all the ids in case query is empty
-        if query_terms.is_empty() {
-            return self.doc_term_count.keys().collect();
-        }
-
-        // pick terms that fuzzy match query terms
-        // query term -> (fuzzy term, similarity)[]
-        let mut all_query_terms = HashMap::new();
-        for (query_position, query_term) in query_terms.iter().enumerate() {
-            let fuzzy_terms = self.get_fuzzy_terms(query_term);
-            if fuzzy_terms.is_empty() {
-                log::debug!("Couldn't find terms for query term '{query_term}'");
-                return vec![];
+                terms
             }
-
-            all_query_terms.insert(query_term, (query_position, fuzzy_terms));
+            MatchMode::Exact => self
+                .terms_index
+                .get_key_value(query_term)
+                .map(|(term, _)| vec![TermCandidate::exact(term)])
+                .unwrap_or_default(),
         }
+    }
 
-        log::debug!(
-            "{} query terms -> {} fuzzy matched terms",
-            query_terms.len(),
-            all_query_terms
-                .values()
-                .map(|(_, fuzzy_terms)| fuzzy_terms.len())
-                .sum::<usize>()
-        );
+    /// BM25 score of the best matching field of the document.
+    fn bm25_score(
+        &self,
+        document_id: &str,
+        document_term_matches: &DocumentTermMatches,
+        idf: f64,
+        similarity: f64,
+    ) -> f64 {
+        let field_term_counts = self
+            .doc_field_term_count
+            .get(document_id)
+            .expect("Indexed document must have field term counts");
+
+        let field_boosts = self.doc_field_boost.get(document_id);
+
+        document_term_matches
+            .iter()
+            .map(|(field, positions)| {
+                let field_len = *field_term_counts
+                    .get(field)
+                    .expect("Matched field must have a term count")
+                    as f64;
+                let avg_field_len = *self
+                    .avg_field_len
+                    .get(field)
+                    .expect("Matched field must have an average term count");
+                let tf = positions.len() as f64;
+                let numerator = tf * (K1 + 1.0);
+                let denominator = tf + K1 * (1.0 - B + B * (field_len / avg_field_len));
+
+                let field_multiplier = field_boosts
+                    .and_then(|field_boosts| field_boosts.get(field))
+                    .map_or(1.0, |field_boost| field_boost.value());
+
+                idf * (numerator / denominator) * similarity * field_multiplier
+            })
+            .max_by(f64::total_cmp)
+            .expect("Indexed document term matches must contain at least one field")
+    }
+
+    /// Scores documents that match the query term, keeping the best candidate per document.
+    fn match_term(
+        &self,
+        query_term: &str,
+        mode: MatchMode,
+        scope: Option<FieldId>,
+    ) -> ItemMatches<'_> {
+        let candidates = self.get_term_candidates(query_term, mode);
 
-        let mut scores = all_query_terms
+        candidates
             .into_par_iter()
-            .flat_map(|(query_term, (query_position, fuzzy_terms))| {
-                fuzzy_terms.into_par_iter().flat_map(move |candidate| {
-                    let idf = self.idf(candidate.term);
-
-                    let doc_map = self
-                        .terms_index
-                        .get(candidate.term)
-                        .expect("fuzzy matched term must be indexed");
-
-                    doc_map
-                        .par_iter()
-                        .map(move |(document_id, document_term_matches)| {
-                            (
-                                query_term,
-                                query_position,
-                                candidate.score_multiplier,
-                                idf,
-                                document_id,
-                                document_term_matches,
-                            )
-                        })
-                })
+            .flat_map(|candidate| {
+                let idf = self.idf(candidate.term);
+
+                let doc_map = self
+                    .terms_index
+                    .get(candidate.term)
+                    .expect("matched term must be indexed");
+
+                doc_map
+                    .par_iter()
+                    .filter_map(move |(document_id, document_term_matches)| {
+                        let matches = scope_matches(document_term_matches, scope)?;
+                        let score =
+                            self.bm25_score(document_id, &matches, idf, candidate.score_multiplier);
+
+                        Some((document_id, ItemMatch { score, matches }))
+                    })
             })
-            .map(
-                |(
-                    query_term,
-                    query_position,
-                    similarity,
-                    idf,
-                    document_id,
-                    document_term_matches,
-                )| {
-                    // Calculate BM25 score
+            .collect::<Vec<_>>()
+            .into_iter()
+            .fold(HashMap::new(), |mut result, (document_id, item_match)| {
+                match result.entry(document_id) {
+                    Entry::Occupied(mut entry) => {
+                        if item_match.score > entry.get().score {
+                            entry.insert(item_match);
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(item_match);
+                    }
+                }
+
+                result
+            })
+    }
 
-                    let field_term_counts = self
-                        .doc_field_term_count
-                        .get(document_id)
-                        .expect("Indexed document must have field term counts");
+    /// Scores documents that contain the exact phrase in one of the fields.
+    fn match_phrase(&self, terms: &[String], scope: Option<FieldId>) -> ItemMatches<'_> {
+        let Some(doc_maps) = terms
+            .iter()
+            .map(|term| self.terms_index.get(term))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return HashMap::new();
+        };
 
-                    let field_boosts = self.doc_field_boost.get(document_id);
-                    let doc_bm25_score = document_term_matches
+        doc_maps[0]
+            .par_iter()
+            .filter_map(|(document_id, first_term_matches)| {
+                let term_matches = doc_maps
+                    .iter()
+                    .map(|doc_map| scope_matches(doc_map.get(document_id)?, scope))
+                    .collect::<Option<Vec<_>>>()?;
+
+                let has_phrase = term_matches[0].keys().any(|field| {
+                    term_matches
                         .iter()
-                        .map(|(field, positions)| {
-                            let field_len = *field_term_counts
-                                .get(field)
-                                .expect("Matched field must have a term count")
-                                as f64;
-                            let avg_field_len = *self
-                                .avg_field_len
-                                .get(field)
-                                .expect("Matched field must have an average term count");
-                            let tf = positions.len() as f64;
-                            let numerator = tf * (K1 + 1.0);
-                            let denominator = tf + K1 * (1.0 - B + B * (field_len / avg_field_len));
-
-                            let field_multiplier = field_boosts
-                                .and_then(|field_boosts| field_boosts.get(field))
-                                .map_or(1.0, |field_boost| field_boost.value());
-
-                            idf * (numerator / denominator) * similarity * field_multiplier
-                        })
-                        .max_by(f64::total_cmp)
-                        .expect("Indexed document term matches must contain at least one field");
-
-                    (
-                        query_term,
-                        query_position,
-                        document_id,
-                        doc_bm25_score,
-                        document_term_matches,
-                    )
-                },
-            )
-            .collect::<Vec<_>>()
+                        .map(|matches| matches.get(field).map(Vec::as_slice))
+                        .collect::<Option<Vec<_>>>()
+                        .is_some_and(|positions| has_exact_phrase(&positions))
+                });
+                if !has_phrase {
+                    return None;
+                }
+
+                let score = terms
+                    .iter()
+                    .zip(&term_matches)
+                    .map(|(term, matches)| {
+                        self.bm25_score(document_id, matches, self.idf(term), 1.0)
+                    })
+                    .sum::<f64>();
+
+                Some((
+                    document_id,
+                    ItemMatch {
+                        score: score * PHRASE_MULTIPLIER,
+                        matches: scope_matches(first_term_matches, scope)?,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn match_item(&self, item: &QueryItem) -> ItemMatches<'_> {
+        let (QueryItem::Term { field, .. } | QueryItem::Phrase { field, .. }) = item;
+
+        let scope = match field {
+            Some(field) => match self.fields.iter().position(|item| item == field) {
+                Some(field_id) => Some(field_id),
+                // the field isn't indexed in any document
+                None => return HashMap::new(),
+            },
+            None => None,
+        };
+
+        match item {
+            QueryItem::Term { term, mode, .. } => self.match_term(term, *mode, scope),
+            QueryItem::Phrase { terms, .. } => self.match_phrase(terms, scope),
+        }
+    }
+
+    /// Searches documents, most relevant first.
+    ///
+    /// Every query term must match; `"quoted phrases"` must match contiguously, `term*` matches
+    /// only term prefixes, `-term` excludes documents, and `a OR b` matches either alternative.
+    pub fn search(&self, query: &str) -> Vec<&String> {
+        self.search_with_matches(query)
+            .into_iter()
+            .map(|result| result.document_id)
+            .collect()
+    }
+
+    /// Same as [`FTSEngine::search`], but also returns where each document matched the query.
+    pub fn search_with_matches(&self, query: &str) -> Vec<SearchMatch<'_>> {
+        let query = SearchQuery::parse(query, &self.tokenizer);
+
+        let item_matches = query
+            .iter_items()
+            .collect::<HashSet<_>>()
             .into_iter()
-            .fold(
-                HashMap::new(),
-                |mut scores,
-                 (
-                    query_term,
-                    query_position,
+            .map(|item| (item, self.match_item(item)))
+            .collect::<HashMap<_, _>>();
+
+        let excluded_ids = query
+            .excluded
+            .iter()
+            .flat_map(|item| item_matches[item].keys().copied())
+            .collect::<HashSet<_>>();
+
+        // return all the ids in case query is empty
+        if query.is_empty() {
+            return self
+                .doc_term_count
+                .keys()
+                .filter(|document_id| !excluded_ids.contains(document_id))
+                .map(|document_id| SearchMatch {
                     document_id,
-                    doc_bm25_score,
-                    document_term_matches,
-                )| {
-                    let document_scorer: &mut DocumentScorer =
-                        scores.entry(document_id).or_default();
-
-                    document_scorer.update_term_score(
-                        query_term,
+                    fields: vec![],
+                })
+                .collect();
+        }
+
+        if let Some(clause) = query
+            .clauses
+            .iter()
+            .find(|clause| clause.iter().all(|item| item_matches[item].is_empty()))
+        {
+            log::debug!("Couldn't find documents for query clause {clause:?}");
+            return vec![];
+        }
+
+        log::debug!(
+            "{} query clauses -> {} matched documents",
+            query.clauses.len(),
+            item_matches.values().map(HashMap::len).sum::<usize>()
+        );
+
+        let mut scores: HashMap<&String, DocumentScorer> = HashMap::new();
+        for (query_position, clause) in query.clauses.iter().enumerate() {
+            for item in clause {
+                for (document_id, item_match) in &item_matches[item] {
+                    if excluded_ids.contains(document_id) {
+                        continue;
+                    }
+
+                    scores.entry(document_id).or_default().update_term_score(
                         query_position,
-                        doc_bm25_score,
-                        document_term_matches,
+                        item_match.score,
+                        &item_match.matches,
                     );
+                }
+            }
+        }
 
-                    scores
-                },
-            );
-
-        // keep only documents that match all query terms
-        scores.retain(|_, document_scorer| document_scorer.terms_count() == query_terms.len());
+        // keep only documents that match all query clauses
+        scores.retain(|_, document_scorer| document_scorer.terms_count() == query.clauses.len());
 
         let mut result = scores
             .into_iter()
@@ -422,10 +569,82 @@ impl FTSEngine {
 
         result
             .into_iter()
-            .map(|(document_id, _)| document_id)
+            .map(|(document_id, _)| SearchMatch {
+                document_id,
+                fields: self.collect_field_matches(document_id, &query, &item_matches),
+            })
             .collect()
     }
 
+    /// Collects token positions of the query items that matched the document, grouped by field.
+    /// Fields with more hits go first.
+    fn collect_field_matches(
+        &self,
+        document_id: &String,
+        query: &SearchQuery,
+        item_matches: &HashMap<&QueryItem, ItemMatches<'_>>,
+    ) -> Vec<FieldMatch> {
+        let mut positions: HashMap<FieldId, BTreeSet<usize>> = HashMap::new();
+
+        for item in query.clauses.iter().flatten() {
+            let Some(item_match) = item_matches[item].get(document_id) else {
+                continue;
+            };
+
+            for (field, field_positions) in item_match.matches.iter() {
+                let field_hits = positions.entry(*field).or_default();
+
+                match item {
+                    QueryItem::Term { .. } => field_hits.extend(field_positions),
+                    QueryItem::Phrase { terms, .. } => {
+                        // a phrase match only keeps the positions of its first term
+                        let phrase_positions = terms
+                            .iter()
+                            .map(|term| {
+                                self.terms_index
+                                    .get(term)
+                                    .and_then(|doc_map| doc_map.get(document_id))
+                                    .and_then(|matches| matches.get(field))
+                                    .map(Vec::as_slice)
+                                    .unwrap_or_default()
+                            })
+                            .collect::<Vec<_>>();
+
+                        for first_position in field_positions {
+                            let is_phrase = phrase_positions.iter().enumerate().all(
+                                |(offset, term_positions)| {
+                                    term_positions.contains(&(first_position + offset))
+                                },
+                            );
+
+                            if is_phrase {
+                                field_hits.extend(*first_position..first_position + terms.len());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut fields = positions
+            .into_iter()
+            .filter(|(_, positions)| !positions.is_empty())
+            .map(|(field, positions)| FieldMatch {
+                field: self.fields[field].clone(),
+                positions: positions.into_iter().collect(),
+            })
+            .collect::<Vec<_>>();
+
+        fields.sort_by(|a, b| {
+            b.positions
+                .len()
+                .cmp(&a.positions.len())
+                .then_with(|| a.field.cmp(&b.field))
+        });
+
+        fields
+    }
+
     pub fn get_stats(&self) -> FTSStats<'_> {
         let terms_count = self.terms_index.len();
         let docs_count = self.doc_term_count.len();
@@ -462,6 +681,20 @@ impl FTSEngine {
     }
 }
 
+/// Restricts document term matches to the scoped field, if any.
+fn scope_matches(
+    matches: &DocumentTermMatches,
+    scope: Option<FieldId>,
+) -> Option<Cow<'_, DocumentTermMatches>> {
+    let Some(field) = scope else {
+        return Some(Cow::Borrowed(matches));
+    };
+
+    let positions = matches.get(&field)?;
+
+    Some(Cow::Owned(HashMap::from([(field, positions.clone())])))
+}
+
 /// Scores a fuzzy query-term match against a longer indexed term.
 ///
 /// The extra prefix length catches boundary typos such as `biter` -> `bitter`
@@ -554,6 +787,21 @@ impl<'term> TermCandidate<'term> {
     }
 }
 
+/// A search result with the fields that matched the query.
+#[derive(Debug)]
+pub struct SearchMatch<'fts> {
+    pub document_id: &'fts String,
+    /// Fields with more hits go first.
+    pub fields: Vec<FieldMatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMatch {
+    pub field: String,
+    /// Sorted positions of the matched tokens in the field.
+    pub positions: Vec<usize>,
+}
+
 #[derive(Debug)]
 pub struct FTSStats<'fts> {
     pub top_10_terms: OrderMap<&'fts str, usize>, // term -> term_count
@@ -569,7 +817,8 @@ mod tests {
     use crate::full_text_search::FieldBoost;
 
     use super::{
-        FTSEngine, MAX_MATCHED_TERMS_PER_QUERY_TERM, char_prefix, fuzzy_prefix_similarity,
+        FTSEngine, FieldMatch, MAX_MATCHED_TERMS_PER_QUERY_TERM, char_prefix,
+        fuzzy_prefix_similarity,
     };
 
     #[derive(Clone)]
@@ -848,6 +1097,105 @@ mod tests {
         assert!(fts.search("alpha beta").is_empty());
     }
 
+    #[test]
+    fn test_phrase_search() {
+        let fts = new_test_fts(&[
+            TestDoc::new(1, "left hand", "darkness"),
+            TestDoc::new(2, "hand left", "darkness"),
+            TestDoc::new(3, "left", "hand of darkness"),
+        ]);
+
+        assert_eq!(search_ids(&fts, r#""left hand""#), vec!["1"]);
+        assert_eq!(search_ids(&fts, r#""hand of darkness""#), vec!["3"]);
+        assert!(fts.search(r#""left darkness""#).is_empty());
+
+        // phrase terms must match exactly
+        assert!(fts.search(r#""lef hand""#).is_empty());
+        assert!(fts.search(r#""hnad""#).is_empty());
+    }
+
+    #[test]
+    fn test_excluded_terms() {
+        let fts = new_test_fts(&[
+            TestDoc::new(1, "alpha", "beta"),
+            TestDoc::new(2, "alpha", "gamma"),
+            TestDoc::new(3, "delta", "beta gamma"),
+        ]);
+
+        assert_eq!(search_ids(&fts, "alpha -beta"), vec!["2"]);
+        assert_eq!(search_ids(&fts, r#"gamma -"beta gamma""#), vec!["2"]);
+
+        assert!(fts.search("-alpha -delta -beta*").is_empty());
+        assert_eq!(search_ids(&fts, "-gamma"), vec!["1"]);
+
+        // excluded terms don't match fuzzily
+        assert_eq!(search_ids(&fts, "alpha -bet").len(), 2);
+    }
+
+    #[test]
+    fn test_prefix_terms() {
+        let fts = new_test_fts(&[
+            TestDoc::new(1, "alpha", "value"),
+            TestDoc::new(2, "alpha", "valuable"),
+            TestDoc::new(3, "alpha", "vlaue"),
+        ]);
+
+        assert_eq!(search_ids(&fts, "valu*"), vec!["1", "2"]);
+        assert_eq!(search_ids(&fts, "vlau*"), vec!["3"]);
+        assert!(fts.search("vale*").is_empty());
+    }
+
+    #[test]
+    fn test_or_alternatives() {
+        let fts = new_test_fts(&[
+            TestDoc::new(1, "alpha", "beta"),
+            TestDoc::new(2, "alpha", "gamma"),
+            TestDoc::new(3, "delta", "beta gamma"),
+        ]);
+
+        let mut ids = search_ids(&fts, "alpha beta OR gamma");
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2"]);
+
+        let mut ids = search_ids(&fts, "delta OR alpha");
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+
+        assert_eq!(search_ids(&fts, "missing OR delta"), vec!["3"]);
+        assert!(fts.search("missing OR absent").is_empty());
+    }
+
+    #[test]
+    fn test_field_scoped_search() {
+        let fts = new_test_fts(&[
+            TestDoc::new(1, "alpha beta", "gamma"),
+            TestDoc::new(2, "gamma", "alpha beta"),
+        ]);
+
+        assert_eq!(search_ids(&fts, "title:alpha"), vec!["1"]);
+        assert_eq!(search_ids(&fts, "data:alp*"), vec!["2"]);
+        assert_eq!(search_ids(&fts, r#"data:"alpha beta""#), vec!["2"]);
+        assert_eq!(search_ids(&fts, "gamma -title:gamma"), vec!["1"]);
+        assert_eq!(search_ids(&fts, "title:gamma OR data:gamma").len(), 2);
+
+        // typos are still recovered within the field
+        assert_eq!(search_ids(&fts, "title:alpah"), vec!["1"]);
+
+        assert!(fts.search("unknown:alpha").is_empty());
+        assert!(fts.search(r#"title:"beta gamma""#).is_empty());
+    }
+
+    #[test]
+    fn test_phrase_hits_rank_above_scattered_matches() {
+        let fts = new_test_fts(&[
+            TestDoc::new(1, "york", "new guide to the city"),
+            TestDoc::new(2, "guide", "new york"),
+        ]);
+
+        assert_eq!(search_ids(&fts, r#""new york" OR york"#), vec!["2", "1"]);
+        assert_eq!(search_ids(&fts, r#"york OR "new york""#), vec!["2", "1"]);
+    }
+
     #[test]
     fn test_proximity_boost() {
         {
@@ -920,6 +1268,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_low_field_boost() {
+        let doc1 = TestDoc::new(1, "other", "test data");
+        let doc2 = TestDoc::new(2, "other", "test data");
+
+        let mut fts = FTSEngine::new();
+
+        let mut field_boost = HashMap::new();
+        field_boost.insert("data", FieldBoost::new(0.5).unwrap());
+        fts.index_document(doc1.id.clone(), doc1.get_fields(), field_boost);
+
+        doc2.insert(&mut fts);
+
+        assert_eq!(fts.search("test"), vec!["2", "1"]);
+
+        assert!(FieldBoost::new(0.05).is_err());
+        assert!(FieldBoost::new(2.5).is_err());
+    }
+
+    #[test]
+    fn test_search_with_matches() {
+        let fts = new_test_fts(&[
+            TestDoc::new(1, "left hand", "the left hand of darkness, left"),
+            TestDoc::new(2, "other", "nothing"),
+        ]);
+
+        let results = fts.search_with_matches(r#""hand of" left -nothing"#);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_id, "1");
+        assert_eq!(
+            results[0].fields,
+            vec![
+                FieldMatch {
+                    field: "data".to_string(),
+                    positions: vec![1, 2, 3, 5],
+                },
+                FieldMatch {
+                    field: "title".to_string(),
+                    positions: vec![0],
+                },
+            ]
+        );
+
+        let results = fts.search_with_matches("");
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.fields.is_empty()));
+    }
+
     mod search_quality_tests {
         use super::*;
 
@@ -0,0 +1,363 @@
+use super::tokenizer::Tokenizer;
+
+const OR_OPERATOR: &str = "OR";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum MatchMode {
+    /// exact, prefix or fuzzy candidates
+    Fuzzy,
+    /// exact or prefix candidates, `term*`
+    Prefix,
+    /// only the exact term
+    Exact,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum QueryItem {
+    Term {
+        term: String,
+        mode: MatchMode,
+        /// Match only this indexed field, `field:term`
+        field: Option<String>,
+    },
+    /// Terms that must appear contiguously and in order in the same field.
+    Phrase {
+        terms: Vec<String>,
+        field: Option<String>,
+    },
+}
+
+/// A parsed full-text search query.
+///
+/// Every clause must match (strict AND); a clause matches if any of its alternatives matches.
+/// Records that match any excluded item are dropped.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct SearchQuery {
+    pub clauses: Vec<Vec<QueryItem>>,
+    pub excluded: Vec<QueryItem>,
+}
+
+struct RawToken<'q> {
+    text: &'q str,
+    field: Option<&'q str>,
+    quoted: bool,
+    negated: bool,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str, tokenizer: &Tokenizer) -> Self {
+        let mut result = SearchQuery::default();
+
+        let tokens = split_raw_tokens(query);
+        let mut join_with_previous = false;
+
+        for (index, token) in tokens.iter().enumerate() {
+            let is_or = !token.quoted
+                && !token.negated
+                && token.field.is_none()
+                && token.text == OR_OPERATOR;
+            if is_or {
+                // a leading or trailing OR is an ordinary term
+                if !result.clauses.is_empty() && index + 1 < tokens.len() {
+                    join_with_previous = true;
+                    continue;
+                }
+            }
+
+            let items = parse_raw_token(token, tokenizer);
+
+            if token.negated {
+                result.excluded.extend(items);
+                join_with_previous = false;
+                continue;
+            }
+
+            for (item_index, item) in items.into_iter().enumerate() {
+                let last_clause = result.clauses.last_mut();
+
+                match last_clause {
+                    Some(clause) if join_with_previous && item_index == 0 => {
+                        if !clause.contains(&item) {
+                            clause.push(item);
+                        }
+                    }
+                    _ => {
+                        let clause = vec![item];
+                        if !result.clauses.contains(&clause) {
+                            result.clauses.push(clause);
+                        }
+                    }
+                }
+            }
+
+            join_with_previous = false;
+        }
+
+        result
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn iter_items(&self) -> impl Iterator<Item = &QueryItem> {
+        self.clauses.iter().flatten().chain(self.excluded.iter())
+    }
+}
+
+fn parse_raw_token(token: &RawToken, tokenizer: &Tokenizer) -> Vec<QueryItem> {
+    let field = token.field.map(str::to_string);
+
+    if token.quoted {
+        let mut terms = tokenizer.tokenize_query(token.text, false);
+
+        return match terms.len() {
+            0 => vec![],
+            1 => vec![QueryItem::Term {
+                term: terms.remove(0),
+                mode: MatchMode::Exact,
+                field,
+            }],
+            _ => vec![QueryItem::Phrase { terms, field }],
+        };
+    }
+
+    let (text, is_prefix) = match token.text.strip_suffix('*') {
+        Some(text) => (text, true),
+        None => (token.text, false),
+    };
+
+    let mut terms = tokenizer.tokenize_query(text, is_prefix);
+
+    // an excluded word that consists of several terms, like `-foo-bar`, excludes the phrase
+    if token.negated && terms.len() > 1 && !is_prefix {
+        return vec![QueryItem::Phrase { terms, field }];
+    }
+
+    // excluded terms never match fuzzily, so that a typo doesn't hide unrelated records
+    let mode = if token.negated {
+        MatchMode::Exact
+    } else {
+        MatchMode::Fuzzy
+    };
+    let last_mode = if is_prefix { MatchMode::Prefix } else { mode };
+
+    let last_term = terms.pop();
+    terms
+        .into_iter()
+        .map(|term| (term, mode))
+        .chain(last_term.map(|term| (term, last_mode)))
+        .map(|(term, mode)| QueryItem::Term {
+            term,
+            mode,
+            field: field.clone(),
+        })
+        .collect()
+}
+
+/// Parses the `field:` prefix of a `field:term` token. Field names may start with `@`.
+fn parse_field(token: &str) -> Option<(&str, &str)> {
+    let (field, rest) = token.split_once(':')?;
+
+    let name = field.strip_prefix('@').unwrap_or(field);
+    let is_valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    let has_value = rest.starts_with(|c: char| !c.is_whitespace());
+
+    (is_valid_name && has_value).then_some((field, rest))
+}
+
+/// Splits the query by whitespace, keeping double-quoted phrases together.
+/// A `-` prefix marks a word or a phrase as excluded, a `field:` prefix scopes it to a field.
+fn split_raw_tokens(query: &str) -> Vec<RawToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = query;
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+
+        let negated =
+            rest.len() > 1 && rest.starts_with('-') && !rest[1..].starts_with(char::is_whitespace);
+        if negated {
+            rest = &rest[1..];
+        }
+
+        let field = parse_field(rest).map(|(field, value)| {
+            rest = value;
+            field
+        });
+
+        if let Some(phrase) = rest.strip_prefix('"') {
+            let end = phrase.find('"').unwrap_or(phrase.len());
+
+            tokens.push(RawToken {
+                text: &phrase[..end],
+                field,
+                quoted: true,
+                negated,
+            });
+            rest = phrase.get(end + 1..).unwrap_or_default();
+            continue;
+        }
+
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        tokens.push(RawToken {
+            text: &rest[..end],
+            field,
+            quoted: false,
+            negated,
+        });
+        rest = &rest[end..];
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchMode, QueryItem, SearchQuery, Tokenizer};
+
+    fn parse(query: &str) -> SearchQuery {
+        SearchQuery::parse(query, &Tokenizer::default())
+    }
+
+    fn term(term: &str, mode: MatchMode) -> QueryItem {
+        QueryItem::Term {
+            term: term.into(),
+            mode,
+            field: None,
+        }
+    }
+
+    fn phrase(terms: &[&str]) -> QueryItem {
+        QueryItem::Phrase {
+            terms: terms.iter().map(|term| term.to_string()).collect(),
+            field: None,
+        }
+    }
+
+    fn in_field(item: QueryItem, name: &str) -> QueryItem {
+        match item {
+            QueryItem::Term { term, mode, .. } => QueryItem::Term {
+                term,
+                mode,
+                field: Some(name.into()),
+            },
+            QueryItem::Phrase { terms, .. } => QueryItem::Phrase {
+                terms,
+                field: Some(name.into()),
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        assert_eq!(
+            parse("Café  résumé café"),
+            SearchQuery {
+                clauses: vec![
+                    vec![term("cafe", MatchMode::Fuzzy)],
+                    vec![term("resume", MatchMode::Fuzzy)],
+                ],
+                excluded: vec![],
+            }
+        );
+
+        assert!(parse(" ,.! ").is_empty());
+        assert!(parse("\"\"").is_empty());
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        assert_eq!(
+            parse(r#""Left Hand" -dark val* a OR b OR "c d" -"e f" "g""#),
+            SearchQuery {
+                clauses: vec![
+                    vec![phrase(&["left", "hand"])],
+                    vec![term("val", MatchMode::Prefix)],
+                    vec![
+                        term("a", MatchMode::Fuzzy),
+                        term("b", MatchMode::Fuzzy),
+                        phrase(&["c", "d"]),
+                    ],
+                    vec![term("g", MatchMode::Exact)],
+                ],
+                excluded: vec![term("dark", MatchMode::Exact), phrase(&["e", "f"])],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_edge_cases() {
+        // OR without a term on both sides is an ordinary term
+        assert_eq!(
+            parse("OR value OR"),
+            SearchQuery {
+                clauses: vec![
+                    vec![term("or", MatchMode::Fuzzy)],
+                    vec![term("value", MatchMode::Fuzzy)],
+                ],
+                excluded: vec![],
+            }
+        );
+
+        // lowercase or is an ordinary term
+        assert_eq!(parse("a or b").clauses.len(), 3);
+
+        // a dash inside a word or followed by whitespace doesn't exclude
+        assert_eq!(
+            parse("well-known - x"),
+            SearchQuery {
+                clauses: vec![
+                    vec![term("well", MatchMode::Fuzzy)],
+                    vec![term("known", MatchMode::Fuzzy)],
+                    vec![term("x", MatchMode::Fuzzy)],
+                ],
+                excluded: vec![],
+            }
+        );
+
+        assert_eq!(
+            parse("-well-known -pre*"),
+            SearchQuery {
+                clauses: vec![],
+                excluded: vec![phrase(&["well", "known"]), term("pre", MatchMode::Prefix)],
+            }
+        );
+
+        // unterminated quote
+        assert_eq!(
+            parse(r#"x "a b"#).clauses,
+            vec![vec![term("x", MatchMode::Fuzzy)], vec![phrase(&["a", "b"])]]
+        );
+    }
+
+    #[test]
+    fn test_parse_field_scopes() {
+        assert_eq!(
+            parse(r#"authors:"Le Guin" @title:left* -comment:dark title:a OR b 12:30 text: x"#),
+            SearchQuery {
+                clauses: vec![
+                    vec![in_field(phrase(&["le", "guin"]), "authors")],
+                    vec![in_field(term("left", MatchMode::Prefix), "@title")],
+                    vec![
+                        in_field(term("a", MatchMode::Fuzzy), "title"),
+                        term("b", MatchMode::Fuzzy),
+                    ],
+                    vec![term("12", MatchMode::Fuzzy)],
+                    vec![term("30", MatchMode::Fuzzy)],
+                    vec![term("text", MatchMode::Fuzzy)],
+                    vec![term("x", MatchMode::Fuzzy)],
+                ],
+                excluded: vec![in_field(term("dark", MatchMode::Exact), "comment")],
+            }
+        );
+
+        // a field-scoped OR is a term
+        assert_eq!(parse("a title:OR b").clauses.len(), 3);
+    }
+}
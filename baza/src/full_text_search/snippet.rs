@@ -0,0 +1,209 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+use super::tokenizer::tokenize_with_offsets;
+
+// Number of tokens in a snippet, including the context around the hits.
+const SNIPPET_TOKENS: usize = 20;
+
+// Number of tokens to show before the first hit in a snippet.
+const CONTEXT_TOKENS_BEFORE: usize = 5;
+
+const ELLIPSIS: &str = "…";
+
+/// A short excerpt of a matched field, with the matched tokens marked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snippet {
+    pub text: String,
+    /// Byte ranges of the matched tokens in the `text`, in order.
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    /// Extracts the part of the `text` with the most hits, where hits are token positions as
+    /// returned by the search engine. Whitespace is collapsed, and ellipses mark cut text.
+    pub fn build(text: &str, hits: &[usize]) -> Option<Self> {
+        let tokens = tokenize_with_offsets(text)
+            .into_iter()
+            .map(|(_, range)| range)
+            .collect::<Vec<_>>();
+
+        let mut hits = hits
+            .iter()
+            .copied()
+            .filter(|position| *position < tokens.len())
+            .collect::<Vec<_>>();
+        hits.sort_unstable();
+        hits.dedup();
+
+        // pick the hit that starts the window with the most hits
+        let window_hits = SNIPPET_TOKENS - CONTEXT_TOKENS_BEFORE;
+        let anchor = *hits.iter().max_by_key(|anchor| {
+            let count = hits
+                .iter()
+                .filter(|position| (**anchor..**anchor + window_hits).contains(*position))
+                .count();
+
+            // prefer earlier hits on ties
+            (count, std::cmp::Reverse(**anchor))
+        })?;
+
+        let end = (anchor.saturating_sub(CONTEXT_TOKENS_BEFORE) + SNIPPET_TOKENS).min(tokens.len());
+        let start = end.saturating_sub(SNIPPET_TOKENS);
+
+        let mut snippet = Snippet {
+            text: String::new(),
+            highlights: Vec::new(),
+        };
+
+        if start > 0 {
+            snippet.text.push_str(ELLIPSIS);
+        } else {
+            push_whitespace_collapsed(&mut snippet.text, text[..tokens[0].start].trim_start());
+        }
+
+        let mut previous_hit = false;
+        for (position, range) in tokens.iter().enumerate().take(end).skip(start) {
+            if position > start {
+                push_whitespace_collapsed(
+                    &mut snippet.text,
+                    &text[tokens[position - 1].end..range.start],
+                );
+            }
+
+            let is_hit = hits.binary_search(&position).is_ok();
+            let highlight_start = snippet.text.len();
+            snippet.text.push_str(&text[range.clone()]);
+
+            if is_hit {
+                match snippet.highlights.last_mut() {
+                    // merge adjacent hits, like phrases, into a single highlight
+                    Some(highlight) if previous_hit => highlight.end = snippet.text.len(),
+                    _ => snippet.highlights.push(highlight_start..snippet.text.len()),
+                }
+            }
+            previous_hit = is_hit;
+        }
+
+        if end < tokens.len() {
+            snippet.text.push_str(ELLIPSIS);
+        } else {
+            push_whitespace_collapsed(&mut snippet.text, text[tokens[end - 1].end..].trim_end());
+        }
+
+        Some(snippet)
+    }
+
+    /// Splits the snippet text into fragments, marking the highlighted ones.
+    pub fn fragments(&self) -> Vec<(&str, bool)> {
+        let mut fragments = Vec::with_capacity(self.highlights.len() * 2 + 1);
+
+        let mut position = 0;
+        for highlight in &self.highlights {
+            if position < highlight.start {
+                fragments.push((&self.text[position..highlight.start], false));
+            }
+            fragments.push((&self.text[highlight.clone()], true));
+
+            position = highlight.end;
+        }
+        if position < self.text.len() {
+            fragments.push((&self.text[position..], false));
+        }
+
+        fragments
+    }
+
+    /// Renders the snippet text, wrapping the highlights with `open` and `close` markers.
+    pub fn render(&self, open: &str, close: &str) -> String {
+        self.fragments()
+            .into_iter()
+            .map(|(text, highlight)| {
+                if highlight {
+                    format!("{open}{text}{close}")
+                } else {
+                    text.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+fn push_whitespace_collapsed(result: &mut String, value: &str) {
+    let mut previous_whitespace = false;
+
+    for char in value.chars() {
+        if char.is_whitespace() {
+            if !previous_whitespace {
+                result.push(' ');
+            }
+            previous_whitespace = true;
+        } else {
+            result.push(char);
+            previous_whitespace = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Snippet;
+
+    fn render(text: &str, hits: &[usize]) -> Option<String> {
+        Snippet::build(text, hits).map(|snippet| snippet.render("[", "]"))
+    }
+
+    #[test]
+    fn test_build_snippet() {
+        assert_eq!(
+            render("  The quick brown fox,\n\n jumps. ", &[2]),
+            Some("The quick [brown] fox, jumps.".to_string())
+        );
+
+        assert_eq!(render("The quick brown fox", &[]), None);
+        assert_eq!(render("The quick brown fox", &[10]), None);
+        assert_eq!(render("", &[0]), None);
+    }
+
+    #[test]
+    fn test_build_snippet_merges_adjacent_hits() {
+        assert_eq!(
+            render("The quick brown fox", &[1, 2]),
+            Some("The [quick brown] fox".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_snippet_cuts_long_text() {
+        let text = (0..100)
+            .map(|index| format!("w{index}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        assert_eq!(
+            render(&text, &[50]).unwrap(),
+            "…w45 w46 w47 w48 w49 [w50] w51 w52 w53 w54 w55 w56 w57 w58 w59 w60 w61 w62 w63 w64…"
+        );
+
+        // the window with the most hits wins
+        assert_eq!(
+            render(&text, &[3, 80, 83]).unwrap(),
+            "…w75 w76 w77 w78 w79 [w80] w81 w82 [w83] w84 w85 w86 w87 w88 w89 w90 w91 w92 w93 w94…"
+        );
+
+        // the window is shifted to fill the snippet at the end of the text
+        assert_eq!(
+            render(&text, &[99]).unwrap(),
+            "…w80 w81 w82 w83 w84 w85 w86 w87 w88 w89 w90 w91 w92 w93 w94 w95 w96 w97 w98 [w99]"
+        );
+    }
+
+    #[test]
+    fn test_build_snippet_highlights_are_byte_ranges() {
+        let snippet = Snippet::build("Café naïve", &[1]).unwrap();
+
+        assert_eq!(&snippet.text[snippet.highlights[0].clone()], "naïve");
+        assert_eq!(snippet.fragments(), vec![("Café ", false), ("naïve", true)]);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Result, bail};
+use charabia::Script;
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum StemmingLanguage {
+    English,
+    Ukrainian,
+}
+
+impl StemmingLanguage {
+    pub const ALL: [StemmingLanguage; 2] = [StemmingLanguage::English, StemmingLanguage::Ukrainian];
+
+    /// Languages are picked by the script of the word, since language detection is unreliable
+    /// for short texts like titles.
+    pub(super) fn from_script(script: Script) -> Option<Self> {
+        match script {
+            Script::Latin => Some(StemmingLanguage::English),
+            Script::Cyrillic => Some(StemmingLanguage::Ukrainian),
+            _ => None,
+        }
+    }
+}
+
+/// Stems words of the supported languages; created once per tokenizer.
+pub(super) struct WordStemmer {
+    english: Stemmer,
+}
+
+impl WordStemmer {
+    /// Stems a lowercase word.
+    pub fn stem(&self, language: StemmingLanguage, word: &str) -> String {
+        match language {
+            StemmingLanguage::English => self.english.stem(word).to_string(),
+            StemmingLanguage::Ukrainian => stem_ukrainian(word),
+        }
+    }
+}
+
+impl Default for WordStemmer {
+    fn default() -> Self {
+        WordStemmer {
+            english: Stemmer::create(Algorithm::English),
+        }
+    }
+}
+
+impl Clone for WordStemmer {
+    fn clone(&self) -> Self {
+        WordStemmer::default()
+    }
+}
+
+impl fmt::Debug for WordStemmer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WordStemmer")
+    }
+}
+
+// stemmers have no configuration, so all of them are equal
+impl PartialEq for WordStemmer {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for WordStemmer {}
+
+impl fmt::Display for StemmingLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StemmingLanguage::English => "English",
+            StemmingLanguage::Ukrainian => "Ukrainian",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for StemmingLanguage {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let Some(language) = StemmingLanguage::ALL
+            .into_iter()
+            .find(|language| language.to_string().eq_ignore_ascii_case(value))
+        else {
+            bail!(
+                "Unsupported stemming language '{value}', must be one of [{}]",
+                StemmingLanguage::ALL
+                    .map(|language| language.to_string())
+                    .join(", ")
+            );
+        };
+
+        Ok(language)
+    }
+}
+
+const UKRAINIAN_VOWELS: &[char] = &['а', 'е', 'є', 'и', 'і', 'ї', 'о', 'у', 'ю', 'я'];
+
+const UKRAINIAN_REFLEXIVE_SUFFIXES: &[&str] = &["ся", "сь", "си"];
+
+// Noun, adjective and verb endings, longest first.
+const UKRAINIAN_ENDINGS: &[&str] = &[
+    "ають", "яють", "ують", "аєте", "аємо", //
+    "аєш", "ами", "ями", "ові", "еві", "єві", "ого", "ому", "ими", "іми", "ати", "яти", "ити",
+    "іти", "ють", "уть", "ать", "ять", "ить", "іть", "ала", "али", "ало", "яла", "яли", "яло",
+    "ила", "или", "ило", "ете", "ємо", "емо", "ите", "имо", //
+    "ах", "ях", "ам", "ям", "ом", "ем", "єм", "ою", "ею", "єю", "ів", "їв", "ий", "ій", "ої", "их",
+    "іх", "им", "ім", "ає", "ує", "ює", "аю", "ую", "юю", "еш", "єш", "иш", "ть", "ти", "ав", "ив",
+    "ей", //
+    "а", "я", "о", "е", "є", "и", "і", "ї", "у", "ю", "й", "ь",
+];
+
+/// A light suffix-stripping stemmer: removes the reflexive suffix and the longest inflection
+/// ending after the first vowel of the word.
+fn stem_ukrainian(word: &str) -> String {
+    let chars = word.chars().collect::<Vec<_>>();
+
+    let Some(first_vowel) = chars.iter().position(|c| UKRAINIAN_VOWELS.contains(c)) else {
+        return word.to_string();
+    };
+    // endings are removed only from the part of the word after the first vowel
+    let stem_min_len = first_vowel + 1;
+
+    let mut stem_len = chars.len();
+    strip_ukrainian_suffix(
+        &chars,
+        stem_min_len,
+        &mut stem_len,
+        UKRAINIAN_REFLEXIVE_SUFFIXES,
+    );
+    strip_ukrainian_suffix(&chars, stem_min_len, &mut stem_len, UKRAINIAN_ENDINGS);
+
+    chars[..stem_len].iter().collect()
+}
+
+fn strip_ukrainian_suffix(
+    chars: &[char],
+    stem_min_len: usize,
+    stem_len: &mut usize,
+    suffixes: &[&str],
+) {
+    for suffix in suffixes {
+        let suffix_len = suffix.chars().count();
+        if *stem_len < stem_min_len + suffix_len {
+            continue;
+        }
+
+        let start = *stem_len - suffix_len;
+        if chars[start..*stem_len].iter().copied().eq(suffix.chars()) {
+            *stem_len = start;
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StemmingLanguage, WordStemmer};
+
+    #[test]
+    fn test_stem_english() {
+        let stemmer = WordStemmer::default();
+        let stem = |word| stemmer.stem(StemmingLanguage::English, word);
+
+        assert_eq!(stem("books"), stem("book"));
+        assert_eq!(stem("running"), stem("run"));
+        assert_eq!(stem("connected"), stem("connection"));
+    }
+
+    #[test]
+    fn test_stem_ukrainian() {
+        let stemmer = WordStemmer::default();
+        let stem = |word| stemmer.stem(StemmingLanguage::Ukrainian, word);
+
+        for word in ["книга", "книги", "книгою", "книгами", "книгах"] {
+            assert_eq!(stem(word), "книг", "{word}");
+        }
+        for word in ["читати", "читаю", "читаєш", "читав", "читала", "читаються"]
+        {
+            assert_eq!(stem(word), "чит", "{word}");
+        }
+        for word in ["нова", "новий", "нового", "новими"] {
+            assert_eq!(stem(word), "нов", "{word}");
+        }
+
+        // the part of the word before the first vowel is never removed
+        assert_eq!(stem("я"), "я");
+        assert_eq!(stem("сьогодні"), "сьогодн");
+    }
+
+    #[test]
+    fn test_parse_stemming_language() {
+        assert_eq!(
+            "ukrainian".parse::<StemmingLanguage>().unwrap(),
+            StemmingLanguage::Ukrainian
+        );
+        assert_eq!(
+            "English".parse::<StemmingLanguage>().unwrap(),
+            StemmingLanguage::English
+        );
+        assert!("Klingon".parse::<StemmingLanguage>().is_err());
+    }
+}
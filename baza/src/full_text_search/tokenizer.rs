@@ -1,61 +1,184 @@
-use charabia::Tokenize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+use charabia::{Token, Tokenize};
 use deunicode::deunicode;
+use serde::{Deserialize, Serialize};
+
+use super::stemmer::{StemmingLanguage, WordStemmer};
+
+/// Returns word terms with their byte ranges in the input, without stemming.
+pub fn tokenize_with_offsets(input: &str) -> Vec<(String, Range<usize>)> {
+    Tokenizer::default().tokenize_with_offsets(input)
+}
 
-pub fn tokenize_with_offsets(input: &str) -> Vec<(String, usize)> {
-    // TODO remove stop words
-    input
-        .tokenize()
-        .filter_map(|token| {
-            token
-                .is_word()
-                .then(|| (deunicode(token.lemma()).to_lowercase(), token.byte_start))
-        })
-        .collect()
+/// Turns text into search terms; the same tokenizer must be used for indexing and queries.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tokenizer {
+    stemming: BTreeSet<StemmingLanguage>,
+    // term -> the first term of its synonym group
+    synonyms: BTreeMap<String, String>,
+    #[serde(skip)]
+    stemmer: WordStemmer,
 }
 
-pub fn tokenize_with_positions(input: &str) -> Vec<(String, usize)> {
-    tokenize_with_offsets(input)
-        .into_iter()
-        .enumerate()
-        .map(|(position, (term, _))| (term, position))
-        .collect()
+impl Tokenizer {
+    /// Synonym groups are lists of single words; other entries are ignored.
+    pub fn new(
+        stemming: impl IntoIterator<Item = StemmingLanguage>,
+        synonym_groups: &[Vec<String>],
+    ) -> Self {
+        let mut tokenizer = Tokenizer {
+            stemming: stemming.into_iter().collect(),
+            synonyms: BTreeMap::new(),
+            stemmer: WordStemmer::default(),
+        };
+
+        for group in synonym_groups {
+            let terms = group
+                .iter()
+                .filter_map(|word| {
+                    let mut terms = tokenizer.tokenize_with_offsets(word);
+
+                    (terms.len() == 1).then(|| terms.remove(0).0)
+                })
+                .collect::<Vec<_>>();
+
+            let Some(canonical_term) = terms.first() else {
+                continue;
+            };
+
+            for term in &terms {
+                // the first group wins if a term is in several groups
+                if term != canonical_term && !tokenizer.synonyms.contains_key(term) {
+                    tokenizer
+                        .synonyms
+                        .insert(term.clone(), canonical_term.clone());
+                }
+            }
+        }
+
+        tokenizer
+    }
+
+    fn normalize(&self, token: &Token, stem: bool) -> String {
+        let lemma = token.lemma().to_lowercase();
+
+        let lemma = match StemmingLanguage::from_script(token.script) {
+            Some(language) if stem && self.stemming.contains(&language) => {
+                self.stemmer.stem(language, &lemma)
+            }
+            _ => lemma,
+        };
+
+        deunicode(&lemma).to_lowercase()
+    }
+
+    fn tokenize_words(&self, input: &str, stem_last_word: bool) -> Vec<(String, Range<usize>)> {
+        // TODO remove stop words
+        let tokens = input.tokenize().filter(Token::is_word).collect::<Vec<_>>();
+
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| {
+                let stem = stem_last_word || index + 1 < tokens.len();
+
+                (
+                    self.normalize(token, stem),
+                    token.byte_start..token.byte_end,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns word terms with their byte ranges in the input.
+    pub fn tokenize_with_offsets(&self, input: &str) -> Vec<(String, Range<usize>)> {
+        self.tokenize_words(input, true)
+    }
+
+    /// Returns word terms with their positions, for indexing.
+    ///
+    /// Words from synonym groups are also indexed as the first word of the group, at the same
+    /// position, so that queries for any of the synonyms match.
+    pub fn tokenize_with_positions(&self, input: &str) -> Vec<(String, usize)> {
+        let mut terms = Vec::new();
+
+        for (position, (term, _)) in self.tokenize_with_offsets(input).into_iter().enumerate() {
+            if let Some(synonym) = self.synonyms.get(&term) {
+                terms.push((synonym.clone(), position));
+            }
+            terms.push((term, position));
+        }
+
+        terms
+    }
+
+    /// Returns query terms, replacing synonyms with the first word of their group.
+    ///
+    /// A prefix term, the last term of `term*`, is matched against indexed term prefixes, so
+    /// it is neither stemmed nor replaced with a synonym.
+    pub fn tokenize_query(&self, input: &str, is_prefix: bool) -> Vec<String> {
+        let mut terms = self
+            .tokenize_words(input, !is_prefix)
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect::<Vec<_>>();
+
+        let last_index = terms.len().saturating_sub(1);
+        for (index, term) in terms.iter_mut().enumerate() {
+            if is_prefix && index == last_index {
+                continue;
+            }
+
+            if let Some(synonym) = self.synonyms.get(term) {
+                *term = synonym.clone();
+            }
+        }
+
+        terms
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{tokenize_with_offsets, tokenize_with_positions};
+    use crate::full_text_search::StemmingLanguage;
+
+    use super::{Tokenizer, tokenize_with_offsets};
 
     #[test]
     fn test_tokenize_with_offsets() {
         assert_eq!(
             tokenize_with_offsets("Hello, 世界! Rust."),
             vec![
-                ("hello".to_string(), 0),
-                ("shi jie".to_string(), 7),
-                ("rust".to_string(), 15)
+                ("hello".to_string(), 0..5),
+                ("shi jie".to_string(), 7..13),
+                ("rust".to_string(), 15..19)
             ]
         );
 
         assert_eq!(
             tokenize_with_offsets("Café naïve façade résumé"),
             vec![
-                ("cafe".to_string(), 0),
-                ("naive".to_string(), 6),
-                ("facade".to_string(), 13),
-                ("resume".to_string(), 21)
+                ("cafe".to_string(), 0..5),
+                ("naive".to_string(), 6..12),
+                ("facade".to_string(), 13..20),
+                ("resume".to_string(), 21..29)
             ]
         );
 
         assert_eq!(
             tokenize_with_offsets("Słowikowskiego"),
-            vec![("slowikowskiego".to_string(), 0),]
+            vec![("slowikowskiego".to_string(), 0..15),]
         );
 
         assert_eq!(
             tokenize_with_offsets("ТеСт ЇЖак"),
             vec![
-                ("test".to_string(), 0), //
-                ("izhak".to_string(), 9),
+                ("test".to_string(), 0..8), //
+                ("izhak".to_string(), 9..17),
             ]
         );
     }
@@ -63,7 +186,7 @@ mod tests {
     #[test]
     fn test_tokenize_with_positions() {
         assert_eq!(
-            tokenize_with_positions("Hello, 世界! Rust."),
+            Tokenizer::default().tokenize_with_positions("Hello, 世界! Rust."),
             vec![
                 ("hello".to_string(), 0),
                 ("shi jie".to_string(), 1),
@@ -71,4 +194,65 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_stemming() {
+        let tokenizer = Tokenizer::new([StemmingLanguage::English], &[]);
+
+        assert_eq!(
+            tokenizer.tokenize_with_positions("Reading books, читаючи книги"),
+            vec![
+                ("read".to_string(), 0),
+                ("book".to_string(), 1),
+                ("chitaiuchi".to_string(), 2),
+                ("knigi".to_string(), 3),
+            ]
+        );
+
+        let tokenizer = Tokenizer::new(StemmingLanguage::ALL, &[]);
+        assert_eq!(
+            tokenizer.tokenize_query("книги", false),
+            tokenizer.tokenize_query("книгою", false)
+        );
+
+        // prefix terms aren't stemmed
+        assert_eq!(
+            tokenizer.tokenize_query("reading books", true),
+            vec!["read".to_string(), "books".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_synonyms() {
+        let tokenizer = Tokenizer::new(
+            [StemmingLanguage::English],
+            &[
+                vec!["car".into(), "automobiles".into(), "auto".into()],
+                vec!["auto".into(), "self".into()],
+                vec!["two words".into(), "single".into()],
+            ],
+        );
+
+        assert_eq!(
+            tokenizer.tokenize_with_positions("Auto car Automobile self"),
+            vec![
+                ("car".to_string(), 0),
+                ("auto".to_string(), 0),
+                ("car".to_string(), 1),
+                ("car".to_string(), 2),
+                ("automobil".to_string(), 2),
+                ("auto".to_string(), 3),
+                ("self".to_string(), 3),
+            ]
+        );
+
+        assert_eq!(
+            tokenizer.tokenize_query("automobile single", false),
+            vec!["car".to_string(), "singl".to_string()]
+        );
+        assert_eq!(
+            tokenizer.tokenize_query("auto*", true),
+            vec!["auto".to_string()]
+        );
+    }
 }
@@ -1,4 +1,5 @@
 mod algorithms;
+mod asset_text_service;
 mod auto_commit_service;
 mod backup;
 mod baza;
@@ -15,15 +16,30 @@ pub mod merge;
 pub mod schema;
 
 pub use backup::{RestoreCheckReport, RestoreOptions};
-pub use baza::{BLOBSCount, Baza, DocumentsCount, StagingError, ValidationError};
+pub use baza::{
+    ActivityEvent, ActivityFilter, ActivityKind, ActivityPage, AsOf, AssetsDedupeReport,
+    BLOBSCount, Baza, BazaView, BlobLocation, ConflictBranch, ConflictField, DocumentConflict,
+    DocumentsCount, DuplicateAsset, FieldResolution, FsckIssue, FsckIssueKind, FsckReport,
+    GarbageReport, HistoryPruneReport, MergePoint, MergeRecord, OrphanedBlob, PendingAssetText,
+    StagingError, UnreferencedAsset, ValidationError,
+};
 pub use baza_info::BazaInfo;
-pub use baza_manager::{BazaManager, StorageKeyUnlockResult};
+pub use baza_manager::{
+    BazaManager, DataMigrationReport, MigratedDocument, StorageKeyUnlockResult,
+};
 pub use baza_paths::BazaPaths;
-pub use baza_state::{BazaState, DocumentHead, Filter, ListPage, Locks};
+pub use baza_state::{
+    BazaState, Comparison, DEFAULT_PAGE_SIZE, DocumentHead, Filter, ListPage, Locks, MAX_PAGE_SIZE,
+    Predicate, SearchEngine, SearchSnippet, SortField, SortKey, StructuredQuery,
+};
 pub use baza_storage::BazaStorage;
 
+pub use asset_text_service::{AssetTextService, AssetTextTask};
 pub use auto_commit_service::{AutoCommitService, AutoCommitTask};
 pub use document_expert::DocumentExpert;
-pub use merge::{DocumentDataDiff, diff_document_data};
+pub use merge::{
+    DocumentDataDiff, FieldChange, FieldChangeKind, MergeDecision, MergeDecisionKind,
+    MergeStrategy, diff_document_data, diff_document_fields,
+};
 
 pub const DEV_MODE: bool = cfg!(not(feature = "production-mode"));
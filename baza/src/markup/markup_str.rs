@@ -42,6 +42,45 @@ impl MarkupStr<'_> {
         refs
     }
 
+    /// Splits the markup into top-level blocks, like paragraphs, lists or code blocks, each with
+    /// the whitespace that follows it. Concatenated blocks are equal to the markup.
+    #[must_use]
+    pub fn split_blocks(&self) -> Vec<&str> {
+        let text = self.0.as_ref();
+
+        let mut block_starts = Vec::new();
+        let mut depth = 0;
+        for (event, byte_range) in self.parse().into_offset_iter() {
+            if depth == 0 && !matches!(event, Event::End(_)) {
+                block_starts.push(byte_range.start);
+            }
+
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => {}
+            }
+        }
+
+        // the leading whitespace belongs to the first block
+        if let Some(first_start) = block_starts.first_mut() {
+            *first_start = 0;
+        }
+
+        let mut blocks = block_starts
+            .windows(2)
+            .map(|window| &text[window[0]..window[1]])
+            .collect::<Vec<_>>();
+
+        match block_starts.last() {
+            Some(last_start) => blocks.push(&text[*last_start..]),
+            None if !text.is_empty() => blocks.push(text),
+            None => {}
+        }
+
+        blocks
+    }
+
     #[must_use]
     pub fn preview(&self, lines: usize) -> Self {
         self.0
@@ -426,6 +465,28 @@ mod tests {
         serde_json::to_value(ast).expect("ast must serialize")
     }
 
+    #[test]
+    fn test_split_blocks() {
+        let markup = "\n# Title\n\nFirst paragraph\nwith two lines.\n\n- item 1\n- item 2\n\n```\ncode\n\nblock\n```\nLast";
+        let markup_str = MarkupStr::from(markup);
+        let blocks = markup_str.split_blocks();
+
+        assert_eq!(
+            blocks,
+            vec![
+                "\n# Title\n\n",
+                "First paragraph\nwith two lines.\n\n",
+                "- item 1\n- item 2\n\n",
+                "```\ncode\n\nblock\n```\n",
+                "Last",
+            ]
+        );
+        assert_eq!(blocks.concat(), markup);
+
+        assert!(MarkupStr::from("").split_blocks().is_empty());
+        assert_eq!(MarkupStr::from("  ").split_blocks(), vec!["  "]);
+    }
+
     #[test]
     fn test_parse_header() {
         let ast = into_ast("# TEST HEADER");
@@ -13,6 +13,15 @@ pub fn extract_id(text: &str) -> Option<Id> {
     }
 }
 
+/// Replaces the links to one document with the links to another one.
+#[must_use]
+pub fn replace_ref(text: &str, old_id: &Id, new_id: &Id) -> String {
+    text.replace(
+        &format!("{REF_LINK_PREFIX}{old_id}"),
+        &format!("{REF_LINK_PREFIX}{new_id}"),
+    )
+}
+
 #[must_use]
 fn create_link(url: &str, text: &str) -> String {
     if text.is_empty() {
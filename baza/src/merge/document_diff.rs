@@ -1,7 +1,10 @@
+use std::{collections::BTreeSet, fmt};
+
 use anyhow::Result;
+use serde::Serialize;
 use similar::{Algorithm, TextDiff};
 
-use crate::entities::Document;
+use crate::entities::{Document, DocumentData};
 
 /// Unified diff between two canonical document data renderings.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -36,6 +39,62 @@ pub fn diff_document_data(
     })
 }
 
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+impl fmt::Display for FieldChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            FieldChangeKind::Added => "added",
+            FieldChangeKind::Changed => "changed",
+            FieldChangeKind::Removed => "removed",
+        };
+
+        write!(f, "{value}")
+    }
+}
+
+/// A change of one field of the document data.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub kind: FieldChangeKind,
+}
+
+/// Lists the fields that differ between two versions of the document data, ordered by name.
+/// Fields with `null` values are treated as missing.
+pub fn diff_document_fields(old: &DocumentData, new: &DocumentData) -> Vec<FieldChange> {
+    let fields = old
+        .iter_fields()
+        .chain(new.iter_fields())
+        .map(|(field, _value)| field.as_str())
+        .collect::<BTreeSet<_>>();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let kind = match (old.get(field), new.get(field)) {
+                (None, Some(_)) => FieldChangeKind::Added,
+                (Some(_), None) => FieldChangeKind::Removed,
+                (Some(old_value), Some(new_value)) if old_value != new_value => {
+                    FieldChangeKind::Changed
+                }
+                _ => return None,
+            };
+
+            Some(FieldChange {
+                field: field.to_string(),
+                kind,
+            })
+        })
+        .collect()
+}
+
 fn canonical_document_data(document: &Document) -> Result<String> {
     let mut data = serde_json::to_string_pretty(&document.data)?;
     data.push('\n');
@@ -48,9 +107,9 @@ mod tests {
     use anyhow::Result;
     use serde_json::json;
 
-    use crate::entities::{Document, DocumentType};
+    use crate::entities::{Document, DocumentType, new_test_data};
 
-    use super::diff_document_data;
+    use super::{FieldChange, FieldChangeKind, diff_document_data, diff_document_fields};
 
     #[test]
     fn test_diff_document_data_uses_pretty_json() -> Result<()> {
@@ -81,4 +140,30 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_diff_document_fields() {
+        let old = new_test_data(json!({"title": "Old", "body": "same", "tags": null, "year": 1}));
+        let new = new_test_data(json!({"title": "New", "body": "same", "tags": ["a"]}));
+
+        assert_eq!(
+            diff_document_fields(&old, &new),
+            vec![
+                FieldChange {
+                    field: "tags".to_string(),
+                    kind: FieldChangeKind::Added,
+                },
+                FieldChange {
+                    field: "title".to_string(),
+                    kind: FieldChangeKind::Changed,
+                },
+                FieldChange {
+                    field: "year".to_string(),
+                    kind: FieldChangeKind::Removed,
+                },
+            ]
+        );
+
+        assert!(diff_document_fields(&old, &old).is_empty());
+    }
 }
@@ -1,21 +1,162 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt, str::FromStr};
 
-use anyhow::{Context, Result, ensure};
+use anyhow::{Context, Result, bail, ensure};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::merge::{merge_slices_three_way, merge_strings_three_way};
+use crate::merge::{
+    merge_markup_three_way, merge_names_three_way, merge_ordered_sets_three_way,
+    merge_sets_three_way, merge_strings_three_way,
+};
 
 use crate::{
     entities::{Document, DocumentType, parse_string_vec},
-    schema::{DataSchema, FieldType},
+    schema::{DataSchema, Field, FieldType},
 };
 
+/// How the conflict was resolved, for the document or for one of its fields.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeDecisionKind {
+    /// All branches erased the document, the oldest erasure was taken.
+    AllErased,
+    /// Some branches erased the document while others edited it; the edits were kept.
+    ErasedLostToEdit,
+    /// Took the value of the left side, i.e. of the older branches merged so far.
+    TookLeft,
+    /// Took the value of the right side, i.e. of the next newer branch.
+    TookRight,
+    /// One side removed the field while the other left it unchanged.
+    RemovedField,
+    /// Word-level three-way text merge.
+    TextMerge,
+    /// Set merge of unordered list items.
+    SetMerge,
+    /// Order-preserving merge of list items.
+    OrderedSetMerge,
+    /// Set merge of comma-separated names.
+    NamesMerge,
+    /// Took the value of the newer branch.
+    LastWriteWins,
+    /// Three-way merge of markup blocks and words.
+    MarkupMerge,
+    /// Three-way merge of markup, both sides changed the same words and conflict markers were added.
+    MarkupConflict,
+}
+
+impl fmt::Display for MergeDecisionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            MergeDecisionKind::AllErased => "all erased",
+            MergeDecisionKind::ErasedLostToEdit => "erased lost to edit",
+            MergeDecisionKind::TookLeft => "took left",
+            MergeDecisionKind::TookRight => "took right",
+            MergeDecisionKind::RemovedField => "removed field",
+            MergeDecisionKind::TextMerge => "three-way text merge",
+            MergeDecisionKind::SetMerge => "set merge",
+            MergeDecisionKind::OrderedSetMerge => "ordered list merge",
+            MergeDecisionKind::NamesMerge => "names merge",
+            MergeDecisionKind::LastWriteWins => "last write wins",
+            MergeDecisionKind::MarkupMerge => "markup merge",
+            MergeDecisionKind::MarkupConflict => "markup merge with conflict markers",
+        };
+
+        write!(f, "{value}")
+    }
+}
+
+/// A decision made by `MergeExpert`. Branches are merged pairwise from the oldest to the newest,
+/// so a field could get a decision per each merged branch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MergeDecision {
+    /// The field name, or `None` for the decisions about the whole document.
+    pub field: Option<String>,
+    pub kind: MergeDecisionKind,
+}
+
+impl MergeDecision {
+    fn document(kind: MergeDecisionKind) -> Self {
+        MergeDecision { field: None, kind }
+    }
+
+    fn field(field: &str, kind: MergeDecisionKind) -> Self {
+        MergeDecision {
+            field: Some(field.to_string()),
+            kind,
+        }
+    }
+}
+
+/// How to merge fields that were changed on both sides.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Merge the values according to the field type.
+    #[default]
+    Auto,
+    /// Take the value of the older branch.
+    PreferOlder,
+    /// Take the value of the newer branch.
+    PreferNewer,
+}
+
+impl MergeStrategy {
+    pub const ALL: [MergeStrategy; 3] = [
+        MergeStrategy::Auto,
+        MergeStrategy::PreferOlder,
+        MergeStrategy::PreferNewer,
+    ];
+}
+
+impl fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MergeStrategy::Auto => "auto",
+            MergeStrategy::PreferOlder => "prefer-older",
+            MergeStrategy::PreferNewer => "prefer-newer",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for MergeStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let Some(strategy) = MergeStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string().eq_ignore_ascii_case(value))
+        else {
+            bail!(
+                "Unsupported merge strategy '{value}', must be one of [{}]",
+                MergeStrategy::ALL
+                    .map(|strategy| strategy.to_string())
+                    .join(", ")
+            );
+        };
+
+        Ok(strategy)
+    }
+}
+
 pub struct MergeExpert {
     schema: DataSchema,
+    strategy: MergeStrategy,
 }
 
 impl MergeExpert {
     pub fn new(schema: DataSchema) -> Self {
-        MergeExpert { schema }
+        MergeExpert {
+            schema,
+            strategy: MergeStrategy::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.strategy = strategy;
+
+        self
     }
 
     fn validate_args(&self, base: &Option<Document>, originals: &Vec<&Document>) -> Result<()> {
@@ -54,8 +195,19 @@ impl MergeExpert {
     pub fn merge_originals(
         &self,
         base: Option<Document>,
-        mut originals: Vec<&Document>,
+        originals: Vec<&Document>,
     ) -> Result<Document> {
+        let (document, _decisions) = self.merge_originals_with_decisions(base, originals)?;
+
+        Ok(document)
+    }
+
+    /// Same as `merge_originals`, but also returns the decisions made during the merge.
+    pub fn merge_originals_with_decisions(
+        &self,
+        base: Option<Document>,
+        mut originals: Vec<&Document>,
+    ) -> Result<(Document, Vec<MergeDecision>)> {
         self.validate_args(&base, &originals)?;
 
         // sort from oldest to newest
@@ -64,15 +216,24 @@ impl MergeExpert {
         // if all erased, just use the oldest one
         let all_erased = originals.iter().all(|document| document.is_erased());
         if all_erased {
-            return Ok(originals[0].clone());
+            return Ok((
+                originals[0].clone(),
+                vec![MergeDecision::document(MergeDecisionKind::AllErased)],
+            ));
         }
 
+        let mut decisions = Vec::new();
+
         // if there are erased & non-erased, use only non-erased
+        let count = originals.len();
         originals.retain(|document| !document.is_erased());
+        if originals.len() != count {
+            decisions.push(MergeDecision::document(MergeDecisionKind::ErasedLostToEdit));
+        }
 
         // there was only one non-erased, use it
         if originals.len() == 1 {
-            return Ok(originals[0].clone());
+            return Ok((originals[0].clone(), decisions));
         }
 
         let mut originals = originals.into_iter();
@@ -80,10 +241,10 @@ impl MergeExpert {
         let mut doc_a = originals.next().expect("Originals can't be empty").clone();
 
         for doc_b in originals {
-            doc_a = self.merge_documents(base.as_ref(), &doc_a, doc_b)?;
+            doc_a = self.merge_documents(base.as_ref(), &doc_a, doc_b, &mut decisions)?;
         }
 
-        Ok(doc_a)
+        Ok((doc_a, decisions))
     }
 
     fn merge_documents(
@@ -91,121 +252,169 @@ impl MergeExpert {
         base: Option<&Document>,
         doc_a: &Document,
         doc_b: &Document,
+        decisions: &mut Vec<MergeDecision>,
     ) -> Result<Document> {
         let mut result = doc_a.clone();
 
         for field in self.schema.iter_fields(&doc_a.document_type)? {
-            let value_base = base.and_then(|base| base.data.get(field.name));
-            let value_a = doc_a.data.get(field.name);
-            let value_b = doc_b.data.get(field.name);
-
-            // handle cases when field values are equal, or when there's an explicit resolution
-            let (value_a, value_b) = match (value_base, value_a, value_b) {
-                (None, Some(value_a), None) => {
-                    result.data.set(field.name, value_a);
-                    continue;
+            let value_base = base.and_then(|base| base.data.get(&field.name));
+            let value_a = doc_a.data.get(&field.name);
+            let value_b = doc_b.data.get(&field.name);
+
+            // value_a and value_b are equal, do nothing
+            if value_a == value_b {
+                continue;
+            }
+
+            let is_list = matches!(
+                field.field_type,
+                FieldType::RefList(_) | FieldType::People {} | FieldType::Countries {}
+            );
+
+            // handle cases when only one side changed the field, or when one side removed it
+            let mut kind = if value_base == value_a {
+                if value_b.is_some() {
+                    MergeDecisionKind::TookRight
+                } else {
+                    MergeDecisionKind::RemovedField
                 }
-                (None, None, Some(value_b)) => {
-                    result.data.set(field.name, value_b);
-                    continue;
+            } else if value_base == value_b {
+                if value_a.is_some() {
+                    MergeDecisionKind::TookLeft
+                } else {
+                    MergeDecisionKind::RemovedField
                 }
-                (Some(value_base), Some(value_a), None) => {
-                    if value_base == value_a {
-                        result.data.remove(field.name);
-                    } else {
-                        result.data.set(field.name, value_a);
-                    }
-
-                    continue;
+            } else if value_b.is_none() && !is_list {
+                MergeDecisionKind::TookLeft
+            } else if value_a.is_none() && !is_list {
+                MergeDecisionKind::TookRight
+            } else {
+                match self.strategy {
+                    MergeStrategy::Auto => get_merge_strategy(field),
+                    MergeStrategy::PreferOlder => MergeDecisionKind::TookLeft,
+                    MergeStrategy::PreferNewer => MergeDecisionKind::TookRight,
                 }
-                (Some(value_base), None, Some(value_b)) => {
-                    if value_base == value_b {
-                        result.data.remove(field.name);
-                    } else {
-                        result.data.set(field.name, value_b);
-                    }
+            };
+
+            let value = match kind {
+                MergeDecisionKind::TookLeft => value_a.cloned(),
+                MergeDecisionKind::TookRight => value_b.cloned(),
+                MergeDecisionKind::RemovedField => None,
+                MergeDecisionKind::TextMerge => {
+                    let value_base = parse_optional_str(value_base, "value_base")?;
+                    let value_a = parse_optional_str(value_a, "value_a")?;
+                    let value_b = parse_optional_str(value_b, "value_b")?;
 
-                    continue;
+                    Some(merge_strings_three_way(value_base, value_a, value_b).into())
                 }
-                (_, Some(value_a), Some(value_b)) => {
-                    // value_a and value_b are equal, do nothing
-                    if value_a == value_b {
-                        continue;
+                MergeDecisionKind::MarkupMerge | MergeDecisionKind::MarkupConflict => {
+                    let value_base = parse_optional_str(value_base, "value_base")?;
+                    let value_a = parse_optional_str(value_a, "value_a")?;
+                    let value_b = parse_optional_str(value_b, "value_b")?;
+
+                    let (value, has_conflicts) =
+                        merge_markup_three_way(value_base, value_a, value_b);
+                    if has_conflicts {
+                        kind = MergeDecisionKind::MarkupConflict;
                     }
 
-                    (value_a, value_b)
+                    Some(value.into())
                 }
-                (None, None, None) | (Some(_), None, None) => {
-                    // value_a and value_b are equal, do nothing
-                    continue;
+                MergeDecisionKind::SetMerge
+                | MergeDecisionKind::OrderedSetMerge
+                | MergeDecisionKind::NamesMerge => {
+                    merge_lists(field, value_base, value_a, value_b)?
+                }
+                MergeDecisionKind::LastWriteWins => value_b.cloned(),
+                MergeDecisionKind::AllErased | MergeDecisionKind::ErasedLostToEdit => {
+                    unreachable!("document decisions aren't made per field")
                 }
             };
 
-            match field.field_type {
-                FieldType::String {}
-                | FieldType::People {}
-                | FieldType::Countries {}
-                | FieldType::MarkupString {} => {
-                    let value_base = value_base
-                        .map(|value_base| {
-                            value_base
-                                .as_str()
-                                .context("Expected value_base to be a string")
-                        })
-                        .transpose()?;
-
-                    let value_a = value_a
-                        .as_str()
-                        .context("Expected value_a to be a string")?;
-
-                    let value_b = value_b
-                        .as_str()
-                        .context("Expected value_b to be a string")?;
-
-                    let resulting_value =
-                        merge_strings_three_way(value_base.unwrap_or_default(), value_a, value_b);
-
-                    result.data.set(field.name, resulting_value);
-                }
+            match value {
+                Some(value) => result.data.set(&field.name, value),
+                None => result.data.remove(&field.name),
+            }
 
-                // Merge string arrays
-                FieldType::RefList(_) => {
-                    let value_base = value_base
-                        .map(|value_base| {
-                            parse_string_vec(value_base)
-                                .context("Failed to use value_base as Vec<&str>")
-                        })
-                        .transpose()?;
+            decisions.push(MergeDecision::field(&field.name, kind));
+        }
 
-                    let value_a =
-                        parse_string_vec(value_a).context("Failed to use value_a as Vec<&str>")?;
+        Ok(result)
+    }
+}
 
-                    let value_b =
-                        parse_string_vec(value_b).context("Failed to use value_b as Vec<&str>")?;
+fn get_merge_strategy(field: &Field) -> MergeDecisionKind {
+    match field.field_type {
+        FieldType::String {} => MergeDecisionKind::TextMerge,
+        FieldType::MarkupString {} => MergeDecisionKind::MarkupMerge,
+        FieldType::RefList(_) if field.is_ordered_ref_list() => MergeDecisionKind::OrderedSetMerge,
+        FieldType::RefList(_) => MergeDecisionKind::SetMerge,
+        FieldType::People {} | FieldType::Countries {} => MergeDecisionKind::NamesMerge,
+        FieldType::Flag {}
+        | FieldType::NaturalNumber {}
+        | FieldType::Ref(_)
+        | FieldType::Enum(_)
+        | FieldType::Date {}
+        | FieldType::Duration {} => MergeDecisionKind::LastWriteWins,
+    }
+}
 
-                    let resulting_value = merge_slices_three_way(
-                        value_base.unwrap_or_default().as_slice(),
-                        &value_a,
-                        &value_b,
-                    );
+/// Merges fields that hold lists of items: `RefList`s, and comma-separated `People` and
+/// `Countries`. A missing value is treated as an empty list, so that removal of the last item
+/// isn't lost. Returns `None` if the field should be removed.
+fn merge_lists(
+    field: &Field,
+    value_base: Option<&Value>,
+    value_a: Option<&Value>,
+    value_b: Option<&Value>,
+) -> Result<Option<Value>> {
+    if let FieldType::RefList(_) = field.field_type {
+        let value_base = parse_optional_string_vec(value_base, "value_base")?;
+        let list_a = parse_optional_string_vec(value_a, "value_a")?;
+        let list_b = parse_optional_string_vec(value_b, "value_b")?;
+
+        let resulting_value = if field.is_ordered_ref_list() {
+            merge_ordered_sets_three_way(&value_base, &list_a, &list_b)
+        } else {
+            merge_sets_three_way(&value_base, &list_a, &list_b)
+        };
+
+        if resulting_value.is_empty() && (value_a.is_none() || value_b.is_none()) {
+            return Ok(None);
+        }
 
-                    result.data.set(field.name, resulting_value);
-                }
+        return Ok(Some(resulting_value.into()));
+    }
 
-                // Last Write Wins
-                FieldType::Flag {}
-                | FieldType::NaturalNumber {}
-                | FieldType::Ref(_)
-                | FieldType::Enum(_)
-                | FieldType::Date {}
-                | FieldType::Duration {} => {
-                    result.data.set(field.name, value_b);
-                }
-            }
-        }
+    let resulting_value = merge_names_three_way(
+        parse_optional_str(value_base, "value_base")?,
+        parse_optional_str(value_a, "value_a")?,
+        parse_optional_str(value_b, "value_b")?,
+    );
 
-        Ok(result)
+    if resulting_value.is_empty() && (value_a.is_none() || value_b.is_none()) {
+        return Ok(None);
     }
+
+    Ok(Some(resulting_value.into()))
+}
+
+fn parse_optional_string_vec<'v>(value: Option<&'v Value>, name: &str) -> Result<Vec<&'v str>> {
+    let Some(value) = value else {
+        return Ok(Vec::new());
+    };
+
+    parse_string_vec(value).with_context(|| format!("Failed to use {name} as Vec<&str>"))
+}
+
+fn parse_optional_str<'v>(value: Option<&'v Value>, name: &str) -> Result<&'v str> {
+    let Some(value) = value else {
+        return Ok("");
+    };
+
+    value
+        .as_str()
+        .with_context(|| format!("Expected {name} to be a string"))
 }
 
 #[cfg(test)]
@@ -289,24 +498,35 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_merge_ref_list() {
-        let schema = DataSchema::new(
+    fn new_list_schema() -> DataSchema {
+        let field = |name: &str, field_type| Field {
+            name: name.into(),
+            field_type,
+            mandatory: false,
+            readonly: false,
+            ordered: None,
+        };
+
+        DataSchema::new(
             "test",
             vec![DataDescription {
-                document_type: "test_type",
-                title_format: "title",
-                fields: vec![Field {
-                    name: "refs",
-                    field_type: FieldType::RefList(&["*"]),
-                    mandatory: false,
-                    readonly: false,
-                }],
+                document_type: "test_type".into(),
+                title_format: "title".into(),
+                fields: vec![
+                    field("refs", FieldType::RefList(vec!["*".into()])),
+                    field("items", FieldType::RefList(vec![])),
+                    field("authors", FieldType::People {}),
+                    field("countries", FieldType::Countries {}),
+                    field("notes", FieldType::MarkupString {}),
+                ],
             }],
-        );
+        )
+    }
 
+    #[test]
+    fn test_merge_ref_list() {
         assert_schema_merge_result(
-            schema,
+            new_list_schema(),
             json!({ "refs": &["base"] }),
             vec![
                 json!({ "refs": &["first", "base"] }),
@@ -314,6 +534,130 @@ mod tests {
             ],
             json!({ "refs": &["first", "base", "second"]}),
         );
+
+        // the member removed on one device and moved on another is removed
+        assert_schema_merge_result(
+            new_list_schema(),
+            json!({ "refs": &["a", "b", "c"] }),
+            vec![
+                json!({ "refs": &["c", "a", "b"] }),
+                json!({ "refs": &["a", "b"] }),
+            ],
+            json!({ "refs": &["a", "b"]}),
+        );
+
+        // the member added on both devices is kept once
+        assert_schema_merge_result(
+            new_list_schema(),
+            json!({ "refs": &["a"] }),
+            vec![
+                json!({ "refs": &["x", "a"] }),
+                json!({ "refs": &["a", "x"] }),
+            ],
+            json!({ "refs": &["x", "a"]}),
+        );
+    }
+
+    #[test]
+    fn test_merge_unordered_ref_list() {
+        // concurrent add and remove on two devices
+        assert_schema_merge_result(
+            new_list_schema(),
+            json!({ "items": &["a", "b", "c"] }),
+            vec![
+                json!({ "items": &["a", "c", "x"] }),
+                json!({ "items": &["y", "b", "c"] }),
+            ],
+            json!({ "items": &["c", "x", "y"]}),
+        );
+
+        // removal of the last item isn't lost
+        assert_schema_merge_result(
+            new_list_schema(),
+            json!({ "items": &["a"] }),
+            vec![json!({}), json!({ "items": &["a", "b"] })],
+            json!({ "items": &["b"]}),
+        );
+        assert_schema_merge_result(
+            new_list_schema(),
+            json!({ "items": &["a"] }),
+            vec![json!({}), json!({ "items": &["a"] })],
+            json!({}),
+        );
+    }
+
+    #[test]
+    fn test_merge_people_and_countries() {
+        assert_schema_merge_result(
+            new_list_schema(),
+            json!({
+                "authors": "Terry Pratchett, Neil Gaiman",
+                "countries": "Ukraine",
+            }),
+            vec![
+                json!({
+                    "authors": "Terry Pratchett, Neil Gaiman, Ursula K. Le Guin",
+                    "countries": "Ukraine, Poland",
+                }),
+                json!({
+                    "authors": "Neil Gaiman",
+                    "countries": "",
+                }),
+            ],
+            json!({
+                "authors": "Neil Gaiman, Ursula K. Le Guin",
+                "countries": "Poland",
+            }),
+        );
+    }
+
+    #[test]
+    fn test_merge_markup() {
+        let expert = MergeExpert::new(new_list_schema());
+
+        let base = new_document(json!({ "notes": "The quick brown fox.\n\nThe lazy dog." }));
+        let left = base
+            .clone()
+            .with_data(json!({ "notes": "The quick red fox.\n\nThe lazy dog." }));
+        let right = base
+            .clone()
+            .with_data(json!({ "notes": "The quick brown fox.\n\nThe sleepy dog." }));
+        let conflicting = base
+            .clone()
+            .with_data(json!({ "notes": "The quick grey fox.\n\nThe lazy dog." }));
+
+        let (result, decisions) = expert
+            .merge_originals_with_decisions(Some(base.clone()), vec![&left, &right])
+            .unwrap();
+        assert_eq!(
+            result.data,
+            new_document(json!({ "notes": "The quick red fox.\n\nThe sleepy dog." })).data
+        );
+        assert_eq!(
+            decisions,
+            vec![MergeDecision::field(
+                "notes",
+                MergeDecisionKind::MarkupMerge
+            )]
+        );
+
+        let (result, decisions) = expert
+            .merge_originals_with_decisions(Some(base), vec![&left, &conflicting])
+            .unwrap();
+        assert_eq!(
+            result.data,
+            new_document(json!({
+                "notes": "The quick <<<<<<< red ======= grey >>>>>>> fox.\n\nThe lazy dog."
+            }))
+            .data
+        );
+        assert_eq!(
+            decisions,
+            vec![MergeDecision::field(
+                "notes",
+                MergeDecisionKind::MarkupConflict
+            )]
+        );
     }
 
     #[test]
@@ -365,4 +709,75 @@ mod tests {
             json!({ "test": "base left right"}),
         );
     }
+
+    #[test]
+    fn test_merge_decisions() {
+        let expert = MergeExpert::new(new_list_schema());
+
+        let base = new_document(json!({ "refs": ["a"], "authors": "Terry Pratchett" }));
+        let left = base.clone().with_data(json!({
+            "refs": ["a", "b"],
+            "authors": "Terry Pratchett",
+            "items": ["x"],
+        }));
+        let right = base.clone().with_data(json!({ "refs": ["c", "a"] }));
+        let erased = base.clone().with_data(Value::Null);
+
+        let (_, decisions) = expert
+            .merge_originals_with_decisions(Some(base.clone()), vec![&left, &right, &erased])
+            .unwrap();
+
+        assert_eq!(
+            decisions,
+            vec![
+                MergeDecision::document(MergeDecisionKind::ErasedLostToEdit),
+                MergeDecision::field("refs", MergeDecisionKind::OrderedSetMerge),
+                MergeDecision::field("items", MergeDecisionKind::TookLeft),
+                MergeDecision::field("authors", MergeDecisionKind::RemovedField),
+            ]
+        );
+
+        let (_, decisions) = expert
+            .merge_originals_with_decisions(Some(base), vec![&erased, &erased.clone()])
+            .unwrap();
+        assert_eq!(
+            decisions,
+            vec![MergeDecision::document(MergeDecisionKind::AllErased)]
+        );
+    }
+
+    #[test]
+    fn test_merge_strategy() {
+        let base = new_document(json!({ "test": "base", "ref": "base" }));
+        let older = base
+            .clone()
+            .with_data(json!({ "test": "base left", "ref": "base" }));
+        let newer = base
+            .clone()
+            .with_data(json!({ "test": "base right", "ref": "newer" }));
+
+        let merge = |strategy| {
+            MergeExpert::new(DataSchema::new_test_schema())
+                .with_strategy(strategy)
+                .merge_originals(Some(base.clone()), vec![&older, &newer])
+                .unwrap()
+                .data
+        };
+
+        // one-sided changes are kept with any strategy
+        assert_eq!(
+            merge(MergeStrategy::PreferOlder),
+            new_document(json!({ "test": "base left", "ref": "newer" })).data
+        );
+        assert_eq!(
+            merge(MergeStrategy::PreferNewer),
+            new_document(json!({ "test": "base right", "ref": "newer" })).data
+        );
+
+        assert_eq!(
+            "prefer-newer".parse::<MergeStrategy>().unwrap(),
+            MergeStrategy::PreferNewer
+        );
+        assert!("theirs".parse::<MergeStrategy>().is_err());
+    }
 }
@@ -0,0 +1,330 @@
+use similar::{Algorithm, DiffTag, DiffableStr, TextDiff};
+
+use crate::markup::MarkupStr;
+
+use super::{DEFAULT_MERGE_TIMEOUT, should_add_whitespace};
+
+const CONFLICT_MARKER_LEFT: &str = "<<<<<<< ";
+const CONFLICT_MARKER_SEPARATOR: &str = " ======= ";
+const CONFLICT_MARKER_RIGHT: &str = " >>>>>>>";
+
+/// A change of the base `[start, end)` items into the `items`.
+struct Hunk<'s> {
+    start: usize,
+    end: usize,
+    items: Vec<&'s str>,
+}
+
+impl Hunk<'_> {
+    fn is_insert(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+fn get_hunks<'s>(base: &[&'s str], other: &[&'s str]) -> Vec<Hunk<'s>> {
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .timeout(DEFAULT_MERGE_TIMEOUT)
+        .diff_slices(base, other);
+
+    diff.ops()
+        .iter()
+        .map(|op| op.as_tag_tuple())
+        .filter(|(tag, _, _)| *tag != DiffTag::Equal)
+        .map(|(_, base_range, other_range)| Hunk {
+            start: base_range.start,
+            end: base_range.end,
+            items: other[other_range].to_vec(),
+        })
+        .collect()
+}
+
+/// Applies the hunks of one side to the base `[start, end)` items.
+fn apply_hunks<'s>(
+    base: &[&'s str],
+    start: usize,
+    end: usize,
+    hunks: &[&Hunk<'s>],
+) -> Vec<&'s str> {
+    let mut result = Vec::new();
+
+    let mut pos = start;
+    for hunk in hunks {
+        result.extend_from_slice(&base[pos..hunk.start]);
+        result.extend_from_slice(&hunk.items);
+        pos = hunk.end;
+    }
+    result.extend_from_slice(&base[pos..end]);
+
+    result
+}
+
+enum Chunk<'s> {
+    Resolved(Vec<&'s str>),
+    Conflict {
+        base: Vec<&'s str>,
+        left: Vec<&'s str>,
+        right: Vec<&'s str>,
+    },
+}
+
+/// Three-way merge of the sequences: changes of different base items are combined, changes of
+/// the same base items on both sides are returned as conflicts.
+fn diff3<'s>(base: &[&'s str], left: &[&'s str], right: &[&'s str]) -> Vec<Chunk<'s>> {
+    let mut hunks = get_hunks(base, left)
+        .into_iter()
+        .map(|hunk| (true, hunk))
+        .chain(get_hunks(base, right).into_iter().map(|hunk| (false, hunk)))
+        .collect::<Vec<_>>();
+    hunks.sort_by_key(|(_, hunk)| (hunk.start, hunk.end));
+
+    let mut chunks = Vec::new();
+    let mut pos = 0;
+
+    let mut hunks = hunks.iter().peekable();
+    while let Some((is_left, hunk)) = hunks.next() {
+        let start = hunk.start;
+        let mut end = hunk.end;
+        let mut group = vec![(*is_left, hunk)];
+
+        // collect hunks that change the same base items, or insert at the same position
+        while let Some((is_left, next)) = hunks.peek() {
+            let overlaps = next.start < end;
+            let same_insert = next.is_insert() && start == end && next.start == start;
+            if !overlaps && !same_insert {
+                break;
+            }
+
+            end = end.max(next.end);
+            group.push((*is_left, next));
+            hunks.next();
+        }
+
+        if pos < start {
+            chunks.push(Chunk::Resolved(base[pos..start].to_vec()));
+        }
+        pos = end;
+
+        let left_hunks = group
+            .iter()
+            .filter(|(is_left, _)| *is_left)
+            .map(|(_, hunk)| *hunk)
+            .collect::<Vec<_>>();
+        let right_hunks = group
+            .iter()
+            .filter(|(is_left, _)| !*is_left)
+            .map(|(_, hunk)| *hunk)
+            .collect::<Vec<_>>();
+
+        let left_items = apply_hunks(base, start, end, &left_hunks);
+        let right_items = apply_hunks(base, start, end, &right_hunks);
+
+        if left_hunks.is_empty() {
+            chunks.push(Chunk::Resolved(right_items));
+        } else if right_hunks.is_empty() || left_items == right_items {
+            chunks.push(Chunk::Resolved(left_items));
+        } else {
+            chunks.push(Chunk::Conflict {
+                base: base[start..end].to_vec(),
+                left: left_items,
+                right: right_items,
+            });
+        }
+    }
+
+    if pos < base.len() {
+        chunks.push(Chunk::Resolved(base[pos..].to_vec()));
+    }
+
+    chunks
+}
+
+/// Word-level three-way merge of a block of markup. Returns `true` if the same words were
+/// changed on both sides, and conflict markers were added.
+fn merge_words(result: &mut String, base: &str, left: &str, right: &str) -> bool {
+    let base_tokens = base.tokenize_unicode_words();
+    let left_tokens = left.tokenize_unicode_words();
+    let right_tokens = right.tokenize_unicode_words();
+
+    let mut has_conflicts = false;
+
+    for chunk in diff3(&base_tokens, &left_tokens, &right_tokens) {
+        match chunk {
+            Chunk::Resolved(tokens) => result.extend(tokens),
+
+            // both sides inserted different words at the same position, keep both
+            Chunk::Conflict { base, left, right } if base.is_empty() => {
+                let left = left.concat();
+                let right = right.concat();
+
+                result.push_str(&left);
+                if should_add_whitespace(&left, &right) {
+                    result.push(' ');
+                }
+                result.push_str(&right);
+            }
+
+            Chunk::Conflict { left, right, .. } => {
+                has_conflicts = true;
+
+                // keep surrounding whitespace outside of the markers
+                let left = left.concat();
+                let right = right.concat();
+                let leading = common_whitespace_prefix_len(&left, &right);
+                let trailing = common_whitespace_suffix_len(&left[leading..], &right[leading..]);
+
+                result.push_str(&left[..leading]);
+                result.push_str(CONFLICT_MARKER_LEFT);
+                result.push_str(left[leading..left.len() - trailing].trim());
+                result.push_str(CONFLICT_MARKER_SEPARATOR);
+                result.push_str(right[leading..right.len() - trailing].trim());
+                result.push_str(CONFLICT_MARKER_RIGHT);
+                result.push_str(&left[left.len() - trailing..]);
+            }
+        }
+    }
+
+    has_conflicts
+}
+
+fn common_whitespace_prefix_len(a: &str, b: &str) -> usize {
+    a.chars()
+        .zip(b.chars())
+        .take_while(|(a, b)| a == b && a.is_whitespace())
+        .map(|(a, _)| a.len_utf8())
+        .sum()
+}
+
+fn common_whitespace_suffix_len(a: &str, b: &str) -> usize {
+    a.chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(a, b)| a == b && a.is_whitespace())
+        .map(|(a, _)| a.len_utf8())
+        .sum()
+}
+
+/// Markup-aware three-way merge: blocks, like paragraphs or list items, changed on one side are
+/// taken as is, and blocks changed on both sides are merged word by word. When both sides
+/// changed the same words, both versions are kept between the conflict markers.
+/// Returns the merged markup, and `true` if there are conflict markers.
+pub fn merge_markup_three_way(base: &str, left: &str, right: &str) -> (String, bool) {
+    let base_markup = MarkupStr::from(base);
+    let left_markup = MarkupStr::from(left);
+    let right_markup = MarkupStr::from(right);
+
+    let base_blocks = base_markup.split_blocks();
+    let left_blocks = left_markup.split_blocks();
+    let right_blocks = right_markup.split_blocks();
+
+    let mut result = String::with_capacity(base.len().max(left.len()).max(right.len()));
+    let mut has_conflicts = false;
+
+    for chunk in diff3(&base_blocks, &left_blocks, &right_blocks) {
+        match chunk {
+            Chunk::Resolved(blocks) => result.extend(blocks),
+
+            // the same blocks were changed on both sides, merge them one by one if possible
+            Chunk::Conflict { base, left, right }
+                if base.len() == left.len() && base.len() == right.len() =>
+            {
+                for ((base, left), right) in base.into_iter().zip(left).zip(right) {
+                    has_conflicts |= merge_words(&mut result, base, left, right);
+                }
+            }
+
+            Chunk::Conflict { base, left, right } => {
+                has_conflicts |=
+                    merge_words(&mut result, &base.concat(), &left.concat(), &right.concat());
+            }
+        }
+    }
+
+    (result, has_conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_markup_three_way;
+
+    fn merge(base: &str, left: &str, right: &str) -> String {
+        let (result, has_conflicts) = merge_markup_three_way(base, left, right);
+        assert_eq!(result.contains("<<<<<<<"), has_conflicts);
+
+        result
+    }
+
+    #[test]
+    fn test_merge_different_blocks() {
+        assert_eq!(
+            merge(
+                "# Title\n\nFirst paragraph.\n\nSecond paragraph.\n",
+                "# Title\n\nFirst paragraph, edited.\n\nSecond paragraph.\n",
+                "# Title\n\nFirst paragraph.\n\nSecond paragraph, edited.\n\nThird paragraph.\n",
+            ),
+            "# Title\n\nFirst paragraph, edited.\n\nSecond paragraph, edited.\n\nThird paragraph.\n"
+        );
+
+        // one side removed the block
+        assert_eq!(
+            merge(
+                "First.\n\nSecond.\n\nThird.",
+                "First.\n\nThird.",
+                "First!\n\nSecond.\n\nThird.",
+            ),
+            "First!\n\nThird."
+        );
+    }
+
+    #[test]
+    fn test_merge_different_sentences_of_paragraph() {
+        assert_eq!(
+            merge(
+                "The quick brown fox. It jumps over the lazy dog.\n\nThe end.",
+                "The quick red fox. It jumps over the lazy dog.\n\nThe end.",
+                "The quick brown fox. It jumps over the sleepy dog.\n\nThe end.",
+            ),
+            "The quick red fox. It jumps over the sleepy dog.\n\nThe end."
+        );
+
+        // insertions at the same position are kept
+        assert_eq!(
+            merge("Some text", "Some long text", "Some good text"),
+            "Some long good text"
+        );
+    }
+
+    #[test]
+    fn test_merge_conflicting_words() {
+        assert_eq!(
+            merge(
+                "The quick brown fox.\n\nThe end.",
+                "The quick red fox.\n\nThe end.",
+                "The quick grey fox.\n\nThe end!",
+            ),
+            "The quick <<<<<<< red ======= grey >>>>>>> fox.\n\nThe end!"
+        );
+
+        // the same change on both sides isn't a conflict
+        assert_eq!(
+            merge(
+                "The quick brown fox.",
+                "The quick red fox.",
+                "The quick red fox."
+            ),
+            "The quick red fox."
+        );
+    }
+
+    #[test]
+    fn test_merge_list_items() {
+        assert_eq!(
+            merge(
+                "- [ ] milk\n- [ ] bread\n",
+                "- [x] milk\n- [ ] bread\n",
+                "- [ ] milk\n- [ ] bread\n- [ ] eggs\n",
+            ),
+            "- [x] milk\n- [ ] bread\n- [ ] eggs\n"
+        );
+    }
+}
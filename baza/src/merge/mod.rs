@@ -1,11 +1,16 @@
 mod document_diff;
 mod expert;
+mod markup;
 
-pub use document_diff::{DocumentDataDiff, diff_document_data};
+pub use document_diff::{
+    DocumentDataDiff, FieldChange, FieldChangeKind, diff_document_data, diff_document_fields,
+};
 pub(crate) use expert::MergeExpert;
+pub use expert::{MergeDecision, MergeDecisionKind, MergeStrategy};
+pub use markup::merge_markup_three_way;
 
 use core::fmt;
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
 use similar::{Algorithm, DiffOp, DiffTag, DiffableStr, TextDiff};
 
@@ -371,6 +376,71 @@ pub fn merge_slices_three_way<'s>(
     merger.result
 }
 
+/// Merges lists of unique items, like tag members, as sets: items added by any side are kept,
+/// items removed by any side are dropped. Keeps the order of the left side, with the right side
+/// additions appended.
+pub fn merge_sets_three_way<'s>(
+    base: &[&'s str],
+    left: &[&'s str],
+    right: &[&'s str],
+) -> Vec<&'s str> {
+    let base = base.iter().copied().collect::<HashSet<_>>();
+    let left_set = left.iter().copied().collect::<HashSet<_>>();
+    let right_set = right.iter().copied().collect::<HashSet<_>>();
+
+    let mut seen = HashSet::new();
+
+    left.iter()
+        .copied()
+        .filter(|value| !base.contains(value) || right_set.contains(value))
+        .chain(
+            right
+                .iter()
+                .copied()
+                .filter(|value| !base.contains(value) && !left_set.contains(value)),
+        )
+        .filter(|value| seen.insert(*value))
+        .collect()
+}
+
+/// Merges ordered lists of unique items, like collection members. Same as
+/// `merge_slices_three_way`, but items removed by any side are dropped even if the other side moved
+/// them, and items added or moved by both sides are kept only once.
+pub fn merge_ordered_sets_three_way<'s>(
+    base: &[&'s str],
+    left: &[&'s str],
+    right: &[&'s str],
+) -> Vec<&'s str> {
+    let left_set = left.iter().copied().collect::<HashSet<_>>();
+    let right_set = right.iter().copied().collect::<HashSet<_>>();
+
+    let removed = base
+        .iter()
+        .copied()
+        .filter(|value| !left_set.contains(value) || !right_set.contains(value))
+        .collect::<HashSet<_>>();
+
+    let mut seen = HashSet::new();
+
+    merge_slices_three_way(base, left, right)
+        .into_iter()
+        .filter(|value| !removed.contains(value) && seen.insert(*value))
+        .collect()
+}
+
+fn split_names(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Merges comma-separated lists of names, like `People` or `Countries`, name by name.
+pub fn merge_names_three_way(base: &str, left: &str, right: &str) -> String {
+    merge_sets_three_way(&split_names(base), &split_names(left), &split_names(right)).join(", ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -604,4 +674,87 @@ mod tests {
             &["a", "x", "y", "p", "q", "c"]
         );
     }
+
+    #[test]
+    fn test_merge_sets_three_way() {
+        // concurrent additions on both sides
+        assert_eq!(
+            merge_sets_three_way(&["a", "b"], &["a", "b", "c"], &["d", "a", "b"]),
+            &["a", "b", "c", "d"]
+        );
+
+        // removal on one side, addition on the other
+        assert_eq!(
+            merge_sets_three_way(&["a", "b", "c"], &["a", "c"], &["a", "b", "c", "d"]),
+            &["a", "c", "d"]
+        );
+        assert_eq!(
+            merge_sets_three_way(&["a", "b", "c"], &["a", "b", "c", "d"], &["a", "c"]),
+            &["a", "c", "d"]
+        );
+
+        // the same item added on both sides
+        assert_eq!(
+            merge_sets_three_way(&["a"], &["a", "x"], &["x", "a"]),
+            &["a", "x"]
+        );
+
+        // both sides removed different items
+        assert_eq!(
+            merge_sets_three_way(&["a", "b", "c"], &["b", "c"], &["a", "b"]),
+            &["b"]
+        );
+    }
+
+    #[test]
+    fn test_merge_ordered_sets_three_way() {
+        // additions keep their positions
+        assert_eq!(
+            merge_ordered_sets_three_way(
+                &["a", "b", "c"],
+                &["x", "a", "b", "c"],
+                &["a", "b", "y", "c"]
+            ),
+            &["x", "a", "b", "y", "c"]
+        );
+
+        // the item moved on one side and removed on the other is removed
+        assert_eq!(
+            merge_ordered_sets_three_way(&["a", "b", "c"], &["c", "a", "b"], &["a", "b"]),
+            &["a", "b"]
+        );
+
+        // the item moved on one side keeps the new position
+        assert_eq!(
+            merge_ordered_sets_three_way(&["a", "b", "c"], &["c", "a", "b"], &["a", "b", "c", "d"]),
+            &["c", "a", "b", "d"]
+        );
+
+        // the same item added on both sides at different positions
+        assert_eq!(
+            merge_ordered_sets_three_way(&["a", "b"], &["x", "a", "b"], &["a", "b", "x"]),
+            &["x", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_merge_names_three_way() {
+        assert_eq!(
+            merge_names_three_way(
+                "Ursula K. Le Guin, Terry Pratchett",
+                "Ursula K. Le Guin,Terry Pratchett, Neil Gaiman",
+                "Ursula K. Le Guin",
+            ),
+            "Ursula K. Le Guin, Neil Gaiman"
+        );
+
+        assert_eq!(
+            merge_names_three_way("", "Ukraine", "Poland, Ukraine"),
+            "Ukraine, Poland"
+        );
+        assert_eq!(
+            merge_names_three_way("Ukraine", "", "Ukraine, Poland"),
+            "Poland"
+        );
+    }
 }
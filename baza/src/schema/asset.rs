@@ -14,38 +14,106 @@ pub const ASSET_TYPE: &str = "asset";
 
 pub fn get_asset_definition() -> DataDescription {
     DataDescription {
-        document_type: ASSET_TYPE,
-        title_format: "${filename}",
+        document_type: ASSET_TYPE.into(),
+        title_format: "${filename}".into(),
         fields: vec![
             Field {
-                name: "filename",
+                name: "filename".into(),
                 field_type: FieldType::String {},
                 mandatory: true,
                 readonly: false,
+                ordered: None,
             },
             Field {
-                name: "media_type",
+                name: "media_type".into(),
                 field_type: FieldType::String {},
                 mandatory: true,
                 readonly: false,
+                ordered: None,
             },
             Field {
-                name: "size", // in bytes
+                name: "size".into(), // in bytes
                 field_type: FieldType::NaturalNumber {},
                 mandatory: true,
                 readonly: true,
+                ordered: None,
             },
             Field {
-                name: "content_sha256",
+                name: "content_sha256".into(),
                 field_type: FieldType::String {},
                 mandatory: true,
                 readonly: true,
+                ordered: None,
             },
             Field {
-                name: "age_x25519_key",
+                name: "age_x25519_key".into(),
                 field_type: FieldType::String {},
                 mandatory: true,
                 readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "width".into(), // in pixels
+                field_type: FieldType::NaturalNumber {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "height".into(), // in pixels
+                field_type: FieldType::NaturalNumber {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "taken_at".into(),
+                field_type: FieldType::Date {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "gps".into(), // latitude,longitude
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "duration".into(), // h:mm:ss
+                field_type: FieldType::Duration {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "artist".into(),
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "title".into(),
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "album".into(),
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "page_count".into(),
+                field_type: FieldType::NaturalNumber {},
+                mandatory: false,
+                readonly: true,
+                ordered: None,
             },
         ],
     }
@@ -67,9 +135,56 @@ pub struct AssetData {
     pub content_sha256: String,
     #[serde(serialize_with = "expose_secret_string")]
     pub age_x25519_key: SecretString,
+
+    // metadata extracted from the file when the asset is created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gps: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u64>,
 }
 
 impl AssetData {
+    /// Creates asset data without the file metadata.
+    #[must_use]
+    pub fn new(
+        filename: String,
+        media_type: String,
+        size: u64,
+        content_sha256: String,
+        age_x25519_key: SecretString,
+    ) -> Self {
+        AssetData {
+            filename,
+            media_type,
+            size,
+            content_sha256,
+            age_x25519_key,
+            width: None,
+            height: None,
+            taken_at: None,
+            gps: None,
+            duration: None,
+            artist: None,
+            title: None,
+            album: None,
+            page_count: None,
+        }
+    }
+
     #[must_use]
     pub fn is_image(&self) -> bool {
         self.media_type.starts_with("image/")
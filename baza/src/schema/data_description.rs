@@ -1,12 +1,12 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use super::field::Field;
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct DataDescription {
-    pub document_type: &'static str,
+    pub document_type: String,
     pub fields: Vec<Field>,
-    pub title_format: &'static str, // https://docs.rs/tinytemplate/latest/tinytemplate/syntax/
+    pub title_format: String, // https://docs.rs/tinytemplate/latest/tinytemplate/syntax/
 }
 
 impl DataDescription {
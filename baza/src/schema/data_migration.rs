@@ -0,0 +1,340 @@
+use anyhow::{Context, Result, bail, ensure};
+use serde_json::Value;
+
+use crate::entities::{Document, DocumentData};
+
+use super::FieldType;
+
+/// A declarative data migration. It is applied to every document snapshot in the storage when
+/// the storage is upgraded to `data_version`.
+#[derive(Debug, Clone)]
+pub struct DataMigration {
+    pub data_version: u8,
+    pub description: String,
+    pub types: Vec<TypeMigration>,
+}
+
+/// Field transformations for the documents of a single type, applied in order.
+#[derive(Debug, Clone)]
+pub struct TypeMigration {
+    pub document_type: String,
+    pub fields: Vec<FieldMigration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldMigration {
+    Rename {
+        field: String,
+        new_field: String,
+    },
+    /// Converts the field value into a value of the new field type.
+    Coerce {
+        field: String,
+        field_type: FieldType,
+    },
+    /// Replaces enum values, `(old value, new value)`.
+    RemapEnum {
+        field: String,
+        values: Vec<(String, String)>,
+    },
+    Drop {
+        field: String,
+    },
+}
+
+impl DataMigration {
+    /// Applies the migration to the document, and returns names of the changed fields.
+    pub fn apply(&self, document: &mut Document) -> Result<Vec<String>> {
+        let mut changed_fields = Vec::new();
+
+        let type_migrations = self
+            .types
+            .iter()
+            .filter(|type_migration| document.document_type.is(&type_migration.document_type));
+
+        for type_migration in type_migrations {
+            for field_migration in &type_migration.fields {
+                let changed = field_migration.apply(&mut document.data).with_context(|| {
+                    format!(
+                        "Failed to migrate field '{}' of document {} to data version {}",
+                        field_migration.get_field(),
+                        document.id,
+                        self.data_version
+                    )
+                })?;
+
+                if changed {
+                    changed_fields.push(field_migration.get_field().to_string());
+                }
+            }
+        }
+
+        Ok(changed_fields)
+    }
+}
+
+impl FieldMigration {
+    #[must_use]
+    pub fn get_field(&self) -> &str {
+        match self {
+            FieldMigration::Rename { field, .. }
+            | FieldMigration::Coerce { field, .. }
+            | FieldMigration::RemapEnum { field, .. }
+            | FieldMigration::Drop { field } => field,
+        }
+    }
+
+    fn apply(&self, data: &mut DocumentData) -> Result<bool> {
+        let Some(value) = data.get(self.get_field()) else {
+            return Ok(false);
+        };
+
+        match self {
+            FieldMigration::Rename { field, new_field } => {
+                ensure!(
+                    data.get(new_field).is_none(),
+                    "field '{new_field}' already exists"
+                );
+
+                data.rename(field, new_field);
+            }
+            FieldMigration::Coerce { field, field_type } => {
+                let new_value = coerce_value(value, field_type)?;
+                if &new_value == value {
+                    return Ok(false);
+                }
+
+                data.set(field, new_value);
+            }
+            FieldMigration::RemapEnum { field, values } => {
+                let Some(new_value) = values
+                    .iter()
+                    .find(|(old_value, _)| value.as_str() == Some(old_value))
+                    .map(|(_, new_value)| new_value.clone())
+                else {
+                    return Ok(false);
+                };
+
+                data.set(field, new_value);
+            }
+            FieldMigration::Drop { field } => {
+                data.remove(field);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+fn value_to_string(value: &Value) -> Result<String> {
+    let result = match value {
+        Value::String(value) => value.clone(),
+        Value::Number(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(ToString::to_string)
+                    .context("expected an array of strings")
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join(", "),
+        _ => bail!("can't convert {value} into a string"),
+    };
+
+    Ok(result)
+}
+
+fn coerce_value(value: &Value, field_type: &FieldType) -> Result<Value> {
+    let result = match (field_type, value) {
+        (
+            FieldType::String {}
+            | FieldType::MarkupString {}
+            | FieldType::Date {}
+            | FieldType::Duration {}
+            | FieldType::People {}
+            | FieldType::Countries {},
+            value,
+        ) => Value::String(value_to_string(value)?),
+
+        (FieldType::NaturalNumber {}, Value::Number(number)) if number.is_u64() => value.clone(),
+        (FieldType::NaturalNumber {}, Value::String(value)) => {
+            let number: u64 = value
+                .trim()
+                .parse()
+                .with_context(|| format!("can't convert '{value}' into a natural number"))?;
+
+            number.into()
+        }
+
+        (FieldType::Flag {}, Value::Bool(_)) => value.clone(),
+        (FieldType::Flag {}, Value::String(value)) => match value.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" => true.into(),
+            "false" | "no" | "0" | "" => false.into(),
+            _ => bail!("can't convert '{value}' into a flag"),
+        },
+        (FieldType::Flag {}, Value::Number(number)) => match number.as_u64() {
+            Some(0) => false.into(),
+            Some(1) => true.into(),
+            _ => bail!("can't convert {number} into a flag"),
+        },
+
+        (FieldType::Ref(_), Value::String(_)) => value.clone(),
+        (FieldType::Ref(_), Value::Array(items)) => match items.as_slice() {
+            [] => Value::String(String::new()),
+            [item] if item.is_string() => item.clone(),
+            _ => bail!("can't convert {value} into a single ref"),
+        },
+
+        (FieldType::RefList(_), Value::String(item)) if item.is_empty() => Value::Array(vec![]),
+        (FieldType::RefList(_), Value::String(_)) => Value::Array(vec![value.clone()]),
+        (FieldType::RefList(_), Value::Array(_)) => value.clone(),
+
+        (FieldType::Enum(options), Value::String(option)) => {
+            ensure!(
+                option.is_empty() || options.contains(option),
+                "'{option}' isn't one of {}",
+                options.join(", ")
+            );
+
+            value.clone()
+        }
+
+        (field_type, value) => bail!("can't convert {value} into {field_type:?}"),
+    };
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        entities::{DocumentType, new_document},
+        schema::{DataMigration, FieldMigration, FieldType, TypeMigration},
+    };
+
+    fn new_migration(fields: Vec<FieldMigration>) -> DataMigration {
+        DataMigration {
+            data_version: 3,
+            description: "test".into(),
+            types: vec![TypeMigration {
+                document_type: "test_type".into(),
+                fields,
+            }],
+        }
+    }
+
+    fn coerce(field_type: FieldType) -> DataMigration {
+        new_migration(vec![FieldMigration::Coerce {
+            field: "value".into(),
+            field_type,
+        }])
+    }
+
+    #[test]
+    fn test_rename_and_drop_fields() {
+        let migration = new_migration(vec![
+            FieldMigration::Rename {
+                field: "old".into(),
+                new_field: "new".into(),
+            },
+            FieldMigration::Drop {
+                field: "legacy".into(),
+            },
+            FieldMigration::Drop {
+                field: "missing".into(),
+            },
+        ]);
+
+        let mut document = new_document(json!({ "old": "value", "legacy": 1 }));
+        let changed_fields = migration.apply(&mut document).unwrap();
+
+        assert_eq!(changed_fields, vec!["old", "legacy"]);
+        assert_eq!(document.data, new_document(json!({ "new": "value" })).data);
+
+        let mut document = new_document(json!({ "old": "value", "new": "other" }));
+        assert!(migration.apply(&mut document).is_err());
+    }
+
+    #[test]
+    fn test_ignores_other_document_types() {
+        let migration = new_migration(vec![FieldMigration::Drop {
+            field: "test".into(),
+        }]);
+
+        let mut document = new_document(json!({ "test": "value" }));
+        document.document_type = DocumentType::new("other_type");
+
+        assert!(migration.apply(&mut document).unwrap().is_empty());
+        assert_eq!(document.data.get_str("test"), Some("value"));
+    }
+
+    #[test]
+    fn test_coerce_fields() {
+        let cases = [
+            (FieldType::String {}, json!(42), json!("42")),
+            (FieldType::People {}, json!(["a", "b"]), json!("a, b")),
+            (FieldType::NaturalNumber {}, json!(" 7 "), json!(7)),
+            (FieldType::Flag {}, json!("yes"), json!(true)),
+            (FieldType::Flag {}, json!(0), json!(false)),
+            (FieldType::RefList(vec![]), json!("id"), json!(["id"])),
+            (FieldType::RefList(vec![]), json!(""), json!([])),
+            (FieldType::Ref(vec![]), json!(["id"]), json!("id")),
+            (FieldType::Enum(vec!["a".into()]), json!("a"), json!("a")),
+        ];
+
+        for (field_type, value, expected) in cases {
+            let mut document = new_document(json!({ "value": value }));
+            coerce(field_type.clone()).apply(&mut document).unwrap();
+
+            assert_eq!(
+                document.data.get("value"),
+                Some(&expected),
+                "coerce {value} into {field_type:?}"
+            );
+        }
+
+        let mut document = new_document(json!({ "value": "42" }));
+        let changed_fields = coerce(FieldType::String {}).apply(&mut document).unwrap();
+        assert!(changed_fields.is_empty());
+    }
+
+    #[test]
+    fn test_coerce_fails_on_invalid_values() {
+        let cases = [
+            (FieldType::NaturalNumber {}, json!("-1")),
+            (FieldType::Flag {}, json!("maybe")),
+            (FieldType::Ref(vec![]), json!(["a", "b"])),
+            (FieldType::Enum(vec!["a".into()]), json!("b")),
+            (FieldType::String {}, json!({ "a": 1 })),
+        ];
+
+        for (field_type, value) in cases {
+            let mut document = new_document(json!({ "value": value }));
+
+            assert!(
+                coerce(field_type.clone()).apply(&mut document).is_err(),
+                "coerce {value} into {field_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_remap_enum_values() {
+        let migration = new_migration(vec![FieldMigration::RemapEnum {
+            field: "status".into(),
+            values: vec![("Todo".into(), "Inbox".into())],
+        }]);
+
+        let mut document = new_document(json!({ "status": "Todo" }));
+        assert_eq!(migration.apply(&mut document).unwrap(), vec!["status"]);
+        assert_eq!(document.data.get_str("status"), Some("Inbox"));
+
+        let mut document = new_document(json!({ "status": "Done" }));
+        assert!(migration.apply(&mut document).unwrap().is_empty());
+        assert_eq!(document.data.get_str("status"), Some("Done"));
+    }
+}
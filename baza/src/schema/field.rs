@@ -1,39 +1,42 @@
 use std::collections::HashSet;
 
 use anyhow::{Result, anyhow, bail};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
     entities::{DocumentType, Id},
-    markup::MarkupStr,
+    markup::{MarkupStr, replace_ref},
     schema::ASSET_TYPE,
 };
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum FieldType {
     String {},        // string
     MarkupString {},  // string
     Flag {},          // bool
     NaturalNumber {}, // u64
     // DocumentType[], empty array means any document type
-    Ref(&'static [&'static str]), // string
+    Ref(Vec<String>), // string
     // DocumentType[], empty array means any document type
-    RefList(&'static [&'static str]), // string[]
+    RefList(Vec<String>), // string[]
     // string[], possible enum values
-    Enum(&'static [&'static str]), // string
-    Date {},                       // string
-    Duration {},                   // string
-    People {},                     // string
-    Countries {},                  // string
+    Enum(Vec<String>), // string
+    Date {},           // string
+    Duration {},       // string
+    People {},         // string
+    Countries {},      // string
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Field {
-    pub name: &'static str,
+    pub name: String,
     pub field_type: FieldType,
     pub mandatory: bool,
     pub readonly: bool,
+    /// If the order of the `RefList` items matters, see [`Field::is_ordered_ref_list`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ordered: Option<bool>,
 }
 
 impl Field {
@@ -51,15 +54,37 @@ impl Field {
         )
     }
 
+    /// If the field value is indexed by the full-text search engine.
+    #[must_use]
+    pub fn is_searchable(&self) -> bool {
+        matches!(
+            self.field_type,
+            FieldType::String {} | FieldType::MarkupString {} | FieldType::People {}
+        )
+    }
+
+    /// If the order of the `RefList` items matters. Unless the field sets `ordered`, lists
+    /// restricted to specific document types, like books of a collection, are ordered, and
+    /// unrestricted lists, like tag items, are sets.
+    #[must_use]
+    pub fn is_ordered_ref_list(&self) -> bool {
+        let FieldType::RefList(types) = &self.field_type else {
+            return false;
+        };
+
+        self.ordered.unwrap_or(!types.is_empty())
+    }
+
     #[must_use]
     pub fn could_be_cover(&self) -> bool {
-        matches!(self.field_type, FieldType::Ref(&[ASSET_TYPE])) && self.name == "cover"
+        matches!(&self.field_type, FieldType::Ref(types) if types == &[ASSET_TYPE])
+            && self.name == "cover"
     }
 
     #[must_use]
     pub fn could_ref_assets(&self) -> bool {
-        match self.field_type {
-            FieldType::Ref(a) | FieldType::RefList(a) => a.contains(&ASSET_TYPE),
+        match &self.field_type {
+            FieldType::Ref(a) | FieldType::RefList(a) => a.iter().any(|item| item == ASSET_TYPE),
             _ => false,
         }
     }
@@ -102,19 +127,66 @@ impl Field {
         result
     }
 
-    pub fn extract_search_data<'v>(&self, value: &'v Value) -> Result<Option<&'v str>> {
-        // TODO also search in Ref and RefList document titles
-
+    /// Returns the value with the references to one document replaced with the references to
+    /// another one, or `None` if the value doesn't reference the document.
+    #[must_use]
+    pub fn replace_refs(&self, value: &Value, old_id: &Id, new_id: &Id) -> Option<Value> {
         match self.field_type {
-            FieldType::String {} | FieldType::MarkupString {} | FieldType::People {} => {
-                let data = value
-                    .as_str()
-                    .ok_or_else(|| anyhow!("failed to extract field {}", self.name))?;
+            FieldType::MarkupString {} => {
+                let text = value.as_str().expect("field must be string");
+                let markup: MarkupStr = text.into();
+
+                if !markup.extract_refs().contains(old_id) {
+                    return None;
+                }
+
+                Some(Value::String(replace_ref(text, old_id, new_id)))
+            }
+            FieldType::Ref(_) => {
+                let value: Id = serde_json::from_value(value.clone()).expect("field must parse");
+
+                if value != *old_id {
+                    return None;
+                }
+
+                Some(Value::String(new_id.to_string()))
+            }
+            FieldType::RefList(_) => {
+                let value: Vec<Id> =
+                    serde_json::from_value(value.clone()).expect("field must parse");
 
-                Ok(Some(data))
+                if !value.contains(old_id) {
+                    return None;
+                }
+
+                // keep the position of the first reference, if both documents are in the list
+                let mut result: Vec<Value> = Vec::with_capacity(value.len());
+                for id in value {
+                    let id = if id == *old_id { new_id.clone() } else { id };
+                    let id = Value::String(id.to_string());
+
+                    if !result.contains(&id) {
+                        result.push(id);
+                    }
+                }
+
+                Some(Value::Array(result))
             }
-            _ => Ok(None),
+            _ => None,
+        }
+    }
+
+    /// Referenced document titles are indexed by `BazaState`, since they aren't in the value.
+    pub fn extract_search_data<'v>(&self, value: &'v Value) -> Result<Option<&'v str>> {
+        if !self.is_searchable() {
+            return Ok(None);
         }
+
+        let data = value
+            .as_str()
+            .ok_or_else(|| anyhow!("failed to extract field {}", self.name))?;
+
+        Ok(Some(data))
     }
 
     #[allow(clippy::too_many_lines)]
@@ -136,7 +208,7 @@ impl Field {
             bail!("mandatory field '{}' is empty", self.name);
         }
 
-        match self.field_type {
+        match &self.field_type {
             FieldType::String {}
             | FieldType::MarkupString {}
             | FieldType::Ref(_)
@@ -206,7 +278,8 @@ impl Field {
                     );
                 }
 
-                if !options.contains(&value.as_str().unwrap_or_default()) {
+                let value_str = value.as_str().unwrap_or_default();
+                if !options.iter().any(|option| option == value_str) {
                     bail!(
                         "field '{}' is {}, expected to be one of {}",
                         self.name,
@@ -221,8 +294,8 @@ impl Field {
     }
 
     #[must_use]
-    pub fn get_expected_ref_types(&self) -> Option<&[&str]> {
-        match self.field_type {
+    pub fn get_expected_ref_types(&self) -> Option<&[String]> {
+        match &self.field_type {
             FieldType::Ref(document_types) | FieldType::RefList(document_types) => {
                 Some(document_types)
             }
@@ -232,11 +305,51 @@ impl Field {
 
     #[must_use]
     pub fn can_collect(&self, document_type: &DocumentType) -> bool {
-        match self.field_type {
+        match &self.field_type {
             FieldType::RefList(ref_types) => {
-                ref_types.is_empty() || ref_types.contains(&document_type.as_ref())
+                ref_types.is_empty() || ref_types.iter().any(|ref_type| document_type.is(ref_type))
             }
             _ => false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, FieldType};
+
+    fn new_field(field_type: FieldType, ordered: Option<bool>) -> Field {
+        Field {
+            name: "items".into(),
+            field_type,
+            mandatory: false,
+            readonly: false,
+            ordered,
+        }
+    }
+
+    #[test]
+    fn test_is_ordered_ref_list() {
+        // by default, only lists restricted to specific document types are ordered
+        assert!(new_field(FieldType::RefList(vec!["book".into()]), None).is_ordered_ref_list());
+        assert!(!new_field(FieldType::RefList(vec![]), None).is_ordered_ref_list());
+
+        assert!(
+            !new_field(FieldType::RefList(vec!["book".into()]), Some(false)).is_ordered_ref_list()
+        );
+        assert!(new_field(FieldType::RefList(vec![]), Some(true)).is_ordered_ref_list());
+
+        assert!(!new_field(FieldType::Ref(vec!["book".into()]), Some(true)).is_ordered_ref_list());
+    }
+
+    #[test]
+    fn test_deserialize_field_without_ordered() {
+        let field: Field = serde_json::from_str(
+            r#"{"name":"items","field_type":{"RefList":[]},"mandatory":false,"readonly":false}"#,
+        )
+        .unwrap();
+        assert_eq!(field.ordered, None);
+
+        assert!(!serde_json::to_string(&field).unwrap().contains("ordered"));
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{Document, DocumentType},
+    schema::{Field, FieldType},
+};
+
+use super::{DataDescription, DataSchema};
+
+pub const HISTORY_SETTINGS_TYPE: &str = "history settings";
+
+pub fn get_history_settings_definition() -> DataDescription {
+    DataDescription {
+        document_type: HISTORY_SETTINGS_TYPE.into(),
+        title_format: "History settings".into(),
+        fields: vec![
+            Field {
+                name: "keep_last".into(), // number of the latest snapshots to keep per document
+                field_type: FieldType::NaturalNumber {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+            Field {
+                name: "keep_daily_days".into(), // keep the last snapshot of a day for N days
+                field_type: FieldType::NaturalNumber {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+            Field {
+                name: "pinned_types".into(), // comma-separated types that keep all snapshots
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+        ],
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct HistorySettingsData {
+    #[serde(default)]
+    pub keep_last: Option<u64>,
+    #[serde(default)]
+    pub keep_daily_days: Option<u64>,
+    #[serde(default)]
+    pub pinned_types: String,
+}
+
+impl HistorySettingsData {
+    pub fn get_pinned_types(&self) -> Vec<DocumentType> {
+        self.pinned_types
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(DocumentType::new)
+            .collect()
+    }
+
+    pub fn validate_pinned_types(&self, schema: &DataSchema) -> Result<()> {
+        for document_type in self.get_pinned_types() {
+            schema.get_data_description(&document_type)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub type HistorySettings = Document<HistorySettingsData>;
+
+/// Which old snapshots of the documents to keep when pruning the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryRetentionPolicy {
+    pub keep_last: usize,
+    pub keep_daily_days: u64,
+    pub pinned_types: HashSet<DocumentType>,
+}
+
+impl HistoryRetentionPolicy {
+    pub fn is_pinned(&self, document_type: &DocumentType) -> bool {
+        self.pinned_types.contains(document_type)
+    }
+}
+
+/// Builds the history retention policy from the history settings documents. Settings of all
+/// documents are combined so that the most snapshots are kept. Without any settings documents
+/// there's no policy, and all snapshots are kept.
+pub fn build_history_retention_policy(
+    settings: &[HistorySettingsData],
+) -> Option<HistoryRetentionPolicy> {
+    if settings.is_empty() {
+        return None;
+    }
+
+    let keep_last = settings
+        .iter()
+        .filter_map(|settings| settings.keep_last)
+        .max()
+        .unwrap_or_default();
+
+    let keep_daily_days = settings
+        .iter()
+        .filter_map(|settings| settings.keep_daily_days)
+        .max()
+        .unwrap_or_default();
+
+    let pinned_types = settings
+        .iter()
+        .flat_map(HistorySettingsData::get_pinned_types)
+        .collect();
+
+    Some(HistoryRetentionPolicy {
+        keep_last: usize::try_from(keep_last).unwrap_or(usize::MAX),
+        keep_daily_days,
+        pinned_types,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::DocumentType;
+
+    use super::{HistoryRetentionPolicy, HistorySettingsData, build_history_retention_policy};
+
+    #[test]
+    fn test_build_history_retention_policy() {
+        assert_eq!(build_history_retention_policy(&[]), None);
+
+        let policy = build_history_retention_policy(&[
+            HistorySettingsData {
+                keep_last: Some(5),
+                keep_daily_days: None,
+                pinned_types: " book, ,film ".into(),
+            },
+            HistorySettingsData {
+                keep_last: Some(3),
+                keep_daily_days: Some(30),
+                pinned_types: "note".into(),
+            },
+        ]);
+
+        assert_eq!(
+            policy,
+            Some(HistoryRetentionPolicy {
+                keep_last: 5,
+                keep_daily_days: 30,
+                pinned_types: [
+                    DocumentType::new("book"),
+                    DocumentType::new("film"),
+                    DocumentType::new("note"),
+                ]
+                .into(),
+            })
+        );
+    }
+}
@@ -1,62 +1,237 @@
-use anyhow::{Context, Result, anyhow};
-use baza_common::get_string_hash_sha256;
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use baza_common::{get_string_hash_sha256, log, render_template_with_vars};
 use serde::Serialize;
 
 use crate::entities::{DocumentType, ERASED_DOCUMENT_TYPE};
 
 pub use asset::*;
 pub use data_description::*;
+pub use data_migration::*;
 pub use field::*;
+pub use history_settings::*;
+pub use saved_search::*;
+pub use search_settings::*;
+pub use type_definition::*;
 
 mod asset;
 mod data_description;
+mod data_migration;
 mod field;
+mod history_settings;
+mod saved_search;
+mod search_settings;
+mod type_definition;
 
-const ERASED_DOCUMENT_DATA_DESCRIPTION: &DataDescription = &DataDescription {
-    document_type: ERASED_DOCUMENT_TYPE,
-    title_format: "Erased document",
-    fields: vec![],
-};
+fn get_erased_document_definition() -> DataDescription {
+    DataDescription {
+        document_type: ERASED_DOCUMENT_TYPE.into(),
+        title_format: "Erased document".into(),
+        fields: vec![],
+    }
+}
 
 #[derive(Serialize, Clone)]
 pub struct DataSchema {
     name: String,
     data_version: u8,
     modules: Vec<DataDescription>,
+    /// Runtime-defined document types, loaded from type definition documents.
+    custom_modules: Vec<DataDescription>,
+    #[serde(skip)]
+    migrations: Vec<DataMigration>,
 }
 
 impl DataSchema {
     #[must_use]
     pub fn new(name: impl Into<String>, mut modules: Vec<DataDescription>) -> Self {
-        modules.push(ERASED_DOCUMENT_DATA_DESCRIPTION.clone());
+        modules.push(get_erased_document_definition());
         modules.push(get_asset_definition());
+        modules.push(get_type_definition_definition());
+        modules.push(get_saved_search_definition());
+        modules.push(get_search_settings_definition());
+        modules.push(get_history_settings_definition());
 
         DataSchema {
             name: name.into(),
             data_version: 2,
             modules,
+            custom_modules: vec![],
+            migrations: vec![],
+        }
+    }
+
+    /// Registers declarative data migrations. Every migration bumps the data version by one.
+    #[must_use]
+    pub fn with_migrations(mut self, migrations: Vec<DataMigration>) -> Self {
+        for migration in migrations {
+            assert_eq!(
+                migration.data_version,
+                self.data_version + 1,
+                "data migrations must have consecutive data versions"
+            );
+
+            self.data_version = migration.data_version;
+            self.migrations.push(migration);
+        }
+
+        self
+    }
+
+    /// Returns a copy of the built-in schema extended with the given runtime-defined types.
+    ///
+    /// Definitions that collide with already known types or fail validation are skipped, so
+    /// a bad definition synced from another instance never prevents the baza from opening.
+    #[must_use]
+    pub fn with_custom_types(&self, definitions: Vec<DataDescription>) -> Self {
+        let mut custom_modules: Vec<DataDescription> = Vec::with_capacity(definitions.len());
+
+        for definition in definitions {
+            let document_type = DocumentType::new(&definition.document_type);
+
+            if self.is_builtin_type(&document_type)
+                || custom_modules
+                    .iter()
+                    .any(|module| document_type.is(&module.document_type))
+            {
+                log::warn!("Ignoring duplicate definition of document type '{document_type}'");
+                continue;
+            }
+
+            custom_modules.push(definition);
+        }
+
+        // definitions may reference each other, so drop invalid ones until the set is stable
+        loop {
+            let schema = DataSchema {
+                custom_modules: custom_modules.clone(),
+                ..self.clone()
+            };
+
+            let types_count = custom_modules.len();
+            custom_modules.retain(|definition| {
+                if let Err(err) = schema.validate_custom_type(definition) {
+                    log::warn!(
+                        "Ignoring invalid definition of document type '{}': {err}",
+                        definition.document_type
+                    );
+
+                    return false;
+                }
+
+                true
+            });
+
+            if custom_modules.len() == types_count {
+                return schema;
+            }
         }
     }
 
+    /// Validates a runtime-defined document type against the types known to this schema.
+    pub fn validate_custom_type(&self, definition: &DataDescription) -> Result<()> {
+        let document_type = definition.document_type.as_str();
+
+        ensure!(!document_type.trim().is_empty(), "document type is empty");
+        ensure!(
+            document_type.trim() == document_type,
+            "document type '{document_type}' must not start or end with whitespace"
+        );
+        ensure!(
+            !self.is_builtin_type(&DocumentType::new(document_type)),
+            "document type '{document_type}' is a built-in type"
+        );
+        ensure!(
+            !definition.fields.is_empty(),
+            "document type '{document_type}' must have at least one field"
+        );
+
+        let mut field_names = HashSet::new();
+        for field in &definition.fields {
+            ensure!(!field.name.trim().is_empty(), "field name is empty");
+            ensure!(
+                !field.name.starts_with('@'),
+                "field name '{}' must not start with '@'",
+                field.name
+            );
+            ensure!(
+                field_names.insert(field.name.as_str()),
+                "field '{}' is defined more than once",
+                field.name
+            );
+            ensure!(
+                field.ordered.is_none() || matches!(field.field_type, FieldType::RefList(_)),
+                "field '{}' isn't a ref list, it can't be ordered",
+                field.name
+            );
+
+            match &field.field_type {
+                FieldType::Ref(ref_types) | FieldType::RefList(ref_types) => {
+                    for ref_type in ref_types {
+                        ensure!(
+                            ref_type == document_type
+                                || self
+                                    .get_data_description(&DocumentType::new(ref_type))
+                                    .is_ok(),
+                            "field '{}' references unknown document type '{ref_type}'",
+                            field.name
+                        );
+                    }
+                }
+                FieldType::Enum(options) => {
+                    ensure!(
+                        !options.is_empty(),
+                        "enum field '{}' has no options",
+                        field.name
+                    );
+
+                    let unique_options = options.iter().collect::<HashSet<_>>();
+                    ensure!(
+                        unique_options.len() == options.len(),
+                        "enum field '{}' has duplicate options",
+                        field.name
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        let title_fields = definition
+            .fields
+            .iter()
+            .filter(|field| field.could_be_in_title())
+            .map(|field| (field.name.as_str(), ""))
+            .collect::<HashMap<_, _>>();
+
+        if let Err(err) = render_template_with_vars(&definition.title_format, &title_fields) {
+            bail!("invalid title format '{}': {err}", definition.title_format);
+        }
+
+        Ok(())
+    }
+
     #[cfg(test)]
     pub fn new_test_schema() -> Self {
         Self::new(
             "test",
             vec![DataDescription {
-                document_type: "test_type",
-                title_format: "${test}",
+                document_type: "test_type".into(),
+                title_format: "${test}".into(),
                 fields: vec![
                     Field {
-                        name: "test",
+                        name: "test".into(),
                         field_type: FieldType::String {},
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                     Field {
-                        name: "ref",
-                        field_type: FieldType::Ref(&["test_type"]),
+                        name: "ref".into(),
+                        field_type: FieldType::Ref(vec!["test_type".into()]),
                         mandatory: false,
                         readonly: false,
+                        ordered: None,
                     },
                 ],
             }],
@@ -69,9 +244,8 @@ impl DataSchema {
     }
 
     pub fn get_data_description(&self, document_type: &DocumentType) -> Result<&DataDescription> {
-        self.modules
-            .iter()
-            .find(|module| document_type.is(module.document_type))
+        self.iter_modules()
+            .find(|module| document_type.is(&module.document_type))
             .ok_or_else(|| {
                 let types = self.get_document_types().join(", ");
 
@@ -92,12 +266,36 @@ impl DataSchema {
         Ok(data_description.fields.iter())
     }
 
+    /// Returns definitions of the field with the given name in all document types.
+    pub fn find_fields<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Field> {
+        self.iter_modules()
+            .flat_map(|module| module.fields.iter())
+            .filter(move |field| field.name == name)
+    }
+
+    fn iter_modules(&self) -> impl Iterator<Item = &DataDescription> {
+        self.modules.iter().chain(self.custom_modules.iter())
+    }
+
     #[must_use]
-    pub fn get_document_types(&self) -> Vec<&'static str> {
+    pub fn get_document_types(&self) -> Vec<&str> {
+        self.iter_modules()
+            .map(|module| module.document_type.as_str())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn is_builtin_type(&self, document_type: &DocumentType) -> bool {
         self.modules
             .iter()
-            .map(|module| module.document_type)
-            .collect()
+            .any(|module| document_type.is(&module.document_type))
+    }
+
+    #[must_use]
+    pub fn is_custom_type(&self, document_type: &DocumentType) -> bool {
+        self.custom_modules
+            .iter()
+            .any(|module| document_type.is(&module.document_type))
     }
 
     #[must_use]
@@ -105,6 +303,13 @@ impl DataSchema {
         self.data_version
     }
 
+    /// Returns migrations required to upgrade data from the given data version, in order.
+    pub fn get_pending_migrations(&self, data_version: u8) -> impl Iterator<Item = &DataMigration> {
+        self.migrations
+            .iter()
+            .filter(move |migration| migration.data_version > data_version)
+    }
+
     /// Returns a stable hash of the full serialized schema contract.
     pub fn fingerprint(&self) -> Result<String> {
         let serialized_schema =
@@ -116,7 +321,32 @@ impl DataSchema {
 
 #[cfg(test)]
 mod tests {
-    use super::DataSchema;
+    use crate::entities::DocumentType;
+
+    use super::{DataDescription, DataSchema, Field, FieldType};
+
+    fn new_custom_type(document_type: &str, field_type: FieldType) -> DataDescription {
+        DataDescription {
+            document_type: document_type.into(),
+            title_format: "${name}".into(),
+            fields: vec![
+                Field {
+                    name: "name".into(),
+                    field_type: FieldType::String {},
+                    mandatory: true,
+                    readonly: false,
+                    ordered: None,
+                },
+                Field {
+                    name: "other".into(),
+                    field_type,
+                    mandatory: false,
+                    readonly: false,
+                    ordered: None,
+                },
+            ],
+        }
+    }
 
     #[test]
     fn test_schema_fingerprint_is_stable() {
@@ -135,4 +365,85 @@ mod tests {
             changed.fingerprint().unwrap()
         );
     }
+
+    #[test]
+    fn test_with_custom_types() {
+        let schema = DataSchema::new_test_schema().with_custom_types(vec![
+            new_custom_type("recipe", FieldType::RefList(vec!["place".into()])),
+            new_custom_type("place", FieldType::Ref(vec!["test_type".into()])),
+            new_custom_type("recipe", FieldType::String {}),
+            new_custom_type("test_type", FieldType::String {}),
+            new_custom_type("broken", FieldType::Ref(vec!["unknown".into()])),
+            new_custom_type("dependent", FieldType::Ref(vec!["broken".into()])),
+        ]);
+
+        assert!(schema.is_custom_type(&DocumentType::new("recipe")));
+        assert!(schema.is_custom_type(&DocumentType::new("place")));
+        assert!(!schema.is_custom_type(&DocumentType::new("test_type")));
+        assert!(!schema.is_custom_type(&DocumentType::new("broken")));
+        assert!(!schema.is_custom_type(&DocumentType::new("dependent")));
+
+        // the first definition of a type wins
+        let recipe = schema
+            .get_data_description(&DocumentType::new("recipe"))
+            .unwrap();
+        assert_eq!(
+            recipe.get_field("other").unwrap().field_type,
+            FieldType::RefList(vec!["place".into()])
+        );
+    }
+
+    #[test]
+    fn test_validate_custom_type() {
+        let schema = DataSchema::new_test_schema();
+
+        assert!(
+            schema
+                .validate_custom_type(&new_custom_type("recipe", FieldType::String {}))
+                .is_ok()
+        );
+        assert!(
+            schema
+                .validate_custom_type(&new_custom_type(" recipe", FieldType::String {}))
+                .is_err()
+        );
+        assert!(
+            schema
+                .validate_custom_type(&new_custom_type("recipe", FieldType::Enum(vec![])))
+                .is_err()
+        );
+
+        let mut definition = new_custom_type("recipe", FieldType::String {});
+        definition.fields[1].name = "name".into();
+        assert!(schema.validate_custom_type(&definition).is_err());
+
+        let mut definition = new_custom_type("recipe", FieldType::String {});
+        definition.fields[1].name = "@title".into();
+        assert!(schema.validate_custom_type(&definition).is_err());
+
+        let mut definition = new_custom_type("recipe", FieldType::String {});
+        definition.title_format = "${missing}".into();
+        assert!(schema.validate_custom_type(&definition).is_err());
+
+        // only ref lists could be ordered
+        let mut definition = new_custom_type("recipe", FieldType::String {});
+        definition.fields[1].ordered = Some(true);
+        assert!(schema.validate_custom_type(&definition).is_err());
+
+        let mut definition = new_custom_type("recipe", FieldType::RefList(vec![]));
+        definition.fields[1].ordered = Some(true);
+        assert!(schema.validate_custom_type(&definition).is_ok());
+    }
+
+    #[test]
+    fn test_custom_types_change_fingerprint() {
+        let schema = DataSchema::new_test_schema();
+        let extended =
+            schema.with_custom_types(vec![new_custom_type("recipe", FieldType::String {})]);
+
+        assert_ne!(
+            schema.fingerprint().unwrap(),
+            extended.fingerprint().unwrap()
+        );
+    }
 }
@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Filter, SortKey, StructuredQuery,
+    entities::{Document, DocumentType},
+    schema::{DataSchema, Field, FieldType},
+};
+
+use super::DataDescription;
+
+pub const SAVED_SEARCH_TYPE: &str = "saved search";
+
+pub fn get_saved_search_definition() -> DataDescription {
+    DataDescription {
+        document_type: SAVED_SEARCH_TYPE.into(),
+        title_format: "${name}".into(),
+        fields: vec![
+            Field {
+                name: "name".into(),
+                field_type: FieldType::String {},
+                mandatory: true,
+                readonly: false,
+                ordered: None,
+            },
+            Field {
+                name: "query".into(), // see StructuredQuery
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+            Field {
+                name: "document_types".into(), // comma-separated
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+            Field {
+                name: "sort".into(), // comma-separated sort keys, like "rating:desc,@title"
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+        ],
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SavedSearchData {
+    pub name: String,
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub document_types: String,
+    #[serde(default)]
+    pub sort: String,
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+impl SavedSearchData {
+    pub fn get_document_types(&self) -> Vec<DocumentType> {
+        split_list(&self.document_types)
+            .map(DocumentType::new)
+            .collect()
+    }
+
+    pub fn get_sort(&self) -> Result<Vec<SortKey>> {
+        split_list(&self.sort)
+            .map(|key| key.parse().context("Invalid sort key"))
+            .collect()
+    }
+
+    pub fn validate_document_types(&self, schema: &DataSchema) -> Result<()> {
+        for document_type in self.get_document_types() {
+            schema.get_data_description(&document_type)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_query(&self, schema: &DataSchema) -> Result<()> {
+        StructuredQuery::parse(&self.query, schema)?;
+
+        Ok(())
+    }
+
+    pub fn validate_sort(&self, schema: &DataSchema) -> Result<()> {
+        for key in self.get_sort()? {
+            key.validate(schema)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds a list filter for the first page of the saved search results.
+    pub fn to_filter(&self, schema: &DataSchema) -> Result<Filter> {
+        self.validate_document_types(schema)?;
+        self.validate_query(schema)?;
+        self.validate_sort(schema)?;
+
+        Ok(Filter {
+            document_types: self.get_document_types(),
+            query: self.query.clone(),
+            sort: self.get_sort()?,
+            ..Default::default()
+        })
+    }
+}
+
+pub type SavedSearch = Document<SavedSearchData>;
+
+#[cfg(test)]
+mod tests {
+    use crate::{SortField, SortKey, entities::DocumentType, schema::DataSchema};
+
+    use super::SavedSearchData;
+
+    fn new_saved_search(query: &str, document_types: &str, sort: &str) -> SavedSearchData {
+        SavedSearchData {
+            name: "Test".into(),
+            query: query.into(),
+            document_types: document_types.into(),
+            sort: sort.into(),
+        }
+    }
+
+    #[test]
+    fn test_saved_search_to_filter() {
+        let schema = DataSchema::new_test_schema();
+
+        let filter = new_saved_search("test:value", "test_type, asset", "@title:desc")
+            .to_filter(&schema)
+            .unwrap();
+
+        assert_eq!(filter.query, "test:value");
+        assert_eq!(
+            filter.document_types,
+            vec![DocumentType::new("test_type"), DocumentType::new("asset")]
+        );
+        assert_eq!(filter.sort, vec![SortKey::new(SortField::Title, true)]);
+        assert_eq!(filter.page, 0);
+
+        let filter = new_saved_search("", "", "").to_filter(&schema).unwrap();
+        assert!(filter.document_types.is_empty());
+        assert!(filter.sort.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_saved_search() {
+        let schema = DataSchema::new_test_schema();
+
+        assert!(
+            new_saved_search("", "unknown", "")
+                .to_filter(&schema)
+                .is_err()
+        );
+        assert!(
+            new_saved_search("unknown:value", "", "")
+                .to_filter(&schema)
+                .is_err()
+        );
+        assert!(
+            new_saved_search("", "", "unknown")
+                .to_filter(&schema)
+                .is_err()
+        );
+        assert!(
+            new_saved_search("", "", "@title:up")
+                .to_filter(&schema)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::Document,
+    full_text_search::{StemmingLanguage, Tokenizer},
+    schema::{Field, FieldType},
+};
+
+use super::DataDescription;
+
+pub const SEARCH_SETTINGS_TYPE: &str = "search settings";
+
+pub fn get_search_settings_definition() -> DataDescription {
+    DataDescription {
+        document_type: SEARCH_SETTINGS_TYPE.into(),
+        title_format: "Search settings".into(),
+        fields: vec![
+            Field {
+                name: "stemming".into(), // comma-separated languages, like "English, Ukrainian"
+                field_type: FieldType::String {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+            Field {
+                name: "synonyms".into(), // a group of comma-separated words per line
+                field_type: FieldType::MarkupString {},
+                mandatory: false,
+                readonly: false,
+                ordered: None,
+            },
+        ],
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SearchSettingsData {
+    #[serde(default)]
+    pub stemming: String,
+    #[serde(default)]
+    pub synonyms: String,
+}
+
+impl SearchSettingsData {
+    pub fn get_stemming_languages(&self) -> Result<Vec<StemmingLanguage>> {
+        self.stemming
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    pub fn get_synonym_groups(&self) -> Vec<Vec<String>> {
+        self.synonyms
+            .lines()
+            .map(|line| {
+                line.split(',')
+                    .map(str::trim)
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+}
+
+pub type SearchSettings = Document<SearchSettingsData>;
+
+/// Builds the search tokenizer from the search settings documents, ordered by document id.
+/// Stemming languages and synonym groups of all documents are combined; invalid documents are
+/// ignored. Without any settings documents, stemming is disabled.
+pub fn build_search_tokenizer(settings: &[SearchSettingsData]) -> Tokenizer {
+    let stemming = settings
+        .iter()
+        .filter_map(|settings| settings.get_stemming_languages().ok())
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let synonym_groups = settings
+        .iter()
+        .flat_map(SearchSettingsData::get_synonym_groups)
+        .collect::<Vec<_>>();
+
+    Tokenizer::new(stemming, &synonym_groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::full_text_search::{StemmingLanguage, Tokenizer};
+
+    use super::{SearchSettingsData, build_search_tokenizer};
+
+    #[test]
+    fn test_parse_search_settings() {
+        let settings = SearchSettingsData {
+            stemming: " english, ,Ukrainian ".into(),
+            synonyms: "car, auto\n\nsingle\n film,movie , ".into(),
+        };
+
+        assert_eq!(
+            settings.get_stemming_languages().unwrap(),
+            vec![StemmingLanguage::English, StemmingLanguage::Ukrainian]
+        );
+        assert_eq!(
+            settings.get_synonym_groups(),
+            vec![
+                vec!["car".to_string(), "auto".to_string()],
+                vec!["film".to_string(), "movie".to_string()],
+            ]
+        );
+
+        let settings = SearchSettingsData {
+            stemming: "English, Klingon".into(),
+            ..Default::default()
+        };
+        assert!(settings.get_stemming_languages().is_err());
+    }
+
+    #[test]
+    fn test_build_search_tokenizer() {
+        assert_eq!(build_search_tokenizer(&[]), Tokenizer::default());
+
+        let tokenizer = build_search_tokenizer(&[
+            SearchSettingsData {
+                stemming: "English".into(),
+                synonyms: "car, auto".into(),
+            },
+            SearchSettingsData {
+                stemming: "Klingon".into(),
+                synonyms: "film, movie".into(),
+            },
+        ]);
+        assert_eq!(
+            tokenizer,
+            Tokenizer::new(
+                [StemmingLanguage::English],
+                &[
+                    vec!["car".into(), "auto".into()],
+                    vec!["film".into(), "movie".into()],
+                ]
+            )
+        );
+    }
+}
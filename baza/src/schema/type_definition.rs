@@ -0,0 +1,68 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::Document,
+    schema::{Field, FieldType},
+};
+
+use super::DataDescription;
+
+pub const TYPE_DEFINITION_TYPE: &str = "type definition";
+
+pub fn get_type_definition_definition() -> DataDescription {
+    DataDescription {
+        document_type: TYPE_DEFINITION_TYPE.into(),
+        title_format: "${document_type}".into(),
+        fields: vec![
+            Field {
+                name: "document_type".into(),
+                field_type: FieldType::String {},
+                mandatory: true,
+                readonly: true,
+                ordered: None,
+            },
+            Field {
+                name: "title_format".into(),
+                field_type: FieldType::String {},
+                mandatory: true,
+                readonly: false,
+                ordered: None,
+            },
+            Field {
+                name: "fields".into(), // JSON array of field definitions
+                field_type: FieldType::String {},
+                mandatory: true,
+                readonly: false,
+                ordered: None,
+            },
+        ],
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TypeDefinitionData {
+    pub document_type: String,
+    pub title_format: String,
+    pub fields: String,
+}
+
+impl TypeDefinitionData {
+    pub fn to_data_description(&self) -> Result<DataDescription> {
+        let fields: Vec<Field> = serde_json::from_str(&self.fields).with_context(|| {
+            format!(
+                "Failed to parse fields of document type '{}'",
+                self.document_type
+            )
+        })?;
+
+        Ok(DataDescription {
+            document_type: self.document_type.clone(),
+            title_format: self.title_format.clone(),
+            fields,
+        })
+    }
+}
+
+pub type TypeDefinition = Document<TypeDefinitionData>;